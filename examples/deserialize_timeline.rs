@@ -0,0 +1,42 @@
+//! Compares deserializing a large timeline from the response's bytes, as the
+//! client does, with decoding it into a `String` first.
+//!
+//! Run it with optimizations for meaningful numbers:
+//!
+//! ```text
+//! cargo run --release --example deserialize_timeline
+//! ```
+use elefren::entities::status::Status;
+use std::time::{Duration, Instant};
+
+const STATUSES: usize = 40;
+const PAGES: u32 = 500;
+
+fn time<F: FnMut(&[u8]) -> Vec<Status>>(page: &[u8], mut deserialize: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..PAGES {
+        assert_eq!(deserialize(page).len(), STATUSES);
+    }
+    start.elapsed()
+}
+
+fn main() {
+    let status = include_str!("../tests/fixtures/status.json");
+    let page = format!("[{}]", vec![status; STATUSES].join(","));
+    let page = page.as_bytes();
+
+    let from_text = time(page, |page| {
+        let text = String::from_utf8_lossy(page).into_owned();
+        serde_json::from_str(&text).unwrap()
+    });
+    let from_slice = time(page, |page| serde_json::from_slice(page).unwrap());
+
+    println!(
+        "{} pages of {} statuses ({} bytes each)",
+        PAGES,
+        STATUSES,
+        page.len()
+    );
+    println!("String + from_str: {:?}", from_text);
+    println!("from_slice:        {:?}", from_slice);
+}
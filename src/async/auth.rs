@@ -8,10 +8,7 @@ use crate::{
     requests::StatusesRequest,
 };
 use http_types::{Method, Request, Response};
-use hyper_old_types::header::{parsing, Link, RelationType};
 use serde::Serialize;
-use smol::{prelude::*, Async};
-use std::net::{TcpStream, ToSocketAddrs};
 use url::Url;
 
 /// strategies for authenticating mastodon requests need to implement this trait
@@ -1,52 +1,280 @@
 use crate::{
     entities::{account::Account, card::Card, context::Context, status::Status},
     errors::{Error, Result},
+    redirect::RedirectPolicy,
 };
+#[cfg(not(feature = "wasm"))]
+use async_native_tls::TlsConnector;
+#[cfg(not(feature = "wasm"))]
+use http_types::headers::{AUTHORIZATION, LOCATION};
 use http_types::{Method, Request, Response};
-use hyper_old_types::header::{parsing, Link, RelationType};
+#[cfg(not(feature = "wasm"))]
 use smol::{prelude::*, Async};
-use std::net::{TcpStream, ToSocketAddrs};
+#[cfg(not(feature = "wasm"))]
+use std::{
+    net::{TcpStream, ToSocketAddrs},
+    sync::Arc,
+};
 use url::Url;
 
-// taken pretty much verbatim from `smol`s example
-
-/// Sends a request and fetches the response.
-pub(super) async fn fetch(req: Request) -> Result<Response> {
-    // Figure out the host and the port.
-    let host = req
-        .url()
-        .host()
-        .ok_or_else(|| String::from("No host found"))?
-        .to_string();
-    let port = req
-        .url()
-        .port_or_known_default()
-        .ok_or_else(|| Error::Other(String::from("No port found")))?;
-
-    // Connect to the host.
-    let socket_addr = {
-        let host = host.clone();
-        smol::unblock(move || (host.as_str(), port).to_socket_addrs())
-            .await?
-            .next()
-            .ok_or_else(|| Error::Other(String::from("No socket addr")))?
-    };
-    let stream = Async::<TcpStream>::connect(socket_addr).await?;
-
-    // Send the request and wait for the response.
-    let resp = match req.url().scheme() {
-        "http" => async_h1::connect(stream, req).await?,
-        "https" => {
-            // In case of HTTPS, establish a secure TLS connection first.
-            let stream = async_native_tls::connect(&host, stream).await?;
-            async_h1::connect(stream, req).await?
-        },
-        scheme => return Err(Error::Other(format!("unsupported scheme '{}'", scheme))),
-    };
-    Ok(resp)
+/// Connection settings for the async client
+///
+/// By default, connections are made directly to the instance, TLS uses the
+/// platform's default settings, and only redirects to the same origin are
+/// followed, see `RedirectPolicy`.
+///
+/// With the `wasm` feature, requests are sent through the browser's `fetch`
+/// API instead, which takes care of TLS and proxies itself, so there is
+/// nothing to configure.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use elefren::r#async::{Client, ClientConfig, TlsConnector};
+/// use url::Url;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let config = ClientConfig::new()
+///     .tls(TlsConnector::new().use_sni(true))
+///     .proxy(Url::parse("http://localhost:3128")?);
+/// let client = Client::with_config("https://mastodon.social", config)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "wasm", derive(Copy))]
+pub struct ClientConfig {
+    #[cfg(not(feature = "wasm"))]
+    tls: Option<Arc<TlsConnector>>,
+    #[cfg(not(feature = "wasm"))]
+    proxy: Option<Url>,
+    #[cfg(not(feature = "wasm"))]
+    redirect_policy: RedirectPolicy,
+    max_body_size: Option<usize>,
+}
+
+// The largest body `deserialize` reads, attached to responses by `fetch`
+#[derive(Debug, Clone, Copy)]
+pub(super) struct BodyLimit(pub(super) usize);
+
+impl ClientConfig {
+    /// Create the default configuration
+    pub fn new() -> ClientConfig {
+        Default::default()
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    /// Use `connector` to establish TLS connections, e.g. to add root
+    /// certificates or a client identity
+    pub fn tls(mut self, connector: TlsConnector) -> Self {
+        self.tls = Some(Arc::new(connector));
+        self
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    /// Tunnel all connections through the HTTP proxy at `proxy`, using
+    /// `CONNECT` requests
+    pub fn proxy(mut self, proxy: Url) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    /// Follow redirects according to `policy`
+    ///
+    /// Only redirects of `GET` and `HEAD` requests are followed, as the
+    /// body of other requests can't be sent again.
+    pub fn redirect_policy(mut self, policy: RedirectPolicy) -> Self {
+        self.redirect_policy = policy;
+        self
+    }
+
+    /// Refuse to read response bodies larger than `bytes`, failing with
+    /// `Error::BodyTooLarge` instead. By default, bodies of any size are read.
+    pub fn max_body_size(mut self, bytes: usize) -> Self {
+        self.max_body_size = Some(bytes);
+        self
+    }
+
+    /// Sends a request and fetches the response, marking it with the body
+    /// size limit
+    pub(super) async fn fetch(&self, req: Request) -> Result<Response> {
+        let mut response = self.send_request(req).await?;
+        if let Some(limit) = self.max_body_size {
+            response.ext_mut().insert(BodyLimit(limit));
+        }
+        Ok(response)
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    // Sends a request and fetches the response, following redirects
+    // according to the redirect policy.
+    async fn send_request(&self, mut req: Request) -> Result<Response> {
+        let mut redirects = 0;
+        loop {
+            let retry = match req.method() {
+                Method::Get | Method::Head => Some(req.clone()),
+                _ => None,
+            };
+            let resp = self.fetch_once(req).await?;
+            let location = match resp.header(LOCATION) {
+                Some(location) if resp.status().is_redirection() => {
+                    location.last().as_str().to_string()
+                },
+                _ => return Ok(resp),
+            };
+            let mut next = match retry {
+                Some(next) => next,
+                None => return Ok(resp),
+            };
+            let from = next.url().clone();
+            let to = from.join(&location)?;
+            if !self.redirect_policy.allows(&from, &to) {
+                if self.redirect_policy == RedirectPolicy::SameOrigin {
+                    return Err(Error::CrossOriginRedirect {
+                        url: from.to_string(),
+                        location: to.to_string(),
+                    });
+                }
+                return Ok(resp);
+            }
+            if redirects == RedirectPolicy::MAX_REDIRECTS {
+                return Err(Error::Other(String::from("too many redirects")));
+            }
+            if from.host_str() != to.host_str()
+                || from.port_or_known_default() != to.port_or_known_default()
+            {
+                next.remove_header(AUTHORIZATION);
+            }
+            *next.url_mut() = to;
+            req = next;
+            redirects += 1;
+        }
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    // Sends a single request and fetches the response.
+    // taken pretty much verbatim from `smol`s example
+    async fn fetch_once(&self, req: Request) -> Result<Response> {
+        // Figure out the host and the port.
+        let host = req
+            .url()
+            .host()
+            .ok_or_else(|| String::from("No host found"))?
+            .to_string();
+        let port = req
+            .url()
+            .port_or_known_default()
+            .ok_or_else(|| Error::Other(String::from("No port found")))?;
+
+        // Connect to the host, or to the proxy.
+        let stream = match self.proxy {
+            Some(ref proxy) => {
+                let proxy_host = proxy
+                    .host_str()
+                    .ok_or_else(|| Error::Other(String::from("No proxy host found")))?;
+                let proxy_port = proxy
+                    .port_or_known_default()
+                    .ok_or_else(|| Error::Other(String::from("No proxy port found")))?;
+                let mut stream = connect(proxy_host.to_string(), proxy_port).await?;
+                tunnel(&mut stream, &host, port).await?;
+                stream
+            },
+            None => connect(host.clone(), port).await?,
+        };
+
+        // Send the request and wait for the response, remembering the URL for
+        // error messages.
+        let url = req.url().clone();
+        let mut resp = match req.url().scheme() {
+            "http" => async_h1::connect(stream, req).await?,
+            "https" => {
+                // In case of HTTPS, establish a secure TLS connection first.
+                let stream = match self.tls {
+                    Some(ref connector) => connector.connect(&host, stream).await?,
+                    None => async_native_tls::connect(&host, stream).await?,
+                };
+                async_h1::connect(stream, req).await?
+            },
+            scheme => return Err(Error::Other(format!("unsupported scheme '{}'", scheme))),
+        };
+        resp.ext_mut().insert(url);
+        Ok(resp)
+    }
+
+    #[cfg(feature = "wasm")]
+    // Sends a request through the browser's `fetch` API and converts the
+    // response back.
+    async fn send_request(&self, mut req: Request) -> Result<Response> {
+        use gloo_net::http::{Method as FetchMethod, RequestBuilder};
+
+        let method = req
+            .method()
+            .to_string()
+            .parse::<FetchMethod>()
+            .map_err(|e| Error::Other(e.to_string()))?;
+        let mut builder = RequestBuilder::new(req.url().as_str()).method(method);
+        for (name, values) in req.iter() {
+            for value in values.iter() {
+                builder = builder.header(name.as_str(), value.as_str());
+            }
+        }
+        let body = req.body_bytes().await?;
+        let request = if body.is_empty() {
+            builder.build()?
+        } else {
+            builder.body(js_sys::Uint8Array::from(&body[..]))?
+        };
+
+        let fetched = request.send().await?;
+        let mut response = Response::new(fetched.status());
+        for (name, value) in fetched.headers().entries() {
+            response.append_header(name.as_str(), value.as_str());
+        }
+        response.set_body(fetched.binary().await?);
+        response.ext_mut().insert(req.url().clone());
+        Ok(response)
+    }
+}
+
+#[cfg(not(feature = "wasm"))]
+async fn connect(host: String, port: u16) -> Result<Async<TcpStream>> {
+    let socket_addr = smol::unblock(move || (host.as_str(), port).to_socket_addrs())
+        .await?
+        .next()
+        .ok_or_else(|| Error::Other(String::from("No socket addr")))?;
+    Ok(Async::<TcpStream>::connect(socket_addr).await?)
 }
 
-pub(super) async fn get(url: Url) -> Result<Response> {
-    let req = Request::new(Method::Get, url);
-    Ok(fetch(req).await?)
+// Asks the proxy at the other end of `stream` to open a tunnel to
+// `host:port`
+#[cfg(not(feature = "wasm"))]
+async fn tunnel(stream: &mut Async<TcpStream>, host: &str, port: u16) -> Result<()> {
+    let request = format!(
+        "CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n\r\n",
+        host = host,
+        port = port
+    );
+    stream.write_all(request.as_bytes()).await?;
+
+    // read the response headers byte by byte, so that nothing belonging to
+    // the tunneled connection is consumed
+    let mut response = Vec::new();
+    while !response.ends_with(b"\r\n\r\n") {
+        let mut byte = [0u8];
+        if stream.read(&mut byte).await? == 0 {
+            return Err(Error::Other(String::from("proxy closed the connection")));
+        }
+        response.push(byte[0]);
+    }
+
+    let response = String::from_utf8_lossy(&response);
+    let status_line = response.lines().next().unwrap_or("");
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') => Ok(()),
+        _ => Err(Error::Other(format!(
+            "proxy refused tunnel: {}",
+            status_line
+        ))),
+    }
 }
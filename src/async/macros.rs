@@ -0,0 +1,88 @@
+// Async counterparts of the blocking client's route macros. They accept the
+// same declarations, see `routes.rs`.
+
+macro_rules! http_method {
+    (get) => {
+        http_types::Method::Get
+    };
+    (post) => {
+        http_types::Method::Post
+    };
+    (put) => {
+        http_types::Method::Put
+    };
+    (patch) => {
+        http_types::Method::Patch
+    };
+    (delete) => {
+        http_types::Method::Delete
+    };
+}
+
+macro_rules! async_route {
+    (($method:ident) $name:ident: $url:expr => $ret:ty, $($rest:tt)*) => {
+        doc_comment::doc_comment! {
+            concat!("Equivalent to `", stringify!($method), " /api/v1/", $url, "`"),
+            pub async fn $name(&self) -> Result<$ret> {
+                let url = self.base_url.join(concat!("api/v1/", $url))?;
+                let response = self.send(Request::new(http_method!($method), url)).await?;
+                deserialize(response).await
+            }
+        }
+
+        async_route!{$($rest)*}
+    };
+
+    () => {}
+}
+
+macro_rules! async_route_id {
+    (($method:ident) $name:ident: $url:expr => $ret:ty, $($rest:tt)*) => {
+        doc_comment::doc_comment! {
+            concat!("Equivalent to `", stringify!($method), " /api/v1/", $url, "`"),
+            pub async fn $name(&self, id: &str) -> Result<$ret> {
+                let url = self.base_url.join(&format!(concat!("api/v1/", $url), crate::routes::encode_segment(id)?))?;
+                let response = self.send(Request::new(http_method!($method), url)).await?;
+                deserialize(response).await
+            }
+        }
+
+        async_route_id!{$($rest)*}
+    };
+
+    () => {}
+}
+
+macro_rules! async_paged_routes_with_id {
+    (($method:ident) $name:ident $(, $name_with:ident)?: $url:expr => $ret:ty, $($rest:tt)*) => {
+        doc_comment::doc_comment! {
+            concat!("Equivalent to `", stringify!($method), " /api/v1/", $url, "`"),
+            pub async fn $name<'client>(&'client self, id: &str) -> Result<Page<'client, $ret, A>> {
+                let url = self.base_url.join(&format!(concat!("api/v1/", $url), crate::routes::encode_segment(id)?))?;
+                Ok(Page::new(Request::new(http_method!($method), url), &self.auth, &self.config))
+            }
+        }
+
+        $(
+            doc_comment::doc_comment! {
+                concat!(
+                    "Equivalent to `", stringify!($method), " /api/v1/", $url,
+                    "`, bounded by `request`"
+                ),
+                pub async fn $name_with<'client>(
+                    &'client self,
+                    id: &str,
+                    request: &PageRequest,
+                ) -> Result<Page<'client, $ret, A>> {
+                    let url = self.base_url.join(&format!(concat!("api/v1/", $url), crate::routes::encode_segment(id)?))?;
+                    let url = Url::parse(&request.apply_to(url.as_str())?)?;
+                    Ok(Page::new(Request::new(http_method!($method), url), &self.auth, &self.config))
+                }
+            }
+        )?
+
+        async_paged_routes_with_id!{$($rest)*}
+    };
+
+    () => {}
+}
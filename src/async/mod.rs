@@ -1,5 +1,10 @@
 //! Async Mastodon Client
 //!
+//! With the `async` feature, requests are sent over plain TCP using
+//! `async-h1`. The `wasm` feature swaps this for the browser's `fetch` API,
+//! for use on `wasm32-unknown-unknown`; since the blocking client can't be
+//! built for that target, combine it with `default-features = false`.
+//!
 //! # Example
 //!
 //! ```rust,no_run
@@ -32,21 +37,40 @@ use crate::{
         activity::Activity,
         card::Card,
         context::Context,
-        instance::Instance,
+        instance::{Instance, PeersMatching, Rule, TranslationLanguages},
+        notification::Notification,
         poll::Poll,
         status::{Emoji, Status, Tag},
     },
     errors::{Error, Result},
-    requests::{DirectoryRequest, StatusesRequest},
+    instance_url::InstanceUrl,
+    json_backend,
+    requests::{
+        DirectoryRequest, HashtagRequest, NotificationsRequest, PageRequest, StatusesRequest,
+        TimelineRequest, ToQueryString,
+    },
+    routes,
+};
+use http_types::{Method, Request, Response, StatusCode};
+use serde::de::DeserializeSeed;
+use std::{
+    fmt::Debug,
+    future::{self, Future},
+    pin::Pin,
+    task,
 };
-use http_types::{Method, Request, Response};
-use std::fmt::Debug;
 use url::Url;
 
+#[cfg(not(feature = "wasm"))]
+pub use async_native_tls::TlsConnector;
 pub use auth::Authenticate;
 use auth::{OAuth, Unauthenticated};
+use client::BodyLimit;
+pub use client::ClientConfig;
 pub use page::Page;
 
+#[macro_use]
+mod macros;
 mod auth;
 mod client;
 mod page;
@@ -54,22 +78,35 @@ mod page;
 /// Async unauthenticated client
 #[derive(Debug)]
 pub struct Client<A: Debug + Authenticate> {
-    base_url: Url,
+    base_url: InstanceUrl,
     auth: A,
+    config: ClientConfig,
 }
 impl Client<Unauthenticated> {
     pub fn new<S: AsRef<str>>(base_url: S) -> Result<Client<Unauthenticated>> {
-        let base_url = Url::parse(base_url.as_ref())?;
+        Client::with_config(base_url, ClientConfig::default())
+    }
+
+    /// Create a client using custom TLS and proxy settings
+    ///
+    /// `base_url` may include a path if the instance is hosted under one,
+    /// e.g. `https://example.com/masto`; routes are appended to it.
+    pub fn with_config<S: AsRef<str>>(
+        base_url: S,
+        config: ClientConfig,
+    ) -> Result<Client<Unauthenticated>> {
+        let base_url = InstanceUrl::parse(base_url.as_ref())?;
         Ok(Client {
             base_url,
             auth: Unauthenticated,
+            config,
         })
     }
 }
 impl<A: Debug + Authenticate> Client<A> {
     async fn send(&self, mut req: Request) -> Result<Response> {
         self.auth.authenticate(&mut req).await?;
-        Ok(client::fetch(req).await?)
+        Ok(self.config.fetch(req).await?)
     }
 
     /// GET /api/v1/timelines/public
@@ -79,10 +116,14 @@ impl<A: Debug + Authenticate> Client<A> {
     ) -> Result<Page<'client, Status, A>> {
         let mut url = self.base_url.join("api/v1/timelines/public")?;
         if let Some(opts) = opts.into() {
-            let qs = opts.to_querystring()?;
-            url.set_query(Some(&qs[..]));
+            let qs = ToQueryString::to_querystring(&opts)?;
+            url.set_query(Some(qs.trim_start_matches('?')));
         };
-        Ok(Page::new(Request::new(Method::Get, url), &self.auth))
+        Ok(Page::new(
+            Request::new(Method::Get, url),
+            &self.auth,
+            &self.config,
+        ))
     }
 
     /// GET /api/v1/timelines/tag/:tag
@@ -93,66 +134,112 @@ impl<A: Debug + Authenticate> Client<A> {
     ) -> Result<Page<'client, Status, A>> {
         let mut url = self
             .base_url
-            .join(&format!("api/v1/timelines/tag/{}", tag))?;
+            .join(&format!("api/v1/timelines/tag/{}", routes::encode_tag(tag)))?;
         if let Some(opts) = opts.into() {
-            let qs = opts.to_querystring()?;
-            url.set_query(Some(&qs[..]));
+            let qs = ToQueryString::to_querystring(&opts)?;
+            url.set_query(Some(qs.trim_start_matches('?')));
         }
-        Ok(Page::new(Request::new(Method::Get, url), &self.auth))
+        Ok(Page::new(
+            Request::new(Method::Get, url),
+            &self.auth,
+            &self.config,
+        ))
     }
 
+    /// GET /api/v1/timelines/public, filtered by `options` and bounded by
+    /// `request`
+    ///
+    /// Same as `MastodonClient::get_public_timeline_with_options`
+    pub async fn public_timeline_with_options<'client>(
+        &'client self,
+        options: &TimelineRequest,
+        request: &PageRequest,
+    ) -> Result<Page<'client, Status, A>> {
+        self.paged(&routes::public_timeline(options)?, request)
+    }
+
+    /// GET /api/v1/timelines/tag/:tag, filtered by `options` and bounded by
+    /// `request`
+    ///
+    /// Same as `MastodonClient::get_hashtag_timeline_with_options`
+    pub async fn hashtag_timeline_with_options<'client>(
+        &'client self,
+        tag: &str,
+        options: &HashtagRequest,
+        request: &PageRequest,
+    ) -> Result<Page<'client, Status, A>> {
+        self.paged(&routes::hashtag_timeline(tag, options)?, request)
+    }
+
+    /// GET /api/v1/notifications, filtered by `options` and bounded by
+    /// `request`
+    ///
+    /// Same as `MastodonClient::notifications_with_options`
+    pub async fn notifications_with_options<'client>(
+        &'client self,
+        options: &NotificationsRequest<'_>,
+        request: &PageRequest,
+    ) -> Result<Page<'client, Notification, A>> {
+        self.paged(&routes::notifications(options)?, request)
+    }
+
+    // The pages of `path`, which may have a query string, bounded by
+    // `request`
+    fn paged<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        request: &PageRequest,
+    ) -> Result<Page<'_, T, A>> {
+        let url = Url::parse(&request.apply_to(self.base_url.join(path)?.as_str())?)?;
+        Ok(Page::new(
+            Request::new(Method::Get, url),
+            &self.auth,
+            &self.config,
+        ))
+    }
+
+    shared_routes!(async_route);
+    shared_routes_id!(async_route_id);
+    shared_paged_routes_with_id!(async_paged_routes_with_id);
+
     /// GET /api/v1/statuses/:id
+    ///
+    /// Same as `get_status`
     pub async fn status(&self, id: &str) -> Result<Status> {
-        let url = self.base_url.join(&format!("api/v1/statuses/{}", id))?;
-        let response = self.send(Request::new(Method::Get, url)).await?;
-        Ok(deserialize(response).await?)
+        self.get_status(id).await
     }
 
     /// GET /api/v1/statuses/:id/context
+    ///
+    /// Same as `get_context`
     pub async fn context(&self, id: &str) -> Result<Context> {
-        let url = self
-            .base_url
-            .join(&format!("api/v1/statuses/{}/context", id))?;
-        let response = self.send(Request::new(Method::Get, url)).await?;
-        Ok(deserialize(response).await?)
+        self.get_context(id).await
     }
 
     /// GET /api/v1/statuses/:id/card
+    ///
+    /// This route was removed in Mastodon 3.0. If the server doesn't know it,
+    /// the card embedded in the status is returned instead.
     pub async fn card(&self, id: &str) -> Result<Card> {
-        let url = self
-            .base_url
-            .join(&format!("api/v1/statuses/{}/card", id))?;
+        let url = self.base_url.join(&format!(
+            "api/v1/statuses/{}/card",
+            routes::encode_segment(id)?
+        ))?;
         let response = self.send(Request::new(Method::Get, url)).await?;
-        Ok(deserialize(response).await?)
-    }
-
-    /// GET /api/v1/statuses/:id/reblogged_by
-    pub async fn reblogged_by<'client>(
-        &'client self,
-        id: &str,
-    ) -> Result<Page<'client, Account, A>> {
-        let url = self
-            .base_url
-            .join(&format!("api/v1/statuses/{}/reblogged_by", id))?;
-        Ok(Page::new(Request::new(Method::Get, url), &self.auth))
-    }
-
-    /// GET /api/v1/statuses/:id/favourited_by
-    pub async fn favourited_by<'client>(
-        &'client self,
-        id: &str,
-    ) -> Result<Page<'client, Account, A>> {
-        let url = self
-            .base_url
-            .join(&format!("api/v1/statuses/{}/favourited_by", id))?;
-        Ok(Page::new(Request::new(Method::Get, url), &self.auth))
+        if response.status() != StatusCode::NotFound {
+            return Ok(deserialize(response).await?);
+        }
+        self.get_status(id)
+            .await?
+            .card
+            .ok_or_else(|| Error::Client(StatusCode::NotFound.into()))
     }
 
     /// GET /api/v1/accounts/:id
+    ///
+    /// Same as `get_account`
     pub async fn account(&self, id: &str) -> Result<Account> {
-        let url = self.base_url.join(&format!("api/v1/accounts/{}", id))?;
-        let response = self.send(Request::new(Method::Get, url)).await?;
-        Ok(deserialize(response).await?)
+        self.get_account(id).await
     }
 
     /// GET /api/v1/accounts/:id/statuses
@@ -161,26 +248,68 @@ impl<A: Debug + Authenticate> Client<A> {
         id: &str,
         request: I,
     ) -> Result<Page<'client, Status, A>> {
-        let mut url = self
-            .base_url
-            .join(&format!("api/v1/accounts/{}/statuses", id))?;
+        let mut url = self.base_url.join(&format!(
+            "api/v1/accounts/{}/statuses",
+            routes::encode_segment(id)?
+        ))?;
         if let Some(request) = request.into() {
-            let qs = request.to_querystring()?;
-            url.set_query(Some(&qs[..]));
+            let qs = ToQueryString::to_querystring(&request)?;
+            url.set_query(Some(qs.trim_start_matches('?')));
         }
-        Ok(Page::new(Request::new(Method::Get, url), &self.auth))
+        Ok(Page::new(
+            Request::new(Method::Get, url),
+            &self.auth,
+            &self.config,
+        ))
     }
 
-    /// GET /api/v1/polls/:id
-    pub async fn poll(&self, id: &str) -> Result<Poll> {
-        let url = self.base_url.join(&format!("api/v1/polls/{}", id))?;
-        let response = self.send(Request::new(Method::Get, url)).await?;
-        Ok(deserialize(response).await?)
+    /// GET /api/v1/accounts/:id/statuses, following the pages until there
+    /// are no more or `limit` statuses have been fetched
+    pub async fn account_statuses_all(
+        &self,
+        id: &str,
+        request: StatusesRequest<'_>,
+        limit: Option<usize>,
+    ) -> Result<Vec<Status>> {
+        let limit = limit.unwrap_or(usize::MAX);
+        let mut statuses = Vec::new();
+        let mut pages = self.account_statuses(id, request).await?;
+        while statuses.len() < limit {
+            match pages.next_page().await? {
+                Some(page) if !page.is_empty() => statuses.extend(page),
+                _ => break,
+            }
+        }
+        statuses.truncate(limit);
+        Ok(statuses)
+    }
+
+    /// Same as `account_statuses_all`, for each account in `ids`, fetching
+    /// the statuses of at most `concurrency` accounts at a time
+    ///
+    /// The statuses are returned in the order of `ids`.
+    pub async fn accounts_statuses_all(
+        &self,
+        ids: &[&str],
+        request: StatusesRequest<'_>,
+        limit: Option<usize>,
+        concurrency: usize,
+    ) -> Result<Vec<Vec<Status>>> {
+        let fetches = ids
+            .iter()
+            .map(|id| self.account_statuses_all(id, request.clone(), limit))
+            .collect();
+        join_bounded(fetches, concurrency)
+            .await
+            .into_iter()
+            .collect()
     }
 
-    /// GET /api/v1/instance
-    pub async fn instance(&self) -> Result<Instance> {
-        let url = self.base_url.join("api/v1/instance")?;
+    /// GET /api/v1/polls/:id
+    pub async fn poll(&self, id: &str) -> Result<Poll> {
+        let url = self
+            .base_url
+            .join(&format!("api/v1/polls/{}", routes::encode_segment(id)?))?;
         let response = self.send(Request::new(Method::Get, url)).await?;
         Ok(deserialize(response).await?)
     }
@@ -192,6 +321,28 @@ impl<A: Debug + Authenticate> Client<A> {
         Ok(deserialize(response).await?)
     }
 
+    /// GET /api/v1/instance/peers, only keeping the domains that contain
+    /// `text`, ignoring case
+    ///
+    /// The other domains are dropped as the response is parsed, instead of
+    /// collecting all of them first.
+    pub async fn peers_matching(&self, text: &str) -> Result<Vec<String>> {
+        let url = self.base_url.join("api/v1/instance/peers")?;
+        let mut response = self.send(Request::new(Method::Get, url)).await?;
+        let status = response.status();
+        if status.is_client_error() {
+            return Err(Error::Client(status.into()));
+        } else if status.is_server_error() {
+            return Err(Error::Server(status.into()));
+        }
+        let bytes = match response.ext().get::<BodyLimit>() {
+            Some(&BodyLimit(limit)) => read_limited(&mut response, limit).await?,
+            None => response.body_bytes().await?,
+        };
+        let mut deserializer = serde_json::Deserializer::from_slice(&bytes);
+        Ok(PeersMatching(text).deserialize(&mut deserializer)?)
+    }
+
     /// GET /api/v1/instance/activity
     pub async fn activity(&self) -> Result<Option<Vec<Activity>>> {
         let url = self.base_url.join("api/v1/instance/activity")?;
@@ -207,45 +358,110 @@ impl<A: Debug + Authenticate> Client<A> {
     }
 
     /// GET /api/v1/directory
-    pub async fn directory<'a, I: Into<Option<DirectoryRequest<'a>>>>(
+    pub async fn directory<I: Into<Option<DirectoryRequest>>>(
         &self,
         opts: I,
     ) -> Result<Vec<Account>> {
-        let mut url = self.base_url.join("api/v1/directory")?;
-        if let Some(opts) = opts.into() {
-            let qs = opts.to_querystring()?;
-            url.set_query(Some(&qs[..]));
-        }
+        let url = self.base_url.join(&routes::directory(opts.into())?)?;
         let response = self.send(Request::new(Method::Get, url)).await?;
         Ok(deserialize(response).await?)
     }
 
     /// GET /api/v1/trends
     pub async fn trends<I: Into<Option<usize>>>(&self, limit: I) -> Result<Vec<Tag>> {
-        let mut url = self.base_url.join("api/v1/trends")?;
-        if let Some(limit) = limit.into() {
-            url.set_query(Some(&format!("?limit={}", limit)));
-        }
+        let url = self.base_url.join(&routes::trends(limit.into()))?;
         let response = self.send(Request::new(Method::Get, url)).await?;
         Ok(deserialize(response).await?)
     }
 }
 
+// Runs `futures`, at most `concurrency` of them at a time, returning their
+// outputs in order
+async fn join_bounded<F: Future>(futures: Vec<F>, concurrency: usize) -> Vec<F::Output> {
+    let concurrency = concurrency.max(1);
+    let mut futures: Vec<Pin<Box<F>>> = futures.into_iter().map(Box::pin).collect();
+    let mut outputs: Vec<Option<F::Output>> = futures.iter().map(|_| None).collect();
+    let (mut started, mut running) = (0, 0);
+    future::poll_fn(|cx| loop {
+        while started < futures.len() && running < concurrency {
+            started += 1;
+            running += 1;
+        }
+        let mut finished = false;
+        for (future, output) in futures[..started].iter_mut().zip(&mut outputs) {
+            if output.is_some() {
+                continue;
+            }
+            if let task::Poll::Ready(value) = future.as_mut().poll(cx) {
+                *output = Some(value);
+                running -= 1;
+                finished = true;
+            }
+        }
+        if running == 0 && started == futures.len() {
+            return task::Poll::Ready(());
+        }
+        // otherwise start the next ones, if there's room now
+        if !finished {
+            return task::Poll::Pending;
+        }
+    })
+    .await;
+    outputs.into_iter().map(|output| output.unwrap()).collect()
+}
+
+// Reads the body of `response`, unless it's larger than `limit`
+async fn read_limited(response: &mut Response, limit: usize) -> Result<Vec<u8>> {
+    let too_large = |response: &Response| Error::BodyTooLarge {
+        url: response
+            .ext()
+            .get::<Url>()
+            .map(|url| url.to_string())
+            .unwrap_or_default(),
+        limit,
+    };
+    if matches!(response.len(), Some(len) if len > limit) {
+        return Err(too_large(response));
+    }
+    // the browser has already read the whole body, so its length is known
+    #[cfg(feature = "wasm")]
+    let body = response.body_bytes().await?;
+    #[cfg(not(feature = "wasm"))]
+    let body = {
+        use smol::io::AsyncReadExt;
+        let mut body = Vec::new();
+        response
+            .take_body()
+            .take(limit as u64 + 1)
+            .read_to_end(&mut body)
+            .await?;
+        body
+    };
+    if body.len() > limit {
+        return Err(too_large(response));
+    }
+    Ok(body)
+}
+
 async fn deserialize<T: serde::de::DeserializeOwned>(mut response: Response) -> Result<T> {
     let status = response.status();
     if status.is_client_error() {
-        // TODO
-        // return Err(Error::Client(status));
-        return Err(Error::Other(String::from("4xx status code")));
+        return Err(Error::Client(status.into()));
     } else if status.is_server_error() {
-        // TODO
-        // return Err(Error::Server(status)) // TODO
-        return Err(Error::Other(String::from("5xx status code")));
+        return Err(Error::Server(status.into()));
     } else if status.is_redirection() || status.is_informational() {
         return Err(Error::Other(String::from("3xx or 1xx status code")));
     }
-    let bytes = response.body_bytes().await?;
-    Ok(match serde_json::from_slice::<T>(&bytes) {
+    let url = response
+        .ext()
+        .get::<Url>()
+        .map(|url| url.to_string())
+        .unwrap_or_default();
+    let bytes = match response.ext().get::<BodyLimit>() {
+        Some(&BodyLimit(limit)) => read_limited(&mut response, limit).await?,
+        None => response.body_bytes().await?,
+    };
+    Ok(match json_backend::from_slice::<T>(&bytes) {
         Ok(t) => {
             log::debug!("{}", String::from_utf8_lossy(&bytes));
             t
@@ -255,9 +471,144 @@ async fn deserialize<T: serde::de::DeserializeOwned>(mut response: Response) ->
             let err = if let Ok(error) = serde_json::from_slice(&bytes) {
                 Error::Api(error)
             } else {
-                e.into()
+                Error::deserialize(url, status, &String::from_utf8_lossy(&bytes), e)
             };
             return Err(err);
         },
     })
 }
+
+#[cfg(all(test, not(feature = "wasm")))]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn test_shared_routes() {
+        let client = Client::new(mockito::server_url()).unwrap();
+        let mock = mockito::mock("GET", "/api/v1/statuses/42/context")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"ancestors": [], "descendants": []}"#)
+            .create();
+        let context = smol::block_on(client.get_context("42")).unwrap();
+        assert!(context.ancestors.is_empty());
+        mock.assert();
+
+        let mock = mockito::mock("GET", "/api/v1/statuses/42/reblogged_by")
+            .match_query(mockito::Matcher::UrlEncoded("limit".into(), "2".into()))
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        let request = PageRequest::new().limit(2);
+        let mut page = smol::block_on(client.reblogged_by_with("42", &request)).unwrap();
+        let accounts = smol::block_on(page.next_page()).unwrap();
+        assert_eq!(accounts.map(|accounts| accounts.len()), Some(0));
+        mock.assert();
+    }
+
+    #[test]
+    fn test_max_body_size() {
+        let config = ClientConfig::new().max_body_size(10);
+        let client = Client::with_config(mockito::server_url(), config).unwrap();
+        let mock = mockito::mock("GET", "/api/v1/statuses/42/context")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"ancestors": [], "descendants": []}"#)
+            .create();
+        let err = smol::block_on(client.get_context("42")).unwrap_err();
+        assert!(matches!(err, Error::BodyTooLarge { limit: 10, .. }));
+        mock.assert();
+    }
+
+    #[test]
+    fn test_account_statuses_all() {
+        let client = Client::new(mockito::server_url()).unwrap();
+        let status = test_fixtures::STATUS;
+        let first = mockito::mock("GET", "/api/v1/accounts/1/statuses")
+            .match_query(mockito::Matcher::UrlEncoded("limit".into(), "2".into()))
+            .with_header("content-type", "application/json")
+            .with_header(
+                "link",
+                &format!(
+                    r#"<{}/api/v1/accounts/1/statuses?max_id=108>; rel="next""#,
+                    mockito::server_url()
+                ),
+            )
+            .with_body(format!("[{}, {}]", status, status))
+            .create();
+        let second = mockito::mock("GET", "/api/v1/accounts/1/statuses?max_id=108")
+            .with_header("content-type", "application/json")
+            .with_body(format!("[{}, {}]", status, status))
+            .create();
+        let empty = mockito::mock("GET", "/api/v1/accounts/2/statuses")
+            .match_query(mockito::Matcher::UrlEncoded("limit".into(), "2".into()))
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+
+        let request = StatusesRequest::new().limit(2);
+        let statuses =
+            smol::block_on(client.accounts_statuses_all(&["1", "2"], request, Some(3), 2)).unwrap();
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses[0].len(), 3);
+        assert!(statuses[1].is_empty());
+        first.assert();
+        second.assert();
+        empty.assert();
+    }
+
+    #[test]
+    fn test_redirects() {
+        let client = Client::new(mockito::server_url()).unwrap();
+        let redirect = mockito::mock("GET", "/api/v1/instance/peers")
+            .with_status(301)
+            .with_header("location", "/api/v1/peers")
+            .create();
+        let peers = mockito::mock("GET", "/api/v1/peers")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        assert!(smol::block_on(client.peers()).unwrap().is_empty());
+        redirect.assert();
+        peers.assert();
+
+        let location = mockito::server_url().replace("127.0.0.1", "localhost") + "/elsewhere";
+        let redirect = mockito::mock("GET", "/api/v1/custom_emojis")
+            .with_status(302)
+            .with_header("location", &location)
+            .create();
+        let result = smol::block_on(client.custom_emojis());
+        assert!(matches!(result, Err(Error::CrossOriginRedirect { .. })));
+        redirect.assert();
+    }
+
+    #[test]
+    fn test_base_url_with_path() {
+        let client = Client::new(format!("{}/masto/", mockito::server_url())).unwrap();
+        let mock = mockito::mock("GET", "/masto/api/v1/custom_emojis")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        let emojis = smol::block_on(client.custom_emojis()).unwrap();
+        assert!(emojis.is_empty());
+        mock.assert();
+    }
+
+    #[test]
+    fn test_public_timeline_with_options() {
+        let client = Client::new(mockito::server_url()).unwrap();
+        let mock = mockito::mock(
+            "GET",
+            "/api/v1/timelines/public?remote=1&only_media=1&limit=5",
+        )
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .create();
+        let options = TimelineRequest::new().remote().only_media();
+        let mut pages = smol::block_on(
+            client.public_timeline_with_options(&options, &PageRequest::new().limit(5)),
+        )
+        .unwrap();
+        assert_eq!(smol::block_on(pages.next_page()).unwrap(), Some(vec![]));
+        mock.assert();
+    }
+}
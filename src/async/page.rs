@@ -1,15 +1,11 @@
-use super::{client, deserialize, Authenticate};
+use super::{deserialize, Authenticate, ClientConfig};
 use crate::{
     entities::{account::Account, card::Card, context::Context, status::Status},
     errors::{Error, Result},
+    link_header,
 };
 use http_types::{Method, Request, Response};
-use hyper_old_types::header::{parsing, Link, RelationType};
-use smol::{prelude::*, Async};
-use std::{
-    fmt::Debug,
-    net::{TcpStream, ToSocketAddrs},
-};
+use std::fmt::Debug;
 use url::Url;
 
 // link header name
@@ -20,16 +16,22 @@ pub struct Page<'client, T, A: Authenticate + Debug + 'client> {
     next: Option<Request>,
     prev: Option<Request>,
     auth: &'client A,
+    config: &'client ClientConfig,
     _marker: std::marker::PhantomData<T>,
 }
 impl<'client, T: serde::de::DeserializeOwned, A: Authenticate + Debug + 'client>
     Page<'client, T, A>
 {
-    pub fn new(next: Request, auth: &'client A) -> Page<'client, T, A> {
+    pub fn new(
+        next: Request,
+        auth: &'client A,
+        config: &'client ClientConfig,
+    ) -> Page<'client, T, A> {
         Page {
             next: Some(next),
             prev: None,
             auth,
+            config,
             _marker: std::marker::PhantomData,
         }
     }
@@ -55,7 +57,7 @@ impl<'client, T: serde::de::DeserializeOwned, A: Authenticate + Debug + 'client>
     async fn send(&mut self, mut req: Request) -> Result<Option<Vec<T>>> {
         self.auth.authenticate(&mut req).await?;
         log::trace!("Request: {:?}", req);
-        let response = client::fetch(req).await?;
+        let response = self.config.fetch(req).await?;
         log::trace!("Response: {:?}", response);
         self.fill_links_from_resp(&response)?;
         let items = deserialize(response).await?;
@@ -71,25 +73,6 @@ impl<'client, T: serde::de::DeserializeOwned, A: Authenticate + Debug + 'client>
 }
 
 fn get_links(response: &Response) -> Result<(Option<Url>, Option<Url>)> {
-    let mut prev = None;
-    let mut next = None;
-
-    if let Some(link_header) = response.header(LINK) {
-        let link_header = link_header.as_str();
-        let link_header = link_header.as_bytes();
-        let link_header: Link = parsing::from_raw_str(&link_header)?;
-        for value in link_header.values() {
-            if let Some(relations) = value.rel() {
-                if relations.contains(&RelationType::Next) {
-                    next = Some(Url::parse(value.link())?);
-                }
-
-                if relations.contains(&RelationType::Prev) {
-                    prev = Some(Url::parse(value.link())?);
-                }
-            }
-        }
-    }
-
-    Ok((prev, next))
+    let headers = response.header(LINK).into_iter().flatten();
+    link_header::from_headers(headers.map(|value| value.as_str()))
 }
@@ -0,0 +1,437 @@
+//! Keeping an instance's domain blocks in line with a shared blocklist
+//!
+//! Blocklists are CSV files, as exported by Mastodon's admin interface, with
+//! a header naming the columns:
+//!
+//! ```text
+//! #domain,#severity,#reject_media,#reject_reports,#public_comment,#obfuscate
+//! spam.example,suspend,false,false,spam,false
+//! ```
+//!
+//! Lists without a header have their columns in that order, and can be as
+//! short as one domain per line, which are suspended.
+//!
+//! Applying a list only adds and updates blocks: the instance's blocks that
+//! the list doesn't mention are reported in the diff, so that its moderators
+//! can decide what to do about them.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # extern crate elefren;
+//! # use elefren::prelude::*;
+//! use elefren::blocklist::Blocklist;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let data = Data {
+//! #   base: "".into(),
+//! #   client_id: "".into(),
+//! #   client_secret: "".into(),
+//! #   redirect: "".into(),
+//! #   token: "".into(),
+//! #   ..Default::default()
+//! # };
+//! let client = Mastodon::from(data);
+//! let blocklist = Blocklist::fetch(&client, "https://example.org/blocklist.csv")?;
+//! // check what would change first
+//! print!("{}", blocklist.apply(&client, true)?);
+//! blocklist.apply(&client, false)?;
+//! # Ok(())
+//! # }
+//! ```
+use std::fmt;
+
+use crate::{
+    entities::admin::{DomainBlock, DomainBlockSeverity},
+    errors::{Error, Result},
+    http_send::HttpSend,
+    mastodon_client::MastodonClient,
+    requests::{DomainBlockRequest, PageRequest},
+    validate_domain, Mastodon,
+};
+
+/// A domain to block, as listed in a blocklist
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlocklistEntry {
+    /// The domain, in lower case
+    pub domain: String,
+    /// What happens to the domain's accounts and statuses
+    pub severity: DomainBlockSeverity,
+    /// Whether media from the domain is rejected
+    pub reject_media: bool,
+    /// Whether reports from the domain are rejected
+    pub reject_reports: bool,
+    /// A comment shown on the instance's about page. An entry without one
+    /// leaves the comment of an existing block as it is.
+    pub public_comment: Option<String>,
+    /// Whether the domain is partly hidden on the about page
+    pub obfuscate: bool,
+}
+
+impl BlocklistEntry {
+    // The request blocking the domain as listed
+    fn request(&self) -> DomainBlockRequest {
+        let request = DomainBlockRequest::new(&self.domain)
+            .severity(self.severity)
+            .reject_media(self.reject_media)
+            .reject_reports(self.reject_reports)
+            .obfuscate(self.obfuscate);
+        match self.public_comment {
+            Some(ref comment) => request.public_comment(comment),
+            None => request,
+        }
+    }
+
+    // Whether `block` already blocks the domain as listed
+    fn is_applied(&self, block: &DomainBlock) -> bool {
+        block.severity == self.severity
+            && block.reject_media == self.reject_media
+            && block.reject_reports == self.reject_reports
+            && block.obfuscate == self.obfuscate
+            && (self.public_comment.is_none() || block.public_comment == self.public_comment)
+    }
+}
+
+/// A list of domains to block, e.g. one shared between instances
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Blocklist {
+    entries: Vec<BlocklistEntry>,
+}
+
+impl Blocklist {
+    /// Parses a blocklist in CSV format, see the [module's
+    /// documentation](index.html)
+    ///
+    /// Blank lines and lines starting with `#` after the header are
+    /// skipped, as are obfuscated domains such as `sp*m.example`, which
+    /// can't be blocked. When a domain is listed twice, the last entry wins.
+    pub fn parse(csv: &str) -> Result<Blocklist> {
+        let mut columns: Vec<String> = COLUMNS.iter().map(|column| column.to_string()).collect();
+        let mut entries: Vec<BlocklistEntry> = Vec::new();
+        for (index, line) in csv.lines().enumerate() {
+            let number = index + 1;
+            let fields = csv_fields(line);
+            let first = fields[0].trim();
+            if index == 0 && first.trim_start_matches('#').eq_ignore_ascii_case("domain") {
+                columns = fields
+                    .iter()
+                    .map(|name| name.trim().trim_start_matches('#').to_lowercase())
+                    .collect();
+                continue;
+            }
+            if first.is_empty() || first.starts_with('#') {
+                continue;
+            }
+            let field = |name: &str| {
+                columns
+                    .iter()
+                    .position(|column| column == name)
+                    .and_then(|i| fields.get(i))
+                    .map(|value| value.trim())
+                    .unwrap_or("")
+            };
+            let domain = field("domain").trim_end_matches('.').to_lowercase();
+            if domain.contains('*') {
+                continue;
+            }
+            validate_domain(&domain).map_err(|e| line_error(number, e))?;
+            let public_comment = field("public_comment");
+            let entry = BlocklistEntry {
+                severity: severity(field("severity")).map_err(|e| line_error(number, e))?,
+                reject_media: flag(field("reject_media")).map_err(|e| line_error(number, e))?,
+                reject_reports: flag(field("reject_reports")).map_err(|e| line_error(number, e))?,
+                public_comment: Some(public_comment.to_string()).filter(|c| !c.is_empty()),
+                obfuscate: flag(field("obfuscate")).map_err(|e| line_error(number, e))?,
+                domain,
+            };
+            match entries
+                .iter_mut()
+                .find(|listed| listed.domain == entry.domain)
+            {
+                Some(listed) => *listed = entry,
+                None => entries.push(entry),
+            }
+        }
+        Ok(Blocklist { entries })
+    }
+
+    /// Downloads the blocklist at `url` with `client`, and parses it
+    pub fn fetch<H: HttpSend>(client: &Mastodon<H>, url: &str) -> Result<Blocklist> {
+        let mut csv = Vec::new();
+        client.download(url, &mut csv)?;
+        let csv = String::from_utf8(csv)
+            .map_err(|_| Error::Other(format!("the blocklist at {} isn't UTF-8", url)))?;
+        Blocklist::parse(&csv)
+    }
+
+    /// The domains of the list, in the order they are listed
+    pub fn entries(&self) -> &[BlocklistEntry] {
+        &self.entries
+    }
+
+    /// What applying the list to an instance with the blocks `current`
+    /// would change
+    pub fn diff(&self, current: &[DomainBlock]) -> BlocklistDiff {
+        let mut diff = BlocklistDiff::default();
+        for entry in &self.entries {
+            match current
+                .iter()
+                .find(|block| block.domain.eq_ignore_ascii_case(&entry.domain))
+            {
+                Some(block) if entry.is_applied(block) => {},
+                Some(block) => diff.changed.push((block.clone(), entry.clone())),
+                None => diff.added.push(entry.clone()),
+            }
+        }
+        diff.unlisted = current
+            .iter()
+            .filter(|block| {
+                !self
+                    .entries
+                    .iter()
+                    .any(|entry| block.domain.eq_ignore_ascii_case(&entry.domain))
+            })
+            .cloned()
+            .collect();
+        diff
+    }
+
+    /// Adds and updates the instance's domain blocks to match the list,
+    /// unless `dry_run` is set, and returns what was, or would have been,
+    /// changed
+    ///
+    /// This needs the `admin:read:domain_blocks` and
+    /// `admin:write:domain_blocks` scopes. It stops at the first request
+    /// that fails, keeping the changes made until then.
+    pub fn apply<H: HttpSend, C: MastodonClient<H>>(
+        &self,
+        client: &C,
+        dry_run: bool,
+    ) -> Result<BlocklistDiff> {
+        let request = PageRequest::new().limit(200);
+        let current = client
+            .admin_domain_blocks_with(&request)?
+            .try_items_iter()
+            .collect::<Result<Vec<_>>>()?;
+        let diff = self.diff(&current);
+        if !dry_run {
+            for entry in &diff.added {
+                client.admin_block_domain(&entry.request())?;
+            }
+            for (block, entry) in &diff.changed {
+                client.admin_update_domain_block(&block.id, &entry.request())?;
+            }
+        }
+        Ok(diff)
+    }
+}
+
+/// The difference between a blocklist and an instance's domain blocks
+///
+/// It's displayed one domain per line, marked with `+` when it's added, `~`
+/// when its block is changed, and `?` when it's blocked but not listed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlocklistDiff {
+    /// The listed domains that aren't blocked yet
+    pub added: Vec<BlocklistEntry>,
+    /// The listed domains that are blocked differently, with their current
+    /// block
+    pub changed: Vec<(DomainBlock, BlocklistEntry)>,
+    /// The blocked domains that aren't listed, which are left alone
+    pub unlisted: Vec<DomainBlock>,
+}
+
+impl BlocklistDiff {
+    /// Whether applying the list changes nothing
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for BlocklistDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for entry in &self.added {
+            writeln!(f, "+ {} ({})", entry.domain, entry.severity)?;
+        }
+        for (block, entry) in &self.changed {
+            writeln!(
+                f,
+                "~ {} ({} -> {})",
+                entry.domain, block.severity, entry.severity
+            )?;
+        }
+        for block in &self.unlisted {
+            writeln!(f, "? {} ({})", block.domain, block.severity)?;
+        }
+        Ok(())
+    }
+}
+
+// The columns of a list without a header, as exported by Mastodon
+const COLUMNS: [&str; 6] = [
+    "domain",
+    "severity",
+    "reject_media",
+    "reject_reports",
+    "public_comment",
+    "obfuscate",
+];
+
+// Splits a line of CSV into its fields, unquoting them
+fn csv_fields(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().expect("there's always a field");
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            },
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    fields
+}
+
+// The severity of an entry, suspending the domain if none is given
+fn severity(value: &str) -> Result<DomainBlockSeverity> {
+    match value.to_lowercase().as_str() {
+        "" | "suspend" => Ok(DomainBlockSeverity::Suspend),
+        // some lists use the name shown in the web interface
+        "silence" | "limit" => Ok(DomainBlockSeverity::Silence),
+        "noop" | "none" => Ok(DomainBlockSeverity::Noop),
+        _ => Err(Error::Other(format!("unknown severity '{}'", value))),
+    }
+}
+
+fn flag(value: &str) -> Result<bool> {
+    match value.to_lowercase().as_str() {
+        "" | "false" | "0" => Ok(false),
+        "true" | "1" => Ok(true),
+        _ => Err(Error::Other(format!(
+            "expected true or false, got '{}'",
+            value
+        ))),
+    }
+}
+
+fn line_error(number: usize, error: Error) -> Error {
+    Error::Other(format!("line {} of the blocklist: {}", number, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Data;
+    use mockito::Matcher;
+
+    fn block(id: &str, domain: &str, severity: &str) -> String {
+        format!(
+            r#"{{"id": "{}", "domain": "{}", "created_at": "2022-11-16T08:15:34.238Z",
+                "severity": "{}", "reject_media": false, "reject_reports": false,
+                "private_comment": null, "public_comment": null, "obfuscate": false}}"#,
+            id, domain, severity
+        )
+    }
+
+    #[test]
+    fn test_parse() {
+        let blocklist = Blocklist::parse(
+            "#domain,#severity,#public_comment,#reject_media\n\
+             Spam.Example.,silence,\"spam, lots of it\",true\n\
+             \n\
+             # a comment\n\
+             sp*m.example,suspend,,\n\
+             other.example,,,\n\
+             spam.example,limit,\"\"\"quoted\"\"\",false\n",
+        )
+        .unwrap();
+        assert_eq!(
+            blocklist.entries(),
+            &[
+                BlocklistEntry {
+                    domain: "spam.example".to_string(),
+                    severity: DomainBlockSeverity::Silence,
+                    reject_media: false,
+                    reject_reports: false,
+                    public_comment: Some("\"quoted\"".to_string()),
+                    obfuscate: false,
+                },
+                BlocklistEntry {
+                    domain: "other.example".to_string(),
+                    severity: DomainBlockSeverity::Suspend,
+                    reject_media: false,
+                    reject_reports: false,
+                    public_comment: None,
+                    obfuscate: false,
+                },
+            ]
+        );
+
+        let blocklist = Blocklist::parse("a.example\nb.example,noop,true,false,,true\n").unwrap();
+        assert_eq!(
+            blocklist.entries()[0].severity,
+            DomainBlockSeverity::Suspend
+        );
+        assert!(blocklist.entries()[1].reject_media && blocklist.entries()[1].obfuscate);
+
+        match Blocklist::parse("a.example\nb.example,ban\n") {
+            Err(Error::Other(message)) => assert!(message.starts_with("line 2 ")),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert!(Blocklist::parse("https://a.example/\n").is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let client = Mastodon::from(Data {
+            base: mockito::server_url().into(),
+            token: "token".into(),
+            ..Default::default()
+        });
+        let current = mockito::mock("GET", "/api/v1/admin/domain_blocks")
+            .match_query(Matcher::UrlEncoded("limit".into(), "200".into()))
+            .with_header("content-type", "application/json")
+            .with_body(format!(
+                "[{}, {}, {}]",
+                block("1", "same.example", "suspend"),
+                block("2", "changed.example", "silence"),
+                block("3", "unlisted.example", "noop")
+            ))
+            .expect(2)
+            .create();
+        let blocklist =
+            Blocklist::parse("same.example\nchanged.example\nnew.example,silence\n").unwrap();
+
+        let diff = blocklist.apply(&client, true).unwrap();
+        assert_eq!(
+            diff.to_string(),
+            "+ new.example (silence)\n\
+             ~ changed.example (silence -> suspend)\n\
+             ? unlisted.example (noop)\n"
+        );
+        assert!(!diff.is_empty());
+
+        let added = mockito::mock("POST", "/api/v1/admin/domain_blocks")
+            .match_body(Matcher::PartialJsonString(
+                r#"{"domain": "new.example", "severity": "silence"}"#.into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(block("4", "new.example", "silence"))
+            .create();
+        let changed = mockito::mock("PUT", "/api/v1/admin/domain_blocks/2")
+            .match_body(Matcher::PartialJsonString(
+                r#"{"severity": "suspend"}"#.into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(block("2", "changed.example", "suspend"))
+            .create();
+        assert_eq!(blocklist.apply(&client, false).unwrap(), diff);
+        current.assert();
+        added.assert();
+        changed.assert();
+    }
+}
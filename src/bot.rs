@@ -0,0 +1,602 @@
+use std::{
+    cmp::Ordering as CmpOrdering,
+    collections::{HashSet, VecDeque},
+    fmt,
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    entities::{
+        event::Event,
+        notification::{Notification, NotificationType},
+        status::Status,
+    },
+    errors::{Error, Result},
+    journal::{JournalEntry, JournalWriter},
+    mastodon_client::MastodonClient,
+};
+
+type Handler<'a, T> = Box<dyn FnMut(&T) + 'a>;
+
+/// An event loop over the user stream, dispatching events to registered
+/// handlers and reconnecting when the connection drops.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// # use std::error::Error;
+/// use elefren::bot::Bot;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let data = Data {
+/// #   base: "".into(),
+/// #   client_id: "".into(),
+/// #   client_secret: "".into(),
+/// #   redirect: "".into(),
+/// #   token: "".into(),
+/// #   ..Default::default()
+/// # };
+/// let client = Mastodon::from(data);
+/// let mut bot = Bot::new(&client);
+/// bot.on_mention(|status| {
+///     println!("{} mentioned me: {}", status.account.acct, status.content);
+/// })
+/// .on_error(|err| eprintln!("streaming error: {}", err));
+/// bot.run()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Bot<'a, C: MastodonClient> {
+    client: &'a C,
+    on_update: Vec<Handler<'a, Status>>,
+    on_status_update: Vec<Handler<'a, Status>>,
+    on_notification: Vec<Handler<'a, Notification>>,
+    on_mention: Vec<Handler<'a, Status>>,
+    on_follow_request: Vec<Handler<'a, Notification>>,
+    on_delete: Vec<Handler<'a, str>>,
+    on_filters_changed: Vec<Box<dyn FnMut() + 'a>>,
+    on_error: Vec<Handler<'a, Error>>,
+    reconnect_delay: Duration,
+    max_reconnect_delay: Duration,
+    max_reconnects: Option<usize>,
+    seen: Option<SeenWindow>,
+    backfill: bool,
+    last_status_id: Option<String>,
+    journal: Option<JournalWriter<Box<dyn Write + 'a>>>,
+    stop: StopHandle,
+}
+
+impl<'a, C: MastodonClient> fmt::Debug for Bot<'a, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Bot")
+            .field("reconnect_delay", &self.reconnect_delay)
+            .field("max_reconnect_delay", &self.max_reconnect_delay)
+            .field("max_reconnects", &self.max_reconnects)
+            .field("seen", &self.seen)
+            .field("backfill", &self.backfill)
+            .field("last_status_id", &self.last_status_id)
+            .field("journal", &self.journal.is_some())
+            .field("stop", &self.stop)
+            .finish()
+    }
+}
+
+impl<'a, C: MastodonClient> Bot<'a, C> {
+    /// Create a new bot that will listen on `client`'s user stream
+    pub fn new(client: &'a C) -> Bot<'a, C> {
+        Bot {
+            client,
+            on_update: Vec::new(),
+            on_status_update: Vec::new(),
+            on_notification: Vec::new(),
+            on_mention: Vec::new(),
+            on_follow_request: Vec::new(),
+            on_delete: Vec::new(),
+            on_filters_changed: Vec::new(),
+            on_error: Vec::new(),
+            reconnect_delay: Duration::from_secs(1),
+            max_reconnect_delay: Duration::from_secs(60),
+            max_reconnects: None,
+            seen: None,
+            backfill: false,
+            last_status_id: None,
+            journal: None,
+            stop: StopHandle::default(),
+        }
+    }
+
+    /// Called for every status that appears in the home timeline
+    pub fn on_update<F: FnMut(&Status) + 'a>(&mut self, handler: F) -> &mut Self {
+        self.on_update.push(Box::new(handler));
+        self
+    }
+
+    /// Called with the new version of every edited status in the home
+    /// timeline
+    pub fn on_status_update<F: FnMut(&Status) + 'a>(&mut self, handler: F) -> &mut Self {
+        self.on_status_update.push(Box::new(handler));
+        self
+    }
+
+    /// Called for every notification, including mentions and follow requests
+    pub fn on_notification<F: FnMut(&Notification) + 'a>(&mut self, handler: F) -> &mut Self {
+        self.on_notification.push(Box::new(handler));
+        self
+    }
+
+    /// Called with the mentioning status for every mention notification
+    pub fn on_mention<F: FnMut(&Status) + 'a>(&mut self, handler: F) -> &mut Self {
+        self.on_mention.push(Box::new(handler));
+        self
+    }
+
+    /// Called for every follow request notification, e.g. to `accept` or
+    /// `reject` it
+    pub fn on_follow_request<F: FnMut(&Notification) + 'a>(&mut self, handler: F) -> &mut Self {
+        self.on_follow_request.push(Box::new(handler));
+        self
+    }
+
+    /// Called with the id of every deleted status
+    pub fn on_delete<F: FnMut(&str) + 'a>(&mut self, handler: F) -> &mut Self {
+        self.on_delete.push(Box::new(handler));
+        self
+    }
+
+    /// Called whenever the user's filters change
+    pub fn on_filters_changed<F: FnMut() + 'a>(&mut self, handler: F) -> &mut Self {
+        self.on_filters_changed.push(Box::new(handler));
+        self
+    }
+
+    /// Called whenever connecting to the stream fails
+    pub fn on_error<F: FnMut(&Error) + 'a>(&mut self, handler: F) -> &mut Self {
+        self.on_error.push(Box::new(handler));
+        self
+    }
+
+    /// How long to wait before the first reconnection attempt. The delay
+    /// doubles with every consecutive failure, up to `max_reconnect_delay`.
+    ///
+    /// Defaults to 1 second.
+    pub fn reconnect_delay(&mut self, delay: Duration) -> &mut Self {
+        self.reconnect_delay = delay;
+        self
+    }
+
+    /// The upper bound for the delay between reconnection attempts
+    ///
+    /// Defaults to 60 seconds.
+    pub fn max_reconnect_delay(&mut self, delay: Duration) -> &mut Self {
+        self.max_reconnect_delay = delay;
+        self
+    }
+
+    /// Give up after this many consecutive failed connection attempts. A
+    /// connection that closes before delivering any events counts as a
+    /// failure.
+    ///
+    /// By default the bot retries forever.
+    pub fn max_reconnects(&mut self, max: usize) -> &mut Self {
+        self.max_reconnects = Some(max);
+        self
+    }
+
+    /// Remember the ids of the last `size` statuses and notifications, and
+    /// drop `Update` and `Notification` events that have already been
+    /// dispatched, e.g. because they were redelivered after a reconnect or
+    /// already fetched by a backfill.
+    pub fn dedup_window(&mut self, size: usize) -> &mut Self {
+        self.seen = Some(SeenWindow::new(size));
+        self
+    }
+
+    /// After a reconnect, fetch the home timeline statuses that were missed
+    /// while disconnected (see `backfill_since`) before resuming live events
+    pub fn backfill_on_reconnect(&mut self, backfill: bool) -> &mut Self {
+        self.backfill = backfill;
+        self
+    }
+
+    /// Fetches the home timeline statuses newer than `status_id` via the REST
+    /// API and dispatches them, oldest first, as `Update` events
+    pub fn backfill_since(&mut self, status_id: &str) -> Result<()> {
+        let mut page = self.client.get_home_timeline()?;
+        let mut items = std::mem::take(&mut page.initial_items);
+        let mut missed = Vec::new();
+        'pages: loop {
+            for status in items {
+                if compare_ids(&status.id, status_id) != CmpOrdering::Greater {
+                    break 'pages;
+                }
+                missed.push(status);
+            }
+            items = match page.next_page()? {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        log::debug!("backfilling {} statuses since {}", missed.len(), status_id);
+        for status in missed.into_iter().rev() {
+            self.dispatch(Event::Update(status));
+        }
+        Ok(())
+    }
+
+    /// Records every event received from the stream to `journal` before
+    /// dispatching it. Failing to record an event is reported to the error
+    /// handlers, and the event is still dispatched.
+    pub fn record_to<W: Write + 'a>(&mut self, journal: JournalWriter<W>) -> &mut Self {
+        let writer: Box<dyn Write + 'a> = Box::new(journal.into_inner());
+        self.journal = Some(JournalWriter::new(writer));
+        self
+    }
+
+    /// Dispatches recorded events to the handlers, in order, as if they had
+    /// been received from the stream
+    ///
+    /// Replayed events aren't recorded again. Stops at the first entry that
+    /// can't be read, returning its error, or when stopped through a
+    /// `StopHandle`.
+    pub fn replay<I: IntoIterator<Item = Result<JournalEntry>>>(
+        &mut self,
+        entries: I,
+    ) -> Result<()> {
+        for entry in entries {
+            self.dispatch(entry?.event);
+            if self.stop.is_stopped() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a handle that can be used to stop the event loop, e.g. from a
+    /// handler or another thread
+    ///
+    /// The loop checks the handle between events, so `run` returns after the
+    /// next event is received or the next reconnection attempt.
+    pub fn stop_handle(&self) -> StopHandle {
+        self.stop.clone()
+    }
+
+    /// Runs the event loop
+    ///
+    /// This only returns when stopped through a `StopHandle`, or with an error
+    /// once `max_reconnects` consecutive attempts have failed.
+    pub fn run(&mut self) -> Result<()> {
+        let mut failures = 0;
+        let mut delay = self.reconnect_delay;
+
+        loop {
+            if self.stop.is_stopped() {
+                return Ok(());
+            }
+
+            let error = match self.client.streaming_user() {
+                Ok(stream) => {
+                    if self.backfill {
+                        if let Some(since) = self.last_status_id.clone() {
+                            if let Err(e) = self.backfill_since(&since) {
+                                for handler in &mut self.on_error {
+                                    handler(&e);
+                                }
+                            }
+                        }
+                    }
+                    let mut received = false;
+                    for event in stream {
+                        received = true;
+                        if let Some(ref mut journal) = self.journal {
+                            if let Err(e) = journal.record(&event) {
+                                for handler in &mut self.on_error {
+                                    handler(&e);
+                                }
+                            }
+                        }
+                        self.dispatch(event);
+                        if self.stop.is_stopped() {
+                            return Ok(());
+                        }
+                    }
+                    log::debug!("streaming connection closed, reconnecting");
+                    if received {
+                        failures = 0;
+                        delay = self.reconnect_delay;
+                        None
+                    } else {
+                        Some(Error::Other("streaming connection closed".to_string()))
+                    }
+                },
+                Err(e) => {
+                    for handler in &mut self.on_error {
+                        handler(&e);
+                    }
+                    Some(e)
+                },
+            };
+
+            if let Some(error) = error {
+                failures += 1;
+                if self.max_reconnects.is_some_and(|max| failures > max) {
+                    return Err(error);
+                }
+            }
+
+            if self.stop.is_stopped() {
+                return Ok(());
+            }
+            thread::sleep(delay);
+            delay = (delay * 2).min(self.max_reconnect_delay);
+        }
+    }
+
+    fn dispatch(&mut self, event: Event) {
+        if let Some(ref mut seen) = self.seen {
+            let is_new = match event {
+                Event::Update(ref status) => seen.insert(Seen::Status(status.id.clone())),
+                Event::Notification(ref notification) => {
+                    seen.insert(Seen::Notification(notification.id.clone()))
+                },
+                _ => true,
+            };
+            if !is_new {
+                return;
+            }
+        }
+
+        match event {
+            Event::Update(ref status) => {
+                let newer = match self.last_status_id {
+                    Some(ref last) => compare_ids(&status.id, last) == CmpOrdering::Greater,
+                    None => true,
+                };
+                if newer {
+                    self.last_status_id = Some(status.id.clone());
+                }
+                for handler in &mut self.on_update {
+                    handler(status);
+                }
+            },
+            Event::StatusUpdate(ref status) => {
+                for handler in &mut self.on_status_update {
+                    handler(status);
+                }
+            },
+            Event::Notification(ref notification) => {
+                for handler in &mut self.on_notification {
+                    handler(notification);
+                }
+                if notification.notification_type == NotificationType::Mention {
+                    if let Some(ref status) = notification.status {
+                        for handler in &mut self.on_mention {
+                            handler(status);
+                        }
+                    }
+                }
+                if notification.is_follow_request() {
+                    for handler in &mut self.on_follow_request {
+                        handler(notification);
+                    }
+                }
+            },
+            Event::Delete(ref id) => {
+                for handler in &mut self.on_delete {
+                    handler(id);
+                }
+            },
+            Event::FiltersChanged => {
+                for handler in &mut self.on_filters_changed {
+                    handler();
+                }
+            },
+            // only sent on the direct stream
+            Event::Conversation(_) => {},
+        }
+    }
+}
+
+// Mastodon ids are numeric strings, so compare by length first
+pub(crate) fn compare_ids(a: &str, b: &str) -> CmpOrdering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Seen {
+    Status(String),
+    Notification(String),
+}
+
+#[derive(Debug, Clone)]
+struct SeenWindow {
+    capacity: usize,
+    order: VecDeque<Seen>,
+    set: HashSet<Seen>,
+}
+
+impl SeenWindow {
+    fn new(capacity: usize) -> SeenWindow {
+        SeenWindow {
+            capacity,
+            order: VecDeque::with_capacity(capacity),
+            set: HashSet::with_capacity(capacity),
+        }
+    }
+
+    // returns false if the id was already in the window
+    fn insert(&mut self, id: Seen) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        if self.set.contains(&id) {
+            return false;
+        }
+        if self.order.len() == self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.set.remove(&oldest);
+            }
+        }
+        self.order.push_back(id.clone());
+        self.set.insert(id);
+        true
+    }
+}
+
+/// Stops a running `Bot`
+#[derive(Debug, Clone, Default)]
+pub struct StopHandle(Arc<AtomicBool>);
+
+impl StopHandle {
+    /// Ask the bot to stop
+    pub fn stop(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether `stop` has been called
+    pub fn is_stopped(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::status::StatusId;
+    use std::cell::{Cell, RefCell};
+
+    struct MockClient {
+        connections: RefCell<Vec<Result<Vec<Event>>>>,
+    }
+
+    impl MastodonClient for MockClient {
+        type Stream = std::vec::IntoIter<Event>;
+
+        fn streaming_user(&self) -> Result<Self::Stream> {
+            let mut connections = self.connections.borrow_mut();
+            if connections.is_empty() {
+                return Err(Error::Other("no more connections".to_string()));
+            }
+            connections.remove(0).map(|events| events.into_iter())
+        }
+    }
+
+    #[test]
+    fn test_dispatch_and_reconnect() {
+        let client = MockClient {
+            connections: RefCell::new(vec![
+                Ok(vec![
+                    Event::Delete(StatusId::new("1").unwrap()),
+                    Event::FiltersChanged,
+                ]),
+                Err(Error::Other("connection refused".to_string())),
+                Ok(vec![Event::Delete(StatusId::new("2").unwrap())]),
+            ]),
+        };
+        let deleted = RefCell::new(Vec::new());
+        let filters_changed = Cell::new(0);
+        let errors = Cell::new(0);
+
+        let mut bot = Bot::new(&client);
+        bot.reconnect_delay(Duration::from_millis(0))
+            .max_reconnects(2)
+            .on_delete(|id| deleted.borrow_mut().push(id.to_string()))
+            .on_filters_changed(|| filters_changed.set(filters_changed.get() + 1))
+            .on_error(|_| errors.set(errors.get() + 1));
+        let result = bot.run();
+        drop(bot);
+
+        assert!(result.is_err());
+        assert_eq!(*deleted.borrow(), vec!["1".to_string(), "2".to_string()]);
+        assert_eq!(filters_changed.get(), 1);
+        // one refused connection, then the final failures after the last stream
+        assert_eq!(errors.get(), 4);
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let client = MockClient {
+            connections: RefCell::new(vec![Ok(vec![
+                Event::Delete(StatusId::new("1").unwrap()),
+                Event::FiltersChanged,
+                Event::Delete(StatusId::new("2").unwrap()),
+            ])]),
+        };
+        let journal = RefCell::new(Vec::new());
+        struct Journal<'a>(&'a RefCell<Vec<u8>>);
+        impl<'a> Write for Journal<'a> {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.borrow_mut().write(buf)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut bot = Bot::new(&client);
+        bot.reconnect_delay(Duration::from_millis(0))
+            .max_reconnects(0)
+            .record_to(JournalWriter::new(Journal(&journal)));
+        assert!(bot.run().is_err());
+        drop(bot);
+
+        let deleted = RefCell::new(Vec::new());
+        let mut bot = Bot::new(&client);
+        bot.on_delete(|id| deleted.borrow_mut().push(id.to_string()));
+        let journal = journal.into_inner();
+        bot.replay(crate::journal::JournalReader::new(&journal[..]))
+            .expect("Couldn't replay journal");
+        drop(bot);
+
+        assert_eq!(*deleted.borrow(), vec!["1".to_string(), "2".to_string()]);
+    }
+
+    #[test]
+    fn test_compare_ids() {
+        assert_eq!(compare_ids("10", "9"), CmpOrdering::Greater);
+        assert_eq!(compare_ids("103", "104"), CmpOrdering::Less);
+        assert_eq!(compare_ids("5", "5"), CmpOrdering::Equal);
+    }
+
+    #[test]
+    fn test_seen_window() {
+        let mut seen = SeenWindow::new(2);
+        assert!(seen.insert(Seen::Status("1".to_string())));
+        assert!(!seen.insert(Seen::Status("1".to_string())));
+        assert!(seen.insert(Seen::Notification("1".to_string())));
+        assert!(seen.insert(Seen::Status("2".to_string())));
+        // "1" has been pushed out of the window
+        assert!(seen.insert(Seen::Status("1".to_string())));
+    }
+
+    #[test]
+    fn test_stop_handle() {
+        let client = MockClient {
+            connections: RefCell::new(vec![Ok(vec![
+                Event::Delete(StatusId::new("1").unwrap()),
+                Event::Delete(StatusId::new("2").unwrap()),
+            ])]),
+        };
+        let deleted = RefCell::new(Vec::new());
+
+        let mut bot = Bot::new(&client);
+        let stop = bot.stop_handle();
+        let deleted_ref = &deleted;
+        bot.on_delete(move |id| {
+            deleted_ref.borrow_mut().push(id.to_string());
+            stop.stop();
+        });
+        bot.run().expect("Couldn't run bot");
+        drop(bot);
+
+        assert_eq!(*deleted.borrow(), vec!["1".to_string()]);
+    }
+}
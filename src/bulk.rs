@@ -0,0 +1,229 @@
+//! Running the same operation for many items, e.g. following every account
+//! of an exported follow list after moving instances.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # extern crate elefren;
+//! # use elefren::prelude::*;
+//! use elefren::bulk::Bulk;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let data = Data {
+//! #   base: "".into(),
+//! #   client_id: "".into(),
+//! #   client_secret: "".into(),
+//! #   redirect: "".into(),
+//! #   token: "".into(),
+//! #   ..Default::default()
+//! # };
+//! let client = Mastodon::from(data);
+//! let ids = vec!["1", "2", "3"];
+//! let results = Bulk::new().concurrency(2).run(
+//!     &client,
+//!     ids,
+//!     |client, id| client.follow(id),
+//!     |done, total| println!("{}/{}", done, total),
+//! );
+//! for (id, result) in results {
+//!     if let Err(e) = result {
+//!         eprintln!("couldn't follow {}: {}", id, e);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    sync::{mpsc, Mutex},
+    thread,
+};
+
+use crate::{errors::Result, http_send::HttpSend, mastodon_client::MastodonClient};
+
+/// Runs an operation for many items against a client, in parallel, while
+/// staying within the server's rate limit.
+///
+/// Before each operation, the rate limit reported by the client's last
+/// response is checked; when fewer requests than there are workers are left,
+/// the workers wait for the rate limit window to reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Bulk {
+    concurrency: usize,
+}
+
+impl Default for Bulk {
+    fn default() -> Self {
+        Bulk::new()
+    }
+}
+
+impl Bulk {
+    /// Run the operations one at a time
+    pub fn new() -> Bulk {
+        Bulk { concurrency: 1 }
+    }
+
+    /// Run up to `concurrency` operations at once. Values below 1 are
+    /// treated as 1.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Calls `op` for every item of `items`, calling `progress` with the
+    /// number of finished and total operations after each one.
+    ///
+    /// Returns every item along with the result of its operation, in the
+    /// order of `items`. A failing operation doesn't stop the others.
+    pub fn run<H, C, I, T, R, F, P>(
+        &self,
+        client: &C,
+        items: I,
+        op: F,
+        mut progress: P,
+    ) -> Vec<(T, Result<R>)>
+    where
+        H: HttpSend,
+        C: MastodonClient<H> + Sync,
+        I: IntoIterator<Item = T>,
+        T: Send,
+        R: Send,
+        F: Fn(&C, &T) -> Result<R> + Sync,
+        P: FnMut(usize, usize),
+    {
+        let items: Vec<T> = items.into_iter().collect();
+        let total = items.len();
+        let queue = Mutex::new(items.into_iter().enumerate());
+        let mut results: Vec<Option<(T, Result<R>)>> = (0..total).map(|_| None).collect();
+
+        thread::scope(|scope| {
+            let (tx, rx) = mpsc::channel();
+            for _ in 0..self.concurrency.min(total) {
+                let tx = tx.clone();
+                let (queue, op) = (&queue, &op);
+                scope.spawn(move || {
+                    // the lock is released at the end of the `let`
+                    while let Some((idx, item)) = {
+                        let next = queue.lock().unwrap().next();
+                        next
+                    } {
+                        self.pace(client);
+                        let result = op(client, &item);
+                        if tx.send((idx, item, result)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(tx);
+
+            for (done, (idx, item, result)) in rx.iter().enumerate() {
+                results[idx] = Some((item, result));
+                progress(done + 1, total);
+            }
+        });
+
+        results.into_iter().flatten().collect()
+    }
+
+    // Waits until the client's rate limit leaves room for every worker
+    fn pace<H: HttpSend, C: MastodonClient<H>>(&self, client: &C) {
+        if let Some(rate_limit) = client.rate_limit() {
+            let wait = rate_limit.wait_time(self.concurrency as u64, client.clock().now());
+            if wait > std::time::Duration::from_secs(0) {
+                log::debug!("rate limit almost exhausted, waiting {:?}", wait);
+                client.clock().sleep(wait);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clock::{Clock, ManualClock},
+        errors::Error,
+        rate_limit::RateLimit,
+    };
+    use chrono::Utc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct MockClient {
+        calls: AtomicUsize,
+        rate_limit: Option<RateLimit>,
+        clock: ManualClock,
+    }
+
+    impl MastodonClient for MockClient {
+        type Stream = std::iter::Empty<crate::entities::event::Event>;
+
+        fn rate_limit(&self) -> Option<RateLimit> {
+            self.rate_limit
+        }
+
+        fn clock(&self) -> &dyn Clock {
+            &self.clock
+        }
+    }
+
+    fn mock(rate_limit: Option<RateLimit>) -> MockClient {
+        MockClient {
+            calls: AtomicUsize::new(0),
+            rate_limit,
+            clock: ManualClock::new(Utc::now()),
+        }
+    }
+
+    #[test]
+    fn test_results_keep_item_order() {
+        let client = mock(None);
+        let mut reports = vec![];
+        let results = Bulk::new().concurrency(4).run(
+            &client,
+            0..10u32,
+            |client, n| {
+                client.calls.fetch_add(1, Ordering::SeqCst);
+                if n % 3 == 0 {
+                    Err(Error::Other(format!("{} failed", n)))
+                } else {
+                    Ok(n * 2)
+                }
+            },
+            |done, total| reports.push((done, total)),
+        );
+
+        assert_eq!(client.calls.load(Ordering::SeqCst), 10);
+        assert_eq!(reports.len(), 10);
+        assert_eq!(reports.last(), Some(&(10, 10)));
+        for (n, (item, result)) in results.into_iter().enumerate() {
+            assert_eq!(item, n as u32);
+            match result {
+                Ok(doubled) => assert_eq!(doubled, item * 2),
+                Err(_) => assert_eq!(item % 3, 0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_waits_for_rate_limit_reset() {
+        let mut client = mock(None);
+        let reset = client.clock.now() + chrono::Duration::seconds(300);
+        client.rate_limit = Some(RateLimit {
+            limit: 300,
+            remaining: 0,
+            reset,
+        });
+        let results = Bulk::new().run(&client, vec![()], |_, _| Ok(()), |_, _| {});
+        assert!(client.clock.now() >= reset);
+        assert_eq!(client.clock.slept().len(), 1);
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn test_no_items() {
+        let client = mock(None);
+        let results: Vec<((), Result<()>)> =
+            Bulk::new().run(&client, vec![], |_, _| Ok(()), |_, _| panic!());
+        assert!(results.is_empty());
+    }
+}
@@ -0,0 +1,538 @@
+//! Reading a stream on its own thread and handing its events over through a
+//! bounded buffer, so that a slow consumer doesn't hold up the connection.
+//!
+//! To feed one stream to several consumers, see [`Broadcast`](struct.Broadcast.html).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # extern crate elefren;
+//! # use elefren::prelude::*;
+//! # use std::error::Error;
+//! use elefren::{
+//!     channel::{Backpressure, ChannelOptions},
+//!     StreamKind,
+//! };
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! # let data = Data {
+//! #   base: "".into(),
+//! #   client_id: "".into(),
+//! #   client_secret: "".into(),
+//! #   redirect: "".into(),
+//! #   token: "".into(),
+//! #   ..Default::default()
+//! # };
+//! let client = Mastodon::from(data);
+//! let options = ChannelOptions::new(1024).backpressure(Backpressure::DropOldest);
+//! let (reader, events) = client.spawn_streaming(StreamKind::User, options)?;
+//! for event in &events {
+//!     println!("{:?}", event?);
+//! }
+//! reader.join().unwrap();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::VecDeque,
+    io,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    entities::event::Event,
+    errors::{Error, Result},
+    EventReader, EventStream,
+};
+
+/// What the reader thread does with a new event when the buffer is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Wait for the consumer to take an event, which stops reading from the
+    /// connection in the meantime
+    Block,
+    /// Discard the oldest buffered event to make room
+    DropOldest,
+}
+
+/// Options for [`EventReader::spawn`](../struct.EventReader.html#method.spawn)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelOptions {
+    capacity: usize,
+    backpressure: Backpressure,
+}
+
+impl Default for ChannelOptions {
+    fn default() -> Self {
+        ChannelOptions::new(256)
+    }
+}
+
+impl ChannelOptions {
+    /// Buffer up to `capacity` events, blocking the reader when the buffer is
+    /// full. Values below 1 are treated as 1.
+    pub fn new(capacity: usize) -> ChannelOptions {
+        ChannelOptions {
+            capacity: capacity.max(1),
+            backpressure: Backpressure::Block,
+        }
+    }
+
+    /// What to do when the buffer is full
+    pub fn backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.backpressure = backpressure;
+        self
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    items: VecDeque<Result<Event>>,
+    dropped: u64,
+    reader_done: bool,
+    receiver_gone: bool,
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    not_empty: Condvar,
+    not_full: Condvar,
+    options: ChannelOptions,
+}
+
+impl Shared {
+    fn new(options: ChannelOptions) -> Arc<Shared> {
+        Arc::new(Shared {
+            state: Mutex::new(State {
+                items: VecDeque::with_capacity(options.capacity.min(1024)),
+                dropped: 0,
+                reader_done: false,
+                receiver_gone: false,
+            }),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            options,
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // neither side can panic while holding the lock
+        self.state.lock().unwrap()
+    }
+
+    // Returns false once the receiver has been dropped
+    fn push(&self, item: Result<Event>) -> bool {
+        let mut state = self.lock();
+        while state.items.len() >= self.options.capacity && !state.receiver_gone {
+            match self.options.backpressure {
+                Backpressure::Block => state = self.not_full.wait(state).unwrap(),
+                Backpressure::DropOldest => {
+                    state.items.pop_front();
+                    state.dropped += 1;
+                },
+            }
+        }
+        if state.receiver_gone {
+            return false;
+        }
+        state.items.push_back(item);
+        self.not_empty.notify_one();
+        true
+    }
+
+    fn finish(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.reader_done = true;
+        }
+        self.not_empty.notify_all();
+    }
+}
+
+// Marks the reader as finished however its thread ends
+struct ReaderGuard(Arc<Shared>);
+
+impl Drop for ReaderGuard {
+    fn drop(&mut self) {
+        self.0.finish();
+    }
+}
+
+/// The receiving end of a stream read on another thread
+///
+/// Yields the stream's events in order, followed by the error that ended the
+/// stream. Dropping the receiver stops the reader thread once it reads its
+/// next event.
+///
+/// The receiver is `Send` and `Sync`, so it can be handed to the thread that
+/// processes the events.
+#[derive(Debug)]
+pub struct EventReceiver {
+    shared: Arc<Shared>,
+}
+
+impl EventReceiver {
+    /// Waits for the next event. Returns `None` once the stream has ended and
+    /// every event has been received.
+    pub fn recv(&self) -> Option<Result<Event>> {
+        let mut state = self.shared.lock();
+        while state.items.is_empty() && !state.reader_done {
+            state = self.shared.not_empty.wait(state).unwrap();
+        }
+        self.take(state)
+    }
+
+    /// Waits up to `timeout` for the next event. Returns `None` if there was
+    /// none; use `is_finished` to tell a timeout from the end of the stream.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Result<Event>> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.lock();
+        while state.items.is_empty() && !state.reader_done {
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            let timeout = deadline - now;
+            let (next, _) = self.shared.not_empty.wait_timeout(state, timeout).unwrap();
+            state = next;
+        }
+        self.take(state)
+    }
+
+    /// Returns the next event if one is buffered, without waiting
+    pub fn try_recv(&self) -> Option<Result<Event>> {
+        self.take(self.shared.lock())
+    }
+
+    /// Whether the stream has ended and every event has been received
+    pub fn is_finished(&self) -> bool {
+        let state = self.shared.lock();
+        state.reader_done && state.items.is_empty()
+    }
+
+    /// The number of events discarded so far because the buffer was full
+    pub fn dropped(&self) -> u64 {
+        self.shared.lock().dropped
+    }
+
+    fn take(&self, mut state: MutexGuard<State>) -> Option<Result<Event>> {
+        let item = state.items.pop_front();
+        if item.is_some() {
+            self.shared.not_full.notify_one();
+        }
+        item
+    }
+}
+
+impl Drop for EventReceiver {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.receiver_gone = true;
+            state.items.clear();
+        }
+        self.shared.not_full.notify_all();
+    }
+}
+
+impl<'a> IntoIterator for &'a EventReceiver {
+    type Item = Result<Event>;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        Iter(self)
+    }
+}
+
+impl IntoIterator for EventReceiver {
+    type Item = Result<Event>;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter(self)
+    }
+}
+
+/// Iterator that waits for the events of an `EventReceiver`
+#[derive(Debug)]
+pub struct Iter<'a>(&'a EventReceiver);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.recv()
+    }
+}
+
+/// Owning iterator that waits for the events of an `EventReceiver`
+#[derive(Debug)]
+pub struct IntoIter(EventReceiver);
+
+impl Iterator for IntoIter {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.recv()
+    }
+}
+
+impl<R: EventStream + Send + 'static> EventReader<R> {
+    /// Reads this stream on a new thread, buffering its events according to
+    /// `options`
+    ///
+    /// The thread runs until reading from the stream fails, which is passed
+    /// on as the last item, or until the receiver is dropped.
+    pub fn spawn(mut self, options: ChannelOptions) -> (JoinHandle<()>, EventReceiver) {
+        let shared = Shared::new(options);
+        let guard = ReaderGuard(shared.clone());
+        let handle = thread::spawn(move || {
+            let guard = guard;
+            loop {
+                let event = self.next_event();
+                let failed = event.is_err();
+                if !guard.0.push(event) || failed {
+                    break;
+                }
+            }
+        });
+        (handle, EventReceiver { shared })
+    }
+}
+
+#[derive(Debug, Default)]
+struct Subscribers {
+    list: Vec<Arc<Shared>>,
+    done: bool,
+}
+
+/// Feeds the events of a single stream to several independent consumers,
+/// without opening a connection for each of them
+///
+/// Every subscriber has its own buffer and backpressure policy. A subscriber
+/// that blocks holds up the others, so slow consumers should usually drop
+/// old events instead.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// # use std::error::Error;
+/// use elefren::channel::{Backpressure, Broadcast, ChannelOptions};
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let data = Data {
+/// #   base: "".into(),
+/// #   client_id: "".into(),
+/// #   client_secret: "".into(),
+/// #   redirect: "".into(),
+/// #   token: "".into(),
+/// #   ..Default::default()
+/// # };
+/// let client = Mastodon::from(data);
+/// let broadcast = Broadcast::new();
+/// let lossy = ChannelOptions::new(1024).backpressure(Backpressure::DropOldest);
+/// let log = broadcast.subscribe(lossy);
+/// let responder = broadcast.subscribe(ChannelOptions::default());
+/// let reader = broadcast.spawn(client.streaming_user()?);
+/// std::thread::spawn(move || {
+///     for event in log {
+///         println!("{:?}", event);
+///     }
+/// });
+/// for event in responder {
+///     // ..
+/// #   let _ = event;
+/// }
+/// reader.join().unwrap();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Broadcast(Arc<Mutex<Subscribers>>);
+
+impl Broadcast {
+    /// A broadcast without any subscribers
+    pub fn new() -> Broadcast {
+        Broadcast::default()
+    }
+
+    /// Adds a consumer, buffering its events according to `options`
+    ///
+    /// It receives the events read after it subscribed, followed by the
+    /// error that ended the stream. As errors can't be cloned, every
+    /// subscriber gets a copy with the same message; I/O errors keep their
+    /// kind.
+    pub fn subscribe(&self, options: ChannelOptions) -> EventReceiver {
+        let shared = Shared::new(options);
+        let mut subscribers = self.0.lock().unwrap();
+        if subscribers.done {
+            shared.finish();
+        } else {
+            subscribers.list.push(shared.clone());
+        }
+        EventReceiver { shared }
+    }
+
+    /// Reads `reader` on a new thread, passing every event on to the
+    /// subscribers
+    ///
+    /// The thread runs until reading from the stream fails, or until every
+    /// subscriber has been dropped. Events read while there are no
+    /// subscribers yet are discarded.
+    pub fn spawn<R: EventStream + Send + 'static>(
+        &self,
+        mut reader: EventReader<R>,
+    ) -> JoinHandle<()> {
+        let guard = BroadcastGuard(self.clone());
+        thread::spawn(move || {
+            let guard = guard;
+            loop {
+                let event = reader.next_event();
+                let subscribers = (guard.0).0.lock().unwrap().list.clone();
+                let event = match event {
+                    Ok(event) => event,
+                    Err(err) => {
+                        for subscriber in subscribers {
+                            subscriber.push(Err(copy_error(&err)));
+                        }
+                        break;
+                    },
+                };
+                let gone: Vec<_> = subscribers
+                    .into_iter()
+                    .filter(|subscriber| !subscriber.push(Ok(event.clone())))
+                    .collect();
+                if !gone.is_empty() {
+                    let mut subscribers = (guard.0).0.lock().unwrap();
+                    subscribers
+                        .list
+                        .retain(|subscriber| !gone.iter().any(|g| Arc::ptr_eq(subscriber, g)));
+                    if subscribers.list.is_empty() {
+                        break;
+                    }
+                }
+            }
+        })
+    }
+}
+
+// Marks every subscriber as finished however the broadcast thread ends
+struct BroadcastGuard(Broadcast);
+
+impl Drop for BroadcastGuard {
+    fn drop(&mut self) {
+        if let Ok(mut subscribers) = (self.0).0.lock() {
+            subscribers.done = true;
+            for subscriber in subscribers.list.drain(..) {
+                subscriber.finish();
+            }
+        }
+    }
+}
+
+fn copy_error(err: &Error) -> Error {
+    match err {
+        Error::Io(e) => Error::Io(io::Error::new(e.kind(), e.to_string())),
+        err => Error::Other(err.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn deletes(ids: &[&str]) -> EventReader<Cursor<String>> {
+        let lines: String = ids
+            .iter()
+            .map(|id| {
+                let message = serde_json::json!({ "event": "delete", "payload": id });
+                format!("{}\n", message)
+            })
+            .collect();
+        EventReader::new(Cursor::new(lines))
+    }
+
+    fn deleted_id(item: Option<Result<Event>>) -> String {
+        match item {
+            Some(Ok(Event::Delete(id))) => id.into(),
+            item => panic!("unexpected item {:?}", item),
+        }
+    }
+
+    #[test]
+    fn test_events_then_error() {
+        let (handle, events) = deletes(&["1", "2"]).spawn(ChannelOptions::default());
+        assert_eq!(deleted_id(events.recv()), "1");
+        assert_eq!(deleted_id(events.recv()), "2");
+        assert!(matches!(events.recv(), Some(Err(_))));
+        assert!(events.recv().is_none());
+        assert!(events.is_finished());
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let options = ChannelOptions::new(2).backpressure(Backpressure::DropOldest);
+        let (handle, events) = deletes(&["1", "2", "3", "4"]).spawn(options);
+        handle.join().unwrap();
+        // the closing error pushed out all but the last event
+        assert_eq!(events.dropped(), 3);
+        assert_eq!(deleted_id(events.try_recv()), "4");
+        assert!(matches!(events.try_recv(), Some(Err(_))));
+        assert!(events.try_recv().is_none());
+    }
+
+    #[test]
+    fn test_block_waits_for_consumer() {
+        let (handle, events) = deletes(&["1", "2", "3"]).spawn(ChannelOptions::new(1));
+        let ids: Vec<String> = (&events)
+            .into_iter()
+            .take(3)
+            .map(|event| match event {
+                Ok(Event::Delete(id)) => id.into(),
+                event => panic!("unexpected event {:?}", event),
+            })
+            .collect();
+        assert_eq!(ids, ["1", "2", "3"]);
+        assert_eq!(events.dropped(), 0);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_broadcast() {
+        let broadcast = Broadcast::new();
+        let first = broadcast.subscribe(ChannelOptions::default());
+        let second = broadcast.subscribe(ChannelOptions::new(4));
+        let handle = broadcast.spawn(deletes(&["1", "2"]));
+        for events in &[first, second] {
+            assert_eq!(deleted_id(events.recv()), "1");
+            assert_eq!(deleted_id(events.recv()), "2");
+            match events.recv() {
+                Some(Err(Error::Io(e))) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+                item => panic!("unexpected item {:?}", item),
+            }
+            assert!(events.recv().is_none());
+        }
+        handle.join().unwrap();
+        assert!(broadcast.subscribe(ChannelOptions::default()).is_finished());
+    }
+
+    #[test]
+    fn test_broadcast_stops_without_subscribers() {
+        let broadcast = Broadcast::new();
+        let events = broadcast.subscribe(ChannelOptions::new(1));
+        let handle = broadcast.spawn(deletes(&["1", "2", "3"]));
+        drop(events);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_dropping_receiver_stops_reader() {
+        let (handle, events) = deletes(&["1", "2", "3"]).spawn(ChannelOptions::new(1));
+        drop(events);
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+//! Where the client gets the current time from, and how it waits
+//!
+//! Retries, rate limit pacing, token expiry and scheduled statuses all depend
+//! on time. They go through a [`Clock`](trait.Clock.html), which is the
+//! system's by default; tests can pass a [`ManualClock`](struct.ManualClock.html)
+//! to `MastodonBuilder::clock` instead, and check what happens without
+//! actually waiting.
+//!
+//! # Example
+//!
+//! ```
+//! # extern crate elefren;
+//! # extern crate chrono;
+//! use chrono::{TimeZone, Utc};
+//! use elefren::clock::{Clock, ManualClock};
+//! use std::time::Duration;
+//!
+//! let clock = ManualClock::new(Utc.with_ymd_and_hms(2023, 5, 1, 12, 0, 0).unwrap());
+//! clock.sleep(Duration::from_secs(90));
+//! assert_eq!(clock.now(), Utc.with_ymd_and_hms(2023, 5, 1, 12, 1, 30).unwrap());
+//! assert_eq!(clock.slept(), vec![Duration::from_secs(90)]);
+//! ```
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+/// A source of the current time, which can also wait
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// The current time
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Blocks the current thread for `duration`
+    fn sleep(&self, duration: Duration);
+}
+
+/// The system's clock. This is the default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// A clock that only moves when told to, for tests
+///
+/// Sleeping returns immediately, after advancing the clock by the duration
+/// and recording it. Clones share the time, so a clone can be kept to
+/// advance the clock given to a client.
+#[derive(Debug, Clone)]
+pub struct ManualClock(Arc<Mutex<ManualState>>);
+
+#[derive(Debug)]
+struct ManualState {
+    now: DateTime<Utc>,
+    slept: Vec<Duration>,
+}
+
+impl ManualClock {
+    /// A clock showing `now`
+    pub fn new(now: DateTime<Utc>) -> ManualClock {
+        ManualClock(Arc::new(Mutex::new(ManualState {
+            now,
+            slept: Vec::new(),
+        })))
+    }
+
+    /// Sets the clock to `now`
+    pub fn set(&self, now: DateTime<Utc>) {
+        self.0.lock().unwrap().now = now;
+    }
+
+    /// Moves the clock forward by `duration`, without recording a sleep
+    pub fn advance(&self, duration: Duration) {
+        let mut state = self.0.lock().unwrap();
+        state.now = advanced(state.now, duration);
+    }
+
+    /// The durations slept so far, in order
+    pub fn slept(&self) -> Vec<Duration> {
+        self.0.lock().unwrap().slept.clone()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0.lock().unwrap().now
+    }
+
+    fn sleep(&self, duration: Duration) {
+        let mut state = self.0.lock().unwrap();
+        state.now = advanced(state.now, duration);
+        state.slept.push(duration);
+    }
+}
+
+// `now` moved forward by `duration`, stopping at the latest representable
+// time rather than overflowing
+fn advanced(now: DateTime<Utc>, duration: Duration) -> DateTime<Utc> {
+    chrono::Duration::from_std(duration)
+        .ok()
+        .and_then(|duration| now.checked_add_signed(duration))
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_manual_clock() {
+        let start = Utc.with_ymd_and_hms(2023, 5, 1, 12, 0, 0).unwrap();
+        let clock = ManualClock::new(start);
+        let shared = clock.clone();
+        shared.advance(Duration::from_secs(30));
+        clock.sleep(Duration::from_millis(1500));
+        assert_eq!(clock.now(), start + chrono::Duration::milliseconds(31_500));
+        assert_eq!(shared.slept(), [Duration::from_millis(1500)]);
+
+        clock.set(start);
+        assert_eq!(shared.now(), start);
+    }
+}
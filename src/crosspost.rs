@@ -0,0 +1,326 @@
+//! Recreating a status on another account, e.g. on another instance
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    entities::status::{Mention, Status},
+    errors::Result,
+    helpers::html::to_plain_text,
+    http_send::HttpSend,
+    mastodon_client::MastodonClient,
+    media_transfer::MediaTransfer,
+    status_builder::{NewPoll, NewStatus, StatusBuilder, Visibility},
+};
+
+// the shortest poll the server accepts, in seconds
+const MIN_POLL_DURATION: i64 = 5 * 60;
+
+/// How `crosspost` recreates a status
+///
+/// By default the status keeps its visibility and doesn't link back to the
+/// original.
+#[derive(Debug, Clone, Default)]
+pub struct CrosspostOptions {
+    link_back: bool,
+    visibility: Option<Visibility>,
+    max_visibility: Option<Visibility>,
+    transfer: MediaTransfer,
+}
+
+impl CrosspostOptions {
+    /// The default options
+    pub fn new() -> CrosspostOptions {
+        CrosspostOptions::default()
+    }
+
+    /// Append the URL of the original status to the text
+    pub fn link_back(mut self, link_back: bool) -> Self {
+        self.link_back = link_back;
+        self
+    }
+
+    /// Post with `visibility`, whatever the visibility of the original
+    pub fn visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = Some(visibility);
+        self
+    }
+
+    /// Post with at most `visibility`, e.g. `Unlisted` to keep crossposts out
+    /// of public timelines
+    pub fn max_visibility(mut self, visibility: Visibility) -> Self {
+        self.max_visibility = Some(visibility);
+        self
+    }
+
+    /// Copy media with `transfer`, e.g. one using an HTTP client with a
+    /// timeout
+    pub fn media_transfer(mut self, transfer: MediaTransfer) -> Self {
+        self.transfer = transfer;
+        self
+    }
+
+    // Builds the new status from `status`, with its media already uploaded
+    // as `media_ids`
+    fn build(
+        &self,
+        status: &Status,
+        media_ids: Vec<String>,
+        now: DateTime<Utc>,
+    ) -> Result<NewStatus> {
+        let mut builder = StatusBuilder::new();
+
+        let mut text = qualify_mentions(&to_plain_text(&status.content), &status.mentions);
+        if self.link_back {
+            let url = status.url.as_deref().unwrap_or(&status.uri);
+            if !text.is_empty() {
+                text.push_str("\n\n");
+            }
+            text.push_str(url);
+        }
+        if !text.is_empty() {
+            builder.status(text);
+        }
+
+        if !status.spoiler_text.is_empty() {
+            builder.spoiler_text(status.spoiler_text.clone());
+        }
+        builder.sensitive(status.sensitive);
+        if let Some(language) = status.language {
+            builder.language(language);
+        }
+
+        let mut visibility = self.visibility.unwrap_or(status.visibility);
+        if let Some(limit) = self.max_visibility {
+            visibility = visibility.at_most(limit);
+        }
+        builder.visibility(visibility.to_send());
+
+        if !media_ids.is_empty() {
+            builder.media_ids(media_ids);
+        }
+        if let Some(ref poll) = status.poll {
+            match DateTime::parse_from_rfc3339(&poll.expires_at) {
+                Ok(expires_at) if !poll.expired && expires_at > now => {
+                    let remaining = (expires_at.with_timezone(&Utc) - now).num_seconds();
+                    let options = poll.options.iter().map(|option| option.title.clone());
+                    builder.poll(
+                        NewPoll::new(options, remaining.max(MIN_POLL_DURATION) as u64)
+                            .multiple(poll.multiple),
+                    );
+                },
+                _ => log::debug!("not copying the poll of {}, it has ended", status.id),
+            }
+        }
+
+        builder.build()
+    }
+}
+
+/// Posts a copy of `status` through `target`, returning the new status
+///
+/// The copy has the text of `status`, its content warning, media (with their
+/// descriptions) and poll, if that is still open; the poll then ends at the
+/// same time, but at least five minutes from now. Its visibility is that of
+/// `status` unless `options` say otherwise. A reblog is copied by copying the
+/// reblogged status.
+///
+/// Mentions are written out with the domain of the account mentioned, so
+/// that they don't mention someone else on the target's instance. Formatting
+/// other than line breaks is lost.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// # use std::error::Error;
+/// use elefren::{
+///     crosspost::{crosspost, CrosspostOptions},
+///     status_builder::Visibility,
+/// };
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let data = Data {
+/// #   base: "".into(),
+/// #   client_id: "".into(),
+/// #   client_secret: "".into(),
+/// #   redirect: "".into(),
+/// #   token: "".into(),
+/// #   ..Default::default()
+/// # };
+/// # let other_data = data.clone();
+/// let source = Mastodon::from(data);
+/// let target = Mastodon::from(other_data);
+/// let options = CrosspostOptions::new()
+///     .link_back(true)
+///     .max_visibility(Visibility::Unlisted);
+/// let status = source.get_status("110")?;
+/// let copy = crosspost(&status, &target, &options)?;
+/// println!("crossposted as {}", copy.id);
+/// # Ok(())
+/// # }
+/// ```
+pub fn crosspost<H: HttpSend, C: MastodonClient<H>>(
+    status: &Status,
+    target: &C,
+    options: &CrosspostOptions,
+) -> Result<Status> {
+    let status = status.reblog.as_deref().unwrap_or(status);
+    let media_ids = status
+        .media_attachments
+        .iter()
+        .map(|attachment| options.transfer.reupload(attachment, target))
+        .collect::<Result<Vec<_>>>()?;
+    target.new_status(options.build(status, media_ids, Utc::now())?)
+}
+
+// Writes out mentions, which appear as `@username` in the text, as
+// `@username@domain`
+fn qualify_mentions(text: &str, mentions: &[Mention]) -> String {
+    let qualified = |handle: &str| -> Option<String> {
+        let mention = mentions.iter().find(|mention| mention.username == handle)?;
+        if mention.acct.contains('@') {
+            return Some(mention.acct.clone());
+        }
+        let domain = url::Url::parse(&mention.url).ok()?.host_str()?.to_string();
+        Some(format!("{}@{}", mention.acct, domain))
+    };
+
+    let mut out = String::with_capacity(text.len());
+    for word in text.split_inclusive(char::is_whitespace) {
+        // allowing for e.g. `(@alice`
+        let start = word
+            .find('@')
+            .filter(|&start| word[..start].chars().all(|c| c.is_ascii_punctuation()));
+        let start = match start {
+            Some(start) => start + 1,
+            None => {
+                out.push_str(word);
+                continue;
+            },
+        };
+        let end = word[start..]
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .map_or(word.len(), |end| start + end);
+        // e.g. `@alice@example.com`, which is already qualified
+        let qualified = if word[end..].starts_with('@') {
+            None
+        } else {
+            qualified(&word[start..end])
+        };
+        match qualified {
+            Some(qualified) => {
+                out.push_str(&word[..start]);
+                out.push_str(&qualified);
+                out.push_str(&word[end..]);
+            },
+            None => out.push_str(word),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{data::Data, test_fixtures, Mastodon};
+    use chrono::Duration;
+
+    fn status() -> Status {
+        let mut status = test_fixtures::status();
+        status.content = r#"<p>hi <span class="h-card"><a href="https://example.com/@bob" class="u-url mention">@<span>bob</span></a></span>, <span class="h-card"><a href="https://other.example/@carol" class="u-url mention">@<span>carol</span></a></span> &amp; @bobby</p><p>bye</p>"#.to_string();
+        status.media_attachments = Vec::new();
+        status.poll = None;
+        status.url = Some("https://example.com/@alice/110".to_string());
+        status
+    }
+
+    #[test]
+    fn test_build() {
+        let status = status();
+        let options = CrosspostOptions::new()
+            .link_back(true)
+            .max_visibility(Visibility::Private);
+        let new_status = options.build(&status, Vec::new(), Utc::now()).unwrap();
+        let json = serde_json::to_value(&new_status).unwrap();
+        assert_eq!(
+            json["status"],
+            "hi @bob@example.com, @carol@other.example & @bobby\n\nbye\n\nhttps://example.com/@alice/110"
+        );
+        assert_eq!(json["visibility"], "private");
+        assert_eq!(json["spoiler_text"], "food");
+    }
+
+    #[test]
+    fn test_build_unknown_visibility() {
+        let mut status = status();
+        status.visibility = Visibility::Unknown;
+        for options in [
+            CrosspostOptions::new(),
+            CrosspostOptions::new().max_visibility(Visibility::Public),
+        ] {
+            let new_status = options.build(&status, Vec::new(), Utc::now()).unwrap();
+            let json = serde_json::to_value(&new_status).unwrap();
+            assert_eq!(json["visibility"], "direct");
+        }
+    }
+
+    #[test]
+    fn test_build_poll() {
+        let mut status = status();
+        let now = Utc::now();
+        status.poll = Some(
+            serde_json::from_value(serde_json::json!({
+                "id": "1",
+                "expires_at": (now + Duration::hours(1)).to_rfc3339(),
+                "expired": false,
+                "multiple": true,
+                "votes_count": 3,
+                "options": [{"title": "yes", "votes_count": 2}, {"title": "no", "votes_count": 1}],
+                "emojis": []
+            }))
+            .unwrap(),
+        );
+        let new_status = CrosspostOptions::new()
+            .visibility(Visibility::Public)
+            .build(&status, Vec::new(), now)
+            .unwrap();
+        let json = serde_json::to_value(&new_status).unwrap();
+        assert_eq!(
+            json["poll"],
+            serde_json::json!({"options": ["yes", "no"], "expires_in": 3600, "multiple": true})
+        );
+        assert_eq!(json["visibility"], "public");
+
+        // an ended poll isn't copied
+        let later = now + Duration::hours(2);
+        let new_status = CrosspostOptions::new()
+            .build(&status, Vec::new(), later)
+            .unwrap();
+        let json = serde_json::to_value(&new_status).unwrap();
+        assert!(json.get("poll").is_none());
+    }
+
+    #[test]
+    fn test_crosspost() {
+        let mock = mockito::mock("POST", "/api/v1/statuses")
+            .match_body(mockito::Matcher::PartialJson(serde_json::json!({
+                "status": "hi @bob@example.com, @carol@other.example & @bobby\n\nbye",
+                "visibility": "unlisted",
+            })))
+            .with_header("content-type", "application/json")
+            .with_body(test_fixtures::STATUS)
+            .create();
+        let target = Mastodon::from(Data {
+            base: mockito::server_url().into(),
+            token: "token".into(),
+            ..Default::default()
+        });
+        let mut reblog = status();
+        reblog.content = String::new();
+        reblog.reblog = Some(Box::new(status()));
+
+        let copy = crosspost(&reblog, &target, &CrosspostOptions::new()).unwrap();
+        mock.assert();
+        assert_eq!(copy.id, "110");
+    }
+}
@@ -1,11 +1,16 @@
+use crate::scopes::Scopes;
+use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 
 /// Raw data about mastodon app. Save `Data` using `serde` to prevent needing
 /// to authenticate on every run.
-#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
 pub struct Data {
-    /// Base url of instance eg. `https://mastodon.social`.
+    /// Base url of instance eg. `https://mastodon.social`. When building a
+    /// client, `https://` is assumed if there is no scheme, and trailing
+    /// slashes are removed. Instances hosted under a path, e.g.
+    /// `https://example.com/masto`, keep it, and the API is expected below it.
     pub base: Cow<'static, str>,
     /// The client's id given by the instance.
     pub client_id: Cow<'static, str>,
@@ -15,4 +20,20 @@ pub struct Data {
     pub redirect: Cow<'static, str>,
     /// The client's access token.
     pub token: Cow<'static, str>,
+    /// When the access token was issued, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_created_at: Option<DateTime<Utc>>,
+    /// The scopes that were granted to the access token, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<Scopes>,
+    /// The key to use when subscribing to push notifications, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vapid_key: Option<String>,
+    /// The token to get a new access token with once this one expires, if
+    /// the instance issued one. See `Mastodon::refresh`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_token: Option<String>,
+    /// When the access token expires, if it does.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
 }
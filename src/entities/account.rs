@@ -1,15 +1,20 @@
 //! A module containing everything relating to a account returned from the api.
 
-use crate::status_builder;
+use super::status::Status;
+#[cfg(feature = "client")]
+use crate::{http_send::HttpSend, Mastodon};
+use crate::{language::Language, status_builder};
 use chrono::prelude::*;
 use serde::{
     de::{self, Unexpected},
     Deserialize, Serialize,
 };
+#[cfg(feature = "client")]
+use std::io;
 use std::path::PathBuf;
 
 /// A struct representing an Account.
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Account {
     /// Equals `username` for local users, includes `@domain` for remote ones.
     pub acct: String,
@@ -52,6 +57,18 @@ pub struct Account {
     pub fields: Option<Vec<MetadataField>>,
     /// Boolean indicating whether this account is a bot or not
     pub bot: Option<bool>,
+    /// The roles of the account that the instance shows publicly, e.g. to
+    /// mark its moderators. Mastodon 4.1 and newer.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<Role>,
+    /// The role of the authenticated account, only given by
+    /// `verify_credentials`. Mastodon 4.0 and newer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<Role>,
+    /// Pleroma and Akkoma specific attributes
+    #[cfg(feature = "pleroma")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pleroma: Option<super::pleroma::PleromaAccount>,
 }
 
 /// A single name: value pair from a user's profile
@@ -63,6 +80,79 @@ pub struct MetadataField {
     pub value: String,
 }
 
+/// A role given to accounts by the instance's admins, such as `Moderator`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct Role {
+    /// The ID of the role. Empty on GoToSocial, which only gives the name.
+    #[serde(default)]
+    pub id: String,
+    /// The name of the role
+    pub name: String,
+    /// The color to show the role in, as a hex code like `#ff3838`, or empty
+    /// for none
+    #[serde(default)]
+    pub color: String,
+    /// Whether the role is shown as a badge on profiles. Only given for the
+    /// role of the authenticated account.
+    #[serde(default)]
+    pub highlighted: bool,
+    /// The permissions of the role as a bitmask in a string. Only given for
+    /// the role of the authenticated account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub permissions: Option<String>,
+}
+
+/// A proof, published on another service, that the account belongs to the
+/// same person as an account there, e.g. on Keybase
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IdentityProof {
+    /// The name of the service, e.g. `Keybase`
+    pub provider: String,
+    /// The username of the account on the service
+    pub provider_username: String,
+    /// When the proof was last checked
+    pub updated_at: DateTime<Utc>,
+    /// URL of the proof on the service
+    pub proof_url: String,
+    /// URL of the account's profile on the service
+    pub profile_url: String,
+}
+
+/// The statuses shown on an account's profile, as fetched by `profile_view`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProfileView {
+    /// The statuses the account pinned to its profile
+    pub pinned: Vec<Status>,
+    /// The first page of the account's statuses, newest first. Pinned
+    /// statuses are included here as well, at the time they were posted.
+    pub statuses: Vec<Status>,
+}
+
+#[cfg(feature = "client")]
+impl Account {
+    /// Downloads the account's avatar into `writer` using `client`, see
+    /// [`Mastodon::download`](../../struct.Mastodon.html#method.download).
+    /// Returns the image's content type.
+    pub fn fetch_avatar<H: HttpSend, W: io::Write + ?Sized>(
+        &self,
+        client: &Mastodon<H>,
+        writer: &mut W,
+    ) -> crate::Result<Option<String>> {
+        client.download(&self.avatar, writer)
+    }
+
+    /// Downloads the account's header image into `writer` using `client`,
+    /// see [`Mastodon::download`](../../struct.Mastodon.html#method.download).
+    /// Returns the image's content type.
+    pub fn fetch_header<H: HttpSend, W: io::Write + ?Sized>(
+        &self,
+        client: &Mastodon<H>,
+        writer: &mut W,
+    ) -> crate::Result<Option<String>> {
+        client.download(&self.header, writer)
+    }
+}
+
 impl MetadataField {
     pub(crate) fn new(name: &str, value: &str) -> MetadataField {
         MetadataField {
@@ -73,13 +163,27 @@ impl MetadataField {
 }
 
 /// An extra object given from `verify_credentials` giving defaults about a user
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Source {
-    privacy: Option<status_builder::Visibility>,
+    /// The default visibility of new statuses
+    pub privacy: Option<status_builder::Visibility>,
+    /// Whether new statuses are marked sensitive by default
     #[serde(deserialize_with = "string_or_bool")]
-    sensitive: bool,
-    note: Option<String>,
-    fields: Option<Vec<MetadataField>>,
+    pub sensitive: bool,
+    /// The biography of the account, as plain text
+    pub note: Option<String>,
+    /// The profile metadata fields, as plain text
+    pub fields: Option<Vec<MetadataField>>,
+    /// The default language of new statuses
+    #[serde(
+        default,
+        deserialize_with = "super::status::language",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub language: Option<Language>,
+    /// The number of pending follow requests. Mastodon 3.0 and newer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_requests_count: Option<u64>,
 }
 
 fn string_or_bool<'de, D: de::Deserializer<'de>>(val: D) -> ::std::result::Result<bool, D::Error> {
@@ -107,41 +211,137 @@ fn string_or_bool<'de, D: de::Deserializer<'de>>(val: D) -> ::std::result::Resul
     })
 }
 
-#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub(crate) struct UpdateSource {
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) privacy: Option<status_builder::Visibility>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) sensitive: Option<bool>,
 }
 
-#[derive(Debug, Default, Serialize, PartialEq)]
+// The changes sent to `update_credentials`, as a multipart form
+#[derive(Debug, Default, PartialEq)]
 pub(crate) struct Credentials {
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) display_name: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) note: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) avatar: Option<PathBuf>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) header: Option<PathBuf>,
-    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) source: Option<UpdateSource>,
-    #[serde(serialize_with = "fields_attributes_ser::ser")]
     pub(crate) fields_attributes: Vec<MetadataField>,
 }
 
-mod fields_attributes_ser {
-    use super::*;
-    use serde::ser::{SerializeMap, Serializer};
-    pub(crate) fn ser<S>(attrs: &[MetadataField], serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut map = serializer.serialize_map(Some(attrs.len()))?;
-        for (i, field) in attrs.iter().enumerate() {
-            map.serialize_entry(&i, &field)?;
+impl Credentials {
+    // The text fields of the form. The profile fields are indexed, i.e.
+    // `fields_attributes[0][name]`, as that's what Rails parses into a list;
+    // some servers drop them if sent as JSON.
+    pub(crate) fn form_fields(&self) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        if let Some(ref display_name) = self.display_name {
+            fields.push(("display_name".to_string(), display_name.clone()));
+        }
+        if let Some(ref note) = self.note {
+            fields.push(("note".to_string(), note.clone()));
         }
-        map.end()
+        if let Some(ref source) = self.source {
+            // `Unknown` has no name the server knows, so it's left unchanged
+            let privacy = source
+                .privacy
+                .filter(|&privacy| privacy != status_builder::Visibility::Unknown);
+            if let Some(privacy) = privacy {
+                fields.push(("source[privacy]".to_string(), privacy.to_string()));
+            }
+            if let Some(sensitive) = source.sensitive {
+                fields.push(("source[sensitive]".to_string(), sensitive.to_string()));
+            }
+        }
+        for (i, field) in self.fields_attributes.iter().enumerate() {
+            fields.push((
+                format!("fields_attributes[{}][name]", i),
+                field.name.clone(),
+            ));
+            fields.push((
+                format!("fields_attributes[{}][value]", i),
+                field.value.clone(),
+            ));
+        }
+        fields
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn test_credentials_form_fields() {
+        let credentials = Credentials {
+            display_name: Some("Alice".to_string()),
+            source: Some(UpdateSource {
+                privacy: Some(status_builder::Visibility::Unlisted),
+                sensitive: Some(true),
+            }),
+            fields_attributes: vec![
+                MetadataField::new("web", "https://example.org"),
+                MetadataField::new("pronouns", "they/them"),
+            ],
+            ..Default::default()
+        };
+        let fields = credentials.form_fields();
+        let fields: Vec<(&str, &str)> = fields
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("display_name", "Alice"),
+                ("source[privacy]", "unlisted"),
+                ("source[sensitive]", "true"),
+                ("fields_attributes[0][name]", "web"),
+                ("fields_attributes[0][value]", "https://example.org"),
+                ("fields_attributes[1][name]", "pronouns"),
+                ("fields_attributes[1][value]", "they/them"),
+            ]
+        );
+        assert!(Credentials::default().form_fields().is_empty());
+    }
+
+    #[test]
+    fn test_deserialize_roles() {
+        let mut json: serde_json::Value = serde_json::from_str(test_fixtures::ACCOUNT).unwrap();
+        let account: Account = serde_json::from_value(json.clone()).unwrap();
+        assert!(account.roles.is_empty());
+        assert_eq!(account.role, None);
+
+        json["roles"] = serde_json::json!([{"id": "3", "name": "Owner", "color": "#ff3838"}]);
+        json["role"] = serde_json::json!({
+            "id": "3", "name": "Owner", "color": "#ff3838",
+            "permissions": "1048575", "highlighted": true
+        });
+        let account: Account = serde_json::from_value(json).unwrap();
+        assert_eq!(account.roles[0].name, "Owner");
+        assert!(!account.roles[0].highlighted);
+        let role = account.role.unwrap();
+        assert!(role.highlighted);
+        assert_eq!(role.permissions.as_deref(), Some("1048575"));
+    }
+
+    #[test]
+    #[cfg(feature = "language")]
+    fn test_deserialize_source() {
+        let source: Source = serde_json::from_str(
+            r#"{"privacy": "unlisted", "sensitive": false, "language": "de", "note": "hi",
+                "fields": [{"name": "web", "value": "https://example.org"}],
+                "follow_requests_count": 3}"#,
+        )
+        .unwrap();
+        assert_eq!(source.privacy, Some(status_builder::Visibility::Unlisted));
+        assert_eq!(source.language, Some(Language::Deu));
+        assert_eq!(source.fields.unwrap()[0].name, "web");
+        assert_eq!(source.follow_requests_count, Some(3));
+
+        let source: Source =
+            serde_json::from_str(r#"{"sensitive": "true", "language": ""}"#).unwrap();
+        assert!(source.sensitive);
+        assert_eq!(source.language, None);
     }
 }
@@ -0,0 +1,83 @@
+//! Module containing the entities of the admin API.
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A domain blocked by the instance, as seen by its moderators
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct DomainBlock {
+    /// The ID of the block.
+    pub id: String,
+    /// The blocked domain.
+    pub domain: String,
+    /// When the domain was blocked.
+    pub created_at: DateTime<Utc>,
+    /// What happens to the domain's accounts and statuses.
+    pub severity: DomainBlockSeverity,
+    /// Whether media from the domain is rejected.
+    pub reject_media: bool,
+    /// Whether reports from the domain are rejected.
+    pub reject_reports: bool,
+    /// A comment only shown to the moderators.
+    #[serde(default)]
+    pub private_comment: Option<String>,
+    /// A comment shown on the instance's about page.
+    #[serde(default)]
+    pub public_comment: Option<String>,
+    /// Whether the domain is partly hidden on the about page.
+    #[serde(default)]
+    pub obfuscate: bool,
+}
+
+/// What happens to a blocked domain's accounts and statuses
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum DomainBlockSeverity {
+    /// Their statuses are hidden from those who don't follow them
+    Silence,
+    /// Their accounts and statuses are removed
+    Suspend,
+    /// Nothing, only media or reports are rejected
+    Noop,
+    /// A value this crate doesn't know, e.g. one added by a newer version or
+    /// a fork of the server
+    Unknown,
+}
+
+deserialize_or_unknown!(DomainBlockSeverity {
+    "silence" => Silence,
+    "suspend" => Suspend,
+    "noop" => Noop,
+});
+
+impl fmt::Display for DomainBlockSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            DomainBlockSeverity::Silence => "silence",
+            DomainBlockSeverity::Suspend => "suspend",
+            DomainBlockSeverity::Noop => "noop",
+            DomainBlockSeverity::Unknown => "unknown",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_domain_block() {
+        let block: DomainBlock = serde_json::from_str(
+            r#"{"id": "1", "domain": "example.org", "created_at": "2022-11-16T08:15:34.238Z",
+                "severity": "silence", "reject_media": true, "reject_reports": false,
+                "private_comment": null, "public_comment": "spam", "obfuscate": false}"#,
+        )
+        .expect("Couldn't deserialize");
+        assert_eq!(block.severity, DomainBlockSeverity::Silence);
+        assert_eq!(block.public_comment.as_deref(), Some("spam"));
+        assert_eq!(
+            serde_json::from_str::<DomainBlockSeverity>(r#""limit""#).unwrap(),
+            DomainBlockSeverity::Unknown
+        );
+    }
+}
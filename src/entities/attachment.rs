@@ -1,15 +1,17 @@
 //! Module containing everything related to media attachements.
-use serde::Deserialize;
+use serde::{Deserialize, Deserializer, Serialize};
 
 /// A struct representing a media attachment.
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Attachment {
     /// ID of the attachment.
     pub id: String,
     /// The media type of an attachment.
     #[serde(rename = "type")]
     pub media_type: MediaType,
-    /// URL of the locally hosted version of the image.
+    /// URL of the locally hosted version of the image. Empty while the
+    /// server is still processing the media.
+    #[serde(deserialize_with = "null_as_empty")]
     pub url: String,
     /// For remote images, the remote URL of the original image.
     pub remote_url: Option<String>,
@@ -24,32 +26,58 @@ pub struct Attachment {
     pub description: Option<String>,
 }
 
+impl Attachment {
+    /// Whether the server is still processing the media, which it may do
+    /// after answering the upload of larger files. The attachment can't be
+    /// added to a status until it's done.
+    pub fn is_processing(&self) -> bool {
+        self.url.is_empty()
+    }
+}
+
+// Servers send a `null` URL while they are processing the media
+fn null_as_empty<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.unwrap_or_default())
+}
+
 /// Information about the attachment itself.
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Meta {
     /// Original version.
     pub original: Option<ImageDetails>,
     /// Smaller version.
     pub small: Option<ImageDetails>,
+    /// The point of an image to keep in view when it's cropped.
+    pub focus: Option<Focus>,
+}
+
+/// The focal point of an image, from -1.0 to 1.0 on both axes, with (0, 0)
+/// in the center and (1, 1) in the top right corner.
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct Focus {
+    /// Horizontal position.
+    pub x: f64,
+    /// Vertical position.
+    pub y: f64,
 }
 
 /// Dimensions of an attachement.
-#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct ImageDetails {
     /// width of attachment, can be null for audio files.
-    width: Option<u64>,
+    pub width: Option<u64>,
     /// height of attachment, can be null for audio files.
-    height: Option<u64>,
+    pub height: Option<u64>,
     /// A string of `widthxheight`.
-    size: Option<String>,
+    pub size: Option<String>,
     /// The aspect ratio of the attachment.
-    aspect: Option<f64>,
+    pub aspect: Option<f64>,
     /// Duration if this is a video/audio file in seconds.
-    duration: Option<f64>,
+    pub duration: Option<f64>,
 }
 
 /// The type of media attachment.
-#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum MediaType {
     /// An image.
     #[serde(rename = "image")]
@@ -63,7 +91,15 @@ pub enum MediaType {
     /// A audio file.
     #[serde(rename = "audio")]
     Audio,
-    /// Unknown format.
+    /// Unknown format, or a type this crate doesn't know.
     #[serde(rename = "unknown")]
     Unknown,
 }
+
+deserialize_or_unknown!(MediaType {
+    "image" => Image,
+    "video" => Video,
+    "gifv" => Gifv,
+    "audio" => Audio,
+    "unknown" => Unknown,
+});
@@ -1,8 +1,8 @@
 //! Module representing cards of statuses.
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// A card of a status.
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Card {
     /// The url associated with the card.
     pub url: String,
@@ -12,18 +12,18 @@ pub struct Card {
     pub description: String,
     /// The image associated with the card, if any.
     pub image: Option<String>,
-    /// OEmbed data
-    author_name: Option<String>,
-    /// OEmbed data
-    author_url: Option<String>,
-    /// OEmbed data
-    provider_name: Option<String>,
-    /// OEmbed data
-    provider_url: Option<String>,
-    /// OEmbed data
-    html: Option<String>,
-    /// OEmbed data
-    width: Option<u64>,
-    /// OEmbed data
-    height: Option<u64>,
+    /// OEmbed data: the author of the page
+    pub author_name: Option<String>,
+    /// OEmbed data: a link to the author of the page
+    pub author_url: Option<String>,
+    /// OEmbed data: the site the page is on
+    pub provider_name: Option<String>,
+    /// OEmbed data: a link to the site the page is on
+    pub provider_url: Option<String>,
+    /// OEmbed data: HTML to embed the page's media with
+    pub html: Option<String>,
+    /// OEmbed data: the width of the embedded media
+    pub width: Option<u64>,
+    /// OEmbed data: the height of the embedded media
+    pub height: Option<u64>,
 }
@@ -1,8 +1,18 @@
 //! A module about contexts of statuses.
+use std::collections::{HashMap, HashSet};
+
 use serde::Deserialize;
 
 use super::status::Status;
 
+/// The most ancestors the server returns to unauthenticated clients
+pub const UNAUTHENTICATED_ANCESTORS_LIMIT: usize = 40;
+/// The most descendants the server returns to unauthenticated clients
+pub const UNAUTHENTICATED_DESCENDANTS_LIMIT: usize = 60;
+/// The most ancestors or descendants the server returns to authenticated
+/// clients
+pub const AUTHENTICATED_CONTEXT_LIMIT: usize = 4096;
+
 /// A context of a status returning a list of statuses it replied to and
 /// statuses replied to it.
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -12,3 +22,232 @@ pub struct Context {
     /// Statuses that replied to this status.
     pub descendants: Vec<Status>,
 }
+
+impl Context {
+    /// Whether the server may have left out statuses because the thread is
+    /// larger than it is willing to return, i.e. whether either list is as
+    /// long as the server's limit
+    ///
+    /// Mastodon returns at most 40 ancestors and 60 descendants to
+    /// unauthenticated clients, and 4096 of each to authenticated ones.
+    pub fn may_be_truncated(&self, authenticated: bool) -> bool {
+        let (ancestors, descendants) = if authenticated {
+            (AUTHENTICATED_CONTEXT_LIMIT, AUTHENTICATED_CONTEXT_LIMIT)
+        } else {
+            (
+                UNAUTHENTICATED_ANCESTORS_LIMIT,
+                UNAUTHENTICATED_DESCENDANTS_LIMIT,
+            )
+        };
+        self.ancestors.len() >= ancestors || self.descendants.len() >= descendants
+    }
+}
+
+/// A status and its context, assembled into a tree of replies
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// use elefren::entities::context::Thread;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let data = Data {
+/// #   base: "".into(),
+/// #   client_id: "".into(),
+/// #   client_secret: "".into(),
+/// #   redirect: "".into(),
+/// #   token: "".into(),
+/// #   ..Default::default()
+/// # };
+/// let client = Mastodon::from(data);
+/// let status = client.get_status("109")?;
+/// let context = client.get_context(&status.id)?;
+/// let thread = Thread::new(status, context);
+/// for (depth, status) in thread.iter() {
+///     println!("{}{}", "  ".repeat(depth), status.content);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thread {
+    focus: String,
+    // the statuses whose parent is not part of the thread, oldest first; the
+    // first one is the start of the conversation
+    roots: Vec<String>,
+    statuses: HashMap<String, Status>,
+    replies: HashMap<String, Vec<String>>,
+}
+
+impl Thread {
+    /// Assembles `status` and its `context` into a thread
+    pub fn new(status: Status, context: Context) -> Thread {
+        let focus = status.id.clone();
+        let ordered: Vec<Status> = context
+            .ancestors
+            .into_iter()
+            .chain(Some(status))
+            .chain(context.descendants)
+            .collect();
+
+        let mut roots = Vec::new();
+        let mut replies: HashMap<String, Vec<String>> = HashMap::new();
+        {
+            let known: HashSet<&str> = ordered.iter().map(|s| s.id.as_str()).collect();
+            for status in &ordered {
+                match status.in_reply_to_id {
+                    Some(ref parent) if known.contains(parent.as_str()) => replies
+                        .entry(parent.clone())
+                        .or_default()
+                        .push(status.id.clone()),
+                    _ => roots.push(status.id.clone()),
+                }
+            }
+        }
+
+        let statuses = ordered
+            .into_iter()
+            .map(|status| (status.id.clone(), status))
+            .collect();
+        Thread {
+            focus,
+            roots,
+            statuses,
+            replies,
+        }
+    }
+
+    /// The status the thread was assembled around
+    pub fn focus(&self) -> &Status {
+        &self.statuses[&self.focus]
+    }
+
+    /// The first status of the conversation that is part of the thread, if
+    /// any; there is none when every status replies to another one of the
+    /// thread, as in a malformed context whose replies form a cycle
+    pub fn root(&self) -> Option<&Status> {
+        self.roots.first().map(|id| &self.statuses[id])
+    }
+
+    /// Whether the whole conversation is part of the thread, i.e. the root
+    /// isn't itself a reply, and every reply's parent is in the thread
+    pub fn is_complete(&self) -> bool {
+        self.roots.len() == 1 && matches!(self.root(), Some(root) if root.in_reply_to_id.is_none())
+    }
+
+    /// The status with the id `id`, if it is part of the thread
+    pub fn get(&self, id: &str) -> Option<&Status> {
+        self.statuses.get(id)
+    }
+
+    /// The status that the status with the id `id` replies to, if it is part
+    /// of the thread
+    pub fn parent(&self, id: &str) -> Option<&Status> {
+        let parent = self.statuses.get(id)?.in_reply_to_id.as_ref()?;
+        self.statuses.get(parent)
+    }
+
+    /// The direct replies to the status with the id `id`, in the order the
+    /// server returned them
+    pub fn replies(&self, id: &str) -> Vec<&Status> {
+        self.replies
+            .get(id)
+            .map(|ids| ids.iter().map(|id| &self.statuses[id]).collect())
+            .unwrap_or_default()
+    }
+
+    /// All statuses of the thread in depth-first order, with their depth
+    /// below the root they descend from. Statuses replying to a status that
+    /// isn't part of the thread come last, as separate subtrees.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Status)> {
+        let mut ordered = Vec::with_capacity(self.statuses.len());
+        let mut stack: Vec<(usize, &String)> = self.roots.iter().rev().map(|id| (0, id)).collect();
+        while let Some((depth, id)) = stack.pop() {
+            ordered.push((depth, &self.statuses[id]));
+            if let Some(replies) = self.replies.get(id) {
+                stack.extend(replies.iter().rev().map(|id| (depth + 1, id)));
+            }
+        }
+        ordered.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    fn status(id: &str, in_reply_to_id: Option<&str>) -> Status {
+        Status {
+            in_reply_to_id: in_reply_to_id.map(String::from),
+            ..test_fixtures::status_with_id(id)
+        }
+    }
+
+    #[test]
+    fn test_thread() {
+        let context = Context {
+            ancestors: vec![status("1", None)],
+            descendants: vec![
+                status("3", Some("2")),
+                status("4", Some("1")),
+                status("5", Some("3")),
+                status("6", Some("2")),
+            ],
+        };
+        let thread = Thread::new(status("2", Some("1")), context);
+        assert_eq!(thread.focus().id, "2");
+        assert_eq!(thread.root().map(|s| s.id.as_str()), Some("1"));
+        assert!(thread.is_complete());
+        assert_eq!(thread.parent("5").map(|s| s.id.as_str()), Some("3"));
+        let replies: Vec<_> = thread.replies("2").iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(replies, vec!["3", "6"]);
+        let order: Vec<_> = thread
+            .iter()
+            .map(|(depth, s)| (depth, s.id.as_str()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![(0, "1"), (1, "2"), (2, "3"), (3, "5"), (2, "6"), (1, "4")]
+        );
+    }
+
+    #[test]
+    fn test_incomplete_thread() {
+        let context = Context {
+            ancestors: vec![status("2", Some("1"))],
+            descendants: vec![status("4", Some("3"))],
+        };
+        let thread = Thread::new(status("5", Some("2")), context);
+        assert_eq!(thread.root().map(|s| s.id.as_str()), Some("2"));
+        assert!(!thread.is_complete());
+        let order: Vec<_> = thread
+            .iter()
+            .map(|(depth, s)| (depth, s.id.as_str()))
+            .collect();
+        assert_eq!(order, vec![(0, "2"), (1, "5"), (0, "4")]);
+    }
+
+    #[test]
+    fn test_thread_without_root() {
+        let context = Context {
+            ancestors: vec![status("1", Some("2"))],
+            descendants: vec![],
+        };
+        let thread = Thread::new(status("2", Some("1")), context);
+        assert!(thread.root().is_none());
+        assert!(!thread.is_complete());
+        assert_eq!(thread.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_may_be_truncated() {
+        let context = Context {
+            ancestors: vec![],
+            descendants: vec![status("1", None); UNAUTHENTICATED_DESCENDANTS_LIMIT],
+        };
+        assert!(context.may_be_truncated(false));
+        assert!(!context.may_be_truncated(true));
+    }
+}
@@ -0,0 +1,17 @@
+//! Module containing everything related to direct conversations.
+use serde::{Deserialize, Serialize};
+
+use super::{account::Account, status::Status};
+
+/// A conversation with direct messages
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct Conversation {
+    /// The ID of the conversation
+    pub id: String,
+    /// Whether the conversation has unread messages
+    pub unread: bool,
+    /// The participants of the conversation, except for the user
+    pub accounts: Vec<Account>,
+    /// The last message in the conversation
+    pub last_status: Option<Status>,
+}
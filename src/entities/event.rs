@@ -1,15 +1,108 @@
-use crate::entities::{notification::Notification, status::Status};
+use crate::entities::{
+    conversation::Conversation,
+    notification::Notification,
+    status::{Status, StatusId},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "event", content = "payload", rename_all = "snake_case")]
 #[allow(clippy::large_enum_variant)]
-/// Events that come from the /streaming/user API call
+/// Events that come from the streaming API
+///
+/// Which events are sent depends on the stream:
+///
+/// | Stream                      | Events                                                               |
+/// |-----------------------------|----------------------------------------------------------------------|
+/// | `User`                      | `Update`, `StatusUpdate`, `Notification`, `Delete`, `FiltersChanged` |
+/// | `Public`, `Hashtag`, `List` | `Update`, `StatusUpdate`, `Delete`                                   |
+/// | `Direct`                    | `Conversation`                                                       |
+///
+/// Events serialize to an adjacently tagged object, e.g.
+/// `{"event":"delete","payload":"1234"}` or `{"event":"filters_changed"}`, so
+/// they can be logged or forwarded and deserialized again later.
 pub enum Event {
     /// Update event
     Update(Status),
     /// Notification event
     Notification(Notification),
-    /// Delete event
-    Delete(String),
+    /// A status was edited; carries the status as it is now
+    #[serde(rename = "status.update")]
+    StatusUpdate(Status),
+    /// A status was deleted; carries its ID
+    Delete(StatusId),
     /// FiltersChanged event
     FiltersChanged,
+    /// A direct conversation was started or received a new message
+    Conversation(Conversation),
+}
+
+/// An `Event` together with when it was received and the payload it was
+/// parsed from, as returned by `EventReader::next_envelope`
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct EventEnvelope {
+    /// The event
+    pub event: Event,
+    /// When the message carrying the event was read from the stream
+    pub received_at: DateTime<Utc>,
+    /// The payload of the event exactly as the server sent it, e.g. the JSON
+    /// of the status for an `Update`. `None` for events without a payload,
+    /// such as `FiltersChanged`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+}
+
+impl From<Notification> for Event {
+    fn from(notification: Notification) -> Event {
+        Event::Notification(notification)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::status;
+
+    #[test]
+    fn test_serialize_unit_and_string_events() {
+        assert_eq!(
+            serde_json::to_string(&Event::FiltersChanged).expect("Couldn't serialize"),
+            r#"{"event":"filters_changed"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Event::Delete(StatusId::new("1234").unwrap()))
+                .expect("Couldn't serialize"),
+            r#"{"event":"delete","payload":"1234"}"#
+        );
+    }
+
+    #[test]
+    fn test_conversation() {
+        let status = status();
+        let event = Event::Conversation(Conversation {
+            id: "418450".to_string(),
+            unread: true,
+            accounts: vec![status.account.clone()],
+            last_status: Some(status),
+        });
+        let ser = serde_json::to_string(&event).expect("Couldn't serialize event");
+        assert!(ser.starts_with(r#"{"event":"conversation","payload":{"id":"418450""#));
+        let de: Event = serde_json::from_str(&ser).expect("Couldn't deserialize event");
+        assert_eq!(event, de);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let status = status();
+        for event in [
+            Event::Update(status),
+            Event::Delete(StatusId::new("1234").unwrap()),
+            Event::FiltersChanged,
+        ] {
+            let ser = serde_json::to_string(&event).expect("Couldn't serialize event");
+            let de: Event = serde_json::from_str(&ser).expect("Couldn't deserialize event");
+            assert_eq!(event, de);
+        }
+    }
 }
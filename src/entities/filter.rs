@@ -1,18 +1,27 @@
+use crate::{entities::status::Status, helpers::html};
+use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
 
 /// Represents a single Filter
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Filter {
-    id: String,
-    phrase: String,
-    context: Vec<FilterContext>,
-    expires_at: Option<String>, // TODO: timestamp
-    irreversible: bool,
-    whole_word: bool,
+    /// The ID of the filter
+    pub id: String,
+    /// The text that is filtered
+    pub phrase: String,
+    /// The contexts in which the filter is applied
+    pub context: Vec<FilterContext>,
+    /// When the filter expires, if ever
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Whether matching statuses are dropped by the server, rather than
+    /// hidden by clients
+    pub irreversible: bool,
+    /// Whether `phrase` only matches whole words
+    pub whole_word: bool,
 }
 
 /// Represents the various types of Filter contexts
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
 pub enum FilterContext {
     /// Represents the "home" context
     #[serde(rename = "home")]
@@ -26,4 +35,298 @@ pub enum FilterContext {
     /// Represents the "thread" context
     #[serde(rename = "thread")]
     Thread,
+    /// Represents the "account" context, i.e. profiles
+    #[serde(rename = "account")]
+    Account,
+    /// A value this crate doesn't know, e.g. one added by a newer version or
+    /// a fork of the server
+    #[serde(rename = "unknown")]
+    Unknown,
+}
+
+deserialize_or_unknown!(FilterContext {
+    "home" => Home,
+    "notifications" => Notifications,
+    "public" => Public,
+    "thread" => Thread,
+    "account" => Account,
+});
+
+/// What a client should do with a status that matched a filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterAction {
+    /// Show the status behind a warning naming the matched filters
+    Warn,
+    /// Drop the status entirely
+    Hide,
+    /// A value this crate doesn't know, e.g. one added by a newer version or
+    /// a fork of the server
+    Unknown,
+}
+
+deserialize_or_unknown!(FilterAction {
+    "warn" => Warn,
+    "hide" => Hide,
+});
+
+/// A filter, as returned by the v2 filters API
+///
+/// Unlike a v1 `Filter`, it groups several keywords, and can also list
+/// individual statuses that are filtered.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterV2 {
+    /// The ID of the filter
+    pub id: String,
+    /// A title given by the user to name the filter
+    pub title: String,
+    /// The contexts in which the filter is applied
+    pub context: Vec<FilterContext>,
+    /// When the filter expires, if ever
+    pub expires_at: Option<DateTime<Utc>>,
+    /// What to do with a status that matches the filter
+    pub filter_action: FilterAction,
+    /// The keywords grouped under this filter
+    #[serde(default)]
+    pub keywords: Vec<FilterKeyword>,
+    /// The statuses grouped under this filter
+    #[serde(default)]
+    pub statuses: Vec<FilterStatus>,
+}
+
+/// A keyword that, when matched, causes a v2 filter to apply
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterKeyword {
+    /// The ID of the keyword
+    pub id: String,
+    /// The phrase to be matched against
+    pub keyword: String,
+    /// Whether the keyword only matches on word boundaries
+    pub whole_word: bool,
+}
+
+/// A single status that a v2 filter applies to, regardless of its content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FilterStatus {
+    /// The ID of the filter status
+    pub id: String,
+    /// The ID of the filtered status
+    pub status_id: String,
+}
+
+/// The filters that matched a status, and the resulting action
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterResult<'a> {
+    /// Every filter that matched the status
+    pub filters: Vec<&'a Filter>,
+    /// `Hide` if any of the matched filters is irreversible, `Warn` otherwise
+    pub action: FilterAction,
+}
+
+impl Filter {
+    /// When the filter expires, if ever
+    pub fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.expires_at
+    }
+
+    /// Whether this filter has an `expires_at` in the past
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .map(|expires_at| expires_at < Utc::now())
+            .unwrap_or(false)
+    }
+
+    /// Whether this filter is active in the given context
+    pub fn applies_to(&self, context: FilterContext) -> bool {
+        self.context.contains(&context)
+    }
+
+    /// Checks the filter against a status the same way the Mastodon web UI
+    /// does: the phrase is matched case-insensitively against the spoiler
+    /// text, the plain-text content, poll options and media descriptions. For
+    /// reblogs, the reblogged status is checked.
+    ///
+    /// Expired filters, and filters that aren't active in `context`, never
+    /// match.
+    pub fn matches(&self, status: &Status, context: FilterContext) -> bool {
+        if self.is_expired() || !self.applies_to(context) {
+            return false;
+        }
+        let status = status.reblog.as_deref().unwrap_or(status);
+        phrase_matches(&searchable_text(status), &self.phrase, self.whole_word)
+    }
+}
+
+/// Evaluates `filters` against a status as it would appear in `context`,
+/// returning the matched filters and the action to take, or `None` if the
+/// status should be shown as-is.
+///
+/// This is useful for streaming consumers, who receive unfiltered statuses.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// # use std::error::Error;
+/// use elefren::entities::{
+///     event::Event,
+///     filter::{apply_filters, FilterAction, FilterContext},
+/// };
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let data = Data {
+/// #   base: "".into(),
+/// #   client_id: "".into(),
+/// #   client_secret: "".into(),
+/// #   redirect: "".into(),
+/// #   token: "".into(),
+/// #   ..Default::default()
+/// # };
+/// let client = Mastodon::from(data);
+/// let filters = client.get_filters()?;
+/// for event in client.streaming_user()? {
+///     if let Event::Update(ref status) = event {
+///         match apply_filters(&filters, status, FilterContext::Home) {
+///             Some(ref result) if result.action == FilterAction::Hide => continue,
+///             Some(_) => println!("[filtered] {}", status.id),
+///             None => println!("{}", status.content),
+///         }
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn apply_filters<'a>(
+    filters: &'a [Filter],
+    status: &Status,
+    context: FilterContext,
+) -> Option<FilterResult<'a>> {
+    let matched: Vec<&Filter> = filters
+        .iter()
+        .filter(|filter| filter.matches(status, context))
+        .collect();
+    if matched.is_empty() {
+        return None;
+    }
+    let action = if matched.iter().any(|filter| filter.irreversible) {
+        FilterAction::Hide
+    } else {
+        FilterAction::Warn
+    };
+    Some(FilterResult {
+        filters: matched,
+        action,
+    })
+}
+
+fn searchable_text(status: &Status) -> String {
+    let mut parts = vec![
+        status.spoiler_text.clone(),
+        html::to_plain_text(&status.content),
+    ];
+    if let Some(ref poll) = status.poll {
+        parts.extend(poll.options.iter().map(|option| option.title.clone()));
+    }
+    parts.extend(
+        status
+            .media_attachments
+            .iter()
+            .filter_map(|attachment| attachment.description.clone()),
+    );
+    parts.join("\n\n")
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn phrase_matches(text: &str, phrase: &str, whole_word: bool) -> bool {
+    let text = text.to_lowercase();
+    let phrase = phrase.trim().to_lowercase();
+    if phrase.is_empty() {
+        return false;
+    }
+    if !whole_word {
+        return text.contains(&phrase);
+    }
+
+    // like the web UI, only require a word boundary on the sides of the phrase
+    // that start or end with a word character
+    let check_start = phrase.chars().next().is_some_and(is_word_char);
+    let check_end = phrase.chars().last().is_some_and(is_word_char);
+    text.match_indices(&phrase).any(|(idx, found)| {
+        let before = text[..idx].chars().last();
+        let after = text[idx + found.len()..].chars().next();
+        (!check_start || !before.is_some_and(is_word_char))
+            && (!check_end || !after.is_some_and(is_word_char))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(phrase: &str, whole_word: bool) -> Filter {
+        Filter {
+            id: "1".to_string(),
+            phrase: phrase.to_string(),
+            context: vec![FilterContext::Home],
+            expires_at: None,
+            irreversible: false,
+            whole_word,
+        }
+    }
+
+    #[test]
+    fn test_phrase_matches() {
+        assert!(phrase_matches("I like Rust a lot", "rust", false));
+        assert!(phrase_matches("rustacean", "rust", false));
+        assert!(phrase_matches("I like Rust a lot", "rust", true));
+        assert!(!phrase_matches("rustacean", "rust", true));
+        assert!(phrase_matches("see #rust", "#rust", true));
+        assert!(!phrase_matches("nothing here", "rust", false));
+        assert!(!phrase_matches("anything", "  ", false));
+    }
+
+    #[test]
+    fn test_context_and_expiry() {
+        let mut f = filter("foo", false);
+        assert!(f.applies_to(FilterContext::Home));
+        assert!(!f.applies_to(FilterContext::Public));
+        assert!(!f.is_expired());
+        f.expires_at = Some("2000-01-01T00:00:00.000Z".parse().unwrap());
+        assert!(f.is_expired());
+        f.expires_at = Some("2999-01-01T00:00:00.000Z".parse().unwrap());
+        assert!(!f.is_expired());
+    }
+
+    #[test]
+    fn test_deserialize_expires_at() {
+        let f: Filter = serde_json::from_str(include_str!("../../tests/fixtures/filter.json"))
+            .expect("Couldn't parse filter");
+        assert_eq!(f.expires_at(), None);
+        let f: Filter = serde_json::from_str(
+            r#"{"id":"1","phrase":"foo","context":["home","thread"],"whole_word":true,
+                "expires_at":"2019-11-26T09:08:06.254Z","irreversible":false}"#,
+        )
+        .expect("Couldn't parse filter");
+        assert_eq!(f.expires_at(), "2019-11-26T09:08:06.254Z".parse().ok());
+        assert!(f.applies_to(FilterContext::Thread));
+    }
+
+    #[test]
+    fn test_unknown_context() {
+        let contexts: Vec<FilterContext> =
+            serde_json::from_str(r#"["home", "account", "chats"]"#).expect("Couldn't parse");
+        assert_eq!(
+            contexts,
+            vec![
+                FilterContext::Home,
+                FilterContext::Account,
+                FilterContext::Unknown
+            ]
+        );
+        let action: FilterAction = serde_json::from_str(r#""blur""#).expect("Couldn't parse");
+        assert_eq!(action, FilterAction::Unknown);
+    }
 }
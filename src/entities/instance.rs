@@ -1,6 +1,11 @@
 //! Module containing everything related to an instance.
 use super::account::Account;
+#[cfg(any(feature = "client", feature = "async", feature = "wasm"))]
+use serde::de::{DeserializeSeed, Deserializer, SeqAccess, Visitor};
 use serde::Deserialize;
+use std::collections::HashMap;
+#[cfg(any(feature = "client", feature = "async", feature = "wasm"))]
+use std::fmt;
 
 /// A struct containing info of an instance.
 #[derive(Debug, Clone, Deserialize, PartialEq)]
@@ -30,6 +35,18 @@ pub struct Instance {
     pub max_toot_chars: Option<u32>,
 }
 
+impl Instance {
+    /// Whether the instance runs glitch-soc or Hometown, which support
+    /// local-only statuses, see `StatusBuilder::local_only`
+    ///
+    /// This is detected from the version, e.g. `4.1.2+glitch` or
+    /// `3.5.3+hometown-1.0.8`.
+    pub fn supports_local_only(&self) -> bool {
+        let version = self.version.to_lowercase();
+        version.contains("glitch") || version.contains("hometown")
+    }
+}
+
 /// Object containing url for streaming api.
 #[derive(Debug, Clone, Deserialize, PartialEq)]
 pub struct StreamingApi {
@@ -38,9 +55,111 @@ pub struct StreamingApi {
 }
 
 /// Statistics about the Mastodon instance.
-#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Hash)]
 pub struct Stats {
-    user_count: u64,
-    status_count: u64,
-    domain_count: u64,
+    /// The number of accounts on the instance.
+    pub user_count: u64,
+    /// The number of statuses posted on the instance.
+    pub status_count: u64,
+    /// The number of other instances this one knows of.
+    pub domain_count: u64,
+}
+
+/// A rule of the instance, which users agree to when signing up and which
+/// reports can refer to, see `ReportRequest::rule_id`
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq, Hash)]
+pub struct Rule {
+    /// The rule's id
+    pub id: String,
+    /// The rule itself
+    pub text: String,
+    /// A longer explanation of the rule, on newer servers
+    pub hint: Option<String>,
+}
+
+/// The languages the instance can translate statuses between, mapping each
+/// source language code to the codes of the languages it can be translated
+/// to.
+pub type TranslationLanguages = HashMap<String, Vec<String>>;
+
+// Deserializes the list of an instance's peers, only keeping the domains
+// that contain the given text, ignoring case. Large instances know tens of
+// thousands of peers, so the others are dropped as they are read instead of
+// collecting them all first.
+#[cfg(any(feature = "client", feature = "async", feature = "wasm"))]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PeersMatching<'a>(pub(crate) &'a str);
+
+#[cfg(any(feature = "client", feature = "async", feature = "wasm"))]
+impl<'de, 'a> DeserializeSeed<'de> for PeersMatching<'a> {
+    type Value = Vec<String>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Vec<String>, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+#[cfg(any(feature = "client", feature = "async", feature = "wasm"))]
+impl<'de, 'a> Visitor<'de> for PeersMatching<'a> {
+    type Value = Vec<String>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of domains")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<String>, A::Error> {
+        let needle = self.0.to_lowercase();
+        let mut peers = Vec::new();
+        while let Some(peer) = seq.next_element::<String>()? {
+            if peer.to_lowercase().contains(&needle) {
+                peers.push(peer);
+            }
+        }
+        Ok(peers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(version: &str) -> Instance {
+        Instance {
+            uri: "example.com".to_string(),
+            title: "Example".to_string(),
+            description: String::new(),
+            email: String::new(),
+            version: version.to_string(),
+            urls: None,
+            stats: None,
+            thumbnail: None,
+            languages: None,
+            contact_account: None,
+            max_toot_chars: None,
+        }
+    }
+
+    #[test]
+    fn test_supports_local_only() {
+        assert!(instance("4.1.2+glitch").supports_local_only());
+        assert!(instance("3.5.3+hometown-1.0.8").supports_local_only());
+        assert!(!instance("4.1.2").supports_local_only());
+        assert!(!instance("2.7.2 (compatible; Pleroma 2.5.0)").supports_local_only());
+    }
+
+    #[cfg(any(feature = "client", feature = "async", feature = "wasm"))]
+    #[test]
+    fn test_peers_matching() {
+        let json = r#"["mastodon.social", "fosstodon.org", "Social.Example", "pleroma.site"]"#;
+        let mut deserializer = serde_json::Deserializer::from_str(json);
+        let peers = PeersMatching("SOCIAL")
+            .deserialize(&mut deserializer)
+            .unwrap();
+        assert_eq!(peers, ["mastodon.social", "Social.Example"]);
+
+        let mut deserializer = serde_json::Deserializer::from_str(r#"{"error": "nope"}"#);
+        assert!(PeersMatching("social")
+            .deserialize(&mut deserializer)
+            .is_err());
+    }
 }
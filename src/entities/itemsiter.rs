@@ -1,5 +1,14 @@
-use crate::page::Page;
+use crate::{
+    errors::Result,
+    http_send::HttpSend,
+    page::{OwnedPage, Page},
+};
 use serde::Deserialize;
+use std::{
+    mem,
+    sync::mpsc::{self, Receiver},
+    thread, vec,
+};
 
 /// Abstracts away the `next_page` logic into a single stream of items
 ///
@@ -14,6 +23,7 @@ use serde::Deserialize;
 /// #   client_secret: "".into(),
 /// #   redirect: "".into(),
 /// #   token: "".into(),
+/// #   ..Default::default()
 /// # };
 /// let client = Mastodon::from(data);
 /// let statuses = client.statuses("user-id", None)?;
@@ -24,15 +34,15 @@ use serde::Deserialize;
 /// # }
 /// ```
 #[derive(Debug, Clone)]
-pub(crate) struct ItemsIter<'a, T: Clone + for<'de> Deserialize<'de>> {
-    page: Page<'a, T>,
+pub(crate) struct ItemsIter<'a, T: Clone + for<'de> Deserialize<'de>, H: 'a + HttpSend> {
+    page: Page<'a, T, H>,
     buffer: Vec<T>,
     cur_idx: usize,
     use_initial: bool,
 }
 
-impl<'a, T: Clone + for<'de> Deserialize<'de>> ItemsIter<'a, T> {
-    pub(crate) fn new(page: Page<'a, T>) -> ItemsIter<'a, T> {
+impl<'a, T: Clone + for<'de> Deserialize<'de>, H: HttpSend> ItemsIter<'a, T, H> {
+    pub(crate) fn new(page: Page<'a, T, H>) -> ItemsIter<'a, T, H> {
         ItemsIter {
             page,
             buffer: vec![],
@@ -46,31 +56,29 @@ impl<'a, T: Clone + for<'de> Deserialize<'de>> ItemsIter<'a, T> {
     }
 
     fn fill_next_page(&mut self) -> Option<()> {
-        let items = if let Ok(items) = self.page.next_page() {
-            items
-        } else {
-            return None;
-        };
-        if let Some(items) = items {
-            if items.is_empty() {
-                return None;
+        // empty pages can still link to further ones
+        loop {
+            match self.page.next_page() {
+                Ok(Some(items)) if items.is_empty() => continue,
+                Ok(Some(items)) => {
+                    self.buffer = items;
+                    self.cur_idx = 0;
+                    return Some(());
+                },
+                _ => return None,
             }
-            self.buffer = items;
-            self.cur_idx = 0;
-            Some(())
-        } else {
-            None
         }
     }
 }
 
-impl<'a, T: Clone + for<'de> Deserialize<'de>> Iterator for ItemsIter<'a, T> {
+impl<'a, T: Clone + for<'de> Deserialize<'de>, H: HttpSend> Iterator for ItemsIter<'a, T, H> {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.use_initial {
-            if self.page.initial_items.is_empty() || self.cur_idx == self.page.initial_items.len() {
-                return None;
+            if self.page.initial_items.is_empty() {
+                self.use_initial = false;
+                return self.next();
             }
             let idx = self.cur_idx;
             if self.cur_idx == self.page.initial_items.len() - 1 {
@@ -90,3 +98,121 @@ impl<'a, T: Clone + for<'de> Deserialize<'de>> Iterator for ItemsIter<'a, T> {
         }
     }
 }
+
+/// Like `ItemsIter`, but yields the error that ends the iteration, instead of
+/// ending as if there were no more items
+#[derive(Debug)]
+pub(crate) struct TryItemsIter<'a, T: for<'de> Deserialize<'de>, H: 'a + HttpSend> {
+    page: Page<'a, T, H>,
+    buffer: vec::IntoIter<T>,
+    done: bool,
+}
+
+impl<'a, T: for<'de> Deserialize<'de>, H: HttpSend> TryItemsIter<'a, T, H> {
+    pub(crate) fn new(mut page: Page<'a, T, H>) -> TryItemsIter<'a, T, H> {
+        let buffer = mem::take(&mut page.initial_items);
+        TryItemsIter {
+            page,
+            buffer: buffer.into_iter(),
+            done: false,
+        }
+    }
+}
+
+impl<'a, T: for<'de> Deserialize<'de>, H: HttpSend> Iterator for TryItemsIter<'a, T, H> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.buffer.next() {
+                return Some(Ok(item));
+            }
+            if self.done {
+                return None;
+            }
+            match self.page.next_page() {
+                Ok(Some(items)) => self.buffer = items.into_iter(),
+                Ok(None) => self.done = true,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                },
+            }
+        }
+    }
+}
+
+/// Walks pages backwards through their `prev` links, yielding the items of
+/// each page in reverse, i.e. from oldest to newest
+#[derive(Debug, Clone)]
+pub(crate) struct RevItemsIter<'a, T: for<'de> Deserialize<'de>, H: 'a + HttpSend> {
+    page: Page<'a, T, H>,
+    // items of the current page, newest first, so that `pop` yields the
+    // oldest
+    buffer: Vec<T>,
+}
+
+impl<'a, T: for<'de> Deserialize<'de>, H: HttpSend> RevItemsIter<'a, T, H> {
+    pub(crate) fn new(mut page: Page<'a, T, H>) -> RevItemsIter<'a, T, H> {
+        let buffer = mem::take(&mut page.initial_items);
+        RevItemsIter { page, buffer }
+    }
+}
+
+impl<'a, T: for<'de> Deserialize<'de>, H: HttpSend> Iterator for RevItemsIter<'a, T, H> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.buffer.pop() {
+                return Some(item);
+            }
+            match self.page.prev_page() {
+                Ok(Some(items)) => self.buffer = items,
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// Like `ItemsIter`, but fetches the next page on a background thread while
+/// the current one is being consumed
+#[derive(Debug)]
+pub(crate) struct PrefetchingItemsIter<T> {
+    buffer: vec::IntoIter<T>,
+    pages: Receiver<Vec<T>>,
+}
+
+impl<T: for<'de> Deserialize<'de> + Send + 'static> PrefetchingItemsIter<T> {
+    pub(crate) fn new<H: HttpSend + Send + 'static>(
+        mut page: OwnedPage<T, H>,
+    ) -> PrefetchingItemsIter<T> {
+        let buffer = mem::take(&mut page.initial_items).into_iter();
+        // a rendezvous channel, so that the thread stays exactly one page
+        // ahead of the consumer
+        let (tx, pages) = mpsc::sync_channel(0);
+        thread::spawn(move || {
+            while let Ok(Some(items)) = page.next_page() {
+                // empty pages can still link to further ones; stop when the
+                // iterator was dropped
+                if !items.is_empty() && tx.send(items).is_err() {
+                    break;
+                }
+            }
+        });
+        PrefetchingItemsIter { buffer, pages }
+    }
+}
+
+impl<T> Iterator for PrefetchingItemsIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.buffer.next() {
+                return Some(item);
+            }
+            self.buffer = self.pages.recv().ok()?.into_iter();
+        }
+    }
+}
@@ -1,7 +1,53 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 /// Used for ser/de of list resources
 #[derive(Clone, Debug, Deserialize, PartialEq)]
 pub struct List {
-    id: String,
-    title: String,
+    /// The ID of the list
+    pub id: String,
+    /// The user-defined title of the list
+    pub title: String,
+    /// Which replies are shown in the list
+    #[serde(default)]
+    pub replies_policy: RepliesPolicy,
+    /// Whether the list's members are removed from the home timeline
+    #[serde(default)]
+    pub exclusive: bool,
 }
+
+/// The changes `reconcile_list` made to a list's members
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ListReconciliation {
+    /// The ids of the accounts that were added to the list
+    pub added: Vec<String>,
+    /// The ids of the accounts that were removed from the list
+    pub removed: Vec<String>,
+}
+
+impl ListReconciliation {
+    /// Whether the list already had the desired members
+    pub fn is_unchanged(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Which replies are shown in a list
+#[derive(Clone, Copy, Debug, Default, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RepliesPolicy {
+    /// Replies to any followed user
+    Followed,
+    /// Replies to members of the list
+    #[default]
+    List,
+    /// No replies at all
+    None,
+    /// A value this crate doesn't know, e.g. one added by a newer version or
+    /// a fork of the server
+    Unknown,
+}
+
+deserialize_or_unknown!(RepliesPolicy {
+    "followed" => Followed,
+    "list" => List,
+    "none" => None,
+});
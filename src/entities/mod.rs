@@ -1,21 +1,67 @@
 use serde::Deserialize;
 
+// Implements `Deserialize` for an enum sent as a string, with an `Unknown`
+// variant for the values that aren't listed, e.g. ones added by a newer
+// version or a fork of the server. They are logged, rather than failing the
+// whole response.
+macro_rules! deserialize_or_unknown {
+    ($name:ident { $($value:literal => $variant:ident,)* }) => {
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> ::std::result::Result<Self, D::Error>
+            where
+                D: serde::Deserializer<'de>,
+            {
+                let value = <String as serde::Deserialize>::deserialize(deserializer)?;
+                Ok(match value.as_str() {
+                    $($value => $name::$variant,)*
+                    _ => {
+                        log::warn!("unknown {} `{}`", stringify!($name), value);
+                        $name::Unknown
+                    },
+                })
+            }
+        }
+    };
+}
+
+// Implements `Eq`, and `Hash` by the `id` alone, for entities the server
+// identifies by it, so that they can be deduplicated with sets and maps.
+// `Attachment` is left out: its `PartialEq` compares floats, so it isn't `Eq`.
+macro_rules! hash_by_id {
+    ($($name:ty,)*) => {
+        $(
+            impl Eq for $name {}
+
+            impl std::hash::Hash for $name {
+                fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                    self.id.hash(state);
+                }
+            }
+        )*
+    };
+}
+
 /// Data structures for ser/de of account-related resources
 pub mod account;
 /// Data structures for ser/de of activity-related resources
 pub mod activity;
+/// Data structures for ser/de of admin API resources
+pub mod admin;
 /// Data structures for ser/de of attachment-related resources
 pub mod attachment;
 /// Data structures for ser/de of card-related resources
 pub mod card;
 /// Data structures for ser/de of contetx-related resources
 pub mod context;
+/// Data structures for ser/de of direct conversations
+pub mod conversation;
 /// Data structures for ser/de of streaming events
 pub mod event;
 /// Data structures for ser/de of filter-related resources
 pub mod filter;
 /// Data structures for ser/de of instance-related resources
 pub mod instance;
+#[cfg(feature = "client")]
 pub(crate) mod itemsiter;
 /// Data structures for ser/de of list-related resources
 pub mod list;
@@ -23,6 +69,9 @@ pub mod list;
 pub mod mention;
 /// Data structures for ser/de of notification-related resources
 pub mod notification;
+/// Data structures for ser/de of Pleroma and Akkoma extensions
+#[cfg(feature = "pleroma")]
+pub mod pleroma;
 /// Data structures for ser/de of poll resources
 pub mod poll;
 /// Data structures for ser/de of push-subscription-related resources
@@ -31,12 +80,34 @@ pub mod push;
 pub mod relationship;
 /// Data structures for ser/de of report-related resources
 pub mod report;
+/// Data structures for ser/de of scheduled statuses
+pub mod scheduled_status;
 /// Data structures for ser/de of search-related resources
 pub mod search_result;
 /// Data structures for ser/de of status-related resources
 pub mod status;
+/// Data structures for ser/de of follow suggestions
+pub mod suggestion;
+
+hash_by_id! {
+    account::Account,
+    admin::DomainBlock,
+    conversation::Conversation,
+    filter::Filter,
+    filter::FilterV2,
+    list::List,
+    notification::Notification,
+    poll::Poll,
+    relationship::Relationship,
+    report::Report,
+    scheduled_status::ScheduledStatus,
+    status::Status,
+}
 
 /// An empty JSON object.
+///
+/// Routes that answer with one return `()` instead; this is kept for code
+/// deserializing such responses itself.
 #[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
 pub struct Empty {}
 
@@ -45,21 +116,26 @@ pub struct Empty {}
 /// modules:
 pub mod prelude {
     pub use super::{
-        account::{Account, Source},
+        account::{Account, IdentityProof, ProfileView, Role, Source},
+        admin::DomainBlock,
         attachment::{Attachment, MediaType},
         card::Card,
         context::Context,
-        event::Event,
-        filter::{Filter, FilterContext},
+        conversation::Conversation,
+        event::{Event, EventEnvelope},
+        filter::{Filter, FilterContext, FilterKeyword, FilterStatus, FilterV2},
         instance::*,
-        list::List,
+        list::{List, ListReconciliation},
         mention::Mention,
-        notification::Notification,
+        notification::{Notification, NotificationType},
+        poll::Poll,
         push::Subscription,
         relationship::Relationship,
         report::Report,
+        scheduled_status::ScheduledStatus,
         search_result::{SearchResult, SearchResultV2},
-        status::{Application, Emoji, Status},
+        status::{Application, Emoji, Status, StatusId, StatusSummary, Tag},
+        suggestion::{Suggestion, SuggestionSource},
         Empty,
     };
 }
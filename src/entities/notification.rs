@@ -1,11 +1,17 @@
 //! Module containing all info about notifications.
 
-use super::{account::Account, status::Status};
+use super::{account::Account, report::Report, status::Status};
+#[cfg(feature = "client")]
+use crate::{
+    errors::{Error, Result},
+    http_send::HttpSend,
+    mastodon_client::MastodonClient,
+};
 use chrono::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// A struct containing info about a notification.
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Notification {
     /// The notification ID.
     pub id: String,
@@ -18,10 +24,59 @@ pub struct Notification {
     pub account: Account,
     /// The Status associated with the notification, if applicable.
     pub status: Option<Status>,
+    /// The report that was filed, for `AdminReport` notifications.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report: Option<Report>,
+    /// The event that cut the relationships, for `SeveredRelationships`
+    /// notifications.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<RelationshipSeveranceEvent>,
 }
 
+/// The moderation action that removed some of the account's follows and
+/// followers, as given by a `SeveredRelationships` notification
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct RelationshipSeveranceEvent {
+    /// The ID of the event.
+    pub id: String,
+    /// What caused the relationships to be cut.
+    #[serde(rename = "type")]
+    pub event_type: RelationshipSeveranceType,
+    /// Whether the list of affected relationships was purged.
+    #[serde(default)]
+    pub purged: bool,
+    /// The name of the instance or account that was blocked or suspended.
+    pub target_name: String,
+    /// The number of follows and followers that were cut.
+    #[serde(default)]
+    pub relationships_count: Option<u64>,
+    /// When the event happened.
+    pub created_at: DateTime<Utc>,
+}
+
+/// What caused relationships to be cut
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationshipSeveranceType {
+    /// An admin blocked a domain
+    DomainBlock,
+    /// The user blocked a domain
+    UserDomainBlock,
+    /// An admin suspended an account
+    AccountSuspension,
+    /// A value this crate doesn't know, e.g. one added by a newer version or
+    /// a fork of the server
+    Unknown,
+}
+
+deserialize_or_unknown!(RelationshipSeveranceType {
+    "domain_block" => DomainBlock,
+    "user_domain_block" => UserDomainBlock,
+    "account_suspension" => AccountSuspension,
+});
+
 /// The type of notification.
-#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum NotificationType {
     /// Someone mentioned the application client in another status.
@@ -32,4 +87,250 @@ pub enum NotificationType {
     Favourite,
     /// Someone followed the application client.
     Follow,
+    /// Someone asked to follow the application client, which has to
+    /// authorize them because the account is locked.
+    #[serde(rename = "follow_request")]
+    FollowRequest,
+    /// A poll the application client voted in or created has ended.
+    Poll,
+    /// Someone the application client enabled notifications for posted.
+    Status,
+    /// A status the application client interacted with was edited.
+    Update,
+    /// Someone signed up to the instance. Only sent to moderators.
+    #[serde(rename = "admin.sign_up")]
+    AdminSignUp,
+    /// Someone filed a report, see `Notification::report`. Only sent to
+    /// moderators.
+    #[serde(rename = "admin.report")]
+    AdminReport,
+    /// Some of the application client's follows or followers were removed
+    /// by a moderation action, see `Notification::event`.
+    #[serde(rename = "severed_relationships")]
+    SeveredRelationships,
+    /// A value this crate doesn't know, e.g. one added by a newer version or
+    /// a fork of the server
+    Unknown,
+}
+
+deserialize_or_unknown!(NotificationType {
+    "mention" => Mention,
+    "reblog" => Reblog,
+    "favourite" => Favourite,
+    "follow" => Follow,
+    "follow_request" => FollowRequest,
+    "poll" => Poll,
+    "status" => Status,
+    "update" => Update,
+    "admin.sign_up" => AdminSignUp,
+    "admin.report" => AdminReport,
+    "severed_relationships" => SeveredRelationships,
+});
+
+impl NotificationType {
+    // The name the server uses for the type, none for `Unknown`
+    pub(crate) fn name(&self) -> Option<&'static str> {
+        Some(match *self {
+            NotificationType::Mention => "mention",
+            NotificationType::Reblog => "reblog",
+            NotificationType::Favourite => "favourite",
+            NotificationType::Follow => "follow",
+            NotificationType::FollowRequest => "follow_request",
+            NotificationType::Poll => "poll",
+            NotificationType::Status => "status",
+            NotificationType::Update => "update",
+            NotificationType::AdminSignUp => "admin.sign_up",
+            NotificationType::AdminReport => "admin.report",
+            NotificationType::SeveredRelationships => "severed_relationships",
+            NotificationType::Unknown => return None,
+        })
+    }
+}
+
+impl Notification {
+    /// Whether someone asked to follow the application client
+    pub fn is_follow_request(&self) -> bool {
+        self.notification_type == NotificationType::FollowRequest
+    }
+
+    /// The account that caused the notification, e.g. the one that
+    /// mentioned, followed or signed up
+    pub fn account(&self) -> &Account {
+        &self.account
+    }
+
+    /// The status the notification is about, for `Mention`, `Reblog`,
+    /// `Favourite`, `Poll`, `Status` and `Update` notifications
+    pub fn status(&self) -> Option<&Status> {
+        self.status.as_ref()
+    }
+
+    /// The report that was filed, for `AdminReport` notifications
+    pub fn report(&self) -> Option<&Report> {
+        self.report.as_ref()
+    }
+
+    /// The event that cut relationships, for `SeveredRelationships`
+    /// notifications
+    pub fn event(&self) -> Option<&RelationshipSeveranceEvent> {
+        self.event.as_ref()
+    }
+}
+
+#[cfg(feature = "client")]
+impl Notification {
+    /// Authorizes the follow request this notification is about
+    ///
+    /// Fails without a request if it isn't about a follow request.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// # use std::error::Error;
+    /// use elefren::entities::event::Event;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// for event in client.streaming_user()? {
+    ///     if let Event::Notification(ref notification) = event {
+    ///         if notification.is_follow_request() {
+    ///             if notification.account.bot == Some(true) {
+    ///                 notification.reject(&client)?;
+    ///             } else {
+    ///                 notification.accept(&client)?;
+    ///             }
+    ///         }
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn accept<H: HttpSend, C: MastodonClient<H>>(&self, client: &C) -> Result<()> {
+        client.authorize_follow_request(self.follow_request_account()?)
+    }
+
+    /// Rejects the follow request this notification is about
+    ///
+    /// Fails without a request if it isn't about a follow request.
+    pub fn reject<H: HttpSend, C: MastodonClient<H>>(&self, client: &C) -> Result<()> {
+        client.reject_follow_request(self.follow_request_account()?)
+    }
+
+    fn follow_request_account(&self) -> Result<&str> {
+        if !self.is_follow_request() {
+            return Err(Error::Other(format!(
+                "notification {} is a {:?} notification, not a follow request",
+                self.id, self.notification_type
+            )));
+        }
+        Ok(&self.account.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{entities::attachment::MediaType, status_builder::Visibility};
+
+    #[test]
+    fn test_unknown_values() {
+        let notification: Notification = serde_json::from_str(include_str!(
+            "../../tests/fixtures/pleroma_notification.json"
+        ))
+        .expect("Couldn't parse notification");
+        assert_eq!(notification.notification_type, NotificationType::Unknown);
+        let status = notification.status.unwrap();
+        assert_eq!(status.visibility, Visibility::Unknown);
+        assert_eq!(status.media_attachments[0].media_type, MediaType::Unknown);
+    }
+
+    #[test]
+    fn test_known_values() {
+        for notification_type in [
+            NotificationType::Mention,
+            NotificationType::Reblog,
+            NotificationType::Favourite,
+            NotificationType::Follow,
+            NotificationType::FollowRequest,
+            NotificationType::Poll,
+            NotificationType::Status,
+            NotificationType::Update,
+            NotificationType::AdminSignUp,
+            NotificationType::AdminReport,
+            NotificationType::SeveredRelationships,
+        ] {
+            let json = serde_json::to_string(&notification_type).unwrap();
+            assert_eq!(
+                serde_json::from_str::<NotificationType>(&json).unwrap(),
+                notification_type
+            );
+        }
+    }
+
+    fn notification(notification_type: &str, extra: serde_json::Value) -> Notification {
+        let mut json = serde_json::json!({
+            "id": "34975861",
+            "type": notification_type,
+            "created_at": "2019-11-23T07:49:02.064Z",
+            "account": serde_json::from_str::<serde_json::Value>(include_str!(
+                "../../tests/fixtures/account.json"
+            ))
+            .unwrap(),
+        });
+        json.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        serde_json::from_value(json).expect("Couldn't parse notification")
+    }
+
+    #[test]
+    fn test_admin_report() {
+        let notification = notification(
+            "admin.report",
+            serde_json::json!({"report": {
+                "id": "48914", "action_taken": false, "category": "spam",
+                "comment": "", "status_ids": ["108882889550545820"]
+            }}),
+        );
+        assert_eq!(
+            notification.notification_type,
+            NotificationType::AdminReport
+        );
+        assert_eq!(notification.status(), None);
+        let report = notification.report().unwrap();
+        assert_eq!(report.id, "48914");
+        assert_eq!(report.status_ids, ["108882889550545820"]);
+        assert_eq!(notification.account().id, notification.account.id);
+    }
+
+    #[test]
+    fn test_severed_relationships() {
+        let notification = notification(
+            "severed_relationships",
+            serde_json::json!({"event": {
+                "id": "1", "type": "domain_block", "purged": false,
+                "target_name": "spam.example", "relationships_count": 2,
+                "created_at": "2024-03-18T15:08:10.144Z"
+            }}),
+        );
+        let event = notification.event().unwrap();
+        assert_eq!(event.event_type, RelationshipSeveranceType::DomainBlock);
+        assert_eq!(event.relationships_count, Some(2));
+        assert_eq!(notification.report(), None);
+
+        let ser = serde_json::to_string(&notification).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Notification>(&ser).unwrap(),
+            notification
+        );
+    }
 }
@@ -0,0 +1,110 @@
+//! Module containing the extensions Pleroma and Akkoma add to the API.
+//!
+//! Only available with the `pleroma` feature.
+use std::collections::HashMap;
+
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `pleroma` attributes of a status
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct PleromaStatus {
+    /// Whether the status was posted on this instance
+    pub local: bool,
+    /// The ID of the status' thread
+    pub conversation_id: Option<u64>,
+    /// The ID of the direct conversation the status is part of
+    pub direct_conversation_id: Option<u64>,
+    /// The `acct` of the author of the status this is a reply to
+    pub in_reply_to_account_acct: Option<String>,
+    /// The content of the status by MIME type, e.g. `text/plain`
+    pub content: HashMap<String, String>,
+    /// The spoiler text of the status by MIME type
+    pub spoiler_text: HashMap<String, String>,
+    /// When the status will be deleted, if it was posted with `expires_in`
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Whether the user muted the status' thread
+    pub thread_muted: Option<bool>,
+    /// The emoji reactions to the status
+    pub emoji_reactions: Vec<EmojiReaction>,
+    /// Whether the status this is a reply to is visible to the user
+    pub parent_visible: Option<bool>,
+}
+
+/// An emoji reaction to a status
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct EmojiReaction {
+    /// The emoji, or the shortcode of a custom emoji
+    pub name: String,
+    /// How many accounts reacted with this emoji
+    pub count: u64,
+    /// Whether the user reacted with this emoji
+    #[serde(default)]
+    pub me: bool,
+}
+
+/// The `pleroma` attributes of an account
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct PleromaAccount {
+    /// The ActivityPub id of the account
+    pub ap_id: Option<String>,
+    /// URL of the account's background image
+    pub background_image: Option<String>,
+    /// Whether the account is an admin of its instance
+    pub is_admin: bool,
+    /// Whether the account is a moderator of its instance
+    pub is_moderator: bool,
+    /// Whether the account's email address has been confirmed
+    pub is_confirmed: bool,
+    /// Whether the account hides who follows it
+    pub hide_followers: bool,
+    /// Whether the account hides who it follows
+    pub hide_follows: bool,
+    /// Whether the account hides how many accounts follow it
+    pub hide_followers_count: bool,
+    /// Whether the account hides how many accounts it follows
+    pub hide_follows_count: bool,
+    /// Whether the account accepts chat messages
+    pub accepts_chat_messages: Option<bool>,
+    /// The tags moderators added to the account
+    pub tags: Vec<String>,
+    /// URL of the favicon of the account's instance
+    pub favicon: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{entities::status::Status, test_fixtures};
+
+    #[test]
+    fn test_status_extensions() {
+        let mut status: serde_json::Value = serde_json::from_str(test_fixtures::STATUS).unwrap();
+        status["pleroma"] = serde_json::json!({
+            "local": false,
+            "conversation_id": 42,
+            "content": {"text/plain": "hi"},
+            "expires_at": "2029-01-01T12:00:00.000Z",
+            "emoji_reactions": [{"name": "🐘", "count": 2, "me": true}],
+            "some_new_field": true
+        });
+        status["account"]["pleroma"] = serde_json::json!({"is_admin": true, "tags": ["x"]});
+        let status: Status = serde_json::from_value(status).unwrap();
+
+        let pleroma = status.pleroma.unwrap();
+        assert_eq!(pleroma.conversation_id, Some(42));
+        assert_eq!(pleroma.content["text/plain"], "hi");
+        assert_eq!(pleroma.emoji_reactions[0].count, 2);
+        assert!(pleroma.expires_at.is_some());
+        let account = status.account.pleroma.unwrap();
+        assert!(account.is_admin);
+        assert_eq!(account.tags, ["x"]);
+    }
+
+    #[test]
+    fn test_missing_extensions() {
+        let account = test_fixtures::account();
+        assert!(account.pleroma.is_none());
+    }
+}
@@ -13,6 +13,31 @@ pub struct Alerts {
     pub mention: Option<bool>,
 }
 
+/// Whose notifications are pushed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Policy {
+    /// Notifications from anyone
+    #[default]
+    All,
+    /// Only notifications from accounts you follow
+    Followed,
+    /// Only notifications from accounts that follow you
+    Follower,
+    /// No notifications at all
+    None,
+    /// A value this crate doesn't know, e.g. one added by a newer version or
+    /// a fork of the server
+    Unknown,
+}
+
+deserialize_or_unknown!(Policy {
+    "all" => All,
+    "followed" => Followed,
+    "follower" => Follower,
+    "none" => None,
+});
+
 /// Represents a new Push subscription
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 pub struct Subscription {
@@ -20,14 +45,20 @@ pub struct Subscription {
     pub id: String,
     /// The endpoint of the subscription
     pub endpoint: String,
-    /// The server key of the subscription
+    /// The server's public key, used to verify that push messages come from
+    /// the server
     pub server_key: String,
-    /// The status of the alerts for this subscription
-    pub alerts: Option<Alerts>,
+    /// The status of the alerts for this subscription. Alerts the server
+    /// didn't mention are disabled.
+    #[serde(default)]
+    pub alerts: Alerts,
+    /// Whose notifications are pushed
+    #[serde(default)]
+    pub policy: Policy,
 }
 
 pub(crate) mod add_subscription {
-    use super::Alerts;
+    use super::{Alerts, Policy};
     use serde::Serialize;
 
     #[derive(Debug, Clone, PartialEq, Serialize, Default)]
@@ -51,16 +82,20 @@ pub(crate) mod add_subscription {
     #[derive(Debug, Clone, PartialEq, Serialize, Default)]
     pub(crate) struct Data {
         pub(crate) alerts: Option<Alerts>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub(crate) policy: Option<Policy>,
     }
 }
 
 pub(crate) mod update_data {
-    use super::Alerts;
+    use super::{Alerts, Policy};
     use serde::Serialize;
 
     #[derive(Debug, Clone, PartialEq, Serialize, Default)]
     pub(crate) struct Data {
         pub(crate) alerts: Option<Alerts>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub(crate) policy: Option<Policy>,
     }
 
     #[derive(Debug, Clone, PartialEq, Serialize, Default)]
@@ -1,11 +1,91 @@
 //! module containing information about a finished report of a user.
-use serde::Deserialize;
+use crate::entities::account::Account;
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
 
 /// A struct containing info about a report.
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Report {
     /// The ID of the report.
     pub id: String,
-    /// The action taken in response to the report.
-    pub action_taken: String,
+    /// Whether an action was taken yet.
+    pub action_taken: bool,
+    /// When an action was taken against the report, if it was.
+    #[serde(default)]
+    pub action_taken_at: Option<DateTime<Utc>>,
+    /// Why the report was filed. Older servers don't send this.
+    #[serde(default)]
+    pub category: Option<ReportCategory>,
+    /// The reason given for the report.
+    #[serde(default)]
+    pub comment: String,
+    /// Whether the report was forwarded to the reported account's instance.
+    #[serde(default)]
+    pub forwarded: bool,
+    /// When the report was created.
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    /// The IDs of the statuses attached to the report.
+    #[serde(default)]
+    pub status_ids: Vec<String>,
+    /// The IDs of the instance rules the reported statuses violate.
+    #[serde(default)]
+    pub rule_ids: Vec<String>,
+    /// The account that was reported. Older servers don't send this.
+    #[serde(default)]
+    pub target_account: Option<Account>,
+}
+
+/// The reason a report was filed
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportCategory {
+    /// Unwanted or repetitive content
+    Spam,
+    /// A violation of one or more instance rules, see `Report::rule_ids`
+    Violation,
+    /// A report filed before categories existed
+    Legacy,
+    /// Some other reason
+    Other,
+    /// A value this crate doesn't know, e.g. one added by a newer version or
+    /// a fork of the server
+    Unknown,
+}
+
+deserialize_or_unknown!(ReportCategory {
+    "spam" => Spam,
+    "violation" => Violation,
+    "legacy" => Legacy,
+    "other" => Other,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn test_deserialize() {
+        let report: Report = serde_json::from_str(&format!(
+            r#"{{"id": "48914", "action_taken": false, "action_taken_at": null,
+                "category": "violation", "comment": "rude", "forwarded": true,
+                "created_at": "2022-08-25T09:56:16.763Z", "status_ids": ["108882889550545820"],
+                "rule_ids": ["2"], "target_account": {}}}"#,
+            test_fixtures::ACCOUNT
+        ))
+        .unwrap();
+        assert_eq!(report.category, Some(ReportCategory::Violation));
+        assert_eq!(report.rule_ids, vec!["2"]);
+        assert!(report.forwarded);
+        assert_eq!(report.target_account.unwrap().username, "alice");
+    }
+
+    #[test]
+    fn test_deserialize_legacy() {
+        let report: Report = serde_json::from_str(r#"{"id": "1", "action_taken": true}"#).unwrap();
+        assert!(report.action_taken);
+        assert_eq!(report.category, None);
+        assert!(report.status_ids.is_empty());
+    }
 }
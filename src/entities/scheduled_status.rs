@@ -0,0 +1,47 @@
+//! Module containing everything related to scheduled statuses.
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::attachment::Attachment;
+use crate::status_builder::NewStatus;
+
+/// A status that will be published in the future
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ScheduledStatus {
+    /// The ID of the scheduled status
+    pub id: String,
+    /// When the status will be published
+    pub scheduled_at: DateTime<Utc>,
+    /// The parameters the status will be posted with. They can be passed to
+    /// `client.schedule_status()` again, e.g. to recreate the status after
+    /// it was deleted.
+    pub params: NewStatus,
+    /// The media attached to the status
+    #[serde(default)]
+    pub media_attachments: Vec<Attachment>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params_round_trip() {
+        let scheduled: ScheduledStatus =
+            serde_json::from_str(include_str!("../../tests/fixtures/scheduled_status.json"))
+                .unwrap();
+        assert_eq!(scheduled.id, "3221");
+        assert_eq!(
+            scheduled.params.scheduled_at(),
+            Some(Utc.with_ymd_and_hms(2029, 1, 1, 12, 0, 0).unwrap())
+        );
+
+        let json = serde_json::to_value(&scheduled.params).unwrap();
+        assert_eq!(json["status"], "so long, and thanks for all the fish");
+        assert_eq!(json["spoiler_text"], "goodbye");
+        assert_eq!(json["visibility"], "unlisted");
+        assert_eq!(json["scheduled_at"], "2029-01-01T12:00:00Z");
+        let again: NewStatus = serde_json::from_value(json).unwrap();
+        assert_eq!(again, scheduled.params);
+    }
+}
@@ -3,13 +3,17 @@
 use super::prelude::*;
 use crate::{
     entities::{card::Card, poll::Poll},
+    errors::Error,
+    helpers::html::to_plain_text,
+    language::{self, Language},
     status_builder::Visibility,
 };
 use chrono::prelude::*;
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Serialize};
+use std::cmp::Ordering;
 
 /// A status from the instance.
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Status {
     /// The ID of the status.
     pub id: String,
@@ -60,7 +64,10 @@ pub struct Status {
     /// The associated card
     pub card: Option<Card>,
     /// The detected language for the status, if detected.
-    pub language: Option<String>,
+    ///
+    /// Codes that aren't known are represented by `Language::Und`.
+    #[serde(default, deserialize_with = "language")]
+    pub language: Option<Language>,
     /// Plain-text source of a status. Returned instead of content when status
     /// is deleted, so the user may redraft from the source text without the
     /// client having to reverse-engineer the original text from the HTML
@@ -76,10 +83,34 @@ pub struct Status {
     pub bookmarked: Option<bool>,
     /// Whether this is the pinned status for the account that posted it.
     pub pinned: Option<bool>,
+    /// Whether the status is only visible on its own instance. Only sent by
+    /// glitch-soc and Hometown.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub local_only: Option<bool>,
+    /// Pleroma and Akkoma specific attributes
+    #[cfg(feature = "pleroma")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pleroma: Option<super::pleroma::PleromaStatus>,
+}
+
+// Parses an ISO 639-1 or 639-3 code, optionally followed by a region (e.g.
+// `zh-TW`), falling back to `Language::Und` for unknown codes. An empty code
+// means no language.
+pub(crate) fn language<'de, D: de::Deserializer<'de>>(
+    val: D,
+) -> Result<Option<Language>, D::Error> {
+    Ok(Option::<String>::deserialize(val)?
+        .filter(|code| !code.is_empty())
+        .map(|code| {
+            let code = code.split(&['-', '_'][..]).next().unwrap_or("");
+            code.to_lowercase()
+                .parse()
+                .unwrap_or_else(|_| language::undetermined())
+        }))
 }
 
 /// A mention of another user.
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Mention {
     /// URL of user's profile (can be remote).
     pub url: String,
@@ -103,7 +134,7 @@ pub struct Emoji {
 }
 
 /// Hashtags in the status.
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Tag {
     /// The hashtag, not including the preceding `#`.
     pub name: String,
@@ -113,22 +144,388 @@ pub struct Tag {
     pub history: Option<Vec<History>>,
 }
 
+/// Statuses are ordered by when they were created, and then by their ids.
+/// Different versions of the same status, e.g. before and after an edit,
+/// can't be ordered.
+impl PartialOrd for Status {
+    fn partial_cmp(&self, other: &Status) -> Option<Ordering> {
+        let ordering = self
+            .created_at
+            .cmp(&other.created_at)
+            .then_with(|| self.id.len().cmp(&other.id.len()))
+            .then_with(|| self.id.cmp(&other.id));
+        if ordering == Ordering::Equal && self != other {
+            None
+        } else {
+            Some(ordering)
+        }
+    }
+}
+
+impl Tag {
+    /// How often the tag was used in the last seven days of its history
+    pub fn uses_last_week(&self) -> u64 {
+        self.last_week().map(|day| day.uses).sum()
+    }
+
+    /// The sum of the number of accounts that used the tag on each of the
+    /// last seven days of its history. An account using it on several days
+    /// is counted once for each day.
+    pub fn accounts_last_week(&self) -> u64 {
+        self.last_week().map(|day| day.accounts).sum()
+    }
+
+    /// Sorts `tags` by `uses_last_week`, the most used first
+    pub fn sort_by_uses(tags: &mut [Tag]) {
+        tags.sort_by_key(|tag| std::cmp::Reverse(tag.uses_last_week()));
+    }
+
+    /// Sorts `tags` by `accounts_last_week`, the one used by the most
+    /// accounts first
+    pub fn sort_by_accounts(tags: &mut [Tag]) {
+        tags.sort_by_key(|tag| std::cmp::Reverse(tag.accounts_last_week()));
+    }
+
+    // The history is sorted by day, starting with today
+    fn last_week(&self) -> impl Iterator<Item = &History> {
+        self.history.iter().flatten().take(7)
+    }
+}
+
 /// Represents daily usage history of a hashtag.
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
 pub struct History {
     /// UNIX timestamp on midnight of the given day.
-    day: String,
+    #[serde(deserialize_with = "string_or_number")]
+    pub day: i64,
     /// the counted usage of the tag within that day.
-    uses: String,
+    #[serde(deserialize_with = "string_or_number")]
+    pub uses: u64,
     /// the total of accounts using the tag within that day.
-    accounts: String,
+    #[serde(deserialize_with = "string_or_number")]
+    pub accounts: u64,
+}
+
+// Mastodon sends the numbers in a tag's history as strings
+fn string_or_number<'de, D, T>(val: D) -> Result<T, D::Error>
+where
+    D: de::Deserializer<'de>,
+    T: Deserialize<'de> + std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber<T> {
+        Number(T),
+        Str(String),
+    }
+
+    match StringOrNumber::<T>::deserialize(val)? {
+        StringOrNumber::Number(n) => Ok(n),
+        StringOrNumber::Str(s) => s.trim().parse().map_err(de::Error::custom),
+    }
 }
 
 /// Application details.
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
 pub struct Application {
     /// Name of the application.
     pub name: String,
     /// Homepage URL of the application.
     pub website: Option<String>,
+    /// The key to use when subscribing to push notifications. Only sent when
+    /// registering or verifying the application.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vapid_key: Option<String>,
+    /// The application's client id. Only sent when registering the
+    /// application.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// The application's client secret. Only sent when registering the
+    /// application.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_secret: Option<String>,
+}
+
+/// The gist of a status, e.g. for logging or storing it without its account,
+/// media, card and poll
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq)]
+pub struct StatusSummary {
+    /// The ID of the status.
+    pub id: String,
+    /// The `acct` of the account which posted the status.
+    pub acct: String,
+    /// The time the status was created.
+    pub created_at: DateTime<Utc>,
+    /// URL to the status page (can be remote)
+    pub url: Option<String>,
+    /// The spoiler text, if the status has one
+    pub spoiler: Option<String>,
+    /// The content of the status as plain text, see
+    /// `helpers::html::to_plain_text`
+    pub plain_text: String,
+}
+
+impl<'a> From<&'a Status> for StatusSummary {
+    fn from(status: &'a Status) -> StatusSummary {
+        StatusSummary {
+            id: status.id.clone(),
+            acct: status.account.acct.clone(),
+            created_at: status.created_at,
+            url: status.url.clone(),
+            spoiler: Some(status.spoiler_text.clone()).filter(|spoiler| !spoiler.is_empty()),
+            plain_text: to_plain_text(&status.content),
+        }
+    }
+}
+
+impl From<Status> for StatusSummary {
+    fn from(status: Status) -> StatusSummary {
+        StatusSummary::from(&status)
+    }
+}
+
+/// The ID of a status, e.g. as carried by `Event::Delete`
+///
+/// Parsing one trims surrounding whitespace, such as the line endings left
+/// over from the streaming API's messages, and rejects an empty ID. It
+/// dereferences to `str`, so it compares and passes as the `String` IDs used
+/// elsewhere.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub struct StatusId(String);
+
+impl StatusId {
+    /// Parses a status ID; see `FromStr`
+    pub fn new(id: impl AsRef<str>) -> crate::Result<StatusId> {
+        id.as_ref().parse()
+    }
+
+    /// The ID as a string slice
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::str::FromStr for StatusId {
+    type Err = Error;
+
+    fn from_str(id: &str) -> crate::Result<StatusId> {
+        let id = id.trim();
+        if id.is_empty() {
+            return Err(Error::Other("a status ID can't be empty".to_string()));
+        }
+        Ok(StatusId(id.to_string()))
+    }
+}
+
+impl TryFrom<String> for StatusId {
+    type Error = Error;
+
+    fn try_from(id: String) -> crate::Result<StatusId> {
+        id.parse()
+    }
+}
+
+impl From<StatusId> for String {
+    fn from(id: StatusId) -> String {
+        id.0
+    }
+}
+
+impl std::ops::Deref for StatusId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for StatusId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for StatusId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq<str> for StatusId {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl<'a> PartialEq<&'a str> for StatusId {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<String> for StatusId {
+    fn eq(&self, other: &String) -> bool {
+        self.0 == *other
+    }
+}
+
+impl PartialEq<StatusId> for String {
+    fn eq(&self, other: &StatusId) -> bool {
+        *self == other.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn test_status_id() {
+        let id = StatusId::new("103270115826048975\r\n").unwrap();
+        assert_eq!(id, "103270115826048975");
+        assert_eq!(id.to_string(), "103270115826048975");
+        assert!(StatusId::new(" \n").is_err());
+        assert_eq!(
+            serde_json::to_string(&id).unwrap(),
+            r#""103270115826048975""#
+        );
+        assert!(serde_json::from_str::<StatusId>(r#""""#).is_err());
+    }
+
+    fn tag(name: &str, history: &[(u64, u64)]) -> Tag {
+        Tag {
+            name: name.to_string(),
+            url: format!("https://example.com/tags/{}", name),
+            history: Some(
+                history
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &(uses, accounts))| History {
+                        day: 1574553600 - 86400 * i as i64,
+                        uses,
+                        accounts,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_history_from_strings_or_numbers() {
+        let history: History =
+            serde_json::from_str(r#"{"day": "1574553600", "uses": "7", "accounts": "3"}"#).unwrap();
+        assert_eq!(history.day, 1574553600);
+        assert_eq!(history.uses, 7);
+        assert_eq!(history.accounts, 3);
+        let history: History =
+            serde_json::from_str(r#"{"day": 1574553600, "uses": 7, "accounts": 3}"#).unwrap();
+        assert_eq!(history.uses, 7);
+        assert!(serde_json::from_str::<History>(
+            r#"{"day": "1574553600", "uses": "many", "accounts": "3"}"#
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_tag_stats() {
+        let rust = tag(
+            "rust",
+            &[
+                (1, 1),
+                (2, 2),
+                (3, 1),
+                (0, 0),
+                (0, 0),
+                (0, 0),
+                (4, 3),
+                (100, 50),
+            ],
+        );
+        assert_eq!(rust.uses_last_week(), 10);
+        assert_eq!(rust.accounts_last_week(), 7);
+        let empty = Tag {
+            history: None,
+            ..tag("empty", &[])
+        };
+        assert_eq!(empty.uses_last_week(), 0);
+    }
+
+    #[test]
+    fn test_sort_tags() {
+        let mut tags = vec![
+            tag("few", &[(1, 1)]),
+            tag("many", &[(5, 1), (5, 1)]),
+            tag("some", &[(3, 3)]),
+        ];
+        Tag::sort_by_uses(&mut tags);
+        let names: Vec<_> = tags.iter().map(|tag| &tag.name[..]).collect();
+        assert_eq!(names, ["many", "some", "few"]);
+        Tag::sort_by_accounts(&mut tags);
+        let names: Vec<_> = tags.iter().map(|tag| &tag.name[..]).collect();
+        assert_eq!(names, ["some", "many", "few"]);
+    }
+
+    #[test]
+    #[cfg(feature = "language")]
+    fn test_language() {
+        let status = test_fixtures::status();
+        assert_eq!(status.language, Some(Language::Eng));
+
+        let language = |json: &str| {
+            let mut status: serde_json::Value =
+                serde_json::from_str(test_fixtures::STATUS).unwrap();
+            status["language"] = serde_json::from_str(json).unwrap();
+            serde_json::from_value::<Status>(status).unwrap().language
+        };
+        assert_eq!(language("\"deu\""), Some(Language::Deu));
+        assert_eq!(language("\"zh-TW\""), Some(Language::Zho));
+        assert_eq!(language("\"xx\""), Some(Language::Und));
+        assert_eq!(language("\"\""), None);
+        assert_eq!(language("null"), None);
+    }
+
+    #[test]
+    fn test_order_and_dedup() {
+        let older = test_fixtures::status();
+        let mut newer = older.clone();
+        newer.id = "1100".to_string();
+        newer.created_at = older.created_at + chrono::Duration::seconds(1);
+        assert!(older < newer);
+        let mut same_time = older.clone();
+        same_time.id = "111".to_string();
+        assert!(older < same_time);
+
+        let mut edited = older.clone();
+        edited.content = "<p>edited</p>".to_string();
+        assert_eq!(older.partial_cmp(&edited), None);
+        let unique: std::collections::HashSet<_> =
+            vec![older.clone(), older, newer].into_iter().collect();
+        assert_eq!(unique.len(), 2);
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut status = test_fixtures::status();
+        let summary = StatusSummary::from(&status);
+        assert_eq!(summary.id, "110");
+        assert_eq!(summary.acct, "alice@example.org");
+        assert_eq!(
+            summary.url.as_deref(),
+            Some("https://example.org/@alice/110")
+        );
+        assert_eq!(summary.spoiler.as_deref(), Some("food"));
+        assert_eq!(summary.plain_text, "hey @bob @carol");
+
+        let json = serde_json::to_string(&summary).unwrap();
+        assert_eq!(
+            serde_json::from_str::<StatusSummary>(&json).unwrap(),
+            summary
+        );
+
+        status.spoiler_text.clear();
+        assert_eq!(StatusSummary::from(status).spoiler, None);
+    }
 }
@@ -0,0 +1,54 @@
+//! Module containing the entities of follow suggestions.
+use super::account::Account;
+use serde::{Deserialize, Serialize};
+
+/// An account suggested to follow, along with why it was suggested
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct Suggestion {
+    /// Why the account was suggested.
+    pub source: SuggestionSource,
+    /// The suggested account.
+    pub account: Account,
+}
+
+/// Why an account was suggested to follow
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestionSource {
+    /// It was picked by the instance's staff
+    Staff,
+    /// The user has interacted with it before, e.g. favourited its statuses
+    PastInteractions,
+    /// It's popular across the instance
+    Global,
+    /// A value this crate doesn't know, e.g. one added by a newer version or
+    /// a fork of the server
+    Unknown,
+}
+
+deserialize_or_unknown!(SuggestionSource {
+    "staff" => Staff,
+    "past_interactions" => PastInteractions,
+    "global" => Global,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggestion_source() {
+        assert_eq!(
+            serde_json::from_str::<SuggestionSource>(r#""past_interactions""#).unwrap(),
+            SuggestionSource::PastInteractions
+        );
+        assert_eq!(
+            serde_json::from_str::<SuggestionSource>(r#""featured""#).unwrap(),
+            SuggestionSource::Unknown
+        );
+        assert_eq!(
+            serde_json::to_string(&SuggestionSource::PastInteractions).unwrap(),
+            r#""past_interactions""#
+        );
+    }
+}
@@ -9,13 +9,16 @@ use ::toml::ser::Error as TomlSerError;
 use async_native_tls::Error as TlsError;
 #[cfg(feature = "env")]
 use envy::Error as EnvyError;
-#[cfg(feature = "async")]
+#[cfg(feature = "wasm")]
+use gloo_net::Error as FetchError;
+#[cfg(any(feature = "async", feature = "wasm"))]
 use http_types::Error as HttpTypesError;
-use hyper_old_types::Error as HeaderParseError;
-use reqwest::{header::ToStrError as HeaderStrError, Error as HttpError, StatusCode};
+#[cfg(feature = "client")]
+use reqwest::{header::ToStrError as HeaderStrError, Error as HttpError};
 use serde_json::Error as SerdeError;
 use serde_qs::Error as SerdeQsError;
 use serde_urlencoded::ser::Error as UrlEncodedError;
+#[cfg(feature = "websocket")]
 use tungstenite::error::Error as WebSocketError;
 use url::ParseError as UrlError;
 
@@ -31,14 +34,66 @@ pub enum Error {
     /// Error deserialising to json. Typically represents a breaking change in
     /// the Mastodon API
     Serde(SerdeError),
+    /// Error deserialising the response to a request. Typically represents a
+    /// breaking change in the Mastodon API
+    Deserialize {
+        /// The URL that was requested
+        url: String,
+        /// The status code of the response
+        status: HttpStatus,
+        /// The start of the response body
+        snippet: String,
+        /// The underlying error
+        source: SerdeError,
+    },
+    /// The server answered with an error page that isn't JSON, e.g. the HTML
+    /// 502 page of a reverse proxy in front of it
+    ErrorPage {
+        /// The URL that was requested
+        url: String,
+        /// The status code of the response
+        status: HttpStatus,
+        /// The content type of the response, e.g. `text/html`
+        content_type: String,
+        /// The start of the response body
+        snippet: String,
+    },
     /// Error serializing to url-encoded string
     UrlEncoded(UrlEncodedError),
+    #[cfg(feature = "client")]
     /// Error encountered in the HTTP backend while requesting a route.
     Http(HttpError),
     /// Wrapper around the `std::io::Error` struct.
     Io(IoError),
     /// Wrapper around the `url::ParseError` struct.
     Url(UrlError),
+    /// The server rejected an OAuth request, e.g. because the authorization
+    /// code was wrong or has already been used
+    OAuth {
+        /// The status code of the response
+        status: HttpStatus,
+        /// The OAuth error code, e.g. `invalid_grant`
+        error: String,
+        /// A human readable description of the error, if the server sent one
+        description: Option<String>,
+    },
+    /// A redirect to a different origin wasn't followed, so that the access
+    /// token isn't sent there. See `RedirectPolicy`.
+    CrossOriginRedirect {
+        /// The URL that was requested
+        url: String,
+        /// Where the server redirected to
+        location: String,
+    },
+    /// The body of a response was larger than the configured limit, and was
+    /// not read. See `MastodonBuilder::max_body_size`, or
+    /// `ClientConfig::max_body_size` for the async client.
+    BodyTooLarge {
+        /// The URL that was requested
+        url: String,
+        /// The limit, in bytes
+        limit: usize,
+    },
     /// Missing Client Id.
     ClientIdRequired,
     /// Missing Client Secret.
@@ -46,9 +101,9 @@ pub enum Error {
     /// Missing Access Token.
     AccessTokenRequired,
     /// Generic client error.
-    Client(StatusCode),
+    Client(HttpStatus),
     /// Generic server error.
-    Server(StatusCode),
+    Server(HttpStatus),
     /// MastodonBuilder & AppBuilder error
     MissingField(&'static str),
     #[cfg(feature = "toml")]
@@ -57,23 +112,26 @@ pub enum Error {
     #[cfg(feature = "toml")]
     /// Error deserializing from toml
     TomlDe(TomlDeError),
+    #[cfg(feature = "client")]
     /// Error converting an http header to a string
     HeaderStrError(HeaderStrError),
-    /// Error parsing the http Link header
-    HeaderParseError(HeaderParseError),
     #[cfg(feature = "env")]
     /// Error deserializing from the environment
     Envy(EnvyError),
     /// Error serializing to a query string
     SerdeQs(SerdeQsError),
+    #[cfg(feature = "websocket")]
     /// WebSocket error
     WebSocket(WebSocketError),
-    #[cfg(feature = "async")]
+    #[cfg(any(feature = "async", feature = "wasm"))]
     /// http-types error
     HttpTypes(HttpTypesError),
     #[cfg(feature = "async")]
     /// TLS error
     Tls(TlsError),
+    #[cfg(feature = "wasm")]
+    /// Error sending a request with the browser's `fetch` API
+    Fetch(FetchError),
     /// Other errors
     Other(String),
 }
@@ -89,7 +147,9 @@ impl error::Error for Error {
         Some(match *self {
             Error::Api(ref e) => e,
             Error::Serde(ref e) => e,
+            Error::Deserialize { ref source, .. } => source,
             Error::UrlEncoded(ref e) => e,
+            #[cfg(feature = "client")]
             Error::Http(ref e) => e,
             Error::Io(ref e) => e,
             Error::Url(ref e) => e,
@@ -97,27 +157,129 @@ impl error::Error for Error {
             Error::TomlSer(ref e) => e,
             #[cfg(feature = "toml")]
             Error::TomlDe(ref e) => e,
+            #[cfg(feature = "client")]
             Error::HeaderStrError(ref e) => e,
-            Error::HeaderParseError(ref e) => e,
             #[cfg(feature = "env")]
             Error::Envy(ref e) => e,
             Error::SerdeQs(ref e) => e,
+            #[cfg(feature = "websocket")]
             Error::WebSocket(ref e) => e,
 
             Error::Client(..) | Error::Server(..) => return None,
+            Error::ErrorPage { .. } => return None,
+            Error::OAuth { .. } => return None,
+            Error::CrossOriginRedirect { .. } => return None,
+            Error::BodyTooLarge { .. } => return None,
             Error::ClientIdRequired => return None,
             Error::ClientSecretRequired => return None,
             Error::AccessTokenRequired => return None,
             Error::MissingField(_) => return None,
-            #[cfg(feature = "async")]
+            #[cfg(any(feature = "async", feature = "wasm"))]
             Error::HttpTypes(..) => return None,
             #[cfg(feature = "async")]
             Error::Tls(ref e) => e,
+            #[cfg(feature = "wasm")]
+            Error::Fetch(ref e) => e,
             Error::Other(..) => return None,
         })
     }
 }
 
+impl Error {
+    // the maximum length of the body snippet in `Error::Deserialize`
+    const SNIPPET_LEN: usize = 256;
+
+    pub(crate) fn deserialize<S: Into<HttpStatus>>(
+        url: String,
+        status: S,
+        body: &str,
+        source: SerdeError,
+    ) -> Error {
+        Error::Deserialize {
+            url,
+            status: status.into(),
+            snippet: Error::snippet(body),
+            source,
+        }
+    }
+
+    // The start of `body`, for error messages
+    pub(crate) fn snippet(body: &str) -> String {
+        match body.char_indices().nth(Error::SNIPPET_LEN) {
+            Some((idx, _)) => format!("{}...", &body[..idx]),
+            None => body.to_string(),
+        }
+    }
+}
+
+/// The HTTP status code of a failed response.
+///
+/// This doesn't depend on the HTTP backend, so the blocking and the async
+/// clients report failures the same way.
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::errors::HttpStatus;
+///
+/// let status = HttpStatus::from(404);
+/// assert_eq!(status.as_u16(), 404);
+/// assert!(status.is_client_error());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HttpStatus(u16);
+
+impl HttpStatus {
+    /// The numeric status code
+    pub fn as_u16(self) -> u16 {
+        self.0
+    }
+
+    /// Whether this is a 4xx status code
+    pub fn is_client_error(self) -> bool {
+        (400..500).contains(&self.0)
+    }
+
+    /// Whether this is a 5xx status code
+    pub fn is_server_error(self) -> bool {
+        (500..600).contains(&self.0)
+    }
+}
+
+impl fmt::Display for HttpStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<u16> for HttpStatus {
+    fn from(status: u16) -> Self {
+        HttpStatus(status)
+    }
+}
+
+#[cfg(feature = "client")]
+impl From<reqwest::StatusCode> for HttpStatus {
+    fn from(status: reqwest::StatusCode) -> Self {
+        HttpStatus(status.as_u16())
+    }
+}
+
+#[cfg(feature = "client")]
+impl PartialEq<reqwest::StatusCode> for HttpStatus {
+    fn eq(&self, other: &reqwest::StatusCode) -> bool {
+        self.0 == other.as_u16()
+    }
+}
+
+#[cfg(any(feature = "async", feature = "wasm"))]
+impl From<http_types::StatusCode> for HttpStatus {
+    fn from(status: http_types::StatusCode) -> Self {
+        HttpStatus(status.into())
+    }
+}
+
 /// Error returned from the Mastodon API.
 #[derive(Clone, Debug, Deserialize)]
 pub struct ApiError {
@@ -150,7 +312,7 @@ macro_rules! from {
 }
 
 from! {
-    HttpError, Http,
+    #[cfg(feature = "client")] HttpError, Http,
     IoError, Io,
     SerdeError, Serde,
     UrlEncodedError, UrlEncoded,
@@ -158,13 +320,13 @@ from! {
     ApiError, Api,
     #[cfg(feature = "toml")] TomlSerError, TomlSer,
     #[cfg(feature = "toml")] TomlDeError, TomlDe,
-    HeaderStrError, HeaderStrError,
-    HeaderParseError, HeaderParseError,
+    #[cfg(feature = "client")] HeaderStrError, HeaderStrError,
     #[cfg(feature = "env")] EnvyError, Envy,
     SerdeQsError, SerdeQs,
-    WebSocketError, WebSocket,
-    #[cfg(feature = "async")] HttpTypesError, HttpTypes,
+    #[cfg(feature = "websocket")] WebSocketError, WebSocket,
+    #[cfg(any(feature = "async", feature = "wasm"))] HttpTypesError, HttpTypes,
     #[cfg(feature = "async")] TlsError, Tls,
+    #[cfg(feature = "wasm")] FetchError, Fetch,
     String, Other,
 }
 
@@ -182,7 +344,6 @@ macro_rules! format_err {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use reqwest;
     use serde_json;
     use serde_urlencoded;
     use std::io;
@@ -196,6 +357,7 @@ mod tests {
         };
     }
 
+    #[cfg(feature = "client")]
     #[test]
     fn from_http_error() {
         let err: HttpError = reqwest::blocking::get("not an actual URL").unwrap_err();
@@ -241,6 +403,49 @@ mod tests {
         assert_is!(err, Error::Api(..));
     }
 
+    #[test]
+    fn deserialize_error_snippet() {
+        let source = serde_json::from_str::<()>("x").unwrap_err();
+        let body = "a".repeat(1000);
+        let err = Error::deserialize("https://example.com".into(), 200, &body, source);
+        match err {
+            Error::Deserialize {
+                url,
+                status,
+                snippet,
+                ..
+            } => {
+                assert_eq!(url, "https://example.com");
+                assert_eq!(status.as_u16(), 200);
+                assert_eq!(snippet.len(), Error::SNIPPET_LEN + 3);
+                assert!(snippet.ends_with("..."));
+            },
+            _ => panic!("expected Error::Deserialize"),
+        }
+
+        let source = serde_json::from_str::<()>("x").unwrap_err();
+        let err = Error::deserialize("https://example.com".into(), 200, "short", source);
+        assert!(matches!(err, Error::Deserialize { ref snippet, .. } if snippet == "short"));
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn http_status() {
+        let status = HttpStatus::from(reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(status.as_u16(), 503);
+        assert!(status.is_server_error());
+        assert!(!status.is_client_error());
+        assert_eq!(status, reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(status.to_string(), "503");
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn http_status_from_http_types() {
+        let status = HttpStatus::from(http_types::StatusCode::NotFound);
+        assert_eq!(status, HttpStatus::from(404));
+    }
+
     #[cfg(feature = "toml")]
     #[test]
     fn from_toml_ser_error() {
@@ -0,0 +1,284 @@
+//! Watching the public or hashtag streams of several instances at once
+
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+    sync::mpsc::{self, Receiver, SyncSender},
+    thread,
+};
+
+use crate::{
+    entities::event::Event, errors::Result, http_send::HttpSend, EventReader, EventStream,
+    MastodonUnauth, StreamKind,
+};
+
+/// An event read from one of the streams of a `Firehose`
+#[derive(Debug)]
+pub struct FirehoseEvent {
+    /// The base URL of the instance the event was read from
+    pub instance: String,
+    /// The event, or the error that ended the instance's stream
+    pub event: Result<Event>,
+}
+
+// Sent by each reader thread, with `None` once it has ended
+type Message = Option<FirehoseEvent>;
+
+// Tells the firehose that a reader thread has ended, however it ends
+struct ReaderGuard(SyncSender<Message>);
+
+impl Drop for ReaderGuard {
+    fn drop(&mut self) {
+        let _ = self.0.send(None);
+    }
+}
+
+// The most recently seen status URIs, forgetting the oldest ones first
+#[derive(Debug)]
+struct RecentUris {
+    capacity: usize,
+    set: HashSet<String>,
+    order: VecDeque<String>,
+}
+
+impl RecentUris {
+    // Returns false if `uri` was already seen
+    fn insert(&mut self, uri: &str) -> bool {
+        if self.set.contains(uri) {
+            return false;
+        }
+        self.set.insert(uri.to_string());
+        self.order.push_back(uri.to_string());
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.set.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// The streams of several instances, merged into one
+///
+/// Every stream is read on its own thread. New statuses that reach more than
+/// one of the instances are passed on only once, recognised by their `uri`;
+/// other events, and the error ending each stream, are passed on as they
+/// are. Iteration ends once every stream has ended.
+///
+/// A `Firehose` is `Send`, but not `Sync`: it can be moved to another thread,
+/// but only read from one at a time.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// # use std::error::Error;
+/// use elefren::{entities::event::Event, firehose::Firehose, MastodonUnauth};
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// let instances = vec![
+///     MastodonUnauth::new("mastodon.social")?,
+///     MastodonUnauth::new("fosstodon.org")?,
+/// ];
+/// let firehose = Firehose::hashtag(&instances, "rust")?;
+/// for item in firehose {
+///     match item.event {
+///         Ok(Event::Update(status)) => println!("{}: {}", item.instance, status.uri),
+///         Err(e) => eprintln!("{} stopped streaming: {}", item.instance, e),
+///         _ => (),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Firehose {
+    sender: SyncSender<Message>,
+    receiver: Receiver<Message>,
+    active: usize,
+    seen: RecentUris,
+}
+
+impl fmt::Debug for Firehose {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Firehose")
+            .field("active", &self.active)
+            .field("seen", &self.seen.order.len())
+            .finish()
+    }
+}
+
+impl Default for Firehose {
+    fn default() -> Self {
+        Firehose::new()
+    }
+}
+
+impl Firehose {
+    /// A firehose without any streams, buffering up to 1024 events
+    pub fn new() -> Firehose {
+        let (sender, receiver) = mpsc::sync_channel(1024);
+        Firehose {
+            sender,
+            receiver,
+            active: 0,
+            seen: RecentUris {
+                capacity: 10_000,
+                set: HashSet::new(),
+                order: VecDeque::new(),
+            },
+        }
+    }
+
+    /// The public hashtag streams for `tag` of all `instances`
+    pub fn hashtag<H: HttpSend>(instances: &[MastodonUnauth<H>], tag: &str) -> Result<Firehose> {
+        let mut firehose = Firehose::new();
+        for instance in instances {
+            let reader = instance.streaming_public_hashtag(tag)?;
+            firehose.add(instance.base().as_str(), reader);
+        }
+        Ok(firehose)
+    }
+
+    /// The federated timeline streams of all `instances`
+    pub fn public<H: HttpSend>(instances: &[MastodonUnauth<H>]) -> Result<Firehose> {
+        let mut firehose = Firehose::new();
+        for instance in instances {
+            firehose.add(instance.base().as_str(), instance.streaming_public()?);
+        }
+        Ok(firehose)
+    }
+
+    /// How many status URIs to remember for recognising duplicates. Defaults
+    /// to 10000; values below 1 are treated as 1.
+    pub fn dedup_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.seen.capacity = capacity.max(1);
+        self
+    }
+
+    /// Opens `stream` on `client` and adds it
+    pub fn open<H: HttpSend>(
+        &mut self,
+        client: &MastodonUnauth<H>,
+        stream: StreamKind,
+    ) -> Result<&mut Self> {
+        let reader = client.streaming(stream)?;
+        Ok(self.add(client.base().as_str(), reader))
+    }
+
+    /// Adds a stream that is already open, labelling its events with
+    /// `instance`
+    pub fn add<S, R>(&mut self, instance: S, mut reader: EventReader<R>) -> &mut Self
+    where
+        S: Into<String>,
+        R: EventStream + Send + 'static,
+    {
+        let instance = instance.into();
+        let guard = ReaderGuard(self.sender.clone());
+        self.active += 1;
+        thread::spawn(move || {
+            let guard = guard;
+            loop {
+                let event = reader.next_event();
+                let failed = event.is_err();
+                let message = Some(FirehoseEvent {
+                    instance: instance.clone(),
+                    event,
+                });
+                if guard.0.send(message).is_err() || failed {
+                    break;
+                }
+            }
+        });
+        self
+    }
+
+    /// Waits for the next event that isn't a duplicate. Returns `None` once
+    /// every stream has ended.
+    pub fn recv(&mut self) -> Option<FirehoseEvent> {
+        while self.active > 0 {
+            // the firehose holds a sender itself, so this can't fail
+            let item = match self.receiver.recv().ok()? {
+                Some(item) => item,
+                None => {
+                    self.active -= 1;
+                    continue;
+                },
+            };
+            if let Ok(Event::Update(ref status)) = item.event {
+                if !self.seen.insert(&status.uri) {
+                    continue;
+                }
+            }
+            return Some(item);
+        }
+        None
+    }
+}
+
+impl Iterator for Firehose {
+    type Item = FirehoseEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+    use std::io::Cursor;
+
+    fn updates(uris: &[&str]) -> EventReader<Cursor<String>> {
+        let mut status: serde_json::Value = serde_json::from_str(test_fixtures::STATUS).unwrap();
+        let lines: String = uris
+            .iter()
+            .map(|uri| {
+                status["uri"] = (*uri).into();
+                let message = serde_json::json!({
+                    "event": "update",
+                    "payload": status.to_string(),
+                });
+                format!("{}\n", message)
+            })
+            .collect();
+        EventReader::new(Cursor::new(lines))
+    }
+
+    #[test]
+    fn test_dedup() {
+        let mut firehose = Firehose::new();
+        firehose
+            .add("https://one.example", updates(&["a", "b"]))
+            .add("https://two.example", updates(&["b", "c", "a"]));
+
+        let mut uris = Vec::new();
+        let mut errors = Vec::new();
+        for item in firehose {
+            match item.event {
+                Ok(Event::Update(status)) => uris.push(status.uri),
+                Err(_) => errors.push(item.instance),
+                Ok(event) => panic!("unexpected event {:?}", event),
+            }
+        }
+        uris.sort();
+        assert_eq!(uris, vec!["a", "b", "c"]);
+        errors.sort();
+        assert_eq!(errors, vec!["https://one.example", "https://two.example"]);
+    }
+
+    #[test]
+    fn test_recent_uris() {
+        let mut recent = RecentUris {
+            capacity: 2,
+            set: HashSet::new(),
+            order: VecDeque::new(),
+        };
+        assert!(recent.insert("a"));
+        assert!(recent.insert("b"));
+        assert!(!recent.insert("a"));
+        assert!(recent.insert("c"));
+        // "a" was forgotten to make room for "c"
+        assert!(recent.insert("a"));
+    }
+}
@@ -1,24 +1,147 @@
-use std::io::{self, BufRead, Write};
+use std::{
+    env,
+    io::{self, BufRead, Write},
+};
 
-use crate::{errors::Result, registration::Registered, Mastodon};
+use crate::{
+    errors::{Error, Result},
+    http_send::HttpSend,
+    registration::Registered,
+    Mastodon,
+};
 
 /// Finishes the authentication process for the given `Registered` object,
 /// using the command-line
-pub fn authenticate(registration: Registered) -> Result<Mastodon> {
+pub fn authenticate<H: HttpSend>(registration: Registered<H>) -> Result<Mastodon<H>> {
+    authenticate_with(
+        registration,
+        |url| {
+            let mut stdout = io::stdout().lock();
+            writeln!(&mut stdout, "Click this link to authorize: {}", url)?;
+            write!(&mut stdout, "Paste the returned authorization code: ")?;
+            Ok(stdout.flush()?)
+        },
+        || {
+            let mut input = String::new();
+            io::stdin().lock().read_line(&mut input)?;
+            Ok(input)
+        },
+    )
+}
+
+/// Finishes the authentication process for the given `Registered` object,
+/// calling `show_url` with the URL the user has to open to authorize the app,
+/// and then `get_code` to obtain the authorization code they were given.
+///
+/// Surrounding whitespace is removed from the code.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// use elefren::helpers::cli;
+///
+/// # fn main() -> elefren::Result<()> {
+/// let registration = Registration::new("https://mastodon.social")
+///     .client_name("elefren-examples")
+///     .build()?;
+/// let mastodon = cli::authenticate_with(
+///     registration,
+///     |url| {
+///         // e.g. open `url` in a browser window
+///         Ok(())
+///     },
+///     || {
+///         // e.g. ask for the code in a dialog
+///         Ok(String::from("the code"))
+///     },
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn authenticate_with<H, S, C>(
+    registration: Registered<H>,
+    show_url: S,
+    get_code: C,
+) -> Result<Mastodon<H>>
+where
+    H: HttpSend,
+    S: FnOnce(&str) -> Result<()>,
+    C: FnOnce() -> Result<String>,
+{
     let url = registration.authorize_url()?;
+    show_url(&url)?;
+    let code = get_code()?;
+    registration.complete(code.trim())
+}
+
+/// Finishes the authentication process for the given `Registered` object
+/// without any interaction, taking the authorization code from the
+/// environment variable `var`, e.g. in test harnesses
+pub fn authenticate_from_env<H: HttpSend>(
+    registration: Registered<H>,
+    var: &str,
+) -> Result<Mastodon<H>> {
+    authenticate_with(
+        registration,
+        |_| Ok(()),
+        || {
+            env::var(var).map_err(|e| {
+                Error::Other(format!(
+                    "can't read the authorization code from {}: {}",
+                    var, e
+                ))
+            })
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scopes::Scopes;
 
-    let stdout = io::stdout();
-    let stdin = io::stdin();
+    fn registered() -> Registered {
+        Registered::from_parts(
+            &mockito::server_url(),
+            "the-id",
+            "the-secret",
+            "urn:ietf:wg:oauth:2.0:oob",
+            Scopes::read_all(),
+            false,
+        )
+    }
 
-    let mut stdout = stdout.lock();
-    let mut stdin = stdin.lock();
+    #[test]
+    fn test_authenticate_with() {
+        let token = mockito::mock("POST", "/oauth/token")
+            .match_query(mockito::Matcher::UrlEncoded(
+                "code".into(),
+                "the-code".into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"access_token":"the-token","scope":"read","created_at":0}"#)
+            .create();
 
-    writeln!(&mut stdout, "Click this link to authorize: {}", url)?;
-    write!(&mut stdout, "Paste the returned authorization code: ")?;
-    stdout.flush()?;
+        let mut shown = None;
+        let mastodon = authenticate_with(
+            registered(),
+            |url| {
+                shown = Some(url.to_string());
+                Ok(())
+            },
+            || Ok(String::from(" the-code\n")),
+        )
+        .unwrap();
+        assert!(shown.unwrap().contains("/oauth/authorize"));
+        assert_eq!(mastodon.data.token, "the-token");
+        token.assert();
+    }
 
-    let mut input = String::new();
-    stdin.read_line(&mut input)?;
-    let code = input.trim();
-    Ok(registration.complete(code)?)
+    #[test]
+    fn test_authenticate_from_missing_env() {
+        let result = authenticate_from_env(registered(), "ELEFREN_TEST_UNSET_CODE");
+        assert!(matches!(result, Err(Error::Other(_))));
+    }
 }
@@ -0,0 +1,264 @@
+//! Checking the entities against the JSON servers actually send
+//!
+//! Servers other than Mastodon, and older or newer versions of it, leave out
+//! fields, add their own and send values Mastodon doesn't, which is what
+//! most often breaks deserializing their responses. [`check_dir`](fn.check_dir.html)
+//! deserializes every fixture under a directory as the entity it's named
+//! after, so that such breakage shows up in tests rather than in production.
+//!
+//! A fixture is a `.json` file holding an entity, or an array of them, as
+//! captured from a server, e.g. with `curl`. The name of the file up to its
+//! first `.` is the entity, one of [`ENTITIES`](constant.ENTITIES.html):
+//! `status.json`, `status.reblog.json` and `status.with-poll.json` are all
+//! checked as a `Status`. Fixtures are usually sorted into a directory per
+//! server, e.g. `mastodon-4.1/status.json` and `akkoma-3.9/status.json`.
+//!
+//! elefren checks its own fixtures, in `tests/fixtures/compat`, this way.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # extern crate elefren;
+//! #[test]
+//! fn test_fixtures_deserialize() {
+//!     elefren::helpers::compat::check_dir("tests/fixtures/servers")
+//!         .unwrap()
+//!         .assert_ok();
+//! }
+//! ```
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::de::DeserializeOwned;
+
+use crate::{
+    entities::{
+        account::Account,
+        attachment::Attachment,
+        card::Card,
+        context::Context,
+        conversation::Conversation,
+        filter::{Filter, FilterV2},
+        instance::{Instance, Rule},
+        list::List,
+        notification::Notification,
+        poll::Poll,
+        push::Subscription,
+        relationship::Relationship,
+        report::Report,
+        scheduled_status::ScheduledStatus,
+        search_result::{SearchResult, SearchResultV2},
+        status::{Emoji, Mention, Status, Tag},
+        suggestion::Suggestion,
+    },
+    errors::{Error, Result},
+    json_backend,
+};
+
+macro_rules! entities {
+    ($($name:literal => $entity:ty,)*) => {
+        /// The names of the entities fixtures can hold, as used by `check`
+        pub const ENTITIES: &[&str] = &[$($name,)*];
+
+        /// Checks that `json`, an entity or an array of them, deserializes
+        /// as the entity called `entity`, one of `ENTITIES`
+        ///
+        /// # Errors
+        ///
+        /// If the entity isn't known, or `json` doesn't deserialize as it.
+        pub fn check(entity: &str, json: &str) -> Result<()> {
+            match entity {
+                $($name => deserialize::<$entity>(json),)*
+                _ => Err(Error::Other(format!("unknown entity `{}`", entity))),
+            }
+        }
+    };
+}
+
+entities! {
+    "account" => Account,
+    "attachment" => Attachment,
+    "card" => Card,
+    "context" => Context,
+    "conversation" => Conversation,
+    "emoji" => Emoji,
+    "filter" => Filter,
+    "filter_v2" => FilterV2,
+    "instance" => Instance,
+    "list" => List,
+    "mention" => Mention,
+    "notification" => Notification,
+    "poll" => Poll,
+    "push_subscription" => Subscription,
+    "relationship" => Relationship,
+    "report" => Report,
+    "rule" => Rule,
+    "scheduled_status" => ScheduledStatus,
+    "search_result" => SearchResult,
+    "search_result_v2" => SearchResultV2,
+    "status" => Status,
+    "suggestion" => Suggestion,
+    "tag" => Tag,
+}
+
+// Deserializes with the same backend as the client's responses
+fn deserialize<T: DeserializeOwned>(json: &str) -> Result<()> {
+    if json.trim_start().starts_with('[') {
+        json_backend::from_str::<Vec<T>>(json)?;
+    } else {
+        json_backend::from_str::<T>(json)?;
+    }
+    Ok(())
+}
+
+/// A fixture that didn't deserialize
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompatFailure {
+    /// The path of the fixture
+    pub path: PathBuf,
+    /// Why it didn't deserialize, e.g. the field that was missing
+    pub error: String,
+}
+
+/// The outcome of `check_dir`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompatReport {
+    /// The fixtures that were checked, in the order they were checked
+    pub checked: Vec<PathBuf>,
+    /// The fixtures that didn't deserialize
+    pub failures: Vec<CompatFailure>,
+}
+
+impl CompatReport {
+    /// Whether every fixture deserialized
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Panics, listing the failures, unless every fixture deserialized
+    ///
+    /// Also panics if there were no fixtures, which usually means the
+    /// directory was wrong.
+    pub fn assert_ok(&self) {
+        assert!(!self.checked.is_empty(), "no fixtures were found");
+        assert!(self.is_ok(), "{}", self);
+    }
+}
+
+impl fmt::Display for CompatReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} fixtures didn't deserialize",
+            self.failures.len(),
+            self.checked.len()
+        )?;
+        for failure in &self.failures {
+            write!(f, "\n{}: {}", failure.path.display(), failure.error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks every `.json` file under `dir`, and its subdirectories, as the
+/// entity it's named after, see the [module documentation](index.html)
+///
+/// # Errors
+///
+/// If `dir`, or a fixture in it, can't be read. Fixtures that don't
+/// deserialize are listed in the report instead.
+pub fn check_dir(dir: impl AsRef<Path>) -> Result<CompatReport> {
+    let mut paths = Vec::new();
+    collect_fixtures(dir.as_ref(), &mut paths)?;
+    paths.sort();
+    let mut report = CompatReport::default();
+    for path in paths {
+        let json = fs::read_to_string(&path)?;
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let entity = file_name.split('.').next().unwrap_or_default();
+        if let Err(e) = check(entity, &json) {
+            let error = match e {
+                Error::Other(message) => message,
+                e => e.to_string(),
+            };
+            report.failures.push(CompatFailure {
+                path: path.clone(),
+                error,
+            });
+        }
+        report.checked.push(path);
+    }
+    Ok(report)
+}
+
+fn collect_fixtures(dir: &Path, paths: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_fixtures(&path, paths)?;
+        } else if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixtures() {
+        let report = check_dir(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/compat"
+        ))
+        .expect("Couldn't read the fixtures");
+        report.assert_ok();
+        let servers = [
+            "mastodon-3.5",
+            "mastodon-4.1",
+            "pleroma-2.5",
+            "akkoma-3.9",
+            "gotosocial-0.11",
+        ];
+        for server in servers {
+            assert!(
+                report
+                    .checked
+                    .iter()
+                    .any(|path| path.parent().unwrap().ends_with(server)),
+                "no fixtures for {}",
+                server
+            );
+        }
+    }
+
+    #[test]
+    fn test_failures() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("relationship.json"), r#"{"id": "1"}"#).unwrap();
+        fs::write(
+            dir.path().join("tag.trending.json"),
+            r#"[{"name": "a", "url": "u"}]"#,
+        )
+        .unwrap();
+        fs::write(dir.path().join("unicorn.json"), "{}").unwrap();
+        fs::write(dir.path().join("README.md"), "").unwrap();
+        let report = check_dir(dir.path()).unwrap();
+        assert_eq!(report.checked.len(), 3);
+        assert_eq!(report.failures.len(), 2);
+        assert!(report.failures[0].path.ends_with("relationship.json"));
+        assert!(report.failures[0].error.contains("missing field"));
+        assert_eq!(report.failures[1].error, "unknown entity `unicorn`");
+        assert!(report
+            .to_string()
+            .starts_with("2 of 3 fixtures didn't deserialize\n"));
+    }
+}
@@ -0,0 +1,237 @@
+/// Converts the HTML `content` of a status (or an account note) into plain
+/// text.
+///
+/// Mastodon only emits a small, sanitized subset of HTML, so this does not try
+/// to be a general purpose HTML parser: tags are dropped, line and paragraph
+/// breaks become newlines, and the common character entities are decoded.
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::helpers::html::to_plain_text;
+///
+/// let text = to_plain_text("<p>Hello <a href=\"https://example.com\">world</a> &amp; co</p><p>bye</p>");
+/// assert_eq!(text, "Hello world & co\n\nbye");
+/// ```
+pub fn to_plain_text(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find(['<', '&']) {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if rest.starts_with('<') {
+            let end = match rest.find('>') {
+                Some(end) => end,
+                None => {
+                    // unterminated tag, treat the remainder as text
+                    out.push_str(rest);
+                    rest = "";
+                    break;
+                },
+            };
+            let tag = rest[1..end].trim().to_ascii_lowercase();
+            let name = tag
+                .trim_start_matches('/')
+                .split(|c: char| c.is_whitespace() || c == '/')
+                .next()
+                .unwrap_or("");
+            match name {
+                "br" => out.push('\n'),
+                "p" if tag.starts_with('/') => out.push_str("\n\n"),
+                _ => {},
+            }
+            rest = &rest[end + 1..];
+        } else {
+            let (decoded, consumed) = decode_entity(rest);
+            out.push_str(&decoded);
+            rest = &rest[consumed..];
+        }
+    }
+    out.push_str(rest);
+
+    out.trim_end().to_string()
+}
+
+// A link (an `<a>` or `<link>` tag with an `href`) found in an HTML document
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Link {
+    pub(crate) href: String,
+    // the space-separated values of the `rel` attribute, lowercased
+    pub(crate) rel: Vec<String>,
+}
+
+// Finds all links in `html`, in document order
+pub(crate) fn links(html: &str) -> Vec<Link> {
+    let mut links = Vec::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        let end = match rest.find('>') {
+            Some(end) => end,
+            None => break,
+        };
+        let tag = &rest[..end];
+        rest = &rest[end + 1..];
+
+        let name_end = tag
+            .find(|c: char| c.is_whitespace() || c == '/')
+            .unwrap_or(tag.len());
+        let name = &tag[..name_end];
+        if !name.eq_ignore_ascii_case("a") && !name.eq_ignore_ascii_case("link") {
+            continue;
+        }
+
+        let mut href = None;
+        let mut rel = Vec::new();
+        for (attr, value) in attributes(&tag[name_end..]) {
+            if attr.eq_ignore_ascii_case("href") {
+                href = Some(to_plain_text(value));
+            } else if attr.eq_ignore_ascii_case("rel") {
+                rel = value
+                    .split_whitespace()
+                    .map(|value| value.to_ascii_lowercase())
+                    .collect();
+            }
+        }
+        if let Some(href) = href {
+            links.push(Link { href, rel });
+        }
+    }
+
+    links
+}
+
+// Splits the attributes of a tag into name/value pairs. Attributes without a
+// value get an empty one.
+fn attributes(mut s: &str) -> Vec<(&str, &str)> {
+    let mut attributes = Vec::new();
+    loop {
+        s = s.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if s.is_empty() {
+            break;
+        }
+        let name_end = s
+            .find(|c: char| c.is_whitespace() || c == '=' || c == '/')
+            .unwrap_or(s.len());
+        let name = &s[..name_end];
+        s = s[name_end..].trim_start();
+
+        let value = if let Some(after) = s.strip_prefix('=') {
+            let after = after.trim_start();
+            match after.chars().next() {
+                Some(quote @ '"') | Some(quote @ '\'') => {
+                    let value_end = after[1..].find(quote).map_or(after.len(), |idx| idx + 1);
+                    s = after.get(value_end + 1..).unwrap_or("");
+                    &after[1..value_end]
+                },
+                _ => {
+                    let value_end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    s = &after[value_end..];
+                    &after[..value_end]
+                },
+            }
+        } else {
+            ""
+        };
+        if name.is_empty() {
+            // a stray `=`, skip it
+            s = s.get(1..).unwrap_or("");
+            continue;
+        }
+        attributes.push((name, value));
+    }
+    attributes
+}
+
+// Decodes the entity at the start of `s` (which starts with `&`), returning
+// the decoded text and the number of bytes consumed
+fn decode_entity(s: &str) -> (String, usize) {
+    let end = match s[1..].find(|c: char| c == ';' || c == '&' || c.is_whitespace()) {
+        Some(idx) if s[1 + idx..].starts_with(';') => idx + 1,
+        _ => return ("&".to_string(), 1),
+    };
+    let entity = &s[1..end];
+    let decoded = match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some('\u{a0}'),
+        _ if entity.starts_with("#x") || entity.starts_with("#X") => {
+            u32::from_str_radix(&entity[2..], 16)
+                .ok()
+                .and_then(std::char::from_u32)
+        },
+        _ if entity.starts_with('#') => entity[1..].parse().ok().and_then(std::char::from_u32),
+        _ => None,
+    };
+    match decoded {
+        Some(c) => (c.to_string(), end + 1),
+        None => (s[..=end].to_string(), end + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strips_tags() {
+        assert_eq!(
+            to_plain_text(
+                "<p><span class=\"h-card\"><a href=\"https://example.com/@foo\" \
+                 class=\"u-url mention\">@<span>foo</span></a></span> hi</p>"
+            ),
+            "@foo hi"
+        );
+    }
+
+    #[test]
+    fn test_line_breaks() {
+        assert_eq!(
+            to_plain_text("<p>one<br>two<br />three</p><p>four</p>"),
+            "one\ntwo\nthree\n\nfour"
+        );
+    }
+
+    #[test]
+    fn test_entities() {
+        assert_eq!(
+            to_plain_text("&lt;b&gt; &quot;x&quot; &#39;y&#39; &#x41; &unknown; a & b"),
+            "<b> \"x\" 'y' A &unknown; a & b"
+        );
+    }
+
+    #[test]
+    fn test_unterminated_tag() {
+        assert_eq!(to_plain_text("a <b"), "a <b");
+    }
+
+    #[test]
+    fn test_links() {
+        let html = "<head><LINK rel=me href='https://example.com/@foo'/>\
+                    <meta name=\"x\"></head><body>\
+                    <a class=\"u-url\" href=\"https://example.com/?a=1&amp;b=2\" \
+                    rel=\"Me noopener\" target=_blank>x</a>\
+                    <a name=\"anchor\">no href</a>\
+                    <abbr href=\"https://not.a.link\">y</abbr></body>";
+        assert_eq!(
+            links(html),
+            vec![
+                Link {
+                    href: "https://example.com/@foo".into(),
+                    rel: vec!["me".into()],
+                },
+                Link {
+                    href: "https://example.com/?a=1&b=2".into(),
+                    rel: vec!["me".into(), "noopener".into()],
+                },
+            ]
+        );
+    }
+}
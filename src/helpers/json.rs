@@ -103,10 +103,36 @@ mod tests {
                 client_secret: "0987dcba".into(),
                 redirect: "urn:ietf:wg:oauth:2.0:oob".into(),
                 token: "fedc5678".into(),
+                ..Default::default()
             }
         );
     }
     #[test]
+    fn test_from_str_with_token_metadata() {
+        let doc = indoc::indoc!(
+            r#"
+            {
+                "base": "https://example.com",
+                "client_id": "adbc01234",
+                "client_secret": "0987dcba",
+                "redirect": "urn:ietf:wg:oauth:2.0:oob",
+                "token": "fedc5678",
+                "token_created_at": "2019-12-08T03:48:33Z",
+                "scopes": "read write"
+            }
+            "#
+        );
+        let desered = from_str(doc).expect("Couldn't deserialize Data");
+        assert_eq!(
+            desered.token_created_at,
+            Some("2019-12-08T03:48:33Z".parse().expect("Couldn't parse date"))
+        );
+        assert_eq!(
+            desered.scopes,
+            Some(crate::scopes::Scopes::read_all() | crate::scopes::Scopes::write_all())
+        );
+    }
+    #[test]
     fn test_from_slice() {
         let doc = DOC.as_bytes();
         let desered = from_slice(&doc).expect("Couldn't deserialize Data");
@@ -118,6 +144,7 @@ mod tests {
                 client_secret: "0987dcba".into(),
                 redirect: "urn:ietf:wg:oauth:2.0:oob".into(),
                 token: "fedc5678".into(),
+                ..Default::default()
             }
         );
     }
@@ -134,6 +161,7 @@ mod tests {
                 client_secret: "0987dcba".into(),
                 redirect: "urn:ietf:wg:oauth:2.0:oob".into(),
                 token: "fedc5678".into(),
+                ..Default::default()
             }
         );
     }
@@ -150,6 +178,7 @@ mod tests {
                 client_secret: "0987dcba".into(),
                 redirect: "urn:ietf:wg:oauth:2.0:oob".into(),
                 token: "fedc5678".into(),
+                ..Default::default()
             }
         );
     }
@@ -161,6 +190,7 @@ mod tests {
             client_secret: "0987dcba".into(),
             redirect: "urn:ietf:wg:oauth:2.0:oob".into(),
             token: "fedc5678".into(),
+            ..Default::default()
         };
         let s = to_string(&data).expect("Couldn't serialize Data");
         let desered = from_str(&s).expect("Couldn't deserialize Data");
@@ -174,6 +204,7 @@ mod tests {
             client_secret: "0987dcba".into(),
             redirect: "urn:ietf:wg:oauth:2.0:oob".into(),
             token: "fedc5678".into(),
+            ..Default::default()
         };
         let v = to_vec(&data).expect("Couldn't write to vec");
         let desered = from_slice(&v).expect("Couldn't deserialize data");
@@ -187,6 +218,7 @@ mod tests {
             client_secret: "0987dcba".into(),
             redirect: "urn:ietf:wg:oauth:2.0:oob".into(),
             token: "fedc5678".into(),
+            ..Default::default()
         };
         let mut buffer = Vec::new();
         to_writer(&data, &mut buffer).expect("Couldn't write to writer");
@@ -202,6 +234,7 @@ mod tests {
             client_secret: "0987dcba".into(),
             redirect: "urn:ietf:wg:oauth:2.0:oob".into(),
             token: "fedc5678".into(),
+            ..Default::default()
         };
         let tempdir = tempdir().expect("Couldn't create tempdir");
         let filename = tempdir.path().join("mastodon-data.json");
@@ -217,6 +250,7 @@ mod tests {
             client_secret: "0987dcba".into(),
             redirect: "urn:ietf:wg:oauth:2.0:oob".into(),
             token: "fedc5678".into(),
+            ..Default::default()
         };
         let file = NamedTempFile::new().expect("Couldn't create tempfile");
         let mut options = OpenOptions::new();
@@ -35,4 +35,25 @@ pub mod json;
 pub mod env;
 
 /// Helpers for working with the command line
+#[cfg(feature = "client")]
 pub mod cli;
+
+/// Helpers for checking that entities deserialize from what different
+/// servers send
+///
+/// Not available on `wasm32`, which has no filesystem to read fixtures from.
+#[cfg(not(target_arch = "wasm32"))]
+pub mod compat;
+
+/// Helpers for working with the HTML returned by the API
+pub mod html;
+
+/// Helpers for verifying the links in an account's profile fields
+#[cfg(feature = "client")]
+pub mod rel_me;
+
+/// Helpers for building and parsing the web URLs of statuses and accounts
+pub mod urls;
+
+/// Helpers for counting the characters of a status the way the server does
+pub mod text;
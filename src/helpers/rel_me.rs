@@ -0,0 +1,170 @@
+use std::io;
+
+use crate::{
+    entities::account::Account, errors::Error, helpers::html, http_send::HttpSend, Mastodon,
+};
+
+// Pages larger than this aren't searched for links, like Mastodon does
+const MAX_PAGE_SIZE: usize = 1024 * 1024;
+
+/// The outcome of verifying one of an account's profile fields
+#[derive(Debug)]
+pub enum Verification {
+    /// The field's value isn't a link
+    NotALink,
+    /// The linked page links back to the profile with `rel="me"`
+    Verified,
+    /// The linked page doesn't link back to the profile
+    NotVerified,
+    /// The linked page couldn't be fetched
+    Failed(Error),
+}
+
+impl Verification {
+    /// Whether the link was verified
+    pub fn is_verified(&self) -> bool {
+        matches!(*self, Verification::Verified)
+    }
+}
+
+/// The verification of a single profile field
+#[derive(Debug)]
+pub struct FieldVerification {
+    /// The name of the field
+    pub name: String,
+    /// The link in the field's value, if any
+    pub link: Option<String>,
+    /// Whether the link could be verified
+    pub verification: Verification,
+}
+
+/// Checks, for each of the profile fields of `account`, whether the linked
+/// page links back to the profile with `rel="me"`. This is what the web
+/// interface shows as a verified link.
+///
+/// The pages are fetched with `client`, one after the other.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// use elefren::helpers::rel_me;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let data = Data {
+/// #   base: "".into(),
+/// #   client_id: "".into(),
+/// #   client_secret: "".into(),
+/// #   redirect: "".into(),
+/// #   token: "".into(),
+/// #   ..Default::default()
+/// # };
+/// let client = Mastodon::from(data);
+/// let account = client.verify_credentials()?;
+/// for field in rel_me::verify_fields(&client, &account) {
+///     let mark = if field.verification.is_verified() { "✓" } else { " " };
+///     println!("{} {}", mark, field.name);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn verify_fields<H: HttpSend>(
+    client: &Mastodon<H>,
+    account: &Account,
+) -> Vec<FieldVerification> {
+    let fields = account.fields.as_deref().unwrap_or(&[]);
+    fields
+        .iter()
+        .map(|field| {
+            let link = html::links(&field.value)
+                .into_iter()
+                .next()
+                .map(|link| link.href);
+            let verification = match link {
+                Some(ref link) => verify_link(client, link, &account.url),
+                None => Verification::NotALink,
+            };
+            FieldVerification {
+                name: field.name.clone(),
+                link,
+                verification,
+            }
+        })
+        .collect()
+}
+
+fn verify_link<H: HttpSend>(client: &Mastodon<H>, link: &str, profile: &str) -> Verification {
+    let mut page = LimitedWriter(Vec::new());
+    if let Err(e) = client.download(link, &mut page) {
+        return Verification::Failed(e);
+    }
+    let page = String::from_utf8_lossy(&page.0);
+    let links_back = html::links(&page)
+        .into_iter()
+        .any(|link| link.rel.iter().any(|rel| rel == "me") && same_url(&link.href, profile));
+    if links_back {
+        Verification::Verified
+    } else {
+        Verification::NotVerified
+    }
+}
+
+fn same_url(a: &str, b: &str) -> bool {
+    a.trim_end_matches('/') == b.trim_end_matches('/')
+}
+
+// Collects at most `MAX_PAGE_SIZE` bytes, failing on larger pages
+struct LimitedWriter(Vec<u8>);
+
+impl io::Write for LimitedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.0.len() + buf.len() > MAX_PAGE_SIZE {
+            return Err(io::Error::other("page too large"));
+        }
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{entities::account::MetadataField, test_fixtures, Data};
+
+    #[test]
+    fn test_verify_fields() {
+        let base = mockito::server_url();
+        let client = Mastodon::from(Data {
+            base: base.clone().into(),
+            ..Default::default()
+        });
+        let mut account = test_fixtures::account();
+        account.url = format!("{}/@alice", base);
+        account.fields = Some(vec![
+            MetadataField::new(
+                "Website",
+                &format!("<a href=\"{}/good\" rel=\"me nofollow\">x</a>", base),
+            ),
+            MetadataField::new("Blog", &format!("<a href=\"{}/bad\">y</a>", base)),
+            MetadataField::new("Pronouns", "they/them"),
+        ]);
+        let good = mockito::mock("GET", "/good")
+            .with_body(format!("<a rel=\"me\" href=\"{}/@alice/\">me</a>", base))
+            .create();
+        let bad = mockito::mock("GET", "/bad")
+            .with_body(format!("<a href=\"{}/@alice\">not rel=me</a>", base))
+            .create();
+
+        let results = verify_fields(&client, &account);
+        assert!(results[0].verification.is_verified());
+        assert!(matches!(results[1].verification, Verification::NotVerified));
+        assert!(matches!(results[2].verification, Verification::NotALink));
+        assert_eq!(results[2].link, None);
+        good.assert();
+        bad.assert();
+    }
+}
@@ -0,0 +1,185 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The number of characters a link counts as, whatever its length
+pub const URL_LENGTH: usize = 23;
+
+/// Counts the characters of a status' text the way Mastodon does when
+/// checking it against the instance's character limit
+///
+/// Links to `http` and `https` URLs count as 23 characters, mentions of
+/// remote accounts only count their username, i.e. `@user@example.org`
+/// counts as `@user`, and characters are counted as the user perceives
+/// them, so an emoji made of several code points counts once.
+///
+/// The server counts the spoiler text as is, without these exceptions; use
+/// [`spoiler_length`](fn.spoiler_length.html) for it.
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::helpers::text::status_length;
+///
+/// assert_eq!(status_length("hi @Gargron@mastodon.social"), 11);
+/// assert_eq!(status_length("see https://example.org/a/very/long/path"), 27);
+/// ```
+pub fn status_length(text: &str) -> usize {
+    countable_text(text).graphemes(true).count()
+}
+
+/// Counts the characters of a spoiler text the way Mastodon does, where
+/// links and mentions count with their full length
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::helpers::text::spoiler_length;
+///
+/// assert_eq!(spoiler_length("🏳️‍🌈 spoilers"), 10);
+/// ```
+pub fn spoiler_length(spoiler_text: &str) -> usize {
+    spoiler_text.graphemes(true).count()
+}
+
+// `text` with links replaced by `URL_LENGTH` placeholder characters and
+// mentions shortened to their username
+fn countable_text(text: &str) -> String {
+    let mut countable = String::with_capacity(text.len());
+    let mut previous = None;
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let entity = if starts_entity(previous) {
+            url_len(rest)
+                .map(|len| (len, "x".repeat(URL_LENGTH)))
+                .or_else(|| mention(rest))
+        } else {
+            None
+        };
+        let len = match entity {
+            Some((len, replacement)) => {
+                countable.push_str(&replacement);
+                len
+            },
+            None => {
+                countable.push(c);
+                c.len_utf8()
+            },
+        };
+        previous = rest[..len].chars().last();
+        rest = &rest[len..];
+    }
+    countable
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// links and mentions aren't recognized in the middle of a word or path
+fn starts_entity(previous: Option<char>) -> bool {
+    !matches!(previous, Some(c) if is_word(c) || c == '/' || c == '@')
+}
+
+// The length of the link at the start of `text`, if any
+fn url_len(text: &str) -> Option<usize> {
+    let scheme = ["https://", "http://"]
+        .iter()
+        .find(|scheme| {
+            text.as_bytes()
+                .get(..scheme.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(scheme.as_bytes()))
+        })?
+        .len();
+    let end = text.find(char::is_whitespace).unwrap_or(text.len());
+    let mut url = &text[..end];
+    // punctuation at the end belongs to the sentence, not the link
+    loop {
+        let trimmed = url.trim_end_matches(&['.', ',', ':', ';', '!', '?', '"', '\''][..]);
+        let unbalanced = trimmed.matches('(').count() < trimmed.matches(')').count();
+        let trimmed = if trimmed.ends_with(')') && unbalanced {
+            &trimmed[..trimmed.len() - 1]
+        } else {
+            trimmed
+        };
+        if trimmed.len() == url.len() {
+            break;
+        }
+        url = trimmed;
+    }
+    let host = url[scheme..].split(&['/', '?', '#'][..]).next()?;
+    if host.is_empty() {
+        return None;
+    }
+    Some(url.len())
+}
+
+// The length of the mention at the start of `text` and what it counts as
+fn mention(text: &str) -> Option<(usize, String)> {
+    let rest = text.strip_prefix('@')?;
+    let username_len = rest
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(rest.len());
+    if username_len == 0 {
+        return None;
+    }
+    let username = &rest[..username_len];
+    let mut len = 1 + username_len;
+    if let Some(domain) = rest[username_len..].strip_prefix('@') {
+        let domain_len = domain
+            .find(|c: char| !(is_word(c) || c == '.' || c == '-'))
+            .unwrap_or(domain.len());
+        // the domain has to end with a word character
+        let domain = domain[..domain_len].trim_end_matches(|c: char| !is_word(c));
+        if !domain.is_empty() {
+            len += 1 + domain.len();
+        }
+    }
+    Some((len, format!("@{}", username)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_text() {
+        assert_eq!(status_length(""), 0);
+        assert_eq!(status_length("awoo!"), 5);
+        assert_eq!(status_length("naïve café"), 10);
+        assert_eq!(status_length("👩‍👩‍👧 family"), 8);
+    }
+
+    #[test]
+    fn test_urls() {
+        assert_eq!(status_length("https://example.org"), URL_LENGTH);
+        assert_eq!(
+            status_length("HTTP://example.org/a/very/long/path?with=query#and-fragment"),
+            URL_LENGTH
+        );
+        assert_eq!(status_length("(see https://example.org)."), URL_LENGTH + 7);
+        assert_eq!(
+            status_length("https://en.wikipedia.org/wiki/Rust_(programming_language)"),
+            URL_LENGTH
+        );
+        assert_eq!(status_length("https:// nope"), 13);
+        assert_eq!(status_length("ftp://example.org"), 17);
+        assert_eq!(status_length("xhttps://example.org"), 20);
+    }
+
+    #[test]
+    fn test_mentions() {
+        assert_eq!(status_length("@Gargron"), 8);
+        assert_eq!(status_length("@Gargron@mastodon.social"), 8);
+        assert_eq!(status_length("@Gargron@mastodon.social."), 9);
+        assert_eq!(status_length("hi @a@b.c and @d@e.f"), 12);
+        assert_eq!(status_length("mail me at me@example.org"), 25);
+        assert_eq!(status_length("@ alone"), 7);
+    }
+
+    #[test]
+    fn test_spoiler_length() {
+        assert_eq!(spoiler_length("@Gargron@mastodon.social"), 24);
+        assert_eq!(spoiler_length("https://example.org"), 19);
+    }
+}
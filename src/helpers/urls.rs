@@ -0,0 +1,194 @@
+use url::Url;
+
+use crate::entities::{account::Account, status::Status};
+
+/// An account, as identified by its profile URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountUrl {
+    /// The instance the URL points to
+    pub domain: String,
+    /// The full `username@domain` of the account, with the domain of its
+    /// home instance
+    pub acct: String,
+}
+
+/// A status, as identified by its web URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusUrl {
+    /// The instance the URL points to
+    pub domain: String,
+    /// The full `username@domain` of the status' author
+    pub acct: String,
+    /// The id of the status on `domain`
+    pub id: String,
+}
+
+/// Parses a profile URL, i.e. `https://instance/@user`,
+/// `https://instance/@user@home` or `https://instance/users/user`
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::helpers::urls::parse_account_url;
+///
+/// let account = parse_account_url("https://mastodon.social/@Gargron").unwrap();
+/// assert_eq!(account.acct, "Gargron@mastodon.social");
+/// ```
+pub fn parse_account_url(url: &str) -> Option<AccountUrl> {
+    let (domain, segments) = split(url)?;
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    match segments[..] {
+        [user] => account(&domain, user),
+        ["users", user] => account(&domain, &format!("@{}", user)),
+        _ => None,
+    }
+}
+
+/// Parses a status URL, i.e. `https://instance/@user/12345`,
+/// `https://instance/@user@home/12345` or
+/// `https://instance/users/user/statuses/12345`
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::helpers::urls::parse_status_url;
+///
+/// let status = parse_status_url("https://mastodon.social/@Gargron/109").unwrap();
+/// assert_eq!(status.domain, "mastodon.social");
+/// assert_eq!(status.acct, "Gargron@mastodon.social");
+/// assert_eq!(status.id, "109");
+/// ```
+pub fn parse_status_url(url: &str) -> Option<StatusUrl> {
+    let (domain, segments) = split(url)?;
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let (account, id) = match segments[..] {
+        [user, id] => (account(&domain, user)?, id),
+        ["users", user, "statuses", id] => (account(&domain, &format!("@{}", user))?, id),
+        _ => return None,
+    };
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    Some(StatusUrl {
+        domain,
+        acct: account.acct,
+        id: id.to_string(),
+    })
+}
+
+/// The web URL of `status` on its home instance
+///
+/// This is the status' `url` when the server sent one, or else derived
+/// from its ActivityPub `uri`.
+pub fn status_url(status: &Status) -> Option<String> {
+    if let Some(ref url) = status.url {
+        return Some(url.clone());
+    }
+    let (domain, segments) = split(&status.uri)?;
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    match segments[..] {
+        ["users", user, "statuses", id] => Some(format!("https://{}/@{}/{}", domain, user, id)),
+        _ => None,
+    }
+}
+
+/// The full `username@domain` of `account`, even for accounts local to the
+/// instance the client talks to, whose `acct` lacks the domain
+pub fn full_acct(account: &Account) -> Option<String> {
+    if account.acct.contains('@') {
+        return Some(account.acct.clone());
+    }
+    let domain = Url::parse(&account.url).ok()?.host_str()?.to_string();
+    Some(format!("{}@{}", account.acct, domain))
+}
+
+// Splits `url` into its host and its non-empty path segments
+fn split(url: &str) -> Option<(String, Vec<String>)> {
+    let parsed = Url::parse(url).ok()?;
+    if parsed.scheme() != "https" && parsed.scheme() != "http" {
+        return None;
+    }
+    let domain = parsed.host_str()?.to_string();
+    let segments = parsed
+        .path_segments()?
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect();
+    Some((domain, segments))
+}
+
+// Parses an `@user` or `@user@home` path segment
+fn account(domain: &str, segment: &str) -> Option<AccountUrl> {
+    let acct = segment.strip_prefix('@')?;
+    let (user, home) = match acct.split_once('@') {
+        Some((user, home)) => (user, home),
+        None => (acct, domain),
+    };
+    let valid = |s: &str| !s.is_empty() && !s.contains('@');
+    if !valid(user) || !valid(home) {
+        return None;
+    }
+    Some(AccountUrl {
+        domain: domain.to_string(),
+        acct: format!("{}@{}", user, home),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    #[test]
+    fn test_parse_account_url() {
+        assert_eq!(
+            parse_account_url("https://example.com/@alice"),
+            Some(AccountUrl {
+                domain: "example.com".into(),
+                acct: "alice@example.com".into(),
+            })
+        );
+        assert_eq!(
+            parse_account_url("https://example.com/@bob@other.example/").map(|a| a.acct),
+            Some("bob@other.example".into())
+        );
+        assert_eq!(
+            parse_account_url("https://example.com/users/alice").map(|a| a.acct),
+            Some("alice@example.com".into())
+        );
+        assert_eq!(parse_account_url("https://example.com/about"), None);
+        assert_eq!(parse_account_url("https://example.com/@"), None);
+        assert_eq!(parse_account_url("not a url"), None);
+    }
+
+    #[test]
+    fn test_parse_status_url() {
+        assert_eq!(
+            parse_status_url("https://example.com/@bob@other.example/109876?x=1"),
+            Some(StatusUrl {
+                domain: "example.com".into(),
+                acct: "bob@other.example".into(),
+                id: "109876".into(),
+            })
+        );
+        assert_eq!(
+            parse_status_url("https://example.com/users/alice/statuses/42").map(|s| s.id),
+            Some("42".into())
+        );
+        assert_eq!(
+            parse_status_url("https://example.com/@alice/12/activity"),
+            None
+        );
+        assert_eq!(parse_status_url("ftp://example.com/@alice/12"), None);
+    }
+
+    #[test]
+    fn test_full_acct() {
+        let mut account = test_fixtures::account();
+        assert_eq!(full_acct(&account), Some("alice@example.org".into()));
+        account.acct = "alice".into();
+        assert_eq!(full_acct(&account), Some("alice@example.org".into()));
+    }
+}
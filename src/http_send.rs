@@ -0,0 +1,80 @@
+use crate::Result;
+use reqwest::blocking::{Client, Request, Response};
+use std::fmt::Debug;
+
+/// Sends the requests made by the blocking clients.
+///
+/// Implement this to intercept, record or rewrite requests at the transport
+/// level, e.g. for testing or instrumentation, and pass it to
+/// [`MastodonBuilder::with_sender`](../struct.MastodonBuilder.html#method.with_sender).
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// # extern crate reqwest;
+/// use elefren::{http_send::HttpSend, Result};
+/// use reqwest::blocking::{Client, Request, Response};
+///
+/// #[derive(Clone, Debug)]
+/// struct Logger;
+///
+/// impl HttpSend for Logger {
+///     fn execute(&self, client: &Client, request: Request) -> Result<Response> {
+///         println!("{} {}", request.method(), request.url());
+///         Ok(client.execute(request)?)
+///     }
+/// }
+/// ```
+pub trait HttpSend: Clone + Debug {
+    /// Send `request` using `client`
+    fn execute(&self, client: &Client, request: Request) -> Result<Response> {
+        Ok(client.execute(request)?)
+    }
+}
+
+/// The default `HttpSend` implementation, which sends requests unchanged
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HttpSender;
+
+impl HttpSend for HttpSender {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Data, MastodonBuilder, MastodonClient};
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Debug, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl HttpSend for Recorder {
+        fn execute(&self, client: &Client, request: Request) -> Result<Response> {
+            self.0
+                .lock()
+                .unwrap()
+                .push(request.url().path().to_string());
+            Ok(client.execute(request)?)
+        }
+    }
+
+    #[test]
+    fn test_custom_sender() {
+        let mock = mockito::mock("GET", "/api/v1/statuses/42/context")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"ancestors": [], "descendants": []}"#)
+            .create();
+        let recorder = Recorder::default();
+        let data = Data {
+            base: mockito::server_url().into(),
+            ..Default::default()
+        };
+        let client = MastodonBuilder::with_sender(recorder.clone())
+            .data(data)
+            .build()
+            .unwrap();
+        client.get_context("42").unwrap();
+        mock.assert();
+        assert_eq!(*recorder.0.lock().unwrap(), ["/api/v1/statuses/42/context"]);
+    }
+}
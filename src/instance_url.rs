@@ -0,0 +1,181 @@
+use std::{fmt, str::FromStr};
+
+use url::Url;
+
+use crate::errors::{Error, Result};
+
+/// The base URL of a Mastodon instance, normalized once so that routes can
+/// be appended to it safely
+///
+/// `https://` is assumed if there is no scheme, the host is lowercased and
+/// internationalized domain names are converted to punycode, and trailing
+/// slashes, query strings and fragments are removed. Only `http` and `https`
+/// URLs with a host are accepted.
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::InstanceUrl;
+///
+/// # fn main() -> elefren::Result<()> {
+/// let base = InstanceUrl::parse("Mastodon.Social/")?;
+/// assert_eq!(base.as_str(), "https://mastodon.social");
+/// assert_eq!(
+///     base.route("/api/v1/instance"),
+///     "https://mastodon.social/api/v1/instance"
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InstanceUrl(Url);
+
+impl InstanceUrl {
+    /// Parse and normalize `base`
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::MissingField` if `base` is empty, e.g. because it was
+    /// left out of a `Data`, `Error::Url` if it isn't a URL, and
+    /// `Error::Other` if it isn't an `http` or `https` URL with a host.
+    pub fn parse(base: &str) -> Result<InstanceUrl> {
+        let trimmed = base.trim();
+        if trimmed.is_empty() {
+            return Err(Error::MissingField("base"));
+        }
+        let mut url = if trimmed.contains("://") {
+            Url::parse(trimmed)?
+        } else {
+            Url::parse(&format!("https://{}", trimmed))?
+        };
+        match url.scheme() {
+            "http" | "https" if url.has_host() => {},
+            _ => return Err(Error::Other(format!("invalid instance URL '{}'", base))),
+        }
+        url.set_query(None);
+        url.set_fragment(None);
+        let path = url.path().trim_end_matches('/').to_string();
+        url.set_path(&path);
+        Ok(InstanceUrl(url))
+    }
+
+    /// The URL, without a trailing slash
+    pub fn as_str(&self) -> &str {
+        self.0.as_str().trim_end_matches('/')
+    }
+
+    /// The URL's host, e.g. `mastodon.social`
+    pub fn host(&self) -> &str {
+        self.0.host_str().unwrap_or_default()
+    }
+
+    /// The URL of `path` on this instance, e.g. `/api/v1/instance`. `path`
+    /// may contain a query string.
+    pub fn route(&self, path: &str) -> String {
+        format!("{}/{}", self.as_str(), path.trim_start_matches('/'))
+    }
+
+    // Like `route`, but parsed
+    pub(crate) fn join(&self, path: &str) -> Result<Url> {
+        Ok(Url::parse(&self.route(path))?)
+    }
+}
+
+impl FromStr for InstanceUrl {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<InstanceUrl> {
+        InstanceUrl::parse(s)
+    }
+}
+
+impl fmt::Display for InstanceUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl AsRef<str> for InstanceUrl {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_scheme() {
+        let base = InstanceUrl::parse("mastodon.social").unwrap();
+        assert_eq!(base.as_str(), "https://mastodon.social");
+        assert_eq!(base.host(), "mastodon.social");
+    }
+
+    #[test]
+    fn test_keeps_scheme_and_port() {
+        assert_eq!(
+            InstanceUrl::parse("http://localhost:3000/")
+                .unwrap()
+                .as_str(),
+            "http://localhost:3000"
+        );
+        assert_eq!(
+            InstanceUrl::parse("http://abcdefgh.onion")
+                .unwrap()
+                .as_str(),
+            "http://abcdefgh.onion"
+        );
+    }
+
+    #[test]
+    fn test_normalizes() {
+        assert_eq!(
+            InstanceUrl::parse(" https://Example.COM/sub//?q=1#top ")
+                .unwrap()
+                .as_str(),
+            "https://example.com/sub"
+        );
+        assert_eq!(
+            InstanceUrl::parse("bücher.example").unwrap().as_str(),
+            "https://xn--bcher-kva.example"
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(matches!(
+            InstanceUrl::parse(""),
+            Err(Error::MissingField("base"))
+        ));
+        assert!(matches!(
+            InstanceUrl::parse("  "),
+            Err(Error::MissingField("base"))
+        ));
+        assert!(InstanceUrl::parse("ftp://example.com").is_err());
+        assert!(InstanceUrl::parse("https://").is_err());
+        assert!(matches!(
+            "not a url".parse::<InstanceUrl>(),
+            Err(Error::Url(_))
+        ));
+    }
+
+    #[test]
+    fn test_route() {
+        let base = InstanceUrl::parse("https://example.com/").unwrap();
+        assert_eq!(
+            base.route("/api/v1/instance"),
+            "https://example.com/api/v1/instance"
+        );
+        assert_eq!(
+            base.route("api/v1/timelines/tag/rust?local=1"),
+            "https://example.com/api/v1/timelines/tag/rust?local=1"
+        );
+        let sub = InstanceUrl::parse("https://example.com/mastodon").unwrap();
+        assert_eq!(
+            sub.join("/api/v1/instance").unwrap().as_str(),
+            "https://example.com/mastodon/api/v1/instance"
+        );
+    }
+}
@@ -0,0 +1,212 @@
+//! Recording streaming events to disk and reading them back
+//!
+//! A journal is a JSON Lines file with one [`JournalEntry`](struct.JournalEntry.html)
+//! per line, holding the event and the time it was received. Entries are only
+//! ever appended, so a journal can be used to pick up after a crash, or to
+//! test bot logic offline against recorded traffic with
+//! [`Bot::replay`](../bot/struct.Bot.html#method.replay).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # extern crate elefren;
+//! # use elefren::prelude::*;
+//! # use std::error::Error;
+//! use elefren::journal::{JournalReader, JournalWriter};
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! # let data = Data {
+//! #   base: "".into(),
+//! #   client_id: "".into(),
+//! #   client_secret: "".into(),
+//! #   redirect: "".into(),
+//! #   token: "".into(),
+//! #   ..Default::default()
+//! # };
+//! let client = Mastodon::from(data);
+//! let mut journal = JournalWriter::open("events.jsonl")?;
+//! for event in client.streaming_user()?.take(100) {
+//!     journal.record(&event)?;
+//! }
+//!
+//! for entry in JournalReader::open("events.jsonl")? {
+//!     let entry = entry?;
+//!     println!("{}: {:?}", entry.received_at, entry.event);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{entities::event::Event, errors::Result};
+
+/// An event, along with when it was received
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// When the event was received
+    pub received_at: DateTime<Utc>,
+    /// The event
+    pub event: Event,
+}
+
+/// Appends events to a journal
+#[derive(Debug)]
+pub struct JournalWriter<W: Write> {
+    writer: W,
+}
+
+impl JournalWriter<BufWriter<File>> {
+    /// Opens the journal at `path` for appending, creating it if it doesn't
+    /// exist yet
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(JournalWriter::new(BufWriter::new(file)))
+    }
+}
+
+impl<W: Write> JournalWriter<W> {
+    /// Write a journal to `writer`
+    pub fn new(writer: W) -> JournalWriter<W> {
+        JournalWriter { writer }
+    }
+
+    /// Records `event` as received now
+    pub fn record(&mut self, event: &Event) -> Result<()> {
+        self.write_entry(&JournalEntry {
+            received_at: Utc::now(),
+            event: event.clone(),
+        })
+    }
+
+    /// Appends `entry` to the journal
+    ///
+    /// Every entry is flushed as soon as it's written, so that at most the
+    /// entry being written is lost in a crash.
+    pub fn write_entry(&mut self, entry: &JournalEntry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Iterator over the entries of a journal, oldest first
+///
+/// Blank lines are skipped, as is a last line that was cut short, e.g.
+/// because the process writing it crashed.
+#[derive(Debug)]
+pub struct JournalReader<R: BufRead> {
+    reader: R,
+    line: String,
+}
+
+impl JournalReader<BufReader<File>> {
+    /// Opens the journal at `path` for reading
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(JournalReader::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+impl<R: BufRead> JournalReader<R> {
+    /// Read a journal from `reader`
+    pub fn new(reader: R) -> JournalReader<R> {
+        JournalReader {
+            reader,
+            line: String::new(),
+        }
+    }
+
+    /// Iterate over the events only, dropping their timestamps
+    pub fn events(self) -> impl Iterator<Item = Result<Event>> {
+        self.map(|entry| entry.map(|entry| entry.event))
+    }
+}
+
+impl<R: BufRead> Iterator for JournalReader<R> {
+    type Item = Result<JournalEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => {},
+                Err(e) => return Some(Err(e.into())),
+            }
+            if self.line.trim().is_empty() {
+                continue;
+            }
+            let complete = self.line.ends_with('\n');
+            return match serde_json::from_str(&self.line) {
+                Ok(entry) => Some(Ok(entry)),
+                // a torn write at the end of the journal
+                Err(_) if !complete => None,
+                Err(e) => Some(Err(e.into())),
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::status::StatusId;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_round_trip() {
+        let mut writer = JournalWriter::new(Vec::new());
+        writer
+            .record(&Event::Delete(StatusId::new("1").unwrap()))
+            .expect("Couldn't record event");
+        writer
+            .record(&Event::FiltersChanged)
+            .expect("Couldn't record event");
+        let journal = writer.into_inner();
+
+        let events: Vec<Event> = JournalReader::new(Cursor::new(journal))
+            .events()
+            .collect::<Result<_>>()
+            .expect("Couldn't read journal");
+        assert_eq!(
+            events,
+            vec![
+                Event::Delete(StatusId::new("1").unwrap()),
+                Event::FiltersChanged
+            ]
+        );
+    }
+
+    #[test]
+    fn test_torn_last_line() {
+        let line =
+            r#"{"received_at":"2023-01-02T10:00:00Z","event":{"event":"delete","payload":"1"}}"#;
+        let journal = format!("{}\n\n{}", line, &line[..30]);
+        let entries: Vec<_> = JournalReader::new(Cursor::new(journal)).collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].as_ref().unwrap().event,
+            Event::Delete(StatusId::new("1").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_corrupt_line() {
+        let mut entries = JournalReader::new(Cursor::new("not json\n"));
+        assert!(entries.next().unwrap().is_err());
+        assert!(entries.next().is_none());
+    }
+}
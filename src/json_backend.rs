@@ -0,0 +1,102 @@
+// Deserialization for the hot paths: response bodies (timelines in
+// particular) and streaming events.
+//
+// serde_json is used by default. With the `simd-json` feature the body is
+// parsed with simd-json instead; if that fails, it's parsed again with
+// serde_json so callers always see a `serde_json::Error`, and so anything
+// simd-json rejects but serde_json accepts still goes through.
+
+use serde::de::DeserializeOwned;
+#[cfg(feature = "client")]
+use serde::de::DeserializeSeed;
+
+/// Deserializes `T` from a JSON document.
+#[cfg(not(feature = "simd-json"))]
+pub(crate) fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> serde_json::Result<T> {
+    serde_json::from_slice(bytes)
+}
+
+/// Deserializes `T` from a JSON document.
+#[cfg(feature = "simd-json")]
+pub(crate) fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> serde_json::Result<T> {
+    // simd-json parses in place, so it needs its own copy of the document
+    let mut scratch = bytes.to_vec();
+    match simd_json::serde::from_slice(&mut scratch) {
+        Ok(t) => Ok(t),
+        Err(_) => serde_json::from_slice(bytes),
+    }
+}
+
+/// Deserializes the value of `seed` from a JSON document.
+#[cfg(all(feature = "client", not(feature = "simd-json")))]
+pub(crate) fn from_slice_seed<S, T>(bytes: &[u8], seed: S) -> serde_json::Result<T>
+where
+    S: for<'de> DeserializeSeed<'de, Value = T> + Clone,
+{
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    let t = seed.deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(t)
+}
+
+/// Deserializes the value of `seed` from a JSON document.
+#[cfg(all(feature = "client", feature = "simd-json"))]
+pub(crate) fn from_slice_seed<S, T>(bytes: &[u8], seed: S) -> serde_json::Result<T>
+where
+    S: for<'de> DeserializeSeed<'de, Value = T> + Clone,
+{
+    let mut scratch = bytes.to_vec();
+    if let Ok(mut deserializer) = simd_json::Deserializer::from_slice(&mut scratch) {
+        if let Ok(t) = seed.clone().deserialize(&mut deserializer) {
+            return Ok(t);
+        }
+    }
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    let t = seed.deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(t)
+}
+
+/// Deserializes `T` from a JSON string.
+pub(crate) fn from_str<T: DeserializeOwned>(s: &str) -> serde_json::Result<T> {
+    from_slice(s.as_bytes())
+}
+
+/// Whether a `Content-Type` header value announces JSON, e.g.
+/// `application/json; charset=utf-8` or `application/activity+json`.
+#[cfg(feature = "client")]
+pub(crate) fn is_json_content_type(content_type: &str) -> bool {
+    let essence = content_type.split(';').next().unwrap_or("").trim();
+    let subtype = essence.rsplit('/').next().unwrap_or("");
+    subtype.eq_ignore_ascii_case("json") || subtype.to_ascii_lowercase().ends_with("+json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::prelude::Status;
+
+    #[test]
+    fn test_from_slice() {
+        let status: Status = from_slice(include_bytes!("../tests/fixtures/status.json"))
+            .expect("Couldn't deserialize Status");
+        assert_eq!(status.id, "110");
+    }
+
+    #[test]
+    fn test_from_str_error_is_serde_json() {
+        let err = from_str::<Status>("not valid json").unwrap_err();
+        assert!(err.is_syntax());
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn test_is_json_content_type() {
+        assert!(is_json_content_type("application/json"));
+        assert!(is_json_content_type("application/json; charset=utf-8"));
+        assert!(is_json_content_type("application/activity+json"));
+        assert!(!is_json_content_type("text/html"));
+        assert!(!is_json_content_type("text/plain; charset=utf-8"));
+        assert!(!is_json_content_type(""));
+    }
+}
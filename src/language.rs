@@ -0,0 +1,140 @@
+//! The language of statuses and accounts
+//!
+//! With the `language` feature, which is enabled by default, `Language` is
+//! the enum from the `isolang` crate. Without it, it is a lighter type that
+//! only checks the code's format, for bots that don't need the list of
+//! languages compiled in.
+
+#[cfg(feature = "language")]
+pub use isolang::Language;
+
+#[cfg(not(feature = "language"))]
+pub use self::code::Language;
+
+// `Language::Und`, for codes the server sends that aren't known
+#[cfg(feature = "language")]
+pub(crate) fn undetermined() -> Language {
+    Language::Und
+}
+
+#[cfg(not(feature = "language"))]
+pub(crate) fn undetermined() -> Language {
+    Language::from_639_3("und").unwrap()
+}
+
+// The shortest code for `language`, as used in `Accept-Language`
+#[cfg(feature = "language")]
+pub(crate) fn language_tag(language: Language) -> String {
+    language
+        .to_639_1()
+        .unwrap_or_else(|| language.to_639_3())
+        .to_string()
+}
+
+#[cfg(not(feature = "language"))]
+pub(crate) fn language_tag(language: Language) -> String {
+    language.code().to_string()
+}
+
+#[cfg(not(feature = "language"))]
+mod code {
+    use crate::errors::Error;
+    use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+    use std::{fmt, str::FromStr};
+
+    /// An ISO 639-1 or ISO 639-3 language code, e.g. `en` or `eng`
+    ///
+    /// Only the format of the code is checked: two or three ASCII letters,
+    /// which are lowercased. Unlike with the `language` feature, `en` and
+    /// `eng` are different values.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate elefren;
+    /// use elefren::Language;
+    ///
+    /// let language: Language = "EN".parse().unwrap();
+    /// assert_eq!(language.code(), "en");
+    /// assert!("english".parse::<Language>().is_err());
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+    pub struct Language {
+        code: [u8; 3],
+        len: u8,
+    }
+
+    impl Language {
+        /// The language with the two letter code `code`
+        pub fn from_639_1(code: &str) -> Option<Language> {
+            Language::parse(code).filter(|language| language.len == 2)
+        }
+
+        /// The language with the three letter code `code`
+        pub fn from_639_3(code: &str) -> Option<Language> {
+            Language::parse(code).filter(|language| language.len == 3)
+        }
+
+        /// The code of the language, as it was given
+        pub fn code(&self) -> &str {
+            // only ASCII letters are stored
+            std::str::from_utf8(&self.code[..self.len as usize]).unwrap()
+        }
+
+        fn parse(code: &str) -> Option<Language> {
+            if !(2..=3).contains(&code.len()) || !code.bytes().all(|b| b.is_ascii_alphabetic()) {
+                return None;
+            }
+            let mut language = Language {
+                code: [0; 3],
+                len: code.len() as u8,
+            };
+            for (stored, byte) in language.code.iter_mut().zip(code.bytes()) {
+                *stored = byte.to_ascii_lowercase();
+            }
+            Some(language)
+        }
+    }
+
+    impl FromStr for Language {
+        type Err = Error;
+
+        fn from_str(code: &str) -> Result<Language, Error> {
+            Language::parse(code)
+                .ok_or_else(|| Error::Other(format!("not a language code: {}", code)))
+        }
+    }
+
+    impl fmt::Display for Language {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(self.code())
+        }
+    }
+
+    impl Serialize for Language {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(self.code())
+        }
+    }
+
+    impl<'de> Deserialize<'de> for Language {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Language, D::Error> {
+            let code = String::deserialize(deserializer)?;
+            code.parse().map_err(de::Error::custom)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn test_parse() {
+            assert_eq!("DE".parse::<Language>().unwrap().code(), "de");
+            assert_eq!(Language::from_639_3("deu").unwrap().to_string(), "deu");
+            assert!(Language::from_639_1("deu").is_none());
+            assert!("d3".parse::<Language>().is_err());
+            assert!("".parse::<Language>().is_err());
+        }
+    }
+}
@@ -43,14 +43,17 @@
 //! #   client_secret: "".into(),
 //! #   redirect: "".into(),
 //! #   token: "".into(),
+//! #   ..Default::default()
 //! # };
 //! let client = Mastodon::from(data);
 //! for event in client.streaming_user()? {
 //!     match event {
 //!         Event::Update(ref status) => { /* .. */ },
+//!         Event::StatusUpdate(ref status) => { /* .. */ },
 //!         Event::Notification(ref notification) => { /* .. */ },
 //!         Event::Delete(ref id) => { /* .. */ },
 //!         Event::FiltersChanged => { /* .. */ },
+//!         Event::Conversation(ref conversation) => { /* .. */ },
 //!     }
 //! }
 //! # Ok(())
@@ -70,823 +73,4822 @@
     unused_qualifications
 )]
 #![cfg_attr(feature = "nightly", allow(broken_intra_doc_links))]
+// the request forms are only sent by the HTTP clients
+#![cfg_attr(not(feature = "client"), allow(dead_code))]
 
-use std::{borrow::Cow, io::BufRead, ops};
+#[cfg(feature = "client")]
+use std::{
+    collections::HashSet,
+    fmt,
+    io::{self, BufRead},
+    mem,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-use reqwest::blocking::{Client, RequestBuilder, Response};
-use tungstenite::client::AutoStream;
+#[cfg(feature = "websocket")]
+use crate::channel::{ChannelOptions, EventReceiver};
+#[cfg(feature = "client")]
+use reqwest::{
+    blocking::{Client, Request, RequestBuilder, Response},
+    header::{
+        HeaderMap, HeaderName, HeaderValue, ACCEPT_LANGUAGE, AUTHORIZATION, CONTENT_TYPE, LOCATION,
+        USER_AGENT,
+    },
+    StatusCode,
+};
+#[cfg(feature = "websocket")]
+use std::net::TcpStream;
+#[cfg(feature = "websocket")]
+use tungstenite::{
+    client::{AutoStream, IntoClientRequest},
+    handshake::HandshakeError,
+    stream::Stream as TungsteniteStream,
+};
 
-use crate::{entities::prelude::*, page::Page};
+#[cfg(feature = "client")]
+use crate::{
+    clock::{Clock, SystemClock},
+    entities::{instance::PeersMatching, prelude::*, status::Tag},
+    http_send::{HttpSend, HttpSender},
+    language::language_tag,
+    page::Page,
+    rate_limit::RateLimit,
+    redirect::RedirectPolicy,
+    registration::{oauth_response, AccessToken},
+    requests::DirectoryRequest,
+    response_meta::ResponseMeta,
+    retry::RetryPolicy,
+    social_graph::SocialGraph,
+    stats::{ClientStats, StatsCounters},
+};
 
-pub use isolang::Language;
+pub use crate::language::Language;
 
 pub use crate::{
     data::Data,
     errors::{ApiError, Error, Result},
-    mastodon_client::{MastodonClient, MastodonUnauthenticated},
+    instance_url::InstanceUrl,
     media_builder::MediaBuilder,
-    registration::Registration,
     requests::{
-        AddFilterRequest, AddPushRequest, StatusesRequest, UpdateCredsRequest, UpdatePushRequest,
+        AddFilterRequest, AddFilterV2Request, AddPushRequest, DomainBlockRequest,
+        FilterKeywordRequest, HashtagRequest, ListRequest, NotificationsRequest, PageRequest,
+        ReportRequest, SearchRequest, StatusesRequest, TimelineRequest, ToQueryString,
+        UpdateCredsRequest, UpdatePushRequest,
     },
     status_builder::{NewStatus, StatusBuilder},
 };
+#[cfg(feature = "client")]
+pub use crate::{
+    mastodon_client::{MastodonClient, MastodonUnauthenticated, StreamKind},
+    registration::Registration,
+};
 
+#[cfg(any(feature = "client", feature = "async", feature = "wasm"))]
+#[macro_use]
+mod routes;
 /// Registering your App
 pub mod apps;
 /// Async client
-#[cfg(feature = "async")]
+#[cfg(any(feature = "async", feature = "wasm"))]
 pub mod r#async;
+#[cfg(feature = "client")]
+pub mod blocklist;
+/// Event loop for writing bots on top of the streaming API
+#[cfg(feature = "client")]
+pub mod bot;
+/// Running many requests in bulk, e.g. for account migrations
+#[cfg(feature = "client")]
+pub mod bulk;
+/// Reading a stream on its own thread, through a bounded buffer
+#[cfg(feature = "client")]
+pub mod channel;
+/// Controlling the time the client sees, e.g. in tests
+pub mod clock;
+#[cfg(feature = "client")]
+pub mod crosspost;
 /// Contains the struct that holds the client auth data
 pub mod data;
 /// Entities returned from the API
+#[macro_use]
 pub mod entities;
 /// Errors
 pub mod errors;
+#[cfg(feature = "websocket")]
+pub mod firehose;
 /// Collection of helpers for serializing/deserializing `Data` objects
 pub mod helpers;
+/// Contains trait for converting `reqwest::Request`s to `reqwest::Response`s
+#[cfg(feature = "client")]
+pub mod http_send;
+mod instance_url;
+/// Recording streaming events to disk and reading them back
+#[cfg(feature = "client")]
+pub mod journal;
+mod json_backend;
+mod language;
+mod link_header;
+#[cfg(feature = "client")]
+pub mod list_session;
+#[cfg(feature = "client")]
 mod mastodon_client;
 /// Constructing media attachments for a status.
 pub mod media_builder;
+#[cfg(feature = "client")]
+pub mod media_transfer;
+pub mod mention_guard;
 /// Handling multiple pages of entities.
+#[cfg(feature = "client")]
 pub mod page;
+#[cfg(feature = "client")]
+pub mod poller;
+#[cfg(feature = "client")]
+pub mod probe;
+/// Tracking the server's rate limits
+#[cfg(feature = "client")]
+pub mod rate_limit;
+/// Controlling which redirects are followed
+pub mod redirect;
 /// Registering your app.
+#[cfg(feature = "client")]
 pub mod registration;
+#[cfg(feature = "client")]
+pub mod relationship_cache;
 /// Requests
 pub mod requests;
+/// Metadata about responses
+#[cfg(feature = "client")]
+pub mod response_meta;
+/// Retrying failed requests
+#[cfg(feature = "client")]
+pub mod retry;
 /// OAuth Scopes
 pub mod scopes;
+#[cfg(feature = "client")]
+pub mod social_graph;
+/// Counting what a client sends and receives
+#[cfg(feature = "client")]
+pub mod stats;
 /// Constructing a status
 pub mod status_builder;
+pub mod status_store;
+#[cfg(test)]
+mod test_fixtures;
+#[cfg(feature = "test-server")]
+pub mod test_server;
+#[cfg(feature = "client")]
+pub mod timeline;
+#[cfg(feature = "client")]
+pub mod timeline_sync;
+#[cfg(feature = "vcr")]
+pub mod vcr;
+#[cfg(feature = "client")]
 #[macro_use]
 mod macros;
 /// Automatically import the things you need
+///
+/// Everything here is part of the stable API: it's only removed or renamed
+/// in a new major version, so code importing it from here keeps compiling
+/// when the modules it's defined in are reorganized.
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::prelude::*;
+///
+/// let status = StatusBuilder::new()
+///     .status("hello")
+///     .visibility(Visibility::Unlisted)
+///     .build()
+///     .unwrap();
+/// let request = PageRequest::new().limit(10);
+/// let statuses: Vec<Status> = Vec::new();
+/// ```
 pub mod prelude {
     pub use crate::{
-        scopes::Scopes, Data, Mastodon, MastodonClient, NewStatus, Registration, StatusBuilder,
-        StatusesRequest,
+        entities::prelude::*,
+        media_builder::MediaBuilder,
+        requests::{
+            AddFilterRequest, AddFilterV2Request, AddPushRequest, DirectoryRequest,
+            DomainBlockRequest, FilterKeywordRequest, HashtagRequest, ListRequest,
+            NotificationsRequest, PageRequest, ReportRequest, SearchRequest, StatusesRequest,
+            TimelineRequest, ToQueryString, UpdateCredsRequest, UpdatePushRequest,
+        },
+        scopes::Scopes,
+        status_builder::{NewStatus, StatusBuilder, Visibility},
+        Data,
+    };
+    #[cfg(feature = "client")]
+    pub use crate::{
+        page::{OwnedPage, Page},
+        Mastodon, MastodonClient, MastodonUnauthenticated, Registration, StreamKind,
     };
 }
 
+#[cfg(feature = "client")]
 /// Your mastodon application client, handles all requests to and from Mastodon.
+///
+/// # Thread safety
+///
+/// `Mastodon` is `Send` and `Sync` as long as its `HttpSend` is, which the
+/// default one is, so it can be shared between threads, e.g. in an `Arc`.
+/// Clones share the rate limit and the metadata of the last response, but
+/// are otherwise independent.
+///
+/// ```
+/// # extern crate elefren;
+/// # use elefren::Mastodon;
+/// fn assert_send_sync<T: Send + Sync>() {}
+/// assert_send_sync::<Mastodon>();
+/// ```
 #[derive(Clone, Debug)]
-pub struct Mastodon {
+pub struct Mastodon<H: HttpSend = HttpSender> {
     client: Client,
-    /// Raw data about your mastodon instance.
-    pub data: Data,
+    http_sender: H,
+    /// Raw data about your mastodon instance, as the client was built with.
+    ///
+    /// Shared between clones, so that cloning a client doesn't copy it. Once
+    /// the access token has been refreshed, `current_data` has the new one.
+    pub data: Arc<Data>,
+    user_agent: Option<String>,
+    language: Option<Language>,
+    default_headers: HeaderMap,
+    retry_policy: RetryPolicy,
+    redirect_policy: RedirectPolicy,
+    max_body_size: Option<usize>,
+    default_limit: Option<usize>,
+    // `data.base`, or the override passed to the builder
+    base_url: InstanceUrl,
+    #[cfg(feature = "websocket")]
+    streaming: StreamingConfig,
+    // shared between clones, as they share the server's rate limit, too
+    rate_limit: Arc<Mutex<Option<RateLimit>>>,
+    // metadata of the last response, for bug reports
+    last_response: Arc<Mutex<Option<ResponseMeta>>>,
+    // shared between clones, like the rate limit
+    stats: Arc<StatsCounters>,
+    // the authenticated account, once it has been fetched
+    me: Arc<Mutex<Option<Account>>>,
+    // `data` with the new access token, once it has been refreshed; shared
+    // between clones, which share the token, too
+    refreshed: Arc<Mutex<Option<Arc<Data>>>>,
+    refresh_on_unauthorized: bool,
+    on_unauthorized: Option<UnauthorizedCallback>,
+    clock: Arc<dyn Clock>,
+    // overrides the `reqwest` client's timeout, see `with_timeout`
+    timeout: Option<Duration>,
 }
 
-impl Mastodon {
-    methods![get, post, delete,];
-
-    fn route(&self, url: &str) -> String {
-        format!("{}{}", self.base, url)
-    }
+#[cfg(feature = "client")]
+type RenewToken = dyn Fn(&Data) -> Option<Data> + Send + Sync;
 
-    pub(crate) fn send_blocking(&self, req: RequestBuilder) -> Result<Response> {
-        let request = req.bearer_auth(&self.token).build()?;
-        Ok(self.client.execute(request)?)
-    }
-}
+#[cfg(feature = "client")]
+// The callback passed to `MastodonBuilder::on_unauthorized`
+#[derive(Clone)]
+struct UnauthorizedCallback(Arc<RenewToken>);
 
-impl From<Data> for Mastodon {
-    /// Creates a mastodon instance from the data struct.
-    fn from(data: Data) -> Mastodon {
-        let mut builder = MastodonBuilder::new();
-        builder.data(data);
-        builder
-            .build()
-            .expect("We know `data` is present, so this should be fine")
+#[cfg(feature = "client")]
+impl fmt::Debug for UnauthorizedCallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("UnauthorizedCallback")
     }
 }
 
-#[async_trait::async_trait]
-impl MastodonClient for Mastodon {
-    type Stream = EventReader<WebSocket>;
+#[cfg(feature = "client")]
+impl<H: HttpSend> Mastodon<H> {
+    methods![get, post, delete,];
 
-    paged_routes! {
-        (get) favourites: "favourites" => Status,
-        (get) blocks: "blocks" => Account,
-        (get) domain_blocks: "domain_blocks" => String,
-        (get) follow_requests: "follow_requests" => Account,
-        (get) get_home_timeline: "timelines/home" => Status,
-        (get) get_local_timeline: "timelines/public?local=true" => Status,
-        (get) get_federated_timeline: "timelines/public?local=false" => Status,
-        (get) get_emojis: "custom_emojis" => Emoji,
-        (get) mutes: "mutes" => Account,
-        (get) notifications: "notifications" => Notification,
-        (get) reports: "reports" => Report,
-        (get (q: &'a str, #[serde(skip_serializing_if = "Option::is_none")] limit: Option<u64>, following: bool,)) search_accounts: "accounts/search" => Account,
-        (get) get_endorsements: "endorsements" => Account,
+    fn route(&self, url: &str) -> String {
+        self.base_url.route(url)
     }
 
-    paged_routes_with_id! {
-        (get) followers: "accounts/{}/followers" => Account,
-        (get) following: "accounts/{}/following" => Account,
-        (get) reblogged_by: "statuses/{}/reblogged_by" => Account,
-        (get) favourited_by: "statuses/{}/favourited_by" => Account,
+    // Appends the bounds of `request` to `url`, limited to the default page
+    // size if the request doesn't set its own
+    fn paged_url(&self, url: &str, request: &PageRequest) -> Result<String> {
+        request.or_limit(self.default_limit).apply_to(url)
     }
 
-    route! {
-        (delete (domain: String,)) unblock_domain: "domain_blocks" => Empty,
-        (get) instance: "instance" => Instance,
-        (get) verify_credentials: "accounts/verify_credentials" => Account,
-        (post (account_id: &str, status_ids: Vec<&str>, comment: String,)) report: "reports" => Report,
-        (post (domain: String,)) block_domain: "domain_blocks" => Empty,
-        (post (id: &str,)) authorize_follow_request: "accounts/follow_requests/authorize" => Empty,
-        (post (id: &str,)) reject_follow_request: "accounts/follow_requests/reject" => Empty,
-        (get  (q: &'a str, resolve: bool,)) search: "search" => SearchResult,
-        (post (uri: Cow<'static, str>,)) follows: "follows" => Account,
-        (post) clear_notifications: "notifications/clear" => Empty,
-        (post (id: &str,)) dismiss_notification: "notifications/dismiss" => Empty,
-        (get) get_push_subscription: "push/subscription" => Subscription,
-        (delete) delete_push_subscription: "push/subscription" => Empty,
-        (get) get_filters: "filters" => Vec<Filter>,
-        (get) get_follow_suggestions: "suggestions" => Vec<Account>,
+    // Builds `req` with the client's headers, but without the access token
+    fn prepare(&self, mut req: RequestBuilder) -> Result<Request> {
+        if let Some(ref user_agent) = self.user_agent {
+            req = req.header(USER_AGENT, user_agent.as_str());
+        }
+        if let Some(language) = self.language {
+            req = req.header(ACCEPT_LANGUAGE, language_tag(language));
+        }
+        if let Some(timeout) = self.timeout {
+            req = req.timeout(timeout);
+        }
+        let mut request = req.build()?;
+        add_default_headers(request.headers_mut(), &self.default_headers);
+        Ok(request)
     }
 
-    route_v2! {
-        (get (q: &'a str, resolve: bool,)) search_v2: "search" => SearchResultV2,
+    // Sets the current access token on `request`
+    fn authorize(&self, mut request: Request) -> Result<Request> {
+        let mut value = HeaderValue::from_str(&format!("Bearer {}", self.latest_data().token))
+            .map_err(|_| Error::Other("invalid access token".to_string()))?;
+        value.set_sensitive(true);
+        request.headers_mut().insert(AUTHORIZATION, value);
+        Ok(request)
     }
 
-    route_id! {
-        (get) get_account: "accounts/{}" => Account,
-        (post) follow: "accounts/{}/follow" => Relationship,
-        (post) unfollow: "accounts/{}/unfollow" => Relationship,
-        (post) block: "accounts/{}/block" => Relationship,
-        (post) unblock: "accounts/{}/unblock" => Relationship,
-        (get) mute: "accounts/{}/mute" => Relationship,
-        (get) unmute: "accounts/{}/unmute" => Relationship,
-        (get) get_notification: "notifications/{}" => Notification,
-        (get) get_status: "statuses/{}" => Status,
-        (get) get_context: "statuses/{}/context" => Context,
-        (get) get_card: "statuses/{}/card" => Card,
-        (post) reblog: "statuses/{}/reblog" => Status,
-        (post) unreblog: "statuses/{}/unreblog" => Status,
-        (post) favourite: "statuses/{}/favourite" => Status,
-        (post) unfavourite: "statuses/{}/unfavourite" => Status,
-        (delete) delete_status: "statuses/{}" => Empty,
-        (get) get_filter: "filters/{}" => Filter,
-        (delete) delete_filter: "filters/{}" => Empty,
-        (delete) delete_from_suggestions: "suggestions/{}" => Empty,
-        (post) endorse_user: "accounts/{}/pin" => Relationship,
-        (post) unendorse_user: "accounts/{}/unpin" => Relationship,
+    pub(crate) fn send_blocking(&self, req: RequestBuilder) -> Result<Response> {
+        let request = self.prepare(req)?;
+        if !self.renews_token() {
+            return self.send_with_retries(self.authorize(request)?);
+        }
+        // kept to send again with a new access token, if the server rejects
+        // the current one
+        let renewal = request.try_clone();
+        let response = self.send_with_retries(self.authorize(request)?)?;
+        if response.status() != StatusCode::UNAUTHORIZED || !self.renew_token()? {
+            return Ok(response);
+        }
+        match renewal {
+            Some(request) => self.send_with_retries(self.authorize(request)?),
+            None => Ok(response),
+        }
     }
 
-    fn add_filter(&self, request: &mut AddFilterRequest) -> Result<Filter> {
-        let url = self.route("/api/v1/filters");
-        let response = self.send_blocking(self.client.post(&url).json(&request))?;
-
-        let status = response.status();
+    // Whether a new access token is looked for when the server rejects the
+    // current one
+    fn renews_token(&self) -> bool {
+        self.on_unauthorized.is_some()
+            || (self.refresh_on_unauthorized && self.latest_data().refresh_token.is_some())
+    }
 
-        if status.is_client_error() {
-            return Err(Error::Client(status));
-        } else if status.is_server_error() {
-            return Err(Error::Server(status));
+    // Gets a new access token after the server rejected the current one,
+    // first with the refresh token, then from the `on_unauthorized` callback.
+    // Returns whether there is a new token to send the request again with.
+    fn renew_token(&self) -> Result<bool> {
+        if self.refresh_on_unauthorized && self.latest_data().refresh_token.is_some() {
+            log::debug!("access token rejected, refreshing it");
+            match self.refresh() {
+                Ok(_) => return Ok(true),
+                Err(e) if self.on_unauthorized.is_none() => return Err(e),
+                Err(e) => log::debug!("refreshing the access token failed: {}", e),
+            }
         }
-
-        deserialise_blocking(response)
+        let data = match self.on_unauthorized {
+            Some(ref callback) => (callback.0)(&self.latest_data()),
+            None => None,
+        };
+        Ok(match data {
+            Some(data) => {
+                *self.refreshed.lock().unwrap() = Some(Arc::new(data));
+                true
+            },
+            None => false,
+        })
     }
 
-    /// PUT /api/v1/filters/:id
-    fn update_filter(&self, id: &str, request: &mut AddFilterRequest) -> Result<Filter> {
-        let url = self.route(&format!("/api/v1/filters/{}", id));
-        let response = self.send_blocking(self.client.put(&url).json(&request))?;
-
-        let status = response.status();
-
-        if status.is_client_error() {
-            return Err(Error::Client(status));
-        } else if status.is_server_error() {
-            return Err(Error::Server(status));
+    fn send_with_retries(&self, mut request: Request) -> Result<Response> {
+        let idempotent = RetryPolicy::is_idempotent(request.method());
+        let mut attempt = 0;
+        loop {
+            // requests with a streaming body can't be cloned, and so are never
+            // retried
+            let retry = if attempt < self.retry_policy.max_retries() {
+                request.try_clone()
+            } else {
+                None
+            };
+            self.stats.count_request(attempt > 0);
+            let mut result = self.http_sender.execute(&self.client, request);
+            if let Ok(ref mut response) = result {
+                response.extensions_mut().insert(self.stats.clone());
+            }
+            if let (Ok(ref mut response), Some(limit)) = (&mut result, self.max_body_size) {
+                response.extensions_mut().insert(BodyLimit(limit));
+            }
+            if let Ok(ref response) = result {
+                let meta = ResponseMeta::from_response(response);
+                meta.log(response.url().as_str());
+                if let Some(rate_limit) = meta.rate_limit {
+                    *self.rate_limit.lock().unwrap() = Some(rate_limit);
+                }
+                *self.last_response.lock().unwrap() = Some(meta);
+                self.check_redirect(response)?;
+            }
+            let retry = match retry {
+                Some(retry) => retry,
+                None => return result,
+            };
+            // other requests are only retried if the server can't have acted
+            // on them, see `RetryPolicy`
+            let delay = match result {
+                Ok(ref response)
+                    if RetryPolicy::is_retryable_status(response.status())
+                        && (idempotent || response.status() == StatusCode::TOO_MANY_REQUESTS) =>
+                {
+                    self.retry_policy.delay(attempt, Some(response.headers()))
+                },
+                Err(Error::Http(ref e)) if e.is_connect() || (idempotent && e.is_timeout()) => {
+                    self.retry_policy.delay(attempt, None)
+                },
+                _ => return result,
+            };
+            log::debug!("request to {} failed, retrying in {:?}", retry.url(), delay);
+            self.clock.sleep(delay);
+            request = retry;
+            attempt += 1;
         }
-
-        deserialise_blocking(response)
     }
 
-    fn update_credentials(&self, builder: UpdateCredsRequest) -> Result<Account> {
-        let changes = builder.build()?;
-        let url = self.route("/api/v1/accounts/update_credentials");
-        let response = self.send_blocking(self.client.patch(&url).json(&changes))?;
-
-        let status = response.status();
-
-        if status.is_client_error() {
-            return Err(Error::Client(status));
-        } else if status.is_server_error() {
-            return Err(Error::Server(status));
+    // A redirect response means the redirect policy refused to follow it;
+    // only `SameOrigin` turns that into an error, `None` asks for the
+    // redirect response itself.
+    fn check_redirect(&self, response: &Response) -> Result<()> {
+        if self.redirect_policy != RedirectPolicy::SameOrigin || !response.status().is_redirection()
+        {
+            return Ok(());
+        }
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok());
+        match location {
+            Some(location) => Err(Error::CrossOriginRedirect {
+                url: response.url().to_string(),
+                location: location.to_string(),
+            }),
+            None => Ok(()),
         }
-
-        deserialise_blocking(response)
     }
 
-    /// Post a new status to the account.
-    fn new_status(&self, status: NewStatus) -> Result<Status> {
-        let response = self.send_blocking(
-            self.client
-                .post(&self.route("/api/v1/statuses"))
-                .json(&status),
-        )?;
-
-        deserialise_blocking(response)
+    /// The authenticated account
+    ///
+    /// The account is fetched with `verify_credentials` the first time, and
+    /// cached from then on, also for clones of this client. Use
+    /// [`refresh_credentials`](#method.refresh_credentials) to fetch it
+    /// again, e.g. after `update_credentials`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// let me = client.me()?;
+    /// println!("logged in as {}", me.acct);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn me(&self) -> Result<Account> {
+        if let Some(ref me) = *self.me.lock().unwrap() {
+            return Ok(me.clone());
+        }
+        self.refresh_credentials()
     }
 
-    /// Get timeline filtered by a hashtag(eg. `#coffee`) either locally or
-    /// federated.
-    fn get_hashtag_timeline(&self, hashtag: &str, local: bool) -> Result<Page<Status>> {
-        let base = "/api/v1/timelines/tag/";
-        let url = if local {
-            self.route(&format!("{}{}?local=1", base, hashtag))
-        } else {
-            self.route(&format!("{}{}", base, hashtag))
-        };
-
-        Page::new(self, self.send_blocking(self.client.get(&url))?)
+    /// Fetches the authenticated account again, replacing the one cached by
+    /// [`me`](#method.me)
+    pub fn refresh_credentials(&self) -> Result<Account> {
+        let me = self.verify_credentials()?;
+        *self.me.lock().unwrap() = Some(me.clone());
+        Ok(me)
     }
 
-    /// Get statuses of a single account by id. Optionally only with pictures
-    /// and or excluding replies.
+    /// Sends a `GET` request to `path`, e.g. `/api/v1/instance`, returning
+    /// the deserialized response together with its metadata
     ///
     /// # Example
     ///
     /// ```no_run
     /// # extern crate elefren;
     /// # use elefren::prelude::*;
-    /// # use std::error::Error;
-    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # use elefren::entities::instance::Instance;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// # let data = Data {
     /// #   base: "".into(),
     /// #   client_id: "".into(),
     /// #   client_secret: "".into(),
     /// #   redirect: "".into(),
     /// #   token: "".into(),
+    /// #   ..Default::default()
     /// # };
     /// let client = Mastodon::from(data);
-    /// let statuses = client.statuses("user-id", None)?;
+    /// let (instance, meta) = client.get_with_meta::<Instance>("/api/v1/instance")?;
+    /// println!("request id: {:?}", meta.request_id);
     /// # Ok(())
     /// # }
     /// ```
+    pub fn get_with_meta<T>(&self, path: &str) -> Result<(T, ResponseMeta)>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+    {
+        let response = self.send_blocking(self.client.get(self.route(path)))?;
+        let meta = ResponseMeta::from_response(&response);
+        Ok((deserialise_blocking(response)?, meta))
+    }
+
+    /// Sends a `POST` request with the JSON `body` to `path`, returning the
+    /// deserialized response together with its metadata
+    pub fn post_with_meta<T, B>(&self, path: &str, body: &B) -> Result<(T, ResponseMeta)>
+    where
+        T: for<'de> serde::Deserialize<'de>,
+        B: serde::Serialize + ?Sized,
+    {
+        let response = self.send_blocking(self.client.post(self.route(path)).json(body))?;
+        let meta = ResponseMeta::from_response(&response);
+        Ok((deserialise_blocking(response)?, meta))
+    }
+
+    /// Sends a `GET` request to `path` and returns a reader over the body
+    /// of the response, as it arrives
+    ///
+    /// Meant for large responses, e.g. exports or media files, which don't
+    /// have to be held in memory this way; `max_body_size` doesn't apply to
+    /// them either.
+    ///
+    /// # Example
     ///
     /// ```no_run
     /// # extern crate elefren;
     /// # use elefren::prelude::*;
-    /// # use std::error::Error;
-    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # use std::{fs::File, io};
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// # let data = Data {
     /// #   base: "".into(),
     /// #   client_id: "".into(),
     /// #   client_secret: "".into(),
     /// #   redirect: "".into(),
     /// #   token: "".into(),
+    /// #   ..Default::default()
     /// # };
     /// let client = Mastodon::from(data);
-    /// let request = StatusesRequest::new()
-    ///     .only_media();
-    /// let statuses = client.statuses("user-id", request)?;
+    /// let mut export = client.get_stream("/settings/exports/follows.csv")?;
+    /// io::copy(&mut export, &mut File::create("follows.csv")?)?;
     /// # Ok(())
     /// # }
     /// ```
-    fn statuses<'a, 'b: 'a, S>(&'b self, id: &'b str, request: S) -> Result<Page<Status>>
-    where
-        S: Into<Option<StatusesRequest<'a>>>,
-    {
-        let mut url = format!("{}/api/v1/accounts/{}/statuses", self.base, id);
-
-        if let Some(request) = request.into() {
-            url = format!("{}{}", url, request.to_querystring()?);
+    pub fn get_stream(&self, path: &str) -> Result<impl io::Read> {
+        let response = self.send_blocking(self.client.get(self.route(path)))?;
+        let status = response.status();
+        if status.is_client_error() || status.is_server_error() {
+            // error responses are small, and read like any other
+            return Err(match deserialise_blocking::<ApiError>(response) {
+                Ok(error) => Error::Api(error),
+                Err(e) => e,
+            });
         }
+        Ok(response)
+    }
 
-        let response = self.send_blocking(self.client.get(&url))?;
+    /// The language set with `MastodonBuilder::accept_language`, if any
+    pub fn language(&self) -> Option<Language> {
+        self.language
+    }
 
-        Page::new(self, response)
+    /// The base URL of the instance that requests are sent to: `data.base`,
+    /// or the override passed to `MastodonBuilder::base_url_override`
+    pub fn base(&self) -> &InstanceUrl {
+        &self.base_url
     }
 
-    /// Returns the client account's relationship to a list of other accounts.
-    /// Such as whether they follow them or vice versa.
-    fn relationships(&self, ids: &[&str]) -> Result<Page<Relationship>> {
-        let mut url = self.route("/api/v1/accounts/relationships?");
+    /// The access token sent with every request, which changes when it is
+    /// refreshed
+    pub fn token(&self) -> String {
+        self.latest_data().token.to_string()
+    }
 
-        if ids.len() == 1 {
-            url += "id=";
-            url += &ids[0];
-        } else {
-            for id in ids {
-                url += "id[]=";
-                url += &id;
-                url += "&";
-            }
-            url.pop();
-        }
+    /// `data`, with the latest access token if it has been refreshed since
+    /// the client was built. Save this rather than `data` to keep the new
+    /// token.
+    pub fn current_data(&self) -> Data {
+        (*self.latest_data()).clone()
+    }
 
-        let response = self.send_blocking(self.client.get(&url))?;
+    // `current_data`, without copying it
+    fn latest_data(&self) -> Arc<Data> {
+        match *self.refreshed.lock().unwrap() {
+            Some(ref data) => data.clone(),
+            None => self.data.clone(),
+        }
+    }
 
-        Page::new(self, response)
+    /// Whether the access token has expired, according to the `expires_at`
+    /// of `current_data`. Tokens without an expiry never do.
+    pub fn is_token_expired(&self) -> bool {
+        match self.latest_data().expires_at {
+            Some(expires_at) => expires_at <= self.clock.now(),
+            None => false,
+        }
     }
 
-    /// Add a push notifications subscription
-    fn add_push_subscription(&self, request: &AddPushRequest) -> Result<Subscription> {
-        let request = request.build()?;
-        let response = self.send_blocking(
-            self.client
-                .post(&self.route("/api/v1/push/subscription"))
-                .json(&request),
-        )?;
+    /// Gets a new access token with the refresh token, for instances that
+    /// issue expiring tokens. The new token is used for all further requests
+    /// by this client and its clones; the returned data includes it, to be
+    /// saved in place of the old one.
+    ///
+    /// Returns `Error::Other` if there is no refresh token, and
+    /// `Error::OAuth` if the server refuses it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let data = Data::default();
+    /// let client = Mastodon::from(data);
+    /// if client.is_token_expired() {
+    ///     let data = client.refresh()?;
+    ///     println!("new token, valid until {:?}", data.expires_at);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn refresh(&self) -> Result<Data> {
+        let mut data = self.current_data();
+        let refresh_token = data
+            .refresh_token
+            .clone()
+            .ok_or_else(|| Error::Other("no refresh token to refresh with".to_string()))?;
+        let req = self.client.post(self.route("/oauth/token")).form(&[
+            ("grant_type", "refresh_token"),
+            ("refresh_token", &refresh_token),
+            ("client_id", &data.client_id),
+            ("client_secret", &data.client_secret),
+        ]);
+        let response = self.http_sender.execute(&self.client, self.prepare(req)?)?;
+        let token: AccessToken = oauth_response(response)?;
 
-        deserialise_blocking(response)
+        let now = self.clock.now();
+        data.token_created_at = Some(token.created_at(now));
+        data.expires_at = token.expires_at(now);
+        data.token = token.access_token.into();
+        if let Some(refresh_token) = token.refresh_token {
+            data.refresh_token = Some(refresh_token);
+        }
+        if let Some(scope) = token.scope {
+            data.scopes = scope.parse().ok().or(data.scopes);
+        }
+        *self.refreshed.lock().unwrap() = Some(Arc::new(data.clone()));
+        Ok(data)
     }
 
-    /// Update the `data` portion of the push subscription associated with this
-    /// access token
-    fn update_push_data(&self, request: &UpdatePushRequest) -> Result<Subscription> {
-        let request = request.build();
-        let response = self.send_blocking(
-            self.client
-                .put(&self.route("/api/v1/push/subscription"))
-                .json(&request),
-        )?;
-
-        deserialise_blocking(response)
+    /// What this client and its clones have sent and received so far
+    pub fn stats(&self) -> ClientStats {
+        let remaining = self.rate_limit.lock().unwrap().map(|limit| limit.remaining);
+        self.stats.snapshot(remaining)
     }
 
-    /// Get all accounts that follow the authenticated user
-    fn follows_me(&self) -> Result<Page<Account>> {
-        let me = self.verify_credentials()?;
-        Ok(self.followers(&me.id)?)
+    /// The metadata of the last response received by this client or its
+    /// clones, e.g. to include the request id in a bug report
+    ///
+    /// Deprecated endpoints are also logged at warn level when they are used.
+    pub fn last_response(&self) -> Option<ResponseMeta> {
+        self.last_response.lock().unwrap().clone()
     }
 
-    /// Get all accounts that the authenticated user follows
-    fn followed_by_me(&self) -> Result<Page<Account>> {
-        let me = self.verify_credentials()?;
-        Ok(self.following(&me.id)?)
+    /// Connects to `stream` and reads it on a new thread, handing its events
+    /// over through a buffer configured by `options`
+    ///
+    /// See the [`channel`](channel/index.html) module for an example.
+    #[cfg(feature = "websocket")]
+    pub fn spawn_streaming(
+        &self,
+        stream: StreamKind,
+        options: ChannelOptions,
+    ) -> Result<(std::thread::JoinHandle<()>, EventReceiver)> {
+        Ok(self.streaming(stream)?.spawn(options))
     }
 
-    /// returns events that are relevant to the authorized user, i.e. home
-    /// timeline & notifications
+    /// A clone of this client whose requests time out after `timeout`,
+    /// instead of after the timeout of its `reqwest` client, which is 30
+    /// seconds by default
+    ///
+    /// This is meant for the few calls that legitimately take longer, such
+    /// as uploading large media, without raising the timeout of every other
+    /// call. Like other clones, it shares the rate limit and access token.
     ///
     /// # Example
     ///
     /// ```no_run
     /// # extern crate elefren;
     /// # use elefren::prelude::*;
-    /// # use std::error::Error;
-    /// use elefren::entities::event::Event;
-    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use std::time::Duration;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
     /// # let data = Data {
     /// #   base: "".into(),
     /// #   client_id: "".into(),
     /// #   client_secret: "".into(),
     /// #   redirect: "".into(),
     /// #   token: "".into(),
+    /// #   ..Default::default()
     /// # };
     /// let client = Mastodon::from(data);
-    /// for event in client.streaming_user()? {
-    ///     match event {
-    ///         Event::Update(ref status) => { /* .. */ },
-    ///         Event::Notification(ref notification) => { /* .. */ },
-    ///         Event::Delete(ref id) => { /* .. */ },
-    ///         Event::FiltersChanged => { /* .. */ },
-    ///     }
-    /// }
+    /// let video = MediaBuilder::new("holiday.mp4".into());
+    /// let attachment = client.with_timeout(Duration::from_secs(300)).media(video)?;
     /// # Ok(())
     /// # }
     /// ```
-    fn streaming_user(&self) -> Result<Self::Stream> {
-        let mut url: url::Url = self.route("/api/v1/streaming").parse()?;
-        url.query_pairs_mut()
-            .append_pair("access_token", &self.token)
-            .append_pair("stream", "user");
-        let mut url: url::Url = reqwest::blocking::get(url.as_str())?
-            .url()
-            .as_str()
-            .parse()?;
-        let new_scheme = match url.scheme() {
-            "http" => "ws",
-            "https" => "wss",
-            x => return Err(Error::Other(format!("Bad URL scheme: {}", x))),
-        };
-        url.set_scheme(new_scheme)
-            .map_err(|_| Error::Other("Bad URL scheme!".to_string()))?;
-
-        let client = tungstenite::connect(url.as_str())?.0;
-
-        Ok(EventReader(WebSocket(client)))
-    }
-
-    /// returns all public statuses
-    fn streaming_public(&self) -> Result<Self::Stream> {
-        let mut url: url::Url = self.route("/api/v1/streaming").parse()?;
-        url.query_pairs_mut()
-            .append_pair("access_token", &self.token)
-            .append_pair("stream", "public");
-        let mut url: url::Url = reqwest::blocking::get(url.as_str())?
-            .url()
-            .as_str()
-            .parse()?;
-        let new_scheme = match url.scheme() {
-            "http" => "ws",
-            "https" => "wss",
-            x => return Err(Error::Other(format!("Bad URL scheme: {}", x))),
-        };
-        url.set_scheme(new_scheme)
-            .map_err(|_| Error::Other("Bad URL scheme!".to_string()))?;
-
-        let client = tungstenite::connect(url.as_str())?.0;
-
-        Ok(EventReader(WebSocket(client)))
-    }
-
-    /// Returns all local statuses
-    fn streaming_local(&self) -> Result<Self::Stream> {
-        let mut url: url::Url = self.route("/api/v1/streaming").parse()?;
-        url.query_pairs_mut()
-            .append_pair("access_token", &self.token)
-            .append_pair("stream", "public:local");
-        let mut url: url::Url = reqwest::blocking::get(url.as_str())?
-            .url()
-            .as_str()
-            .parse()?;
-        let new_scheme = match url.scheme() {
-            "http" => "ws",
-            "https" => "wss",
-            x => return Err(Error::Other(format!("Bad URL scheme: {}", x))),
-        };
-        url.set_scheme(new_scheme)
-            .map_err(|_| Error::Other("Bad URL scheme!".to_string()))?;
-
-        let client = tungstenite::connect(url.as_str())?.0;
-
-        Ok(EventReader(WebSocket(client)))
-    }
-
-    /// Returns all public statuses for a particular hashtag
-    fn streaming_public_hashtag(&self, hashtag: &str) -> Result<Self::Stream> {
-        let mut url: url::Url = self.route("/api/v1/streaming").parse()?;
-        url.query_pairs_mut()
-            .append_pair("access_token", &self.token)
-            .append_pair("stream", "hashtag")
-            .append_pair("tag", hashtag);
-        let mut url: url::Url = reqwest::blocking::get(url.as_str())?
-            .url()
-            .as_str()
-            .parse()?;
-        let new_scheme = match url.scheme() {
-            "http" => "ws",
-            "https" => "wss",
-            x => return Err(Error::Other(format!("Bad URL scheme: {}", x))),
-        };
-        url.set_scheme(new_scheme)
-            .map_err(|_| Error::Other("Bad URL scheme!".to_string()))?;
-
-        let client = tungstenite::connect(url.as_str())?.0;
-
-        Ok(EventReader(WebSocket(client)))
-    }
-
-    /// Returns all local statuses for a particular hashtag
-    fn streaming_local_hashtag(&self, hashtag: &str) -> Result<Self::Stream> {
-        let mut url: url::Url = self.route("/api/v1/streaming").parse()?;
-        url.query_pairs_mut()
-            .append_pair("access_token", &self.token)
-            .append_pair("stream", "hashtag:local")
-            .append_pair("tag", hashtag);
-        let mut url: url::Url = reqwest::blocking::get(url.as_str())?
-            .url()
-            .as_str()
-            .parse()?;
-        let new_scheme = match url.scheme() {
-            "http" => "ws",
-            "https" => "wss",
-            x => return Err(Error::Other(format!("Bad URL scheme: {}", x))),
-        };
-        url.set_scheme(new_scheme)
-            .map_err(|_| Error::Other("Bad URL scheme!".to_string()))?;
-
-        let client = tungstenite::connect(url.as_str())?.0;
-
-        Ok(EventReader(WebSocket(client)))
-    }
-
-    /// Returns statuses for a list
-    fn streaming_list(&self, list_id: &str) -> Result<Self::Stream> {
-        let mut url: url::Url = self.route("/api/v1/streaming").parse()?;
-        url.query_pairs_mut()
-            .append_pair("access_token", &self.token)
-            .append_pair("stream", "list")
-            .append_pair("list", list_id);
-        let mut url: url::Url = reqwest::blocking::get(url.as_str())?
-            .url()
-            .as_str()
-            .parse()?;
-        let new_scheme = match url.scheme() {
-            "http" => "ws",
-            "https" => "wss",
-            x => return Err(Error::Other(format!("Bad URL scheme: {}", x))),
-        };
-        url.set_scheme(new_scheme)
-            .map_err(|_| Error::Other("Bad URL scheme!".to_string()))?;
-
-        let client = tungstenite::connect(url.as_str())?.0;
-
-        Ok(EventReader(WebSocket(client)))
-    }
-
-    /// Returns all direct messages
-    fn streaming_direct(&self) -> Result<Self::Stream> {
-        let mut url: url::Url = self.route("/api/v1/streaming").parse()?;
-        url.query_pairs_mut()
-            .append_pair("access_token", &self.token)
-            .append_pair("stream", "direct");
-        let mut url: url::Url = reqwest::blocking::get(url.as_str())?
-            .url()
-            .as_str()
-            .parse()?;
-        let new_scheme = match url.scheme() {
-            "http" => "ws",
-            "https" => "wss",
-            x => return Err(Error::Other(format!("Bad URL scheme: {}", x))),
-        };
-        url.set_scheme(new_scheme)
-            .map_err(|_| Error::Other("Bad URL scheme!".to_string()))?;
-
-        let client = tungstenite::connect(url.as_str())?.0;
-
-        Ok(EventReader(WebSocket(client)))
-    }
-
-    /// Equivalent to /api/v1/media
-    fn media(&self, media_builder: MediaBuilder) -> Result<Attachment> {
-        use reqwest::blocking::multipart::Form;
-
-        let mut form_data = Form::new().file("file", media_builder.file.as_ref())?;
-
-        if let Some(description) = media_builder.description {
-            form_data = form_data.text("description", description);
-        }
-
-        if let Some(focus) = media_builder.focus {
-            let string = format!("{},{}", focus.0, focus.1);
-            form_data = form_data.text("focus", string);
+    pub fn with_timeout(&self, timeout: Duration) -> Mastodon<H> {
+        Mastodon {
+            timeout: Some(timeout),
+            ..self.clone()
         }
+    }
 
-        let response = self.send_blocking(
-            self.client
-                .post(&self.route("/api/v1/media"))
-                .multipart(form_data),
-        )?;
-
-        let status = response.status();
-
-        if status.is_client_error() {
-            return Err(Error::Client(status));
-        } else if status.is_server_error() {
-            return Err(Error::Server(status));
+    /// An unauthenticated client for the same instance, sharing this
+    /// client's connection pool and `HttpSend` implementation
+    pub fn unauthenticated(&self) -> MastodonUnauth<H> {
+        MastodonUnauth {
+            client: self.client.clone(),
+            http_sender: self.http_sender.clone(),
+            base: self.base_url.clone(),
+            default_headers: self.default_headers.clone(),
         }
-
-        deserialise_blocking(response)
     }
-}
 
-#[derive(Debug)]
+    /// Downloads the file at `url`, e.g. an avatar or a media attachment,
+    /// streaming it into `writer`. Returns the file's content type, if the
+    /// server sent one.
+    ///
+    /// The client's connection pool is reused, but the access token is never
+    /// sent, as files are usually served from a different host.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// let account = client.verify_credentials()?;
+    /// let mut avatar = Vec::new();
+    /// let content_type = client.download(&account.avatar, &mut avatar)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn download<W: io::Write + ?Sized>(
+        &self,
+        url: &str,
+        writer: &mut W,
+    ) -> Result<Option<String>> {
+        let mut url = url::Url::parse(url)?;
+        let mut redirects = 0;
+        let mut response = loop {
+            let mut req = self.client.get(url.clone());
+            if let Some(ref user_agent) = self.user_agent {
+                req = req.header(USER_AGENT, user_agent.as_str());
+            }
+            if let Some(timeout) = self.timeout {
+                req = req.timeout(timeout);
+            }
+            self.stats.count_request(false);
+            let response = self.http_sender.execute(&self.client, req.build()?)?;
+            // media is often served from a different host, and as this
+            // request isn't authenticated, any redirect is safe to follow
+            match response.headers().get(LOCATION) {
+                Some(location)
+                    if response.status().is_redirection()
+                        && redirects < RedirectPolicy::MAX_REDIRECTS =>
+                {
+                    url = response.url().join(location.to_str()?)?;
+                    redirects += 1;
+                },
+                _ => break response,
+            }
+        };
+
+        let status = response.status();
+        if status.is_client_error() {
+            return Err(Error::Client(status.into()));
+        } else if status.is_server_error() {
+            return Err(Error::Server(status.into()));
+        }
+
+        let content_type = response
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+        let bytes = response.copy_to(writer)?;
+        self.stats.count_bytes(bytes);
+        Ok(content_type)
+    }
+}
+
+#[cfg(feature = "client")]
+impl Mastodon {
+    /// Creates a client from `data`, returning an error instead of
+    /// panicking like `Mastodon::from` if `data` is invalid, e.g. because
+    /// `data.base` is empty
+    ///
+    /// This is the `TryFrom<Data>` conversion. It can't be an implementation
+    /// of `TryFrom` while the `From<Data>` implementation is kept, which is
+    /// only there for compatibility and will be removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// assert!(Mastodon::try_from_data(Data::default()).is_err());
+    ///
+    /// let data = Data {
+    ///     base: "https://example.com".into(),
+    ///     token: "the-token".into(),
+    ///     ..Default::default()
+    /// };
+    /// let client = Mastodon::try_from_data(data)?;
+    /// # Ok::<(), elefren::Error>(())
+    /// ```
+    pub fn try_from_data(data: Data) -> Result<Mastodon> {
+        let mut builder = MastodonBuilder::new();
+        builder.data(data);
+        builder.build()
+    }
+}
+
+#[cfg(feature = "client")]
+impl From<Data> for Mastodon {
+    /// Creates a mastodon instance from the data struct.
+    ///
+    /// Prefer [`Mastodon::try_from_data`](struct.Mastodon.html#method.try_from_data),
+    /// or [`MastodonBuilder`](struct.MastodonBuilder.html), which also
+    /// allows configuring the HTTP client, user agent and retry behaviour.
+    ///
+    /// # Panics
+    ///
+    /// If `data.base` is empty or isn't a valid instance URL.
+    fn from(data: Data) -> Mastodon {
+        Mastodon::try_from_data(data)
+            .unwrap_or_else(|e| panic!("invalid `Data` for a client: {}", e))
+    }
+}
+
+#[cfg(feature = "client")]
+#[async_trait::async_trait]
+impl<H: HttpSend> MastodonClient<H> for Mastodon<H> {
+    #[cfg(feature = "websocket")]
+    type Stream = EventReader<WebSocket>;
+    #[cfg(not(feature = "websocket"))]
+    type Stream = std::iter::Empty<Event>;
+
+    paged_routes! {
+        (get) favourites, favourites_with: "favourites" => Status,
+        (get) bookmarks, bookmarks_with: "bookmarks" => Status,
+        (get) blocks, blocks_with: "blocks" => Account,
+        (get) domain_blocks, domain_blocks_with: "domain_blocks" => String,
+        (get) follow_requests, follow_requests_with: "follow_requests" => Account,
+        (get) get_home_timeline, get_home_timeline_with: "timelines/home" => Status,
+        (get) get_local_timeline, get_local_timeline_with: "timelines/public?local=true" => Status,
+        (get) get_federated_timeline, get_federated_timeline_with: "timelines/public?local=false" => Status,
+        (get) mutes, mutes_with: "mutes" => Account,
+        (get) notifications, notifications_with: "notifications" => Notification,
+        (get) reports, reports_with: "reports" => Report,
+        (get) get_endorsements, get_endorsements_with: "endorsements" => Account,
+        (get) conversations, conversations_with: "conversations" => Conversation,
+        (get) scheduled_statuses, scheduled_statuses_with: "scheduled_statuses" => ScheduledStatus,
+        (get) admin_domain_blocks, admin_domain_blocks_with: "admin/domain_blocks" => DomainBlock,
+    }
+
+    shared_paged_routes_with_id!(paged_routes_with_id);
+
+    #[cfg(feature = "pleroma")]
+    paged_routes_with_id! {
+        (get) pleroma_favourites, pleroma_favourites_with: "pleroma/accounts/{}/favourites" => Status,
+    }
+
+    shared_routes!(route);
+
+    route! {
+        (get) verify_credentials: "accounts/verify_credentials" => Account,
+        (get) verify_app_credentials: "apps/verify_credentials" => Application,
+        (post json {request: &ReportRequest => request}) report_with: "reports" => Report,
+        (post json {uri: impl AsRef<str> => serde_json::json!({ "uri": uri.as_ref() })}) follows: "follows" => Account,
+        (post) clear_notifications: "notifications/clear" => (),
+        (post json {id: impl AsRef<str> => serde_json::json!({ "id": id.as_ref() })}) dismiss_notification: "notifications/dismiss" => (),
+        (get) get_push_subscription: "push/subscription" => Subscription,
+        (delete) delete_push_subscription: "push/subscription" => (),
+        (get) get_filters: "filters" => Vec<Filter>,
+        (get) get_follow_suggestions: "suggestions" => Vec<Account>,
+        (get) get_emojis: "custom_emojis" => Vec<Emoji>,
+        (get) instance_peers: "instance/peers" => Vec<String>,
+        (post json {request: &mut AddFilterRequest => request}) add_filter: "filters" => Filter,
+        (patch multipart {builder: UpdateCredsRequest => credentials_form(builder)?}) update_credentials: "accounts/update_credentials" => Account,
+        (post multipart {media_builder: MediaBuilder => media_form(media_builder)?}) media: "media" => Attachment,
+        (get) get_lists: "lists" => Vec<List>,
+        (post json {request: &ListRequest => request}) create_list: "lists" => List,
+        (post json {request: &DomainBlockRequest => request}) admin_block_domain: "admin/domain_blocks" => DomainBlock,
+    }
+
+    route_v2! {
+        (get) get_filters_v2: "filters" => Vec<FilterV2>,
+        (get) get_follow_suggestions_v2: "suggestions" => Vec<Suggestion>,
+        (post json {request: &AddFilterV2Request => request}) add_filter_v2: "filters" => FilterV2,
+    }
+
+    route_v2_id! {
+        (get) get_filter_v2: "filters/{}" => FilterV2,
+        (delete) delete_filter_v2: "filters/{}" => (),
+        (get) filter_keywords: "filters/{}/keywords" => Vec<FilterKeyword>,
+        (post json {request: &FilterKeywordRequest => request}) add_filter_keyword: "filters/{}/keywords" => FilterKeyword,
+        (get) get_filter_keyword: "filters/keywords/{}" => FilterKeyword,
+        (put json {request: &FilterKeywordRequest => request}) update_filter_keyword: "filters/keywords/{}" => FilterKeyword,
+        (delete) delete_filter_keyword: "filters/keywords/{}" => (),
+        (get) filter_statuses: "filters/{}/statuses" => Vec<FilterStatus>,
+        (post json {status_id: impl AsRef<str> => serde_json::json!({ "status_id": status_id.as_ref() })}) add_filter_status: "filters/{}/statuses" => FilterStatus,
+        (get) get_filter_status: "filters/statuses/{}" => FilterStatus,
+        (delete) delete_filter_status: "filters/statuses/{}" => (),
+    }
+
+    shared_routes_id!(route_id);
+
+    route_id! {
+        (get) get_list: "lists/{}" => List,
+        (get) get_media: "media/{}" => Attachment,
+        (put json {request: &ListRequest => request}) update_list: "lists/{}" => List,
+        (delete) delete_list: "lists/{}" => (),
+        (post json {account_ids: &[impl AsRef<str>] => account_ids_body(account_ids)}) add_list_accounts: "lists/{}/accounts" => (),
+        (delete json {account_ids: &[impl AsRef<str>] => account_ids_body(account_ids)}) remove_list_accounts: "lists/{}/accounts" => (),
+        (get) get_scheduled_status: "scheduled_statuses/{}" => ScheduledStatus,
+        (put json {scheduled_at: chrono::DateTime<chrono::Utc> => serde_json::json!({ "scheduled_at": scheduled_at })}) update_scheduled_status: "scheduled_statuses/{}" => ScheduledStatus,
+        (delete) delete_scheduled_status: "scheduled_statuses/{}" => (),
+        (post) follow: "accounts/{}/follow" => Relationship,
+        (post) unfollow: "accounts/{}/unfollow" => Relationship,
+        (post) block: "accounts/{}/block" => Relationship,
+        (post) unblock: "accounts/{}/unblock" => Relationship,
+        (post) mute: "accounts/{}/mute" => Relationship,
+        (post) unmute: "accounts/{}/unmute" => Relationship,
+        (get) get_notification: "notifications/{}" => Notification,
+        (post) reblog: "statuses/{}/reblog" => Status,
+        (post) unreblog: "statuses/{}/unreblog" => Status,
+        (post) favourite: "statuses/{}/favourite" => Status,
+        (post) unfavourite: "statuses/{}/unfavourite" => Status,
+        (delete) delete_status: "statuses/{}" => (),
+        (get) get_filter: "filters/{}" => Filter,
+        (delete) delete_filter: "filters/{}" => (),
+        (delete) delete_from_suggestions: "suggestions/{}" => (),
+        (post) endorse_user: "accounts/{}/pin" => Relationship,
+        (post) unendorse_user: "accounts/{}/unpin" => Relationship,
+        (get) identity_proofs: "accounts/{}/identity_proofs" => Vec<IdentityProof>,
+        (post) authorize_follow_request: "follow_requests/{}/authorize" => (),
+        (post) reject_follow_request: "follow_requests/{}/reject" => (),
+        (put json {request: &mut AddFilterRequest => request}) update_filter: "filters/{}" => Filter,
+        (put json {request: &DomainBlockRequest => request}) admin_update_domain_block: "admin/domain_blocks/{}" => DomainBlock,
+        (delete) admin_unblock_domain: "admin/domain_blocks/{}" => (),
+    }
+
+    /// Post a new status to the account.
+    fn new_status(&self, status: NewStatus) -> Result<Status> {
+        let status = status.or_language(self.language);
+        let response = self.send_blocking(
+            self.client
+                .post(&self.route("/api/v1/statuses"))
+                .json(&status),
+        )?;
+
+        deserialise_blocking(response)
+    }
+
+    /// GET /api/v1/directory
+    fn directory<I: Into<Option<DirectoryRequest>>>(&self, request: I) -> Result<Vec<Account>> {
+        self.get(self.route(&routes::directory(request.into())?))
+    }
+
+    /// GET /api/v1/trends
+    fn trends<I: Into<Option<usize>>>(&self, limit: I) -> Result<Vec<Tag>> {
+        self.get(self.route(&routes::trends(limit.into())))
+    }
+
+    /// Upload `media`, wait for the server to process it, and post `status`
+    /// with it
+    fn post_status_with_media(
+        &self,
+        status: NewStatus,
+        media: Vec<MediaBuilder>,
+    ) -> Result<Status> {
+        let mut media_ids = Vec::with_capacity(media.len());
+        for media_builder in media {
+            let response = self.send_blocking(
+                self.client
+                    .post(self.route("/api/v2/media"))
+                    .multipart(media_form(media_builder)?),
+            )?;
+            let mut attachment: Attachment = deserialise_blocking(response)?;
+            let started = self.clock.now();
+            while attachment.is_processing() {
+                if (self.clock.now() - started).to_std().unwrap_or_default()
+                    >= MEDIA_PROCESSING_TIMEOUT
+                {
+                    return Err(Error::Other(format!(
+                        "media {} was still being processed after {:?}",
+                        attachment.id, MEDIA_PROCESSING_TIMEOUT
+                    )));
+                }
+                self.clock.sleep(MEDIA_PROCESSING_POLL);
+                attachment = self.get_media(&attachment.id)?;
+            }
+            media_ids.push(attachment.id);
+        }
+        self.new_status(status.with_media_ids(media_ids))
+    }
+
+    /// Schedule a status built with `StatusBuilder::scheduled_at`
+    fn schedule_status(&self, status: NewStatus) -> Result<ScheduledStatus> {
+        if status.scheduled_at().is_none() {
+            return Err(Error::Other(
+                "the status has no scheduled_at, post it with new_status".to_string(),
+            ));
+        }
+        let status = status.or_language(self.language);
+        let response = self.send_blocking(
+            self.client
+                .post(self.route("/api/v1/statuses"))
+                .json(&status),
+        )?;
+
+        deserialise_blocking(response)
+    }
+
+    /// Get timeline filtered by a hashtag(eg. `#coffee`) either locally or
+    /// federated.
+    fn get_hashtag_timeline(
+        &self,
+        hashtag: impl AsRef<str>,
+        local: bool,
+    ) -> Result<Page<Status, H>> {
+        self.get_hashtag_timeline_with(hashtag, local, &PageRequest::new())
+    }
+
+    /// Same as `get_hashtag_timeline`, bounded by `request`
+    fn get_hashtag_timeline_with(
+        &self,
+        hashtag: impl AsRef<str>,
+        local: bool,
+        request: &PageRequest,
+    ) -> Result<Page<Status, H>> {
+        let options = if local {
+            HashtagRequest::new().local()
+        } else {
+            HashtagRequest::new()
+        };
+        self.get_hashtag_timeline_with_options(hashtag, &options, request)
+    }
+
+    /// Same as `get_hashtag_timeline_with`, with the filters of `options`
+    fn get_hashtag_timeline_with_options(
+        &self,
+        hashtag: impl AsRef<str>,
+        options: &HashtagRequest,
+        request: &PageRequest,
+    ) -> Result<Page<Status, H>> {
+        let url = self.route(&routes::hashtag_timeline(hashtag.as_ref(), options)?);
+
+        Page::new(
+            self,
+            self.send_blocking(self.client.get(&self.paged_url(&url, request)?))?,
+        )
+    }
+
+    fn get_public_timeline_with_options(
+        &self,
+        options: &TimelineRequest,
+        request: &PageRequest,
+    ) -> Result<Page<Status, H>> {
+        let url = self.route(&routes::public_timeline(options)?);
+
+        Page::new(
+            self,
+            self.send_blocking(self.client.get(&self.paged_url(&url, request)?))?,
+        )
+    }
+
+    /// Get the notifications of the types in `options`, e.g. only mentions
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// # use std::error::Error;
+    /// use elefren::{
+    ///     entities::notification::NotificationType,
+    ///     NotificationsRequest,
+    ///     PageRequest,
+    /// };
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// let options = NotificationsRequest::new().types(&[NotificationType::Mention]);
+    /// let mentions = client.notifications_with_options(&options, &PageRequest::new())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn notifications_with_options(
+        &self,
+        options: &NotificationsRequest,
+        request: &PageRequest,
+    ) -> Result<Page<Notification, H>> {
+        let url = self.route(&routes::notifications(options)?);
+
+        Page::new(
+            self,
+            self.send_blocking(self.client.get(&self.paged_url(&url, request)?))?,
+        )
+    }
+
+    /// Get statuses of a single account by id. Optionally only with pictures
+    /// and or excluding replies.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// let statuses = client.statuses("user-id", None)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// let request = StatusesRequest::new()
+    ///     .only_media();
+    /// let statuses = client.statuses("user-id", request)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn statuses<'a, 'b: 'a, S>(&'b self, id: impl AsRef<str>, request: S) -> Result<Page<Status, H>>
+    where
+        S: Into<Option<StatusesRequest<'a>>>,
+    {
+        let mut url = self.route(&format!(
+            "/api/v1/accounts/{}/statuses",
+            routes::encode_segment(id.as_ref())?
+        ));
+
+        match request.into() {
+            Some(request) => {
+                let request = request.or_limit(self.default_limit);
+                url = format!("{}{}", url, ToQueryString::to_querystring(&request)?);
+            },
+            None => url = self.paged_url(&url, &PageRequest::new())?,
+        }
+
+        let response = self.send_blocking(self.client.get(&url))?;
+
+        Page::new(self, response)
+    }
+
+    /// Fetches the statuses of the account with id `id`, following the pages
+    /// until there are no more or `limit` statuses have been fetched
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// let request = StatusesRequest::new().exclude_reblogs().limit(40);
+    /// let statuses = client.statuses_all("user-id", request, Some(1000))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn statuses_all(
+        &self,
+        id: impl AsRef<str>,
+        request: StatusesRequest,
+        limit: Option<usize>,
+    ) -> Result<Vec<Status>> {
+        let limit = limit.unwrap_or(usize::MAX);
+        let mut statuses = Vec::new();
+        if limit == 0 {
+            return Ok(statuses);
+        }
+        for status in self.statuses(id, request)?.try_items_iter() {
+            statuses.push(status?);
+            if statuses.len() >= limit {
+                break;
+            }
+        }
+        Ok(statuses)
+    }
+
+    /// Fetches what an account's profile shows: its pinned statuses and the
+    /// first page of its statuses
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// let profile = client.profile_view("42")?;
+    /// for status in profile.pinned.iter().chain(&profile.statuses) {
+    ///     println!("{}", status.content);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn profile_view(&self, id: impl AsRef<str>) -> Result<ProfileView> {
+        let id = id.as_ref();
+        let pinned = self.statuses(id, StatusesRequest::new().pinned())?;
+        let statuses = self.statuses(id, None)?;
+        Ok(ProfileView {
+            pinned: pinned.initial_items,
+            statuses: statuses.initial_items,
+        })
+    }
+
+    fn report(&self, account_id: &str, status_ids: Vec<&str>, comment: String) -> Result<Report> {
+        let request = ReportRequest::new(account_id)
+            .status_ids(status_ids)
+            .comment(&comment);
+        self.report_with(&request)
+    }
+
+    fn search_accounts(
+        &self,
+        q: impl AsRef<str>,
+        limit: Option<u64>,
+        following: bool,
+    ) -> Result<Vec<Account>> {
+        let mut query = vec![("q", q.as_ref().to_string())];
+        if let Some(limit) = limit {
+            query.push(("limit", limit.to_string()));
+        }
+        query.push(("following", following.to_string()));
+        let url = format!(
+            "/api/v1/accounts/search?{}",
+            serde_urlencoded::to_string(query)?
+        );
+        self.get(self.route(&url))
+    }
+
+    fn search(&self, q: impl AsRef<str>, resolve: bool) -> Result<SearchResult> {
+        let qs = serde_urlencoded::to_string((("q", q.as_ref()), ("resolve", resolve)))?;
+        self.get(self.route(&format!("/api/v1/search?{}", qs)))
+    }
+
+    fn search_v2(&self, q: impl AsRef<str>, resolve: bool) -> Result<SearchResultV2> {
+        let qs = serde_urlencoded::to_string((("q", q.as_ref()), ("resolve", resolve)))?;
+        self.get(self.route(&format!("/api/v2/search?{}", qs)))
+    }
+
+    fn search_v2_with(&self, request: &SearchRequest) -> Result<SearchResultV2> {
+        let url = format!("/api/v2/search{}", request.to_querystring()?);
+        self.get(self.route(&url))
+    }
+
+    /// Returns the client account's relationship to a list of other accounts.
+    /// Such as whether they follow them or vice versa.
+    fn relationships(&self, ids: &[impl AsRef<str>]) -> Result<Vec<Relationship>> {
+        let mut url = self.route("/api/v1/accounts/relationships?");
+
+        if ids.len() == 1 {
+            url += "id=";
+            url += &routes::percent_encode(ids[0].as_ref());
+        } else {
+            for id in ids {
+                url += "id[]=";
+                url += &routes::percent_encode(id.as_ref());
+                url += "&";
+            }
+            url.pop();
+        }
+
+        self.get(url)
+    }
+
+    /// Add a push notifications subscription
+    fn add_push_subscription(&self, request: &AddPushRequest) -> Result<Subscription> {
+        let request = request.build()?;
+        let response = self.send_blocking(
+            self.client
+                .post(&self.route("/api/v1/push/subscription"))
+                .json(&request),
+        )?;
+
+        deserialise_blocking(response)
+    }
+
+    /// Update the `data` portion of the push subscription associated with this
+    /// access token
+    fn update_push_data(&self, request: &UpdatePushRequest) -> Result<Subscription> {
+        let request = request.build();
+        let response = self.send_blocking(
+            self.client
+                .put(&self.route("/api/v1/push/subscription"))
+                .json(&request),
+        )?;
+
+        deserialise_blocking(response)
+    }
+
+    /// GET /api/v1/statuses/:id/card
+    ///
+    /// This route was removed in Mastodon 3.0. If the server doesn't know it,
+    /// the card embedded in the status is returned instead.
+    fn get_card(&self, id: impl AsRef<str>) -> Result<Card> {
+        let id = id.as_ref();
+        let route = self.route(&format!(
+            "/api/v1/statuses/{}/card",
+            routes::encode_segment(id)?
+        ));
+        let response = self.send_blocking(self.client.get(&route))?;
+        if response.status() != StatusCode::NOT_FOUND {
+            return deserialise_blocking(response);
+        }
+        embedded_card(self.get_status(id)?)
+    }
+
+    fn peers_matching(&self, text: impl AsRef<str>) -> Result<Vec<String>> {
+        let route = self.route("/api/v1/instance/peers");
+        let response = self.send_blocking(self.client.get(&route))?;
+        let peers = PeersMatching(text.as_ref());
+        deserialise_blocking_with(response, |body| json_backend::from_slice_seed(body, peers))
+    }
+
+    /// POST /api/v1/domain_blocks
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Other` without contacting the server if `domain` isn't
+    /// a valid domain name.
+    fn block_domain(&self, domain: impl AsRef<str>) -> Result<()> {
+        let domain = domain.as_ref();
+        validate_domain(domain)?;
+        let response = self.send_blocking(
+            self.client
+                .post(self.route("/api/v1/domain_blocks"))
+                .json(&serde_json::json!({ "domain": domain })),
+        )?;
+
+        let status = response.status();
+
+        if status.is_client_error() {
+            return Err(Error::Client(status.into()));
+        } else if status.is_server_error() {
+            return Err(Error::Server(status.into()));
+        }
+
+        deserialise_blocking(response)
+    }
+
+    /// DELETE /api/v1/domain_blocks
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Other` without contacting the server if `domain` isn't
+    /// a valid domain name.
+    fn unblock_domain(&self, domain: impl AsRef<str>) -> Result<()> {
+        let domain = domain.as_ref();
+        validate_domain(domain)?;
+        let response = self.send_blocking(
+            self.client
+                .delete(self.route("/api/v1/domain_blocks"))
+                .json(&serde_json::json!({ "domain": domain })),
+        )?;
+
+        let status = response.status();
+
+        if status.is_client_error() {
+            return Err(Error::Client(status.into()));
+        } else if status.is_server_error() {
+            return Err(Error::Server(status.into()));
+        }
+
+        deserialise_blocking(response)
+    }
+
+    /// Whether `domain` is blocked, looking through all pages of
+    /// `domain_blocks`
+    fn is_domain_blocked(&self, domain: impl AsRef<str>) -> Result<bool> {
+        let domain = domain.as_ref();
+        let mut page = self.domain_blocks_with(&PageRequest::new().limit(200))?;
+        let mut blocked = mem::take(&mut page.initial_items);
+        loop {
+            if blocked
+                .iter()
+                .any(|b| b.eq_ignore_ascii_case(domain.trim()))
+            {
+                return Ok(true);
+            }
+            match page.next_page()? {
+                Some(next) => blocked = next,
+                None => return Ok(false),
+            }
+        }
+    }
+
+    /// The number of pending follow requests
+    ///
+    /// Mastodon includes the count in the account's `source`; for servers
+    /// that don't, all pages of `follow_requests` are fetched and counted.
+    fn follow_requests_count(&self) -> Result<usize> {
+        let me = self.verify_credentials()?;
+        if let Some(count) = me.source.and_then(|source| source.follow_requests_count) {
+            return Ok(count as usize);
+        }
+        let page = self.follow_requests_with(&PageRequest::new().limit(80))?;
+        Ok(page.collect_all()?.len())
+    }
+
+    /// Goes through all pending follow requests, authorizing those for which
+    /// `predicate` returns `true` and rejecting the rest. Returns the number
+    /// of authorized and rejected requests.
+    ///
+    /// The requests are answered one per second, to stay well within the
+    /// server's rate limits.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// let (authorized, rejected) =
+    ///     client.authorize_all_follow_requests(|account| account.bot != Some(true))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn authorize_all_follow_requests<F>(&self, mut predicate: F) -> Result<(usize, usize)>
+    where
+        F: FnMut(&Account) -> bool,
+    {
+        let page = self.follow_requests_with(&PageRequest::new().limit(80))?;
+        let (mut authorized, mut rejected) = (0, 0);
+        for (i, account) in page.collect_all()?.iter().enumerate() {
+            if i > 0 {
+                self.clock.sleep(FOLLOW_REQUEST_PACING);
+            }
+            if predicate(account) {
+                self.authorize_follow_request(&account.id)?;
+                authorized += 1;
+            } else {
+                self.reject_follow_request(&account.id)?;
+                rejected += 1;
+            }
+        }
+        Ok((authorized, rejected))
+    }
+
+    /// Fetches every account following the account with id `account_id`,
+    /// and every account it follows
+    ///
+    /// The lists are fetched 80 accounts at a time. Before each page the
+    /// client waits if the rate limit is almost used up, so exporting large
+    /// graphs can take a while.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// let me = client.verify_credentials()?;
+    /// let graph = client.export_social_graph(&me.id)?;
+    /// println!("{}", serde_json::to_string_pretty(&graph)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn export_social_graph(&self, account_id: impl AsRef<str>) -> Result<SocialGraph> {
+        let account_id = account_id.as_ref();
+        let request = PageRequest::new().limit(80);
+        let followers = self.followers_with(account_id, &request)?;
+        let followers = social_graph::collect_paced(self, followers)?;
+        let following = self.following_with(account_id, &request)?;
+        let following = social_graph::collect_paced(self, following)?;
+        Ok(SocialGraph::new(
+            account_id,
+            followers,
+            following,
+            self.clock.now(),
+        ))
+    }
+
+    fn rate_limit(&self) -> Option<RateLimit> {
+        *self.rate_limit.lock().unwrap()
+    }
+
+    fn clock(&self) -> &dyn Clock {
+        &*self.clock
+    }
+
+    /// Makes the accounts with `account_ids` the members of the list with id
+    /// `id`, adding and removing only the accounts that differ
+    ///
+    /// The accounts to add have to be followed by the user, or the server
+    /// refuses to add them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// let artists = vec!["1", "23", "456"];
+    /// let changes = client.reconcile_list("42", &artists)?;
+    /// println!("added {:?}, removed {:?}", changes.added, changes.removed);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn reconcile_list(
+        &self,
+        id: impl AsRef<str>,
+        account_ids: &[impl AsRef<str>],
+    ) -> Result<ListReconciliation> {
+        let id = id.as_ref();
+        let current = self
+            .list_accounts_with(id, &PageRequest::new().limit(80))?
+            .try_items_iter()
+            .map(|account| account.map(|account| account.id))
+            .collect::<Result<HashSet<String>>>()?;
+        let mut desired = HashSet::new();
+        let mut changes = ListReconciliation::default();
+        for account_id in account_ids {
+            let account_id = account_id.as_ref();
+            if desired.insert(account_id) && !current.contains(account_id) {
+                changes.added.push(account_id.to_string());
+            }
+        }
+        changes.removed = current
+            .into_iter()
+            .filter(|account_id| !desired.contains(account_id.as_str()))
+            .collect();
+        changes.removed.sort();
+        if !changes.removed.is_empty() {
+            self.remove_list_accounts(id, &changes.removed)?;
+        }
+        if !changes.added.is_empty() {
+            self.add_list_accounts(id, &changes.added)?;
+        }
+        Ok(changes)
+    }
+
+    /// Get all accounts that follow the authenticated user
+    fn follows_me(&self) -> Result<Page<Account, H>> {
+        let me = self.me()?;
+        Ok(self.followers(&me.id)?)
+    }
+
+    /// Get all accounts that the authenticated user follows
+    fn followed_by_me(&self) -> Result<Page<Account, H>> {
+        let me = self.me()?;
+        Ok(self.following(&me.id)?)
+    }
+
+    /// Get the statuses the authenticated user has pinned to their profile
+    fn my_pinned_statuses(&self) -> Result<Page<Status, H>> {
+        let me = self.me()?;
+        let url = self.route(&format!(
+            "/api/v1/accounts/{}/statuses{}",
+            routes::encode_segment(&me.id)?,
+            ToQueryString::to_querystring(&StatusesRequest::new().pinned())?
+        ));
+        Page::new(self, self.send_blocking(self.client.get(&url))?)
+    }
+
+    /// Returns the events of `stream`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// # use std::error::Error;
+    /// use elefren::{entities::event::Event, StreamKind};
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// for event in client.streaming(StreamKind::User)? {
+    ///     match event {
+    ///         Event::Update(ref status) => { /* .. */ },
+    ///         Event::StatusUpdate(ref status) => { /* .. */ },
+    ///         Event::Notification(ref notification) => { /* .. */ },
+    ///         Event::Delete(ref id) => { /* .. */ },
+    ///         Event::FiltersChanged => { /* .. */ },
+    ///         Event::Conversation(ref conversation) => { /* .. */ },
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "websocket")]
+    fn streaming(&self, stream: StreamKind) -> Result<Self::Stream> {
+        connect_streaming(
+            &self.client,
+            self.route("/api/v1/streaming").parse()?,
+            stream,
+            Some(&self.token()),
+            &self.default_headers,
+            &self.streaming,
+        )
+    }
+
+    /// Without the `websocket` feature, streaming isn't available and this
+    /// always fails
+    #[cfg(not(feature = "websocket"))]
+    fn streaming(&self, _stream: StreamKind) -> Result<Self::Stream> {
+        Err(Error::Other(
+            "streaming needs the `websocket` feature".to_string(),
+        ))
+    }
+}
+
+#[cfg(feature = "websocket")]
+#[derive(Debug)]
 /// WebSocket newtype so that EventStream can be implemented without coherency
 /// issues
 pub struct WebSocket(tungstenite::protocol::WebSocket<AutoStream>);
 
-/// A type that streaming events can be read from
-pub trait EventStream {
-    /// Read a message from this stream
-    fn read_message(&mut self) -> Result<String>;
-}
+#[cfg(feature = "client")]
+/// A type that streaming events can be read from
+pub trait EventStream {
+    /// Read a message from this stream
+    fn read_message(&mut self) -> Result<String>;
+}
+
+#[cfg(feature = "client")]
+impl<R: BufRead> EventStream for R {
+    fn read_message(&mut self) -> Result<String> {
+        let mut buf = String::new();
+        if self.read_line(&mut buf)? == 0 {
+            return Err(Error::Io(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "event stream closed",
+            )));
+        }
+        Ok(buf)
+    }
+}
+
+#[cfg(feature = "websocket")]
+impl EventStream for WebSocket {
+    fn read_message(&mut self) -> Result<String> {
+        Ok(self.0.read_message()?.into_text()?)
+    }
+}
+
+#[cfg(feature = "client")]
+#[derive(Debug)]
+/// Iterator that produces events from a mastodon streaming API event stream
+///
+/// Iteration ends when the underlying connection is closed or fails; use
+/// `next_event` to find out why.
+///
+/// An `EventReader` is `Send` if its stream is, as `EventReader<WebSocket>`
+/// is, so it can be moved to a thread of its own; see also
+/// `channel::EventReceiver`.
+pub struct EventReader<R: EventStream> {
+    stream: R,
+    kind: Option<StreamKind>,
+    url: Option<url::Url>,
+    connected_at: chrono::DateTime<chrono::Utc>,
+}
+#[cfg(feature = "client")]
+impl<R: EventStream> Iterator for EventReader<R> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().ok()
+    }
+}
+
+#[cfg(feature = "client")]
+impl<R: EventStream> EventReader<R> {
+    /// Create an `EventReader` over any stream of server-sent-event lines or
+    /// websocket messages
+    pub fn new(stream: R) -> EventReader<R> {
+        EventReader {
+            stream,
+            kind: None,
+            url: None,
+            connected_at: chrono::Utc::now(),
+        }
+    }
+
+    /// The stream this reader was opened for, or `None` if it was created
+    /// with `new`
+    pub fn kind(&self) -> Option<&StreamKind> {
+        self.kind.as_ref()
+    }
+
+    /// The hashtag, if this is a hashtag stream
+    pub fn tag(&self) -> Option<&str> {
+        match self.kind {
+            Some(StreamKind::Hashtag { ref tag, .. }) => Some(tag),
+            _ => None,
+        }
+    }
+
+    /// The id of the list, if this is a list stream
+    pub fn list_id(&self) -> Option<&str> {
+        match self.kind {
+            Some(StreamKind::List(ref id)) => Some(id),
+            _ => None,
+        }
+    }
+
+    /// The URL the stream is connected to, after following the instance's
+    /// redirects, or `None` if the reader was created with `new`
+    ///
+    /// The access token is left out, so the URL can be logged.
+    pub fn url(&self) -> Option<&url::Url> {
+        self.url.as_ref()
+    }
+
+    /// When the connection was established, or when the reader was created
+    /// for readers created with `new`
+    pub fn connected_at(&self) -> chrono::DateTime<chrono::Utc> {
+        self.connected_at
+    }
+
+    /// Reads the next event from the stream
+    ///
+    /// Messages that can't be parsed into an `Event` are skipped, so this only
+    /// returns an error when reading from the underlying stream fails, e.g.
+    /// because the connection was closed.
+    pub fn next_event(&mut self) -> Result<Event> {
+        Ok(self.next_envelope()?.event)
+    }
+
+    /// Like `next_event`, but also returns when the event was received and
+    /// its raw payload, e.g. to measure latency or archive what the server
+    /// sent
+    pub fn next_envelope(&mut self) -> Result<EventEnvelope> {
+        let mut lines = Vec::new();
+        loop {
+            let line = self.stream.read_message()?;
+            let received_at = chrono::Utc::now();
+            let line = line.trim().to_string();
+            if line.is_empty() {
+                // a blank line terminates a server-sent event
+                lines.clear();
+                continue;
+            }
+            if line.starts_with(':') {
+                continue;
+            }
+            let parsed = if line.starts_with('{') {
+                // websocket messages are self-contained JSON objects
+                self.make_event(&[line])
+            } else {
+                lines.push(line);
+                self.make_event(&lines)
+            };
+            if let Ok((event, raw)) = parsed {
+                return Ok(EventEnvelope {
+                    event,
+                    received_at,
+                    raw,
+                });
+            }
+        }
+    }
+
+    // Returns the event along with its payload
+    fn make_event(&self, lines: &[String]) -> Result<(Event, Option<String>)> {
+        let event;
+        let data;
+        if let Some(event_line) = lines.iter().find(|line| line.starts_with("event:")) {
+            event = event_line[6..].trim().to_string();
+            data = lines
+                .iter()
+                .find(|line| line.starts_with("data:"))
+                .map(|x| x[5..].trim().to_string());
+        } else {
+            use serde::Deserialize;
+            #[derive(Deserialize)]
+            struct Message {
+                pub event: String,
+                pub payload: Option<String>,
+            }
+            let message = json_backend::from_str::<Message>(&lines[0])?;
+            event = message.event;
+            data = message.payload;
+        }
+        let event: &str = &event;
+        let raw = data.clone();
+        let event = match event {
+            "notification" => {
+                let data = data.ok_or_else(|| {
+                    Error::Other("Missing `data` line for notification".to_string())
+                })?;
+                let notification = json_backend::from_str::<Notification>(&data)?;
+                Event::Notification(notification)
+            },
+            "update" => {
+                let data =
+                    data.ok_or_else(|| Error::Other("Missing `data` line for update".to_string()))?;
+                let status = json_backend::from_str::<Status>(&data)?;
+                Event::Update(status)
+            },
+            "status.update" => {
+                let data = data.ok_or_else(|| {
+                    Error::Other("Missing `data` line for status.update".to_string())
+                })?;
+                let status = json_backend::from_str::<Status>(&data)?;
+                Event::StatusUpdate(status)
+            },
+            "delete" => {
+                let data =
+                    data.ok_or_else(|| Error::Other("Missing `data` line for delete".to_string()))?;
+                Event::Delete(data.parse()?)
+            },
+            "filters_changed" => Event::FiltersChanged,
+            "conversation" => {
+                let data = data.ok_or_else(|| {
+                    Error::Other("Missing `data` line for conversation".to_string())
+                })?;
+                let conversation = json_backend::from_str::<Conversation>(&data)?;
+                Event::Conversation(conversation)
+            },
+            _ => return Err(Error::Other(format!("Unknown event `{}`", event))),
+        };
+        Ok((event, raw))
+    }
+}
+
+/// Gives access to the fields of `data` directly on the client
+///
+/// Deprecated: this puts every field of `Data` into the client's namespace,
+/// so that adding one can break code calling a method of the same name. Use
+/// `base`, `token` or the `data` field instead. Only available with the
+/// `deref-data` feature, which is enabled by default for now.
+#[cfg(all(feature = "client", feature = "deref-data"))]
+impl<H: HttpSend> std::ops::Deref for Mastodon<H> {
+    type Target = Data;
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
+
+#[cfg(feature = "client")]
+/// Builder for a [`Mastodon`](struct.Mastodon.html) client.
+///
+/// Only `data` is required; everything else falls back to sensible defaults.
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::{prelude::*, retry::RetryPolicy, MastodonBuilder};
+/// # fn main() -> elefren::Result<()> {
+/// # let data = Data {
+/// #   base: "https://example.com".into(),
+/// #   client_id: "".into(),
+/// #   client_secret: "".into(),
+/// #   redirect: "".into(),
+/// #   token: "".into(),
+/// #   ..Default::default()
+/// # };
+/// let client = MastodonBuilder::new()
+///     .data(data)
+///     .user_agent("my-bot/1.0")
+///     .retry_policy(RetryPolicy::new(3))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct MastodonBuilder<H: HttpSend = HttpSender> {
+    client: Option<Client>,
+    http_sender: H,
+    data: Option<Data>,
+    user_agent: Option<String>,
+    language: Option<Language>,
+    default_headers: Vec<(String, String)>,
+    retry_policy: RetryPolicy,
+    redirect_policy: RedirectPolicy,
+    max_body_size: Option<usize>,
+    default_limit: Option<usize>,
+    refresh_on_unauthorized: bool,
+    on_unauthorized: Option<UnauthorizedCallback>,
+    base_url_override: Option<String>,
+    streaming: StreamingConfig,
+    clock: Option<Arc<dyn Clock>>,
+}
+
+#[cfg(feature = "client")]
+impl MastodonBuilder {
+    /// Create a new, empty builder
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+#[cfg(feature = "client")]
+impl<H: HttpSend> MastodonBuilder<H> {
+    /// Create a new, empty builder for a client that sends its requests
+    /// through `http_sender`
+    pub fn with_sender(http_sender: H) -> Self {
+        MastodonBuilder {
+            client: None,
+            http_sender,
+            data: None,
+            user_agent: None,
+            language: None,
+            default_headers: Vec::new(),
+            retry_policy: RetryPolicy::default(),
+            redirect_policy: RedirectPolicy::default(),
+            max_body_size: None,
+            default_limit: None,
+            refresh_on_unauthorized: false,
+            on_unauthorized: None,
+            base_url_override: None,
+            streaming: StreamingConfig::default(),
+            clock: None,
+        }
+    }
+
+    /// Use a preconfigured `reqwest` client, e.g. to set timeouts or a proxy
+    ///
+    /// The client's own redirect policy is used for it, see `redirect_policy`.
+    pub fn client(&mut self, client: Client) -> &mut Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// The instance and authentication data to use. Required.
+    pub fn data(&mut self, data: Data) -> &mut Self {
+        self.data = Some(data);
+        self
+    }
+
+    /// Send this `User-Agent` header with every request
+    pub fn user_agent<I: Into<String>>(&mut self, user_agent: I) -> &mut Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Send the header `name` with `value` with every request to the
+    /// instance, including the requests opening streaming connections, e.g.
+    /// the credentials of an authenticating proxy in front of it
+    ///
+    /// Headers the client sets itself, like `Authorization` and
+    /// `Content-Type`, take precedence.
+    /// Downloads with `download` don't get the header, as they usually go to
+    /// another host.
+    pub fn default_header<N: Into<String>, V: Into<String>>(
+        &mut self,
+        name: N,
+        value: V,
+    ) -> &mut Self {
+        self.default_headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Ask for responses in `language`, by sending it as the
+    /// `Accept-Language` header with every request
+    ///
+    /// The server then localizes its error messages and trends where it can.
+    /// Statuses posted without a language set are posted in `language`.
+    pub fn accept_language(&mut self, language: Language) -> &mut Self {
+        self.language = Some(language);
+        self
+    }
+
+    /// Retry failed requests according to `policy`. By default, requests
+    /// are not retried.
+    pub fn retry_policy(&mut self, policy: RetryPolicy) -> &mut Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Follow redirects according to `policy`. By default, only redirects to
+    /// the same origin are followed.
+    ///
+    /// This configures the client the builder creates; a client passed to
+    /// `client` keeps its own redirect policy, which by default follows up to
+    /// 10 redirects and drops the access token when the host changes.
+    pub fn redirect_policy(&mut self, policy: RedirectPolicy) -> &mut Self {
+        self.redirect_policy = policy;
+        self
+    }
+
+    /// Refuse to read response bodies larger than `bytes`, failing with
+    /// `Error::BodyTooLarge` instead. By default, bodies of any size are read.
+    ///
+    /// This keeps a misbehaving server from filling up the memory of a long
+    /// running bot. Bodies announcing a larger `Content-Length` aren't read
+    /// at all, others are read up to the limit. Downloads with `download`
+    /// aren't limited.
+    pub fn max_body_size(&mut self, bytes: usize) -> &mut Self {
+        self.max_body_size = Some(bytes);
+        self
+    }
+
+    /// Ask for `limit` results per page from the paged routes, unless the
+    /// `PageRequest` or `StatusesRequest` passed to them sets its own limit.
+    /// By default, the server's page size is used.
+    ///
+    /// Backfilling with a larger page size takes fewer requests. The server
+    /// caps the limit, at 40 or 80 depending on the route.
+    pub fn default_limit(&mut self, limit: usize) -> &mut Self {
+        self.default_limit = Some(limit);
+        self
+    }
+
+    /// When the server rejects the access token with a 401 and `data` has a
+    /// refresh token, refresh the access token with `Mastodon::refresh` and
+    /// send the request again. Off by default.
+    ///
+    /// Requests with a streaming body, such as media uploads from a reader,
+    /// can't be sent again, and fail with the 401 instead.
+    pub fn refresh_on_unauthorized(&mut self, refresh: bool) -> &mut Self {
+        self.refresh_on_unauthorized = refresh;
+        self
+    }
+
+    /// Call `callback` with the current data whenever the server rejects the
+    /// access token with a 401, e.g. because the user revoked it, so that the
+    /// application can log in again.
+    ///
+    /// If the callback returns new data, its access token is used from then
+    /// on, also by clones of the client, and the request is sent again. If it
+    /// returns `None`, the request fails with the 401. With
+    /// `refresh_on_unauthorized`, the callback is only called if refreshing
+    /// the token fails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// # use elefren::MastodonBuilder;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let data = Data::default();
+    /// let client = MastodonBuilder::new()
+    ///     .data(data)
+    ///     .on_unauthorized(|data| {
+    ///         eprintln!("the token for {} was revoked, please log in again", data.base);
+    ///         None
+    ///     })
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_unauthorized<F>(&mut self, callback: F) -> &mut Self
+    where
+        F: Fn(&Data) -> Option<Data> + Send + Sync + 'static,
+    {
+        self.on_unauthorized = Some(UnauthorizedCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Use `clock` for the current time and for waiting, e.g. between
+    /// retries, instead of the system's clock
+    ///
+    /// Meant for tests, which can pass a `ManualClock` to check timing
+    /// behaviour without actually waiting.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate elefren;
+    /// # extern crate chrono;
+    /// # use elefren::prelude::*;
+    /// # fn main() -> Result<(), elefren::Error> {
+    /// use elefren::{clock::ManualClock, MastodonBuilder};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new(chrono::Utc::now());
+    /// let data = Data {
+    ///     base: "https://example.com".into(),
+    ///     expires_at: Some(chrono::Utc::now() + chrono::Duration::hours(1)),
+    ///     ..Default::default()
+    /// };
+    /// let client = MastodonBuilder::new()
+    ///     .data(data)
+    ///     .clock(clock.clone())
+    ///     .build()?;
+    /// assert!(!client.is_token_expired());
+    /// clock.advance(Duration::from_secs(2 * 60 * 60));
+    /// assert!(client.is_token_expired());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn clock<C: Clock + 'static>(&mut self, clock: C) -> &mut Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Send requests to `base` instead of `data.base`, e.g. to reach the
+    /// instance through a proxy or a local test server. `data` is left
+    /// untouched, so it can still be saved and reused.
+    pub fn base_url_override<I: Into<String>>(&mut self, base: I) -> &mut Self {
+        self.base_url_override = Some(base.into());
+        self
+    }
+
+    /// Give up on a streaming connection when nothing has been received
+    /// for `timeout`, instead of waiting forever on a connection that died
+    /// silently. The stream then ends, just like when the server closes it.
+    ///
+    /// Mastodon sends a heartbeat every few seconds, so anything above a
+    /// minute is a safe choice.
+    #[cfg(feature = "websocket")]
+    pub fn streaming_read_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.streaming.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Enable TCP keepalive on streaming connections, sending the first probe
+    /// after the connection has been idle for `idle`. This keeps NAT mappings
+    /// alive and lets the OS notice dead peers.
+    #[cfg(feature = "websocket")]
+    pub fn streaming_keepalive(&mut self, idle: Duration) -> &mut Self {
+        self.streaming.keepalive = Some(idle);
+        self
+    }
+
+    /// Connect to `addr` instead of looking up `domain`, e.g. to test
+    /// against a local development instance under its real name
+    ///
+    /// The port of `addr` is ignored; connections go to the port of the URL,
+    /// as usual. This configures the client the builder creates, and
+    /// streaming connections; a client passed to `client` keeps its own
+    /// settings. Only unencrypted streaming connections can be redirected.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate elefren;
+    /// use elefren::{prelude::*, MastodonBuilder};
+    /// # fn main() -> elefren::Result<()> {
+    /// # let data = Data {
+    /// #   base: "http://mastodon.local:3000".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = MastodonBuilder::new()
+    ///     .data(data)
+    ///     .resolve("mastodon.local", ([127, 0, 0, 1], 3000).into())
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve<D: Into<String>>(&mut self, domain: D, addr: SocketAddr) -> &mut Self {
+        self.streaming
+            .resolve
+            .push((domain.into().to_lowercase(), addr));
+        self
+    }
+
+    /// Build the client
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::MissingField` if no `data` was provided or `data.base`
+    /// is empty, `Error::Url` or `Error::Other` if `data.base` or the base
+    /// URL override isn't a valid instance URL, an error if a default header
+    /// isn't a valid header, and `Error::Http` if the HTTP client can't be
+    /// created.
+    pub fn build(&self) -> Result<Mastodon<H>> {
+        Ok(if let Some(ref data) = self.data {
+            let mut data = data.clone();
+            let base = InstanceUrl::parse(&data.base)?;
+            data.base = base.to_string().into();
+            let base_url = match self.base_url_override {
+                Some(ref base_url_override) => InstanceUrl::parse(base_url_override)?,
+                None => base,
+            };
+            let mut default_headers = HeaderMap::new();
+            for (name, value) in &self.default_headers {
+                let (name, value) = parse_header(name, value)?;
+                default_headers.append(name, value);
+            }
+            let client = match self.client {
+                Some(ref client) => client.clone(),
+                None => {
+                    let mut builder = Client::builder().redirect(self.redirect_policy.to_reqwest());
+                    for (domain, addr) in &self.streaming.resolve {
+                        builder = builder.resolve(domain, *addr);
+                    }
+                    builder.build()?
+                },
+            };
+            Mastodon {
+                client,
+                http_sender: self.http_sender.clone(),
+                data: Arc::new(data),
+                user_agent: self.user_agent.clone(),
+                language: self.language,
+                default_headers,
+                retry_policy: self.retry_policy,
+                redirect_policy: self.redirect_policy,
+                max_body_size: self.max_body_size,
+                default_limit: self.default_limit,
+                base_url,
+                #[cfg(feature = "websocket")]
+                streaming: self.streaming.clone(),
+                rate_limit: Default::default(),
+                last_response: Default::default(),
+                stats: Default::default(),
+                me: Default::default(),
+                refreshed: Default::default(),
+                refresh_on_unauthorized: self.refresh_on_unauthorized,
+                on_unauthorized: self.on_unauthorized.clone(),
+                clock: self.clock.clone().unwrap_or_else(|| Arc::new(SystemClock)),
+                timeout: None,
+            }
+        } else {
+            return Err(Error::MissingField("missing field 'data'"));
+        })
+    }
+}
+
+#[cfg(feature = "client")]
+/// Client that can make unauthenticated calls to a mastodon instance
+///
+/// Like `Mastodon`, it is `Send` and `Sync` as long as its `HttpSend` is.
+#[derive(Clone, Debug)]
+pub struct MastodonUnauth<H: HttpSend = HttpSender> {
+    client: Client,
+    http_sender: H,
+    base: InstanceUrl,
+    default_headers: HeaderMap,
+}
+
+#[cfg(feature = "client")]
+impl MastodonUnauth {
+    /// Create a new unauthenticated client
+    pub fn new(base: &str) -> Result<MastodonUnauth> {
+        MastodonUnauth::with_sender(base, HttpSender)
+    }
+}
+
+#[cfg(feature = "client")]
+impl<H: HttpSend> MastodonUnauth<H> {
+    /// Create a new unauthenticated client that sends its requests through
+    /// `http_sender`
+    ///
+    /// `base` may be a full URL such as `http://localhost:3000`; without a
+    /// scheme, `https://` is assumed.
+    pub fn with_sender(base: &str, http_sender: H) -> Result<MastodonUnauth<H>> {
+        Ok(MastodonUnauth {
+            client: Client::new(),
+            http_sender,
+            base: InstanceUrl::parse(base)?,
+            default_headers: HeaderMap::new(),
+        })
+    }
+
+    /// Send the header `name` with `value` with every request to the
+    /// instance, e.g. the credentials of an authenticating proxy in front of
+    /// it, like `MastodonBuilder::default_header`
+    ///
+    /// # Errors
+    ///
+    /// If `name` or `value` isn't a valid header name or value.
+    pub fn default_header<N: Into<String>, V: Into<String>>(
+        mut self,
+        name: N,
+        value: V,
+    ) -> Result<Self> {
+        let (name, value) = parse_header(&name.into(), &value.into())?;
+        self.default_headers.append(name, value);
+        Ok(self)
+    }
+
+    /// Send all requests through `client` instead of a new one, e.g. to
+    /// share a connection pool with a `Mastodon` client
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// The base URL of the instance
+    pub fn base(&self) -> &InstanceUrl {
+        &self.base
+    }
+
+    fn route(&self, url: &str) -> Result<url::Url> {
+        self.base.join(url)
+    }
+
+    fn send_blocking(&self, req: RequestBuilder) -> Result<Response> {
+        let mut req = req.build()?;
+        add_default_headers(req.headers_mut(), &self.default_headers);
+        self.http_sender.execute(&self.client, req)
+    }
+
+    /// Get a stream of the events of `stream`. Only public streams can be
+    /// read without authentication.
+    #[cfg(feature = "websocket")]
+    pub fn streaming(&self, stream: StreamKind) -> Result<EventReader<WebSocket>> {
+        connect_streaming(
+            &self.client,
+            self.route("/api/v1/streaming")?,
+            stream,
+            None,
+            &self.default_headers,
+            &StreamingConfig::default(),
+        )
+    }
+
+    /// Get a stream of the public timeline
+    #[cfg(feature = "websocket")]
+    pub fn streaming_public(&self) -> Result<EventReader<WebSocket>> {
+        self.streaming(StreamKind::Public {
+            local: false,
+            media_only: false,
+        })
+    }
+
+    /// Get a stream of the local timeline
+    #[cfg(feature = "websocket")]
+    pub fn streaming_local(&self) -> Result<EventReader<WebSocket>> {
+        self.streaming(StreamKind::Public {
+            local: true,
+            media_only: false,
+        })
+    }
+
+    /// Get a stream of all public statuses for a particular hashtag
+    #[cfg(feature = "websocket")]
+    pub fn streaming_public_hashtag(&self, hashtag: &str) -> Result<EventReader<WebSocket>> {
+        self.streaming_hashtag(hashtag, &HashtagRequest::new())
+    }
+
+    /// Get a stream of the statuses for a particular hashtag, filtered by
+    /// `options`
+    #[cfg(feature = "websocket")]
+    pub fn streaming_hashtag(
+        &self,
+        hashtag: &str,
+        options: &HashtagRequest,
+    ) -> Result<EventReader<WebSocket>> {
+        self.streaming(StreamKind::hashtag(hashtag, options))
+    }
+}
+
+#[cfg(feature = "client")]
+impl<H: HttpSend> MastodonUnauthenticated<H> for MastodonUnauth<H> {
+    /// GET /api/v1/statuses/:id
+    fn get_status(&self, id: &str) -> Result<Status> {
+        let route = self.route(&format!("/api/v1/statuses/{}", routes::encode_segment(id)?))?;
+        let response = self.send_blocking(self.client.get(route))?;
+        deserialise_blocking(response)
+    }
+
+    /// GET /api/v1/statuses/:id/context
+    fn get_context(&self, id: &str) -> Result<Context> {
+        let route = self.route(&format!(
+            "/api/v1/statuses/{}/context",
+            routes::encode_segment(id)?
+        ))?;
+        let response = self.send_blocking(self.client.get(route))?;
+        deserialise_blocking(response)
+    }
+
+    /// GET /api/v1/statuses/:id/card
+    ///
+    /// This route was removed in Mastodon 3.0. If the server doesn't know it,
+    /// the card embedded in the status is returned instead.
+    fn get_card(&self, id: &str) -> Result<Card> {
+        let route = self.route(&format!(
+            "/api/v1/statuses/{}/card",
+            routes::encode_segment(id)?
+        ))?;
+        let response = self.send_blocking(self.client.get(route))?;
+        if response.status() != StatusCode::NOT_FOUND {
+            return deserialise_blocking(response);
+        }
+        embedded_card(self.get_status(id)?)
+    }
+
+    /// GET /api/v1/custom_emojis
+    fn custom_emojis(&self) -> Result<Vec<Emoji>> {
+        let route = self.route("/api/v1/custom_emojis")?;
+        let response = self.send_blocking(self.client.get(route))?;
+        deserialise_blocking(response)
+    }
+
+    /// GET /api/v1/instance/rules, e.g. to show them before signing up
+    fn instance_rules(&self) -> Result<Vec<Rule>> {
+        let route = self.route("/api/v1/instance/rules")?;
+        let response = self.send_blocking(self.client.get(route))?;
+        deserialise_blocking(response)
+    }
+
+    /// GET /api/v1/directory
+    fn directory<I: Into<Option<DirectoryRequest>>>(&self, request: I) -> Result<Vec<Account>> {
+        let route = self.route(&routes::directory(request.into())?)?;
+        let response = self.send_blocking(self.client.get(route))?;
+        deserialise_blocking(response)
+    }
+
+    /// GET /api/v1/trends
+    fn trends<I: Into<Option<usize>>>(&self, limit: I) -> Result<Vec<Tag>> {
+        let route = self.route(&routes::trends(limit.into()))?;
+        let response = self.send_blocking(self.client.get(route))?;
+        deserialise_blocking(response)
+    }
+}
+
+#[cfg(feature = "client")]
+// Builds the multipart form for uploading the media described by `media_builder`
+fn media_form(media_builder: MediaBuilder) -> Result<reqwest::blocking::multipart::Form> {
+    use reqwest::blocking::multipart::{Form, Part};
+
+    let mut form_data = match media_builder.data {
+        Some(data) => {
+            let mut part = Part::bytes(data).file_name(media_builder.file.into_owned());
+            if let Some(mime_type) = media_builder.mime_type {
+                part = part.mime_str(&mime_type)?;
+            }
+            Form::new().part("file", part)
+        },
+        None => Form::new().file("file", media_builder.file.as_ref())?,
+    };
+
+    if let Some(description) = media_builder.description {
+        form_data = form_data.text("description", description);
+    }
+
+    if let Some(focus) = media_builder.focus {
+        let string = format!("{},{}", focus.0, focus.1);
+        form_data = form_data.text("focus", string);
+    }
+
+    Ok(form_data)
+}
+
+#[cfg(feature = "client")]
+// The body of the routes adding accounts to and removing them from a list
+fn account_ids_body(account_ids: &[impl AsRef<str>]) -> serde_json::Value {
+    let account_ids: Vec<&str> = account_ids.iter().map(|id| id.as_ref()).collect();
+    serde_json::json!({ "account_ids": account_ids })
+}
+
+#[cfg(feature = "client")]
+// Builds the multipart form for `update_credentials`, uploading the avatar
+// and header images from their paths
+fn credentials_form(builder: UpdateCredsRequest) -> Result<reqwest::blocking::multipart::Form> {
+    let credentials = builder.build()?;
+    let mut form = reqwest::blocking::multipart::Form::new();
+    for (name, value) in credentials.form_fields() {
+        form = form.text(name, value);
+    }
+    if let Some(avatar) = credentials.avatar {
+        form = form.file("avatar", avatar)?;
+    }
+    if let Some(header) = credentials.header {
+        form = form.file("header", header)?;
+    }
+    Ok(form)
+}
+
+#[cfg(feature = "client")]
+// How often `post_status_with_media` checks whether media has been processed
+const MEDIA_PROCESSING_POLL: Duration = Duration::from_secs(1);
+
+#[cfg(feature = "client")]
+// How long `post_status_with_media` waits for media to be processed
+const MEDIA_PROCESSING_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[cfg(feature = "client")]
+// How long `authorize_all_follow_requests` waits between two requests
+const FOLLOW_REQUEST_PACING: Duration = Duration::from_secs(1);
+
+#[cfg(feature = "client")]
+// Checks that `domain` looks like a domain name, i.e. dot-separated labels of
+// letters, digits and hyphens, so that e.g. a URL isn't blocked by mistake
+fn validate_domain(domain: &str) -> Result<()> {
+    let valid_label = |label: &str| {
+        !label.is_empty()
+            && label.len() <= 63
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+    };
+    if domain.is_empty() || domain.len() > 253 || !domain.split('.').all(valid_label) {
+        return Err(Error::Other(format!("invalid domain name '{}'", domain)));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "client")]
+// Newer servers only provide a status' card embedded in the status itself
+fn embedded_card(status: Status) -> Result<Card> {
+    status
+        .card
+        .ok_or_else(|| Error::Client(StatusCode::NOT_FOUND.into()))
+}
+
+#[cfg(feature = "websocket")]
+// Connects to `stream` through the streaming API at `url`. The instance may redirect streaming
+// requests to a different host, so the final URL is resolved with a plain HTTP
+// request through `client` before switching to the websocket scheme. That
+// request is sent without `token`, which is only added to the final URL, so
+// that it doesn't depend on the client's redirect policy.
+fn connect_streaming(
+    client: &Client,
+    mut url: url::Url,
+    stream: StreamKind,
+    token: Option<&str>,
+    default_headers: &HeaderMap,
+    config: &StreamingConfig,
+) -> Result<EventReader<WebSocket>> {
+    url.query_pairs_mut().extend_pairs(stream.query_pairs());
+    let mut request = client.get(url).build()?;
+    add_default_headers(request.headers_mut(), default_headers);
+    let response = client.execute(request)?;
+    let mut url = streaming_url(&response)?;
+    set_websocket_scheme(&mut url)?;
+    let connected_url = url.clone();
+    if let Some(token) = token {
+        url.query_pairs_mut().append_pair("access_token", token);
+    }
+
+    let mut request = url.as_str().into_client_request()?;
+    add_default_headers(request.headers_mut(), default_headers);
+    let client = match config.resolved(&url) {
+        Some(addr) => {
+            if url.scheme() == "wss" {
+                return Err(Error::Other(format!(
+                    "can't redirect the encrypted streaming connection to {}",
+                    addr
+                )));
+            }
+            let stream = TungsteniteStream::Plain(TcpStream::connect(addr)?);
+            match tungstenite::client(request, stream) {
+                Ok((client, _)) => client,
+                Err(HandshakeError::Failure(e)) => return Err(handshake_error(e, token.is_some())),
+                Err(HandshakeError::Interrupted(_)) => {
+                    return Err(Error::Other("websocket handshake interrupted".to_string()))
+                },
+            }
+        },
+        None => {
+            tungstenite::connect(request)
+                .map_err(|e| handshake_error(e, token.is_some()))?
+                .0
+        },
+    };
+    let socket = match client.get_ref() {
+        TungsteniteStream::Plain(socket) => socket,
+        TungsteniteStream::Tls(stream) => stream.get_ref(),
+    };
+    config.apply(socket)?;
+
+    Ok(EventReader {
+        stream: WebSocket(client),
+        kind: Some(stream),
+        url: Some(connected_url),
+        connected_at: chrono::Utc::now(),
+    })
+}
+
+#[cfg(feature = "client")]
+// Socket options for streaming connections
+#[derive(Clone, Debug, Default, PartialEq)]
+struct StreamingConfig {
+    #[cfg(feature = "websocket")]
+    read_timeout: Option<Duration>,
+    #[cfg(feature = "websocket")]
+    keepalive: Option<Duration>,
+    // addresses to use instead of looking up a domain, set with `resolve`
+    resolve: Vec<(String, SocketAddr)>,
+}
+
+#[cfg(feature = "websocket")]
+impl StreamingConfig {
+    fn apply(&self, socket: &TcpStream) -> Result<()> {
+        socket.set_read_timeout(self.read_timeout)?;
+        if let Some(idle) = self.keepalive {
+            let keepalive = socket2::TcpKeepalive::new().with_time(idle);
+            socket2::SockRef::from(socket).set_tcp_keepalive(&keepalive)?;
+        }
+        Ok(())
+    }
+
+    // The address to connect to for `url`, if its domain is overridden
+    fn resolved(&self, url: &url::Url) -> Option<SocketAddr> {
+        let host = url.host_str()?.to_lowercase();
+        let port = url.port_or_known_default()?;
+        self.resolve
+            .iter()
+            .find(|(domain, _)| *domain == host)
+            .map(|(_, addr)| SocketAddr::new(addr.ip(), port))
+    }
+}
+
+#[cfg(feature = "client")]
+fn parse_header(name: &str, value: &str) -> Result<(HeaderName, HeaderValue)> {
+    let name = HeaderName::from_bytes(name.as_bytes())
+        .map_err(|_| Error::Other(format!("invalid header name: {}", name)))?;
+    let value = HeaderValue::from_str(value)
+        .map_err(|_| Error::Other(format!("invalid value for header {}", name)))?;
+    Ok((name, value))
+}
+
+#[cfg(feature = "client")]
+// Adds the headers in `defaults` that aren't in `headers` yet
+fn add_default_headers(headers: &mut HeaderMap, defaults: &HeaderMap) {
+    for name in defaults.keys() {
+        if !headers.contains_key(name) {
+            for value in defaults.get_all(name) {
+                headers.append(name.clone(), value.clone());
+            }
+        }
+    }
+}
+
+#[cfg(feature = "websocket")]
+// The URL of the streaming server, following a redirect the client's redirect
+// policy refused
+fn streaming_url(response: &Response) -> Result<url::Url> {
+    if !response.status().is_redirection() {
+        return Ok(response.url().clone());
+    }
+    match response.headers().get(LOCATION) {
+        Some(location) => Ok(response.url().join(location.to_str()?)?),
+        None => Ok(response.url().clone()),
+    }
+}
+
+#[cfg(feature = "websocket")]
+// Turns the server refusing the websocket handshake into the errors other
+// requests fail with, rather than an opaque websocket error. Streams other
+// than the public ones need an access token with the `read` scope.
+fn handshake_error(error: tungstenite::Error, authenticated: bool) -> Error {
+    let status = match error {
+        tungstenite::Error::Http(status) => errors::HttpStatus::from(status.as_u16()),
+        error => return error.into(),
+    };
+    match status.as_u16() {
+        401 if !authenticated => Error::AccessTokenRequired,
+        401 | 403 => {
+            log::error!(
+                "the streaming API refused the access token with {}; check that it's still \
+                 valid and has the `read` scope",
+                status
+            );
+            Error::Client(status)
+        },
+        _ if status.is_server_error() => Error::Server(status),
+        _ => Error::Client(status),
+    }
+}
+
+#[cfg(feature = "websocket")]
+fn set_websocket_scheme(url: &mut url::Url) -> Result<()> {
+    let new_scheme = match url.scheme() {
+        "http" => "ws",
+        "https" => "wss",
+        x => return Err(Error::Other(format!("Bad URL scheme: {}", x))),
+    };
+    url.set_scheme(new_scheme)
+        .map_err(|_| Error::Other("Bad URL scheme!".to_string()))
+}
+
+#[cfg(feature = "client")]
+// The largest body `deserialise_blocking` reads, attached to responses by
+// `send_blocking` if the builder was given one
+#[derive(Debug, Clone, Copy)]
+struct BodyLimit(usize);
+
+#[cfg(feature = "client")]
+// Reads the body of `response`, unless it's larger than `limit`
+fn read_limited(mut response: Response, limit: usize) -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    let too_large = |response: &Response| Error::BodyTooLarge {
+        url: response.url().to_string(),
+        limit,
+    };
+    if matches!(response.content_length(), Some(len) if len > limit as u64) {
+        return Err(too_large(&response));
+    }
+    let mut body = Vec::new();
+    (&mut response)
+        .take(limit as u64 + 1)
+        .read_to_end(&mut body)?;
+    if body.len() > limit {
+        return Err(too_large(&response));
+    }
+    Ok(body)
+}
+
+#[cfg(feature = "client")]
+// Convert the HTTP response body from JSON. Pass up deserialization errors
+// transparently.
+fn deserialise_blocking<T: for<'de> serde::Deserialize<'de>>(response: Response) -> Result<T> {
+    let status = response.status();
+    deserialise_blocking_with(response, |body| {
+        json_backend::from_slice(body).or_else(|e| {
+            // routes without a result answer with `{}`, or nothing at all
+            if status.is_success() && matches!(String::from_utf8_lossy(body).trim(), "" | "{}") {
+                if let Ok(t) = serde_json::from_value(serde_json::Value::Null) {
+                    return Ok(t);
+                }
+            }
+            Err(e)
+        })
+    })
+}
+
+#[cfg(feature = "client")]
+// Reads the HTTP response body, within the `BodyLimit` and counting it in the
+// stats, and converts it with `parse`. If that fails, the body is parsed as
+// an API error instead.
+fn deserialise_blocking_with<T>(
+    response: Response,
+    parse: impl FnOnce(&[u8]) -> serde_json::Result<T>,
+) -> Result<T> {
+    let url = response.url().to_string();
+    let status = response.status();
+    // attached by `send_blocking`, to count the bytes read
+    let stats = response.extensions().get::<Arc<StatsCounters>>().cloned();
+    let count_bytes = |bytes: usize| {
+        if let Some(ref stats) = stats {
+            stats.count_bytes(bytes as u64);
+        }
+    };
+    // error pages of reverse proxies, e.g. nginx's 502 page, are HTML, and
+    // trying to parse them only hides the status behind a parse error
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    if let Some(ref content_type) = content_type {
+        if (status.is_client_error() || status.is_server_error())
+            && !json_backend::is_json_content_type(content_type)
+        {
+            // only the start is needed for the snippet
+            let mut head = Vec::new();
+            let _ = io::Read::read_to_end(&mut io::Read::take(response, 1024), &mut head);
+            count_bytes(head.len());
+            let snippet = Error::snippet(&String::from_utf8_lossy(&head));
+            log::error!(
+                "{} answered {} with {}: {}",
+                url,
+                status,
+                content_type,
+                snippet
+            );
+            return Err(Error::ErrorPage {
+                url,
+                status: status.into(),
+                content_type: content_type.clone(),
+                snippet,
+            });
+        }
+    }
+    // the body is deserialized straight from its bytes, which is noticeably
+    // faster for large pages than decoding it into a `String` first; it's
+    // only turned into text for logging and errors
+    let bytes;
+    let limited;
+    let body: &[u8] = match response.extensions().get::<BodyLimit>() {
+        Some(&BodyLimit(limit)) => {
+            limited = read_limited(response, limit)?;
+            &limited
+        },
+        None => {
+            bytes = response.bytes()?;
+            &bytes
+        },
+    };
+    count_bytes(body.len());
+
+    match parse(body) {
+        Ok(t) => {
+            log::debug!("{}", String::from_utf8_lossy(body));
+            Ok(t)
+        },
+        // If deserializing into the desired type fails try again to
+        // see if this is an error response.
+        Err(e) => {
+            let body = String::from_utf8_lossy(body);
+            log::error!("{}", body);
+            if let Ok(error) = serde_json::from_str(&body) {
+                return Err(Error::Api(error));
+            }
+            Err(Error::deserialize(url, status, &body, e))
+        },
+    }
+}
+
+// Bots share the clients and move the streams between threads; this fails to
+// compile if a change to one of them would break that
+#[cfg(feature = "client")]
+const _: fn() = || {
+    fn send_sync<T: Send + Sync>() {}
+    send_sync::<Mastodon>();
+    send_sync::<MastodonUnauth>();
+    send_sync::<MastodonBuilder>();
+    send_sync::<registration::Registered>();
+    send_sync::<Page<Status>>();
+};
+
+#[cfg(feature = "websocket")]
+const _: fn() = || {
+    fn send_sync<T: Send + Sync>() {}
+    fn send<T: Send>() {}
+    send::<EventReader<WebSocket>>();
+    send_sync::<EventReceiver>();
+    send::<firehose::Firehose>();
+};
+
+#[cfg(all(test, feature = "client"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_requires_data() {
+        assert!(matches!(
+            MastodonBuilder::new().build(),
+            Err(Error::MissingField(_))
+        ));
+    }
+
+    #[test]
+    fn test_builder_validates_base() {
+        assert!(matches!(
+            MastodonBuilder::new().data(Data::default()).build(),
+            Err(Error::MissingField("base"))
+        ));
+        let data = Data {
+            base: "https://exa mple.com".into(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            MastodonBuilder::new().data(data).build(),
+            Err(Error::Url(_))
+        ));
+    }
+
+    #[test]
+    fn test_try_from_data() {
+        assert!(matches!(
+            Mastodon::try_from_data(Data::default()),
+            Err(Error::MissingField("base"))
+        ));
+        let client = Mastodon::try_from_data(Data {
+            base: "Example.com/".into(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(client.data.base, "https://example.com");
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid `Data` for a client")]
+    fn test_from_invalid_data_panics() {
+        let _ = Mastodon::from(Data::default());
+    }
+
+    #[test]
+    fn test_builder_base_url_override() {
+        let data = Data {
+            base: "https://example.com".into(),
+            ..Default::default()
+        };
+        let client = MastodonBuilder::new()
+            .data(data)
+            .base_url_override("http://127.0.0.1:8080/")
+            .build()
+            .unwrap();
+        assert_eq!(
+            client.route("/api/v1/instance"),
+            "http://127.0.0.1:8080/api/v1/instance"
+        );
+        assert_eq!(client.data.base, "https://example.com");
+    }
+
+    #[test]
+    fn test_builder_normalizes_base() {
+        let data = Data {
+            base: "Example.com/".into(),
+            ..Default::default()
+        };
+        let client = MastodonBuilder::new().data(data).build().unwrap();
+        assert_eq!(client.data.base, "https://example.com");
+        assert_eq!(
+            client.route("/api/v1/instance"),
+            "https://example.com/api/v1/instance"
+        );
+
+        let data = Data {
+            base: "ftp://example.com".into(),
+            ..Default::default()
+        };
+        assert!(MastodonBuilder::new().data(data).build().is_err());
+    }
+
+    #[test]
+    fn test_refresh_on_unauthorized() {
+        let data = Data {
+            base: mockito::server_url().into(),
+            client_id: "the-id".into(),
+            token: "old".into(),
+            refresh_token: Some("refresh".into()),
+            ..Default::default()
+        };
+        let client = MastodonBuilder::new()
+            .data(data)
+            .refresh_on_unauthorized(true)
+            .build()
+            .unwrap();
+        let rejected = mockito::mock("GET", "/api/v1/lists")
+            .match_header("authorization", "Bearer old")
+            .with_status(401)
+            .with_body(r#"{"error":"The access token expired"}"#)
+            .create();
+        let token = mockito::mock("POST", "/oauth/token")
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("grant_type".into(), "refresh_token".into()),
+                mockito::Matcher::UrlEncoded("refresh_token".into(), "refresh".into()),
+                mockito::Matcher::UrlEncoded("client_id".into(), "the-id".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"access_token":"new","created_at":0,"expires_in":3600}"#)
+            .create();
+        let accepted = mockito::mock("GET", "/api/v1/lists")
+            .match_header("authorization", "Bearer new")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+
+        assert!(client.get_lists().unwrap().is_empty());
+        rejected.assert();
+        token.assert();
+        accepted.assert();
+        // clones share the refreshed data rather than copying it
+        assert!(Arc::ptr_eq(
+            &client.latest_data(),
+            &client.clone().latest_data()
+        ));
+        let data = client.clone().current_data();
+        assert_eq!(data.token, "new");
+        assert_eq!(data.refresh_token.as_deref(), Some("refresh"));
+        assert_eq!(data.expires_at.unwrap().timestamp(), 3600);
+        assert_eq!(client.data.token, "old");
+    }
+
+    #[test]
+    fn test_is_token_expired() {
+        use crate::clock::ManualClock;
+        use chrono::TimeZone;
+
+        let expires_at = chrono::Utc.with_ymd_and_hms(2023, 5, 1, 12, 0, 0).unwrap();
+        let clock = ManualClock::new(expires_at - chrono::Duration::seconds(1));
+        let mut data = Data {
+            base: "https://example.com".into(),
+            token: "token".into(),
+            expires_at: Some(expires_at),
+            ..Default::default()
+        };
+        let client = MastodonBuilder::new()
+            .data(data.clone())
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        assert!(!client.is_token_expired());
+        clock.advance(Duration::from_secs(1));
+        assert!(client.is_token_expired());
+
+        data.expires_at = None;
+        let client = MastodonBuilder::new()
+            .data(data)
+            .clock(clock)
+            .build()
+            .unwrap();
+        assert!(!client.is_token_expired());
+    }
+
+    #[test]
+    fn test_on_unauthorized() {
+        let data = Data {
+            base: mockito::server_url().into(),
+            token: "revoked".into(),
+            ..Default::default()
+        };
+        let calls = Arc::new(Mutex::new(0));
+        let counter = calls.clone();
+        let client = MastodonBuilder::new()
+            .data(data)
+            .on_unauthorized(move |data| {
+                *counter.lock().unwrap() += 1;
+                Some(Data {
+                    token: "again".into(),
+                    ..data.clone()
+                })
+            })
+            .build()
+            .unwrap();
+        let rejected = mockito::mock("GET", "/api/v1/filters")
+            .match_header("authorization", "Bearer revoked")
+            .with_status(401)
+            .with_body(r#"{"error":"The access token was revoked"}"#)
+            .create();
+        let accepted = mockito::mock("GET", "/api/v1/filters")
+            .match_header("authorization", "Bearer again")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+
+        assert!(client.get_filters().unwrap().is_empty());
+        rejected.assert();
+        accepted.assert();
+        assert_eq!(*calls.lock().unwrap(), 1);
+        assert_eq!(client.token(), "again");
+    }
+
+    #[test]
+    fn test_refresh_without_refresh_token() {
+        let client = mock_client();
+        assert!(matches!(client.refresh(), Err(Error::Other(_))));
+    }
+
+    #[test]
+    fn test_clones_share_data() {
+        let client = mock_client();
+        let clone = client.clone();
+        assert!(Arc::ptr_eq(&client.data, &clone.data));
+    }
+
+    #[test]
+    fn test_base_and_token() {
+        let data = Data {
+            base: "https://example.com".into(),
+            token: "token".into(),
+            ..Default::default()
+        };
+        let client = MastodonBuilder::new()
+            .data(data)
+            .base_url_override("https://proxy.example.com")
+            .build()
+            .unwrap();
+        assert_eq!(client.base().as_str(), "https://proxy.example.com");
+        assert_eq!(client.data.base, "https://example.com");
+        assert_eq!(client.token(), "token");
+        // until the token is refreshed, it's read from `data` itself
+        assert!(Arc::ptr_eq(&client.latest_data(), &client.data));
+    }
+
+    #[test]
+    fn test_default_limit() {
+        let data = Data {
+            base: mockito::server_url().into(),
+            token: "token".into(),
+            ..Default::default()
+        };
+        let client = MastodonBuilder::new()
+            .data(data)
+            .default_limit(80)
+            .build()
+            .unwrap();
+        let mock = mockito::mock("GET", "/api/v1/mutes?limit=80")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        client.mutes().unwrap();
+        mock.assert();
+
+        // a limit set on the request wins
+        let mock = mockito::mock("GET", "/api/v1/accounts/42/followers?limit=5")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        client
+            .followers_with("42", &PageRequest::new().limit(5))
+            .unwrap();
+        mock.assert();
+
+        // including requests with options of their own
+        let mock = mockito::mock("GET", "/api/v1/accounts/42/statuses?only_media=1&limit=80")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        client
+            .statuses("42", StatusesRequest::new().only_media())
+            .unwrap();
+        mock.assert();
+        let mock = mockito::mock("GET", "/api/v1/accounts/42/statuses?limit=5")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        client
+            .statuses("42", StatusesRequest::new().limit(5))
+            .unwrap();
+        mock.assert();
+    }
+
+    #[test]
+    fn test_max_body_size() {
+        let data = Data {
+            base: mockito::server_url().into(),
+            token: "token".into(),
+            ..Default::default()
+        };
+        let status = test_fixtures::STATUS;
+        let client = MastodonBuilder::new()
+            .data(data)
+            .max_body_size(status.len())
+            .build()
+            .unwrap();
+        let mock = mockito::mock("GET", "/api/v1/statuses/110")
+            .with_header("content-type", "application/json")
+            .with_body(status)
+            .create();
+        assert_eq!(client.get_status("110").unwrap().id, "110");
+        mock.assert();
+
+        let mock = mockito::mock("GET", "/api/v1/statuses/111")
+            .with_header("content-type", "application/json")
+            .with_body(format!("{} ", status))
+            .create();
+        let err = client.get_status("111").unwrap_err();
+        assert!(matches!(err, Error::BodyTooLarge { limit, .. } if limit == status.len()));
+        mock.assert();
+
+        // without a `Content-Length`, the body is read up to the limit
+        let mock = mockito::mock("GET", "/api/v1/statuses/112")
+            .with_header("content-type", "application/json")
+            .with_body_from_fn(|w| {
+                for _ in 0..1000 {
+                    w.write_all(b"                ")?;
+                }
+                Ok(())
+            })
+            .create();
+        let err = client.get_status("112").unwrap_err();
+        assert!(matches!(err, Error::BodyTooLarge { ref url, .. } if url.ends_with("/112")));
+        mock.assert();
+    }
+
+    #[test]
+    fn test_post_status_with_media() {
+        let client = mock_client();
+        let attachment = |url: serde_json::Value| {
+            serde_json::json!({
+                "id": "22",
+                "type": "image",
+                "url": url,
+                "preview_url": null,
+                "description": "a lake",
+            })
+            .to_string()
+        };
+        let upload = mockito::mock("POST", "/api/v2/media")
+            .match_body(mockito::Matcher::Regex("a lake".into()))
+            .with_status(202)
+            .with_header("content-type", "application/json")
+            .with_body(attachment(serde_json::Value::Null))
+            .create();
+        let processed = mockito::mock("GET", "/api/v1/media/22")
+            .with_header("content-type", "application/json")
+            .with_body(attachment("https://example.com/lake.jpg".into()))
+            .create();
+        let post = mockito::mock("POST", "/api/v1/statuses")
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({"status": "the view", "media_ids": ["22"]}),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(test_fixtures::STATUS)
+            .create();
+        let status = StatusBuilder::new().status("the view").build().unwrap();
+        let media = MediaBuilder::from_bytes(b"jpeg".to_vec(), "lake.jpg".into())
+            .description("a lake".into());
+        assert_eq!(
+            client
+                .post_status_with_media(status, vec![media])
+                .unwrap()
+                .id,
+            "110"
+        );
+        upload.assert();
+        processed.assert();
+        post.assert();
+    }
+
+    #[test]
+    fn test_html_error_page() {
+        let client = mock_client();
+        let mock = mockito::mock("GET", "/api/v1/statuses/110")
+            .with_status(502)
+            .with_header("content-type", "text/html")
+            .with_body("<html><body><h1>502 Bad Gateway</h1></body></html>")
+            .create();
+        let err = client.get_status("110").unwrap_err();
+        match err {
+            Error::ErrorPage {
+                status,
+                content_type,
+                snippet,
+                ..
+            } => {
+                assert_eq!(status.as_u16(), 502);
+                assert_eq!(content_type, "text/html");
+                assert_eq!(
+                    snippet,
+                    "<html><body><h1>502 Bad Gateway</h1></body></html>"
+                );
+            },
+            err => panic!("expected Error::ErrorPage, got {:?}", err),
+        }
+        mock.assert();
+
+        // JSON errors are still read for their message
+        let mock = mockito::mock("GET", "/api/v1/statuses/111")
+            .with_status(404)
+            .with_header("content-type", "application/json; charset=utf-8")
+            .with_body(r#"{"error": "Record not found"}"#)
+            .create();
+        let err = client.get_status("111").unwrap_err();
+        assert!(matches!(err, Error::Api(_)));
+        mock.assert();
+    }
+
+    #[test]
+    #[cfg(feature = "language")]
+    fn test_accept_language() {
+        let data = Data {
+            base: mockito::server_url().into(),
+            token: "token".into(),
+            ..Default::default()
+        };
+        let client = MastodonBuilder::new()
+            .data(data)
+            .accept_language(Language::Deu)
+            .build()
+            .unwrap();
+        assert_eq!(client.language(), Some(Language::Deu));
+        let mock = mockito::mock("POST", "/api/v1/statuses")
+            .match_header("accept-language", "de")
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({"status": "hallo", "language": "deu"}),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(test_fixtures::STATUS)
+            .create();
+        let status = StatusBuilder::new().status("hallo").build().unwrap();
+        client.new_status(status).unwrap();
+        mock.assert();
+        drop(mock);
+
+        // a language set on the status is kept
+        let mock = mockito::mock("POST", "/api/v1/statuses")
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({"language": "eng"}),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(test_fixtures::STATUS)
+            .create();
+        let status = StatusBuilder::new()
+            .status("hello")
+            .language(Language::Eng)
+            .build()
+            .unwrap();
+        client.new_status(status).unwrap();
+        mock.assert();
+    }
+
+    #[test]
+    fn test_default_header() {
+        let data = Data {
+            base: mockito::server_url().into(),
+            token: "token".into(),
+            ..Default::default()
+        };
+        let client = MastodonBuilder::new()
+            .data(data.clone())
+            .default_header("CF-Access-Client-Id", "abc")
+            .default_header("Authorization", "Basic proxy")
+            .build()
+            .unwrap();
+        let mock = mockito::mock("GET", "/api/v1/custom_emojis")
+            .match_header("cf-access-client-id", "abc")
+            .match_header("authorization", "Bearer token")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        client.get_emojis().unwrap();
+        mock.assert();
+        drop(mock);
+
+        let mock = mockito::mock("GET", "/api/v1/statuses/110")
+            .match_header("cf-access-client-id", "abc")
+            .with_header("content-type", "application/json")
+            .with_body(test_fixtures::STATUS)
+            .create();
+        client.unauthenticated().get_status("110").unwrap();
+        mock.assert();
+
+        let invalid = MastodonBuilder::new()
+            .data(data)
+            .default_header("bad name", "abc")
+            .build();
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_base_with_path() {
+        let mock = mockito::mock("GET", "/masto/api/v1/custom_emojis")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        let data = Data {
+            base: format!("{}/masto/", mockito::server_url()).into(),
+            ..Default::default()
+        };
+        let client = MastodonBuilder::new().data(data).build().unwrap();
+        assert_eq!(client.data.base, format!("{}/masto", mockito::server_url()));
+        assert!(client.get_emojis().unwrap().is_empty());
+        mock.assert();
+
+        let unauth = client.unauthenticated();
+        assert_eq!(
+            unauth.route("/api/v1/streaming").unwrap().path(),
+            "/masto/api/v1/streaming"
+        );
+    }
+
+    #[test]
+    fn test_same_origin_redirect() {
+        let redirect = mockito::mock("GET", "/api/v1/custom_emojis")
+            .with_status(301)
+            .with_header("location", "/api/v2/custom_emojis")
+            .create();
+        let emojis = mockito::mock("GET", "/api/v2/custom_emojis")
+            .match_header("authorization", "Bearer token")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        assert!(mock_client().get_emojis().unwrap().is_empty());
+        redirect.assert();
+        emojis.assert();
+    }
+
+    #[test]
+    fn test_cross_origin_redirect() {
+        // a different host, even though it's the same server
+        let location = mockito::server_url().replace("127.0.0.1", "localhost") + "/elsewhere";
+        let redirect = mockito::mock("GET", "/api/v1/lists")
+            .with_status(302)
+            .with_header("location", &location)
+            .create();
+        let elsewhere = mockito::mock("GET", "/elsewhere").expect(0).create();
+        match mock_client().get_lists() {
+            Err(Error::CrossOriginRedirect { location: to, .. }) => assert_eq!(to, location),
+            other => panic!("expected Error::CrossOriginRedirect, got {:?}", other),
+        }
+        redirect.assert();
+        elsewhere.assert();
+    }
+
+    #[test]
+    #[cfg(feature = "websocket")]
+    fn test_set_websocket_scheme() {
+        let mut url: url::Url = "https://example.com/api/v1/streaming?stream=user"
+            .parse()
+            .unwrap();
+        set_websocket_scheme(&mut url).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "wss://example.com/api/v1/streaming?stream=user"
+        );
 
-impl<R: BufRead> EventStream for R {
-    fn read_message(&mut self) -> Result<String> {
-        let mut buf = String::new();
-        self.read_line(&mut buf)?;
-        Ok(buf)
+        let mut url: url::Url = "http://localhost:3000/".parse().unwrap();
+        set_websocket_scheme(&mut url).unwrap();
+        assert_eq!(url.scheme(), "ws");
+
+        let mut url: url::Url = "ftp://example.com/".parse().unwrap();
+        assert!(set_websocket_scheme(&mut url).is_err());
     }
-}
 
-impl EventStream for WebSocket {
-    fn read_message(&mut self) -> Result<String> {
-        Ok(self.0.read_message()?.into_text()?)
+    #[test]
+    #[cfg(feature = "websocket")]
+    fn test_streaming_config_apply() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let socket = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+
+        StreamingConfig::default().apply(&socket).unwrap();
+        assert_eq!(socket.read_timeout().unwrap(), None);
+
+        let config = StreamingConfig {
+            read_timeout: Some(Duration::from_secs(90)),
+            keepalive: Some(Duration::from_secs(30)),
+            ..Default::default()
+        };
+        config.apply(&socket).unwrap();
+        assert_eq!(
+            socket.read_timeout().unwrap(),
+            Some(Duration::from_secs(90))
+        );
+        assert!(socket2::SockRef::from(&socket).keepalive().unwrap());
     }
-}
 
-#[derive(Debug)]
-/// Iterator that produces events from a mastodon streaming API event stream
-pub struct EventReader<R: EventStream>(R);
-impl<R: EventStream> Iterator for EventReader<R> {
-    type Item = Event;
+    #[test]
+    fn test_resolve() {
+        let data = Data {
+            base: format!("http://mastodon.test:{}", mockito::server_address().port()).into(),
+            token: "token".into(),
+            ..Default::default()
+        };
+        let client = MastodonBuilder::new()
+            .data(data)
+            .resolve("Mastodon.test", mockito::server_address())
+            .build()
+            .unwrap();
+        let mock = mockito::mock("GET", "/api/v1/custom_emojis")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        assert!(client.get_emojis().unwrap().is_empty());
+        mock.assert();
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let mut lines = Vec::new();
-        loop {
-            if let Ok(line) = self.0.read_message() {
-                let line = line.trim().to_string();
-                if line.starts_with(':') || line.is_empty() {
-                    continue;
-                }
-                lines.push(line);
-                if let Ok(event) = self.make_event(&lines) {
-                    lines.clear();
-                    return Some(event);
-                } else {
-                    continue;
-                }
-            }
+        #[cfg(feature = "websocket")]
+        {
+            let addr = |url: &str| client.streaming.resolved(&url.parse().unwrap());
+            let ip = mockito::server_address().ip();
+            assert_eq!(addr("ws://mastodon.test/"), Some(SocketAddr::new(ip, 80)));
+            assert_eq!(
+                addr("wss://MASTODON.test:4000/"),
+                Some(SocketAddr::new(ip, 4000))
+            );
+            assert_eq!(addr("ws://example.com/"), None);
         }
     }
-}
 
-impl<R: EventStream> EventReader<R> {
-    fn make_event(&self, lines: &[String]) -> Result<Event> {
-        let event;
-        let data;
-        if let Some(event_line) = lines.iter().find(|line| line.starts_with("event:")) {
-            event = event_line[6..].trim().to_string();
-            data = lines
-                .iter()
-                .find(|line| line.starts_with("data:"))
-                .map(|x| x[5..].trim().to_string());
-        } else {
-            use serde::Deserialize;
-            #[derive(Deserialize)]
-            struct Message {
-                pub event: String,
-                pub payload: Option<String>,
-            }
-            let message = serde_json::from_str::<Message>(&lines[0])?;
-            event = message.event;
-            data = message.payload;
+    fn mock_client() -> Mastodon {
+        let data = Data {
+            base: mockito::server_url().into(),
+            token: "token".into(),
+            ..Default::default()
+        };
+        Mastodon::from(data)
+    }
+
+    #[test]
+    fn test_relationship_routes_use_post() {
+        type Route = fn(&Mastodon, &str) -> Result<Relationship>;
+        let client = mock_client();
+        let routes: [(&str, Route); 6] = [
+            ("follow", |client, id| client.follow(id)),
+            ("unfollow", |client, id| client.unfollow(id)),
+            ("block", |client, id| client.block(id)),
+            ("unblock", |client, id| client.unblock(id)),
+            ("mute", |client, id| client.mute(id)),
+            ("unmute", |client, id| client.unmute(id)),
+        ];
+        for (action, route) in routes.iter() {
+            let mock = mockito::mock("POST", &*format!("/api/v1/accounts/42/{}", action))
+                .match_header("authorization", "Bearer token")
+                .with_header("content-type", "application/json")
+                .with_body(include_str!("../tests/fixtures/relationship.json"))
+                .create();
+            let relationship = route(&client, "42").unwrap();
+            assert_eq!(relationship.id, "42");
+            mock.assert();
         }
-        let event: &str = &event;
-        Ok(match event {
-            "notification" => {
-                let data = data.ok_or_else(|| {
-                    Error::Other("Missing `data` line for notification".to_string())
-                })?;
-                let notification = serde_json::from_str::<Notification>(&data)?;
-                Event::Notification(notification)
-            },
-            "update" => {
-                let data =
-                    data.ok_or_else(|| Error::Other("Missing `data` line for update".to_string()))?;
-                let status = serde_json::from_str::<Status>(&data)?;
-                Event::Update(status)
-            },
-            "delete" => {
-                let data =
-                    data.ok_or_else(|| Error::Other("Missing `data` line for delete".to_string()))?;
-                Event::Delete(data)
-            },
-            "filters_changed" => Event::FiltersChanged,
-            _ => return Err(Error::Other(format!("Unknown event `{}`", event))),
-        })
     }
-}
 
-impl ops::Deref for Mastodon {
-    type Target = Data;
+    #[test]
+    fn test_instance_translation_languages() {
+        let client = mock_client();
+        let mock = mockito::mock("GET", "/api/v1/instance/translation_languages")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"en": ["de", "fr"], "de": ["en"]}"#)
+            .create();
+        let languages = client.instance_translation_languages().unwrap();
+        assert_eq!(languages["en"], vec!["de", "fr"]);
+        assert!(!languages.contains_key("fr"));
+        mock.assert();
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.data
+    #[test]
+    fn test_instance_rules() {
+        let client = mock_client();
+        let mock = mockito::mock("GET", "/api/v1/instance/rules")
+            .with_header("content-type", "application/json")
+            .with_body(r#"[{"id": "1", "text": "No spam"}, {"id": "4", "text": "Be nice"}]"#)
+            .expect(2)
+            .create();
+        let rules = client.instance_rules().unwrap();
+        assert_eq!(rules[1].id, "4");
+        assert_eq!(rules[1].hint, None);
+        assert_eq!(client.unauthenticated().instance_rules().unwrap(), rules);
+        mock.assert();
     }
-}
 
-struct MastodonBuilder {
-    client: Option<Client>,
-    data: Option<Data>,
-}
+    #[test]
+    fn test_identity_proofs() {
+        let client = mock_client();
+        let mock = mockito::mock("GET", "/api/v1/accounts/42/identity_proofs")
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"[{"provider": "Keybase", "provider_username": "alice",
+                    "updated_at": "2019-08-29T12:34:56.000Z",
+                    "proof_url": "https://keybase.io/alice/sigs/abc",
+                    "profile_url": "https://keybase.io/alice"}]"#,
+            )
+            .create();
+        let proofs = client.identity_proofs("42").unwrap();
+        assert_eq!(proofs.len(), 1);
+        assert_eq!(proofs[0].provider, "Keybase");
+        assert_eq!(proofs[0].provider_username, "alice");
+        mock.assert();
+    }
 
-impl MastodonBuilder {
-    pub fn new() -> Self {
-        MastodonBuilder {
-            client: None,
-            data: None,
+    #[test]
+    fn test_report_with() {
+        let client = mock_client();
+        let mock = mockito::mock("POST", "/api/v1/reports")
+            .match_body(mockito::Matcher::JsonString(
+                r#"{"account_id": "42", "status_ids": ["110"], "category": "violation", "rule_ids": ["2"]}"#
+                    .into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"id": "1", "action_taken": false, "category": "violation", "status_ids": ["110"], "rule_ids": ["2"]}"#,
+            )
+            .create();
+        let request = ReportRequest::new("42").status_id("110").rule_id("2");
+        let report = client.report_with(&request).unwrap();
+        assert_eq!(
+            report.category,
+            Some(entities::report::ReportCategory::Violation)
+        );
+        assert_eq!(report.rule_ids, vec!["2"]);
+        mock.assert();
+        drop(mock);
+
+        let mock = mockito::mock("POST", "/api/v1/reports")
+            .match_body(mockito::Matcher::JsonString(
+                r#"{"account_id": "42", "status_ids": ["110"], "comment": "spam"}"#.into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"id": "2", "action_taken": false}"#)
+            .create();
+        #[allow(deprecated)]
+        let report = client.report("42", vec!["110"], "spam".into()).unwrap();
+        assert_eq!(report.id, "2");
+        mock.assert();
+    }
+
+    #[test]
+    fn test_string_parameters_accept_owned_strings() {
+        let client = mock_client();
+        let status = test_fixtures::status();
+        let mock = mockito::mock("POST", "/api/v1/statuses/110/favourite")
+            .with_header("content-type", "application/json")
+            .with_body(test_fixtures::STATUS)
+            .create();
+        client.favourite(&status.id).unwrap();
+        client.favourite(status.id.clone()).unwrap();
+        mock.expect(2).assert();
+
+        let mock = mockito::mock("GET", "/api/v1/accounts/relationships")
+            .match_query(mockito::Matcher::Exact("id[]=1&id[]=2".into()))
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        let ids = vec![String::from("1"), String::from("2")];
+        assert!(client.relationships(&ids).unwrap().is_empty());
+        mock.assert();
+    }
+
+    #[test]
+    fn test_search_accounts() {
+        let client = mock_client();
+        let mock = mockito::mock("GET", "/api/v1/accounts/search")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("q".into(), "elefren".into()),
+                mockito::Matcher::UrlEncoded("limit".into(), "5".into()),
+                mockito::Matcher::UrlEncoded("following".into(), "true".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        let accounts = client.search_accounts("elefren", Some(5), true).unwrap();
+        assert!(accounts.is_empty());
+        mock.assert();
+    }
+
+    #[test]
+    fn test_validate_domain() {
+        assert!(validate_domain("example.com").is_ok());
+        assert!(validate_domain("xn--bcher-kva.example").is_ok());
+        assert!(validate_domain("bücher.example").is_ok());
+        assert!(validate_domain("localhost").is_ok());
+        assert!(validate_domain("").is_err());
+        assert!(validate_domain("https://example.com").is_err());
+        assert!(validate_domain("example.com/path").is_err());
+        assert!(validate_domain("-example.com").is_err());
+        assert!(validate_domain("example..com").is_err());
+        assert!(validate_domain(&"a".repeat(64)).is_err());
+    }
+
+    #[test]
+    fn test_block_domain_rejects_invalid_domain() {
+        let client = mock_client();
+        let mock = mockito::mock("POST", "/api/v1/domain_blocks")
+            .expect(0)
+            .create();
+        assert!(client.block_domain("https://example.com").is_err());
+        mock.assert();
+    }
+
+    #[test]
+    fn test_is_domain_blocked_walks_pages() {
+        let client = mock_client();
+        let link = format!(
+            "<{}/api/v1/domain_blocks?max_id=2>; rel=\"next\"",
+            mockito::server_url()
+        );
+        let first = mockito::mock("GET", "/api/v1/domain_blocks?limit=200")
+            .with_header("content-type", "application/json")
+            .with_header("link", &link)
+            .with_body(r#"["spam.example"]"#)
+            .expect(2)
+            .create();
+        let second = mockito::mock("GET", "/api/v1/domain_blocks?max_id=2")
+            .with_header("content-type", "application/json")
+            .with_body(r#"["evil.example"]"#)
+            .expect(2)
+            .create();
+        assert!(client.is_domain_blocked("Evil.example").unwrap());
+        assert!(!client.is_domain_blocked("nice.example").unwrap());
+        first.assert();
+        second.assert();
+    }
+
+    #[test]
+    fn test_resume_bookmarks_from_cursor() {
+        let client = mock_client();
+        let link = format!(
+            "<{0}/api/v1/bookmarks?limit=1&max_id=7654>; rel=\"next\", \
+             <{0}/api/v1/bookmarks?limit=1&min_id=7655>; rel=\"prev\"",
+            mockito::server_url()
+        );
+        let first = mockito::mock("GET", "/api/v1/bookmarks?limit=1")
+            .with_header("content-type", "application/json")
+            .with_header("link", &link)
+            .with_body(format!("[{}]", test_fixtures::STATUS))
+            .create();
+        let page = client.bookmarks_with(&PageRequest::new().limit(1)).unwrap();
+        let cursor = page.next_cursor().unwrap();
+        assert_eq!(cursor, PageRequest::new().max_id("7654").limit(1));
+        assert_eq!(
+            page.prev_cursor(),
+            Some(PageRequest::new().min_id("7655").limit(1))
+        );
+        first.assert();
+
+        // the cursor isn't a status id, and is sent back as it is
+        let cursor: PageRequest =
+            serde_json::from_str(&serde_json::to_string(&cursor).unwrap()).unwrap();
+        let second = mockito::mock("GET", "/api/v1/bookmarks?max_id=7654&limit=1")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        let page = client.bookmarks_with(&cursor).unwrap();
+        assert!(page.initial_items.is_empty());
+        assert_eq!(page.next_cursor(), None);
+        second.assert();
+    }
+
+    #[test]
+    fn test_empty_page_with_next_link() {
+        let client = mock_client();
+        let next = |max_id: &str| {
+            format!(
+                "<{}/api/v1/timelines/home?max_id={}>; rel=\"next\"",
+                mockito::server_url(),
+                max_id
+            )
+        };
+        let first = mockito::mock("GET", "/api/v1/timelines/home")
+            .with_header("content-type", "application/json")
+            .with_header("link", &next("100"))
+            .with_body("[]")
+            .create();
+        let second = mockito::mock("GET", "/api/v1/timelines/home?max_id=100")
+            .with_header("content-type", "application/json")
+            .with_header("link", &next("50"))
+            .with_body(format!("[{}]", test_fixtures::STATUS))
+            .create();
+        let last = mockito::mock("GET", "/api/v1/timelines/home?max_id=50")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+
+        let mut page = client.get_home_timeline().unwrap();
+        assert!(page.initial_items.is_empty());
+        assert!(page.has_next());
+        assert!(!page.has_prev());
+        assert_eq!(page.next_page().unwrap().map(|items| items.len()), Some(1));
+        assert_eq!(page.next_page().unwrap(), Some(vec![]));
+        assert!(!page.has_next());
+        assert_eq!(page.next_page().unwrap(), None);
+
+        let ids: Vec<_> = client
+            .get_home_timeline()
+            .unwrap()
+            .items_iter()
+            .map(|status| status.id)
+            .collect();
+        assert_eq!(ids, vec!["110"]);
+        first.expect(2).assert();
+        second.expect(2).assert();
+        last.expect(2).assert();
+    }
+
+    #[test]
+    fn test_page_linking_to_itself() {
+        let client = mock_client();
+        let link = format!(
+            "<{}/api/v1/timelines/home?max_id=1>; rel=\"next\"",
+            mockito::server_url()
+        );
+        let first = mockito::mock("GET", "/api/v1/timelines/home")
+            .with_header("content-type", "application/json")
+            .with_header("link", &link)
+            .with_body("[]")
+            .create();
+        let looping = mockito::mock("GET", "/api/v1/timelines/home?max_id=1")
+            .with_header("content-type", "application/json")
+            .with_header("link", &link)
+            .with_body("[]")
+            .create();
+        let items: Vec<Status> = client.get_home_timeline().unwrap().items_iter().collect();
+        assert!(items.is_empty());
+        first.assert();
+        looping.assert();
+    }
+
+    #[test]
+    fn test_split_link_headers() {
+        let client = mock_client();
+        let mock = mockito::mock("GET", "/api/v1/bookmarks")
+            .with_header("content-type", "application/json")
+            .with_header(
+                "link",
+                &format!(
+                    "<{}/api/v1/bookmarks?max_id=1>; rel=\"next\"",
+                    mockito::server_url()
+                ),
+            )
+            .with_header(
+                "link",
+                &format!(
+                    "<{}/api/v1/bookmarks?min_id=2>; rel=\"prev\"",
+                    mockito::server_url()
+                ),
+            )
+            .with_body("[]")
+            .create();
+        let page = client.bookmarks().unwrap();
+        assert_eq!(page.next_cursor(), Some(PageRequest::new().max_id("1")));
+        assert_eq!(page.prev_cursor(), Some(PageRequest::new().min_id("2")));
+        mock.assert();
+    }
+
+    #[test]
+    fn test_try_items_iter_yields_errors() {
+        let client = mock_client();
+        let link = format!(
+            "<{}/api/v1/blocks?max_id=2>; rel=\"next\"",
+            mockito::server_url()
+        );
+        let first = mockito::mock("GET", "/api/v1/blocks")
+            .with_header("content-type", "application/json")
+            .with_header("link", &link)
+            .with_body(format!("[{}]", test_fixtures::ACCOUNT))
+            .create();
+        let second = mockito::mock("GET", "/api/v1/blocks?max_id=2")
+            .with_status(500)
+            .create();
+        let mut items = client.blocks().unwrap().try_items_iter();
+        assert!(items.next().unwrap().is_ok());
+        assert!(matches!(items.next(), Some(Err(_))));
+        assert!(items.next().is_none());
+        first.assert();
+        second.assert();
+    }
+
+    #[test]
+    fn test_authorize_all_follow_requests() {
+        let client = mock_client();
+        let requests = mockito::mock("GET", "/api/v1/follow_requests?limit=80")
+            .with_header("content-type", "application/json")
+            .with_body(include_str!("../tests/fixtures/follow_requests.json"))
+            .create();
+        let authorize = mockito::mock("POST", "/api/v1/follow_requests/109/authorize")
+            .with_header("content-type", "application/json")
+            .with_body("{}")
+            .create();
+        let reject = mockito::mock("POST", "/api/v1/follow_requests/110/reject")
+            .with_header("content-type", "application/json")
+            .with_body("{}")
+            .create();
+        let counts = client
+            .authorize_all_follow_requests(|account| account.bot != Some(true))
+            .unwrap();
+        assert_eq!(counts, (1, 1));
+        requests.assert();
+        authorize.assert();
+        reject.assert();
+    }
+
+    #[test]
+    fn test_accept_follow_request_notification() {
+        let client = mock_client();
+        let json = format!(
+            r#"{{"id": "7", "type": "follow_request", "created_at": "2023-01-02T10:00:00Z", "account": {}}}"#,
+            test_fixtures::ACCOUNT
+        );
+        let mut notification: Notification = serde_json::from_str(&json).unwrap();
+        assert!(notification.is_follow_request());
+
+        let authorize = mockito::mock("POST", "/api/v1/follow_requests/109/authorize")
+            .with_header("content-type", "application/json")
+            .with_body("{}")
+            .create();
+        notification.accept(&client).unwrap();
+        authorize.assert();
+
+        notification.notification_type = NotificationType::Follow;
+        assert!(notification.reject(&client).is_err());
+    }
+
+    #[test]
+    fn test_statuses_all() {
+        let client = mock_client();
+        let status = test_fixtures::STATUS;
+        let first = mockito::mock("GET", "/api/v1/accounts/5/statuses?limit=2")
+            .with_header("content-type", "application/json")
+            .with_header(
+                "link",
+                &format!(
+                    r#"<{}/api/v1/accounts/5/statuses?max_id=108>; rel="next""#,
+                    mockito::server_url()
+                ),
+            )
+            .with_body(format!("[{}, {}]", status, status))
+            .create();
+        let second = mockito::mock("GET", "/api/v1/accounts/5/statuses?max_id=108")
+            .with_header("content-type", "application/json")
+            .with_body(format!("[{}, {}]", status, status))
+            .create();
+        let statuses = client
+            .statuses_all("5", StatusesRequest::new().limit(2), Some(3))
+            .unwrap();
+        assert_eq!(statuses.len(), 3);
+        first.assert();
+        second.assert();
+    }
+
+    #[test]
+    fn test_request_options() {
+        let client = mock_client();
+        let timeline = mockito::mock("GET", "/api/v1/timelines/public?local=1&limit=5")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        let notifications = mockito::mock(
+            "GET",
+            "/api/v1/notifications?exclude_types[]=favourite&exclude_types[]=reblog&limit=5",
+        )
+        .with_header("content-type", "application/json")
+        .with_body("[]")
+        .create();
+        let request = PageRequest::new().limit(5);
+        client
+            .get_public_timeline_with_options(&TimelineRequest::new().local(), &request)
+            .unwrap();
+        let options = NotificationsRequest::new()
+            .exclude_types(&[NotificationType::Favourite, NotificationType::Reblog]);
+        client
+            .notifications_with_options(&options, &request)
+            .unwrap();
+        timeline.assert();
+        notifications.assert();
+    }
+
+    #[test]
+    fn test_profile_view() {
+        let client = mock_client();
+        let status = test_fixtures::STATUS;
+        let pinned = mockito::mock("GET", "/api/v1/accounts/7/statuses?pinned=1")
+            .with_header("content-type", "application/json")
+            .with_body(format!("[{}]", status))
+            .create();
+        let statuses = mockito::mock("GET", "/api/v1/accounts/7/statuses")
+            .with_header("content-type", "application/json")
+            .with_body(format!("[{}, {}]", status, status))
+            .create();
+        let profile = client.profile_view("7").unwrap();
+        assert_eq!(profile.pinned.len(), 1);
+        assert_eq!(profile.statuses.len(), 2);
+        pinned.assert();
+        statuses.assert();
+    }
+
+    #[test]
+    fn test_export_social_graph() {
+        let client = mock_client();
+        let account =
+            |id: &str| serde_json::to_string(&test_fixtures::account_with_id(id)).unwrap();
+        let followers = mockito::mock("GET", "/api/v1/accounts/5/followers?limit=80")
+            .with_header("content-type", "application/json")
+            .with_header(
+                "link",
+                &format!(
+                    r#"<{}/api/v1/accounts/5/followers?max_id=2>; rel="next""#,
+                    mockito::server_url()
+                ),
+            )
+            .with_body(format!("[{}, {}]", account("1"), account("2")))
+            .create();
+        let more_followers = mockito::mock("GET", "/api/v1/accounts/5/followers?max_id=2")
+            .with_header("content-type", "application/json")
+            .with_body(format!("[{}]", account("3")))
+            .create();
+        let following = mockito::mock("GET", "/api/v1/accounts/5/following?limit=80")
+            .with_header("content-type", "application/json")
+            .with_body(format!("[{}, {}]", account("2"), account("4")))
+            .create();
+
+        let graph = client.export_social_graph("5").unwrap();
+        followers.assert();
+        more_followers.assert();
+        following.assert();
+        assert_eq!(graph.account_id, "5");
+        let followers: Vec<&str> = graph.followers().map(|c| c.id.as_str()).collect();
+        assert_eq!(followers, vec!["2", "1", "3"]);
+        let mutuals: Vec<&str> = graph.mutuals().map(|c| c.id.as_str()).collect();
+        assert_eq!(mutuals, vec!["2"]);
+    }
+
+    #[test]
+    fn test_unit_routes_accept_empty_bodies() {
+        let client = mock_client();
+        for body in &["{}", ""] {
+            let mock = mockito::mock("DELETE", "/api/v1/statuses/110")
+                .with_header("content-type", "application/json")
+                .with_body(body)
+                .create();
+            client.delete_status("110").unwrap();
+            mock.assert();
         }
     }
 
-    pub fn client(&mut self, client: Client) -> &mut Self {
-        self.client = Some(client);
-        self
+    #[test]
+    fn test_rate_limit_is_recorded() {
+        let client = mock_client();
+        assert_eq!(client.rate_limit(), None);
+        let mock = mockito::mock("GET", "/api/v1/custom_emojis")
+            .with_header("content-type", "application/json")
+            .with_header("x-ratelimit-limit", "300")
+            .with_header("x-ratelimit-remaining", "42")
+            .with_header("x-ratelimit-reset", "2023-05-01T12:05:00.000Z")
+            .with_body("[]")
+            .create();
+        let clone = client.clone();
+        client.get_emojis().unwrap();
+        assert_eq!(
+            clone.rate_limit().map(|rate_limit| rate_limit.remaining),
+            Some(42)
+        );
+        mock.assert();
     }
 
-    pub fn data(&mut self, data: Data) -> &mut Self {
-        self.data = Some(data);
-        self
+    #[test]
+    fn test_follow_suggestions_v2() {
+        let client = mock_client();
+        let account = test_fixtures::ACCOUNT;
+        let mock = mockito::mock("GET", "/api/v2/suggestions")
+            .with_header("content-type", "application/json")
+            .with_body(format!(
+                r#"[{{"source": "past_interactions", "account": {}}}]"#,
+                account
+            ))
+            .create();
+        let suggestions = client.get_follow_suggestions_v2().unwrap();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].source, SuggestionSource::PastInteractions);
+        mock.assert();
     }
 
-    pub fn build(self) -> Result<Mastodon> {
-        Ok(if let Some(data) = self.data {
-            Mastodon {
-                client: self.client.unwrap_or_else(Client::new),
-                data,
+    #[test]
+    fn test_last_response_is_recorded() {
+        let client = mock_client();
+        assert!(client.last_response().is_none());
+        let mock = mockito::mock("GET", "/api/v1/suggestions")
+            .with_header("content-type", "application/json")
+            .with_header("x-request-id", "f00dcafe")
+            .with_header("deprecation", "true")
+            .with_body("[]")
+            .create();
+        #[allow(deprecated)]
+        client.get_follow_suggestions().unwrap();
+        let meta = client.last_response().unwrap();
+        assert_eq!(meta.request_id.as_deref(), Some("f00dcafe"));
+        assert!(meta.is_deprecated());
+        mock.assert();
+    }
+
+    #[test]
+    fn test_get_with_meta() {
+        let client = mock_client();
+        let mock = mockito::mock("GET", "/api/v1/custom_emojis")
+            .with_header("content-type", "application/json")
+            .with_header("x-request-id", "0d1e2f")
+            .with_header("x-ratelimit-limit", "300")
+            .with_header("x-ratelimit-remaining", "299")
+            .with_header("x-ratelimit-reset", "2023-05-01T12:05:00.000Z")
+            .with_body("[]")
+            .create();
+        let (emojis, meta) = client
+            .get_with_meta::<Vec<Emoji>>("/api/v1/custom_emojis")
+            .unwrap();
+        assert!(emojis.is_empty());
+        assert_eq!(meta.status, StatusCode::OK);
+        assert_eq!(meta.request_id.as_deref(), Some("0d1e2f"));
+        assert_eq!(
+            meta.rate_limit.map(|rate_limit| rate_limit.remaining),
+            Some(299)
+        );
+        mock.assert();
+    }
+
+    #[test]
+    fn test_post_is_not_retried_after_a_gateway_error() {
+        use crate::clock::ManualClock;
+
+        let client = MastodonBuilder::new()
+            .data(Data {
+                base: mockito::server_url().into(),
+                token: "token".into(),
+                ..Default::default()
+            })
+            .retry_policy(RetryPolicy::new(2))
+            .clock(ManualClock::new(chrono::Utc::now()))
+            .build()
+            .unwrap();
+        let unavailable = mockito::mock("POST", "/api/v1/statuses")
+            .with_status(503)
+            .expect(1)
+            .create();
+        let status = StatusBuilder::new().status("hi").build().unwrap();
+        assert!(client.new_status(status.clone()).is_err());
+        unavailable.assert();
+        drop(unavailable);
+
+        // but it is when the server refused it
+        let rate_limited = mockito::mock("POST", "/api/v1/statuses")
+            .with_status(429)
+            .expect(3)
+            .create();
+        assert!(client.new_status(status).is_err());
+        rate_limited.assert();
+        assert_eq!(client.stats().retries, 2);
+    }
+
+    #[test]
+    fn test_stats() {
+        use crate::clock::ManualClock;
+
+        let client = MastodonBuilder::new()
+            .data(Data {
+                base: mockito::server_url().into(),
+                token: "token".into(),
+                ..Default::default()
+            })
+            .retry_policy(RetryPolicy::new(1))
+            .clock(ManualClock::new(chrono::Utc::now()))
+            .build()
+            .unwrap();
+        let unavailable = mockito::mock("GET", "/api/v1/custom_emojis")
+            .with_status(503)
+            .expect(2)
+            .create();
+        assert!(client.get_emojis().is_err());
+        unavailable.assert();
+        drop(unavailable);
+
+        let emojis = mockito::mock("GET", "/api/v1/custom_emojis")
+            .with_header("content-type", "application/json")
+            .with_header("x-ratelimit-limit", "300")
+            .with_header("x-ratelimit-remaining", "42")
+            .with_header("x-ratelimit-reset", "2023-05-01T12:05:00.000Z")
+            .with_body("[]")
+            .create();
+        client.clone().get_emojis().unwrap();
+        emojis.assert();
+        assert_eq!(
+            client.stats(),
+            ClientStats {
+                requests: 3,
+                retries: 1,
+                bytes_received: 2,
+                rate_limit_remaining: Some(42),
             }
-        } else {
-            return Err(Error::MissingField("missing field 'data'"));
-        })
+        );
     }
-}
 
-/// Client that can make unauthenticated calls to a mastodon instance
-#[derive(Clone, Debug)]
-pub struct MastodonUnauth {
-    client: Client,
-    base: url::Url,
-}
+    #[test]
+    #[cfg(feature = "websocket")]
+    fn test_streaming_unauthorized() {
+        let mock = mockito::mock("GET", "/api/v1/streaming")
+            .match_query(mockito::Matcher::Any)
+            .with_status(401)
+            .with_body(r#"{"error": "Error: Invalid access token"}"#)
+            .expect(4)
+            .create();
+        match mock_client().streaming(StreamKind::User) {
+            Err(Error::Client(status)) => assert_eq!(status.as_u16(), 401),
+            other => panic!("unexpected result {:?}", other),
+        }
+        let unauthenticated = MastodonUnauth::new(&mockito::server_url()).unwrap();
+        assert!(matches!(
+            unauthenticated.streaming(StreamKind::User),
+            Err(Error::AccessTokenRequired)
+        ));
+        mock.assert();
+    }
 
-impl MastodonUnauth {
-    /// Create a new unauthenticated client
-    pub fn new(base: &str) -> Result<MastodonUnauth> {
-        let base = if base.starts_with("https://") {
-            base.to_string()
-        } else {
-            format!("https://{}", base)
+    #[test]
+    fn test_with_timeout() {
+        // accepts connections, but never answers
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = Mastodon::from(Data {
+            base: format!("http://{}", listener.local_addr().unwrap()).into(),
+            token: "token".into(),
+            ..Default::default()
+        });
+        let slow = client.with_timeout(Duration::from_millis(100));
+        match slow.verify_credentials() {
+            Err(Error::Http(e)) => assert!(e.is_timeout()),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(client.timeout, None);
+    }
+
+    #[test]
+    fn test_get_stream() {
+        let client = mock_client();
+        let body =
+            "Account address,Show boosts\n".to_string() + &"a@example.com,true\n".repeat(1000);
+        let mock = mockito::mock("GET", "/settings/exports/follows.csv")
+            .match_header("authorization", "Bearer token")
+            .with_header("content-type", "text/csv")
+            .with_body(&body)
+            .create();
+        let mut streamed = String::new();
+        let mut reader = client.get_stream("/settings/exports/follows.csv").unwrap();
+        io::Read::read_to_string(&mut reader, &mut streamed).unwrap();
+        assert_eq!(streamed, body);
+        mock.assert();
+
+        let _mock = mockito::mock("GET", "/settings/exports/lists.csv")
+            .with_status(403)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"error":"This action is not allowed"}"#)
+            .create();
+        assert!(matches!(
+            client.get_stream("/settings/exports/lists.csv"),
+            Err(Error::Api(ApiError { error: Some(_), .. }))
+        ));
+    }
+
+    #[test]
+    fn test_unauthenticated_shares_base() {
+        let client = mock_client();
+        let mock = mockito::mock("GET", "/api/v1/custom_emojis")
+            .match_header("authorization", mockito::Matcher::Missing)
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        let emojis = client.unauthenticated().custom_emojis().unwrap();
+        assert!(emojis.is_empty());
+        mock.assert();
+    }
+
+    #[test]
+    fn test_directory_and_trends() {
+        let client = mock_client();
+        let directory = mockito::mock("GET", "/api/v1/directory?order=new&local=1")
+            .with_header("content-type", "application/json")
+            .with_body(format!("[{}]", test_fixtures::ACCOUNT))
+            .expect(2)
+            .create();
+        let trends = mockito::mock("GET", "/api/v1/trends?limit=2")
+            .with_header("content-type", "application/json")
+            .with_body(r#"[{"name": "rust", "url": "https://example.com/tags/rust"}]"#)
+            .expect(2)
+            .create();
+
+        let request = DirectoryRequest::new()
+            .order(requests::DirectoryOrder::New)
+            .local();
+        let accounts = client.directory(request).unwrap();
+        assert_eq!(accounts[0].username, "alice");
+        assert_eq!(client.trends(2).unwrap()[0].name, "rust");
+
+        // the same types are accepted without authentication
+        let unauth = client.unauthenticated();
+        assert_eq!(unauth.directory(request).unwrap().len(), 1);
+        assert_eq!(unauth.trends(Some(2)).unwrap().len(), 1);
+        directory.assert();
+        trends.assert();
+    }
+
+    #[test]
+    fn test_unauth_get_status() {
+        let mock = mockito::mock("GET", "/api/v1/statuses/110")
+            .with_header("content-type", "application/json")
+            .with_body(test_fixtures::STATUS)
+            .create();
+        let client = MastodonUnauth::new(&mockito::server_url()).unwrap();
+        assert_eq!(client.get_status("110").unwrap().id, "110");
+        mock.assert();
+    }
+
+    #[test]
+    fn test_unauth_default_header() {
+        let mock = mockito::mock("GET", "/api/v1/statuses/110")
+            .match_header("cf-access-client-id", "abc")
+            .with_header("content-type", "application/json")
+            .with_body(test_fixtures::STATUS)
+            .create();
+        let client = MastodonUnauth::new(&mockito::server_url())
+            .unwrap()
+            .default_header("CF-Access-Client-Id", "abc")
+            .unwrap();
+        client.get_status("110").unwrap();
+        mock.assert();
+
+        let client = MastodonUnauth::new(&mockito::server_url()).unwrap();
+        assert!(client.default_header("bad name", "abc").is_err());
+    }
+
+    #[test]
+    fn test_hashtag_timeline_encodes_tag() {
+        let client = mock_client();
+        let mock = mockito::mock("GET", "/api/v1/timelines/tag/caf%C3%A9?local=1")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        let page = client.get_hashtag_timeline("#café", true).unwrap();
+        assert!(page.initial_items.is_empty());
+        mock.assert();
+    }
+
+    #[test]
+    fn test_list_timeline() {
+        let client = mock_client();
+        let mock = mockito::mock("GET", "/api/v1/timelines/list/42")
+            .match_query(mockito::Matcher::UrlEncoded("limit".into(), "20".into()))
+            .with_header("content-type", "application/json")
+            .with_body(format!("[{}]", test_fixtures::STATUS))
+            .create();
+        let page = client
+            .get_list_timeline_with("42", &PageRequest::new().limit(20))
+            .unwrap();
+        assert_eq!(page.initial_items[0].id, "110");
+        mock.assert();
+    }
+
+    #[test]
+    fn test_my_pinned_statuses() {
+        let client = mock_client();
+        let verify = mockito::mock("GET", "/api/v1/accounts/verify_credentials")
+            .with_header("content-type", "application/json")
+            .with_body(test_fixtures::ACCOUNT)
+            .create();
+        let me = test_fixtures::account();
+        let statuses = mockito::mock("GET", &*format!("/api/v1/accounts/{}/statuses", me.id))
+            .match_query(mockito::Matcher::UrlEncoded("pinned".into(), "1".into()))
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        let page = client.my_pinned_statuses().unwrap();
+        assert!(page.initial_items.is_empty());
+        verify.assert();
+        statuses.assert();
+    }
+
+    #[test]
+    fn test_schedule_status() {
+        use chrono::TimeZone;
+
+        let client = mock_client();
+        let mock = mockito::mock("POST", "/api/v1/statuses")
+            .match_body(mockito::Matcher::PartialJsonString(
+                r#"{"status": "so long", "scheduled_at": "2029-01-01T12:00:00Z"}"#.into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(include_str!("../tests/fixtures/scheduled_status.json"))
+            .create();
+        let status = StatusBuilder::new()
+            .status("so long")
+            .scheduled_at(chrono::Utc.with_ymd_and_hms(2029, 1, 1, 12, 0, 0).unwrap())
+            .build()
+            .unwrap();
+        let scheduled = client.schedule_status(status).unwrap();
+        assert_eq!(scheduled.id, "3221");
+        mock.assert();
+
+        let unscheduled = StatusBuilder::new().status("now").build().unwrap();
+        assert!(client.schedule_status(unscheduled).is_err());
+    }
+
+    #[cfg(feature = "pleroma")]
+    #[test]
+    fn test_pleroma_favourites() {
+        let client = mock_client();
+        let mock = mockito::mock("GET", "/api/v1/pleroma/accounts/42/favourites")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        let page = client.pleroma_favourites("42").unwrap();
+        assert!(page.initial_items.is_empty());
+        mock.assert();
+    }
+
+    #[test]
+    fn test_me_is_cached() {
+        let client = mock_client();
+        let verify = mockito::mock("GET", "/api/v1/accounts/verify_credentials")
+            .with_header("content-type", "application/json")
+            .with_body(test_fixtures::ACCOUNT)
+            .expect(2)
+            .create();
+        let me = client.me().unwrap();
+        assert_eq!(client.clone().me().unwrap().id, me.id);
+        assert_eq!(client.refresh_credentials().unwrap().id, me.id);
+        assert_eq!(client.me().unwrap().id, me.id);
+        verify.assert();
+    }
+
+    #[test]
+    fn test_download_does_not_send_token() {
+        let client = mock_client();
+        let mock = mockito::mock("GET", "/avatars/original/missing.png")
+            .match_header("authorization", mockito::Matcher::Missing)
+            .with_header("content-type", "image/png")
+            .with_body(b"\x89PNG")
+            .create();
+        let mut avatar = Vec::new();
+        let url = format!("{}/avatars/original/missing.png", mockito::server_url());
+        let content_type = client.download(&url, &mut avatar).unwrap();
+        assert_eq!(content_type.as_deref(), Some("image/png"));
+        assert_eq!(avatar, b"\x89PNG");
+        mock.assert();
+    }
+
+    #[test]
+    fn test_custom_emojis_are_not_paged() {
+        let client = mock_client();
+        let mock = mockito::mock("GET", "/api/v1/custom_emojis")
+            .with_header("content-type", "application/json")
+            .with_body(include_str!("../tests/fixtures/custom_emojis.json"))
+            .create();
+        let emojis = client.get_emojis().unwrap();
+        assert_eq!(emojis.len(), 2);
+        assert_eq!(emojis[0].shortcode, "blobaww");
+        mock.assert();
+    }
+
+    #[test]
+    fn test_json_body_route_with_id() {
+        let client = mock_client();
+        let mock = mockito::mock("PUT", "/api/v1/filters/8449")
+            .match_body(mockito::Matcher::PartialJsonString(
+                r#"{"phrase": "test", "context": ["home"]}"#.into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(include_str!("../tests/fixtures/filter.json"))
+            .create();
+        let mut request = AddFilterRequest::new("test", FilterContext::Home);
+        client.update_filter("8449", &mut request).unwrap();
+        mock.assert();
+    }
+
+    #[test]
+    fn test_reconcile_list() {
+        let client = mock_client();
+        let account = |id: &str| {
+            let mut account: serde_json::Value =
+                serde_json::from_str(test_fixtures::ACCOUNT).unwrap();
+            account["id"] = id.into();
+            account
         };
-        Ok(MastodonUnauth {
-            client: Client::new(),
-            base: url::Url::parse(&base)?,
-        })
+        let members = mockito::mock("GET", "/api/v1/lists/42/accounts")
+            .match_query(mockito::Matcher::UrlEncoded("limit".into(), "80".into()))
+            .with_header("content-type", "application/json")
+            .with_body(serde_json::json!([account("1"), account("2")]).to_string())
+            .expect(2)
+            .create();
+        let remove = mockito::mock("DELETE", "/api/v1/lists/42/accounts")
+            .match_body(mockito::Matcher::JsonString(
+                r#"{"account_ids": ["1"]}"#.into(),
+            ))
+            .with_body("{}")
+            .create();
+        let add = mockito::mock("POST", "/api/v1/lists/42/accounts")
+            .match_body(mockito::Matcher::JsonString(
+                r#"{"account_ids": ["3"]}"#.into(),
+            ))
+            .with_body("{}")
+            .create();
+        let changes = client.reconcile_list("42", &["2", "3", "3"]).unwrap();
+        assert_eq!(changes.added, ["3"]);
+        assert_eq!(changes.removed, ["1"]);
+        remove.assert();
+        add.assert();
+
+        let changes = client.reconcile_list("42", &["1", "2"]).unwrap();
+        assert!(changes.is_unchanged());
+        members.assert();
     }
-}
 
-impl MastodonUnauth {
-    fn route(&self, url: &str) -> Result<url::Url> {
-        Ok(self.base.join(url)?)
+    #[test]
+    fn test_update_credentials_sends_indexed_fields() {
+        let client = mock_client();
+        let mock = mockito::mock("PATCH", "/api/v1/accounts/update_credentials")
+            .match_header(
+                "content-type",
+                mockito::Matcher::Regex("^multipart/form-data".into()),
+            )
+            .match_body(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::Regex(
+                    r#"name="fields_attributes\[0\]\[name\]"\r\n\r\nweb\r\n"#.into(),
+                ),
+                mockito::Matcher::Regex(
+                    r#"name="fields_attributes\[1\]\[value\]"\r\n\r\nthey/them\r\n"#.into(),
+                ),
+                mockito::Matcher::Regex(r#"name="source\[privacy\]"\r\n\r\nunlisted"#.into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(test_fixtures::ACCOUNT)
+            .create();
+        let request = UpdateCredsRequest::new()
+            .privacy(status_builder::Visibility::Unlisted)
+            .field_attribute("web", "https://example.org")
+            .field_attribute("pronouns", "they/them");
+        client.update_credentials(request).unwrap();
+        mock.assert();
     }
 
-    fn send_blocking(&self, req: RequestBuilder) -> Result<Response> {
-        let req = req.build()?;
-        Ok(self.client.execute(req)?)
+    #[test]
+    fn test_instance_peers() {
+        let client = mock_client();
+        let mock = mockito::mock("GET", "/api/v1/instance/peers")
+            .with_header("content-type", "application/json")
+            .with_body(r#"["mastodon.social", "fosstodon.org", "social.example"]"#)
+            .expect(2)
+            .create();
+        assert_eq!(client.instance_peers().unwrap().len(), 3);
+        assert_eq!(
+            client.peers_matching("Social").unwrap(),
+            ["mastodon.social", "social.example"]
+        );
+        assert_eq!(client.stats().bytes_received, 2 * 54);
+        mock.assert();
+
+        let _mock = mockito::mock("GET", "/api/v1/instance/peers")
+            .with_status(404)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"error": "Record not found"}"#)
+            .create();
+        assert!(matches!(
+            client.peers_matching("social"),
+            Err(Error::Api(_))
+        ));
     }
 
-    /// Get a stream of the public timeline
-    pub fn streaming_public(&self) -> Result<EventReader<WebSocket>> {
-        let mut url: url::Url = self.route("/api/v1/streaming/public/local")?;
-        url.query_pairs_mut().append_pair("stream", "public");
-        let mut url: url::Url = reqwest::blocking::get(url.as_str())?
-            .url()
-            .as_str()
-            .parse()?;
-        let new_scheme = match url.scheme() {
-            "http" => "ws",
-            "https" => "wss",
-            x => return Err(Error::Other(format!("Bad URL scheme: {}", x))),
+    #[test]
+    fn test_peers_matching_body_limit() {
+        let data = Data {
+            base: mockito::server_url().into(),
+            token: "token".into(),
+            ..Default::default()
         };
-        url.set_scheme(new_scheme)
-            .map_err(|_| Error::Other("Bad URL scheme!".to_string()))?;
+        let client = MastodonBuilder::new()
+            .data(data)
+            .max_body_size(16)
+            .build()
+            .unwrap();
+        let mock = mockito::mock("GET", "/api/v1/instance/peers")
+            .with_header("content-type", "application/json")
+            .with_body(r#"["mastodon.social", "fosstodon.org"]"#)
+            .create();
+        let err = client.peers_matching("social").unwrap_err();
+        assert!(matches!(err, Error::BodyTooLarge { limit: 16, .. }));
+        mock.assert();
+    }
+
+    #[test]
+    fn test_hostile_ids_are_encoded() {
+        let client = mock_client();
+        let status = mockito::mock("GET", "/api/v1/statuses/..%2F..%2Fadmin%3Fx%3D1%23y")
+            .with_header("content-type", "application/json")
+            .with_body(test_fixtures::STATUS)
+            .create();
+        client.get_status("../../admin?x=1#y").unwrap();
+        status.assert();
+
+        let followers = mockito::mock("GET", "/api/v1/accounts/a%2Fb%20c/followers")
+            .with_header("content-type", "application/json")
+            .with_body("[]")
+            .create();
+        client.followers("a/b c").unwrap();
+        followers.assert();
 
-        let client = tungstenite::connect(url.as_str())?.0;
+        let filter = mockito::mock("DELETE", "/api/v2/filters/%25")
+            .with_body("{}")
+            .create();
+        client.delete_filter_v2("%").unwrap();
+        filter.assert();
 
-        Ok(EventReader(WebSocket(client)))
+        assert!(client.get_account("..").is_err());
+        assert!(client.get_status("").is_err());
     }
-}
 
-impl MastodonUnauthenticated for MastodonUnauth {
-    /// GET /api/v1/statuses/:id
-    fn get_status(&self, id: &str) -> Result<Status> {
-        let route = self.route("/api/v1/statuses")?;
-        let route = route.join(id)?;
-        let response = self.send_blocking(self.client.get(route))?;
-        deserialise_blocking(response)
+    #[test]
+    fn test_filters_v2() {
+        let client = mock_client();
+        let filters = mockito::mock("GET", "/api/v2/filters")
+            .with_header("content-type", "application/json")
+            .with_body(include_str!("../tests/fixtures/filters_v2.json"))
+            .create();
+        let keyword = mockito::mock("POST", "/api/v2/filters/19972/keywords")
+            .match_body(mockito::Matcher::JsonString(
+                r#"{"keyword": "worse word", "whole_word": true}"#.into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"id": "1198", "keyword": "worse word", "whole_word": true}"#)
+            .create();
+        let status = mockito::mock("POST", "/api/v2/filters/19972/statuses")
+            .match_body(mockito::Matcher::JsonString(
+                r#"{"status_id": "42"}"#.into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"id": "2", "status_id": "42"}"#)
+            .create();
+
+        let filter = client.get_filters_v2().unwrap().remove(0);
+        assert_eq!(
+            filter.context,
+            vec![FilterContext::Home, FilterContext::Account]
+        );
+        assert_eq!(filter.keywords[0].keyword, "bad word");
+        let added = client
+            .add_filter_keyword(
+                &filter.id,
+                &FilterKeywordRequest::new("worse word").whole_word(),
+            )
+            .unwrap();
+        assert_eq!(added.id, "1198");
+        assert_eq!(
+            client
+                .add_filter_status(&filter.id, "42")
+                .unwrap()
+                .status_id,
+            "42"
+        );
+        filters.assert();
+        keyword.assert();
+        status.assert();
     }
 
-    /// GET /api/v1/statuses/:id/context
-    fn get_context(&self, id: &str) -> Result<Context> {
-        let route = self.route("/api/v1/statuses")?;
-        let route = route.join(id)?;
-        let route = route.join("context")?;
-        let response = self.send_blocking(self.client.get(route))?;
-        deserialise_blocking(response)
+    #[test]
+    fn test_update_list() {
+        let client = mock_client();
+        let mock = mockito::mock("PUT", "/api/v1/lists/13")
+            .match_body(mockito::Matcher::JsonString(
+                r#"{"title": "friends", "replies_policy": "followed", "exclusive": true}"#.into(),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"id": "13", "title": "friends", "replies_policy": "followed", "exclusive": true}"#,
+            )
+            .create();
+        let request = ListRequest::new("friends")
+            .replies_policy(entities::list::RepliesPolicy::Followed)
+            .exclusive(true);
+        let list = client.update_list("13", &request).unwrap();
+        assert_eq!(list.replies_policy, entities::list::RepliesPolicy::Followed);
+        assert!(list.exclusive);
+        mock.assert();
     }
 
-    /// GET /api/v1/statuses/:id/card
-    fn get_card(&self, id: &str) -> Result<Card> {
-        let route = self.route("/api/v1/statuses")?;
-        let route = route.join(id)?;
-        let route = route.join("card")?;
-        let response = self.send_blocking(self.client.get(route))?;
-        deserialise_blocking(response)
+    #[test]
+    fn test_search_v2_with() {
+        let client = mock_client();
+        let mock = mockito::mock("GET", "/api/v2/search")
+            .match_query(mockito::Matcher::AllOf(vec![
+                mockito::Matcher::UrlEncoded("q".into(), "rust".into()),
+                mockito::Matcher::UrlEncoded("type".into(), "hashtags".into()),
+                mockito::Matcher::UrlEncoded("offset".into(), "20".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"accounts": [], "statuses": [], "hashtags": [{"name": "rust",
+                    "url": "https://example.com/tags/rust", "history": [{"day": "1574553600",
+                    "uses": "7", "accounts": "3"}]}]}"#,
+            )
+            .create();
+        let request = SearchRequest::new("rust")
+            .search_type(requests::SearchType::Hashtags)
+            .offset(20);
+        let result = client.search_v2_with(&request).unwrap();
+        let history = result.hashtags[0].history.as_ref().unwrap();
+        assert_eq!(history[0].uses, 7);
+        assert_eq!(request.next_page(&result), None);
+        mock.assert();
     }
-}
 
-// Convert the HTTP response body from JSON. Pass up deserialization errors
-// transparently.
-fn deserialise_blocking<T: for<'de> serde::Deserialize<'de>>(response: Response) -> Result<T> {
-    let body = response.text()?;
+    #[test]
+    fn test_conversation_event() {
+        let conversation = format!(
+            r#"{{"id": "418450", "unread": true, "accounts": [], "last_status": {}}}"#,
+            test_fixtures::STATUS
+        );
+        let message = serde_json::json!({ "event": "conversation", "payload": conversation });
+        let stream = io::Cursor::new(format!("{}\n", message));
+        match EventReader::new(stream).next_event().unwrap() {
+            Event::Conversation(conversation) => {
+                assert_eq!(conversation.id, "418450");
+                assert_eq!(conversation.last_status.unwrap().id, "110");
+            },
+            event => panic!("unexpected event {:?}", event),
+        }
+    }
 
-    match serde_json::from_str(&body) {
-        Ok(t) => {
-            log::debug!("{}", &body);
-            Ok(t)
-        },
-        // If deserializing into the desired type fails try again to
-        // see if this is an error response.
-        Err(e) => {
-            log::error!("{}", &body);
-            if let Ok(error) = serde_json::from_str(&body) {
-                return Err(Error::Api(error));
-            }
-            Err(e.into())
-        },
+    #[test]
+    fn test_delete_event() {
+        let message = serde_json::json!({ "event": "delete", "payload": "1235\n" });
+        let stream = format!("event: delete\r\ndata: 1234\r\n\r\n{}\n", message);
+        let mut reader = EventReader::new(io::Cursor::new(stream));
+        match reader.next_event().unwrap() {
+            Event::Delete(id) => assert_eq!(id, "1234"),
+            event => panic!("unexpected event {:?}", event),
+        }
+        match reader.next_event().unwrap() {
+            Event::Delete(id) => assert_eq!(id, "1235"),
+            event => panic!("unexpected event {:?}", event),
+        }
+        let message = serde_json::json!({ "event": "delete", "payload": " " });
+        let mut reader = EventReader::new(io::Cursor::new(format!("{}\n", message)));
+        assert!(reader.next_event().is_err());
+    }
+
+    #[test]
+    fn test_event_reader_stream_parameters() {
+        let before = chrono::Utc::now();
+        let reader = EventReader::new(io::Cursor::new(String::new()));
+        assert_eq!(reader.kind(), None);
+        assert_eq!(reader.tag(), None);
+        assert_eq!(reader.url(), None);
+        assert!(reader.connected_at() >= before);
+
+        let url: url::Url = "wss://example.com/api/v1/streaming?stream=list&list=12"
+            .parse()
+            .unwrap();
+        let reader = EventReader {
+            url: Some(url.clone()),
+            kind: Some(StreamKind::List("12".to_string())),
+            ..reader
+        };
+        assert_eq!(reader.kind(), Some(&StreamKind::List("12".to_string())));
+        assert_eq!(reader.list_id(), Some("12"));
+        assert_eq!(reader.tag(), None);
+        assert_eq!(reader.url(), Some(&url));
+    }
+
+    #[test]
+    fn test_next_envelope() {
+        let status = test_fixtures::STATUS;
+        let stream = io::Cursor::new(format!(
+            ":thump\nevent: update\ndata: {}\n\nevent: filters_changed\n\n",
+            status.replace('\n', "")
+        ));
+        let mut reader = EventReader::new(stream);
+        let before = chrono::Utc::now();
+        let envelope = reader.next_envelope().unwrap();
+        assert!(matches!(envelope.event, Event::Update(ref status) if status.id == "110"));
+        assert!(envelope.received_at >= before);
+        let raw: serde_json::Value = serde_json::from_str(&envelope.raw.unwrap()).unwrap();
+        assert_eq!(
+            raw,
+            serde_json::from_str::<serde_json::Value>(status).unwrap()
+        );
+
+        let envelope = reader.next_envelope().unwrap();
+        assert_eq!(envelope.event, Event::FiltersChanged);
+        assert_eq!(envelope.raw, None);
     }
 }
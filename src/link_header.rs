@@ -0,0 +1,158 @@
+// A minimal parser for the RFC 5988 `Link` header, which Mastodon uses to
+// point at the previous and next pages of results.
+
+use crate::errors::Result;
+use url::Url;
+
+/// Extracts the `prev` and `next` links from the value of a `Link` header.
+///
+/// Entries that can't be parsed are skipped; a target that isn't a valid URL
+/// is an error.
+pub(crate) fn prev_next(header: &str) -> Result<(Option<Url>, Option<Url>)> {
+    let mut prev = None;
+    let mut next = None;
+
+    for (target, rels) in parse(header) {
+        let is_prev = rels.iter().any(|rel| rel.eq_ignore_ascii_case("prev"));
+        let is_next = rels.iter().any(|rel| rel.eq_ignore_ascii_case("next"));
+        if is_prev {
+            prev = Some(Url::parse(target)?);
+        }
+        if is_next {
+            next = Some(Url::parse(target)?);
+        }
+    }
+
+    Ok((prev, next))
+}
+
+/// Extracts the `prev` and `next` links from all the `Link` headers of a
+/// response, which servers may split a single list of links across.
+///
+/// Used by both the blocking and the async client, which only differ in how
+/// they get at the header values.
+pub(crate) fn from_headers<'a, I>(headers: I) -> Result<(Option<Url>, Option<Url>)>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut prev = None;
+    let mut next = None;
+
+    for header in headers {
+        let (header_prev, header_next) = prev_next(header)?;
+        prev = header_prev.or(prev);
+        next = header_next.or(next);
+    }
+
+    Ok((prev, next))
+}
+
+// Splits the header into `(target, rel values)` pairs
+fn parse(header: &str) -> Vec<(&str, Vec<String>)> {
+    let mut links = Vec::new();
+    let mut rest = header;
+
+    while let Some(start) = rest.find('<') {
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        let target = rest[start + 1..end].trim();
+        rest = &rest[end + 1..];
+
+        // the parameters run until the next comma outside of a quoted string
+        let mut in_quotes = false;
+        let params_end = rest
+            .char_indices()
+            .find(|&(_, c)| {
+                if c == '"' {
+                    in_quotes = !in_quotes;
+                }
+                c == ',' && !in_quotes
+            })
+            .map_or(rest.len(), |(idx, _)| idx);
+        let params = &rest[..params_end];
+        rest = &rest[params_end..];
+
+        let rels = params
+            .split(';')
+            .filter_map(|param| {
+                let (name, value) = param.split_once('=')?;
+                if name.trim().eq_ignore_ascii_case("rel") {
+                    Some(value.trim().trim_matches('"').to_string())
+                } else {
+                    None
+                }
+            })
+            .flat_map(|value| {
+                value
+                    .split_whitespace()
+                    .map(String::from)
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        links.push((target, rels));
+    }
+
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mastodon_header() {
+        let header = "<https://example.com/api/v1/timelines/home?max_id=10>; rel=\"next\", \
+                      <https://example.com/api/v1/timelines/home?min_id=20>; rel=\"prev\"";
+        let (prev, next) = prev_next(header).unwrap();
+        assert_eq!(
+            prev.unwrap().as_str(),
+            "https://example.com/api/v1/timelines/home?min_id=20"
+        );
+        assert_eq!(
+            next.unwrap().as_str(),
+            "https://example.com/api/v1/timelines/home?max_id=10"
+        );
+    }
+
+    #[test]
+    fn test_only_next() {
+        let (prev, next) = prev_next("<https://example.com/?page=2>; rel=next").unwrap();
+        assert!(prev.is_none());
+        assert_eq!(next.unwrap().as_str(), "https://example.com/?page=2");
+    }
+
+    #[test]
+    fn test_multiple_rels_and_params() {
+        let header = "<https://example.com/a,b>; title=\"x, y\"; rel=\"prev start\", \
+                      <https://example.com/c>; rel=\"other\"";
+        let (prev, next) = prev_next(header).unwrap();
+        assert_eq!(prev.unwrap().as_str(), "https://example.com/a,b");
+        assert!(next.is_none());
+    }
+
+    #[test]
+    fn test_empty_and_garbage() {
+        assert_eq!(prev_next("").unwrap(), (None, None));
+        assert_eq!(prev_next("garbage; rel=next").unwrap(), (None, None));
+        assert_eq!(prev_next("<https://example.com/").unwrap(), (None, None));
+    }
+
+    #[test]
+    fn test_from_headers() {
+        assert_eq!(from_headers(None).unwrap(), (None, None));
+        let (prev, next) = from_headers(vec![
+            "<https://example.com/?page=2>; rel=next",
+            "<https://example.com/?page=0>; rel=prev",
+        ])
+        .unwrap();
+        assert_eq!(prev.unwrap().as_str(), "https://example.com/?page=0");
+        assert_eq!(next.unwrap().as_str(), "https://example.com/?page=2");
+    }
+
+    #[test]
+    fn test_invalid_url() {
+        assert!(prev_next("<not a url>; rel=next").is_err());
+    }
+}
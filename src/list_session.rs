@@ -0,0 +1,163 @@
+//! Following a list: its recent statuses, then new ones as they arrive
+
+use std::{cmp::Ordering, collections::VecDeque, fmt};
+
+use crate::{
+    bot::compare_ids,
+    entities::{event::Event, status::Status},
+    errors::Result,
+    http_send::HttpSend,
+    mastodon_client::MastodonClient,
+    requests::PageRequest,
+};
+
+/// The statuses of a list, oldest first: first the most recent ones fetched
+/// through the REST API, then the ones received from the list's stream
+///
+/// The stream is connected before the timeline is fetched, so no status
+/// falls between the two. Only statuses newer than the last one yielded are
+/// passed on, which drops those that show up in both or more than once on
+/// the stream. Iteration ends when the stream does.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// # use std::error::Error;
+/// use elefren::list_session::ListSession;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let data = Data {
+/// #   base: "".into(),
+/// #   client_id: "".into(),
+/// #   client_secret: "".into(),
+/// #   redirect: "".into(),
+/// #   token: "".into(),
+/// #   ..Default::default()
+/// # };
+/// let client = Mastodon::from(data);
+/// for status in ListSession::start(&client, "42", 40)? {
+///     println!("{}: {}", status.account.acct, status.content);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct ListSession<S: Iterator<Item = Event>> {
+    backfill: VecDeque<Status>,
+    stream: S,
+    newest: Option<String>,
+}
+
+impl<S: Iterator<Item = Event>> fmt::Debug for ListSession<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ListSession")
+            .field("backfill", &self.backfill.len())
+            .field("newest", &self.newest)
+            .finish()
+    }
+}
+
+impl<S: Iterator<Item = Event>> ListSession<S> {
+    /// Connects to the stream of the list with id `list_id` and fetches up
+    /// to `backfill` of its most recent statuses
+    pub fn start<H, C>(client: &C, list_id: &str, backfill: usize) -> Result<Self>
+    where
+        H: HttpSend,
+        C: MastodonClient<H, Stream = S>,
+    {
+        let stream = client.streaming_list(list_id)?;
+        let mut statuses = Vec::new();
+        if backfill > 0 {
+            let request = PageRequest::new().limit(backfill.min(40));
+            for status in client
+                .get_list_timeline_with(list_id, &request)?
+                .try_items_iter()
+            {
+                statuses.push(status?);
+                if statuses.len() == backfill {
+                    break;
+                }
+            }
+        }
+        Ok(ListSession::from_parts(statuses, stream))
+    }
+
+    /// Pairs statuses fetched some other way, in any order, with a stream
+    pub fn from_parts(mut backfill: Vec<Status>, stream: S) -> Self {
+        backfill.sort_by(|a, b| compare_ids(&a.id, &b.id));
+        backfill.dedup_by(|a, b| a.id == b.id);
+        ListSession {
+            newest: backfill.last().map(|status| status.id.clone()),
+            backfill: backfill.into(),
+            stream,
+        }
+    }
+
+    /// The id of the newest status yielded so far, or about to be yielded
+    /// from the backfill
+    pub fn newest_id(&self) -> Option<&str> {
+        self.newest.as_deref()
+    }
+}
+
+impl<S: Iterator<Item = Event>> Iterator for ListSession<S> {
+    type Item = Status;
+
+    fn next(&mut self) -> Option<Status> {
+        if let Some(status) = self.backfill.pop_front() {
+            return Some(status);
+        }
+        for event in &mut self.stream {
+            let status = match event {
+                Event::Update(status) => status,
+                _ => continue,
+            };
+            let newer = match self.newest {
+                Some(ref newest) => compare_ids(&status.id, newest) == Ordering::Greater,
+                None => true,
+            };
+            if !newer {
+                continue;
+            }
+            self.newest = Some(status.id.clone());
+            return Some(status);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{entities::status::StatusId, test_fixtures::status_with_id as status};
+
+    fn ids(session: ListSession<std::vec::IntoIter<Event>>) -> Vec<String> {
+        session.map(|status| status.id).collect()
+    }
+
+    #[test]
+    fn test_backfill_then_stream() {
+        let stream = vec![
+            Event::Update(status("11")),
+            Event::Delete(StatusId::new("9").unwrap()),
+            Event::Update(status("12")),
+        ];
+        let session = ListSession::from_parts(vec![status("10"), status("9")], stream.into_iter());
+        assert_eq!(session.newest_id(), Some("10"));
+        assert_eq!(ids(session), ["9", "10", "11", "12"]);
+    }
+
+    #[test]
+    fn test_dedup_and_order() {
+        let stream = vec![
+            // already fetched
+            Event::Update(status("10")),
+            // older than anything yielded
+            Event::Update(status("8")),
+            Event::Update(status("11")),
+            Event::Update(status("11")),
+        ];
+        let session = ListSession::from_parts(vec![status("10"), status("10")], stream.into_iter());
+        assert_eq!(ids(session), ["10", "11"]);
+    }
+}
@@ -16,7 +16,7 @@ macro_rules! methods {
 
 macro_rules! paged_routes {
 
-    (($method:ident) $name:ident: $url:expr => $ret:ty, $($rest:tt)*) => {
+    (($method:ident) $name:ident $(, $name_with:ident)?: $url:expr => $ret:ty, $($rest:tt)*) => {
         doc_comment::doc_comment! {
             concat!(
             "Equivalent to `", stringify!($method), " /api/v1/",
@@ -33,6 +33,7 @@ macro_rules! paged_routes {
             "#     client_secret: \"htnjdiuae\".into(),\n",
             "#     redirect: \"https://example.com\".into(),\n",
             "#     token: \"tsaohueaheis\".into(),\n",
+            "#     ..Default::default()\n",
             "# };\n",
             "let client = Mastodon::from(data);\n",
             "client.", stringify!($name), "();\n",
@@ -40,8 +41,8 @@ macro_rules! paged_routes {
             "# }\n",
             "```"
             ),
-            fn $name(&self) -> Result<Page<$ret>> {
-                let url = self.route(concat!("/api/v1/", $url));
+            fn $name(&self) -> Result<Page<$ret, H>> {
+                let url = self.paged_url(&self.route(concat!("/api/v1/", $url)), &PageRequest::new())?;
                 let response = self.send_blocking(
                         self.client.$method(&url)
                 )?;
@@ -51,94 +52,139 @@ macro_rules! paged_routes {
 
         }
 
+        $(
+        doc_comment::doc_comment! {
+            concat!(
+            "Equivalent to `", stringify!($method), " /api/v1/",
+            $url,
+            "`, bounded by `request`\n# Errors\nIf `access_token` is not set.",
+            "\n",
+            "```no_run",
+            "# extern crate elefren;\n",
+            "# use elefren::prelude::*;\n",
+            "# use elefren::PageRequest;\n",
+            "# fn main() -> Result<(), Box<::std::error::Error>> {\n",
+            "# let data = Data {\n",
+            "#     base: \"https://example.com\".into(),\n",
+            "#     client_id: \"taosuah\".into(),\n",
+            "#     client_secret: \"htnjdiuae\".into(),\n",
+            "#     redirect: \"https://example.com\".into(),\n",
+            "#     token: \"tsaohueaheis\".into(),\n",
+            "#     ..Default::default()\n",
+            "# };\n",
+            "let client = Mastodon::from(data);\n",
+            "client.", stringify!($name_with), "(&PageRequest::new().limit(40));\n",
+            "#   Ok(())\n",
+            "# }\n",
+            "```"
+            ),
+            fn $name_with(&self, request: &PageRequest) -> Result<Page<$ret, H>> {
+                let url = self.paged_url(&self.route(concat!("/api/v1/", $url)), request)?;
+                let response = self.send_blocking(
+                        self.client.$method(&url)
+                )?;
+
+                Page::new(self, response)
+            }
+        }
+        )?
+
         paged_routes!{$($rest)*}
     };
 
-    ((get ($($(#[$m:meta])* $param:ident: $typ:ty,)*)) $name:ident: $url:expr => $ret:ty, $($rest:tt)*) => {
+    () => {}
+}
+
+macro_rules! route_v2 {
+    (($method:ident json { $param:ident: $typ:ty => $body:expr }) $name:ident: $url:expr => $ret:ty, $($rest:tt)*) => {
         doc_comment::doc_comment! {
             concat!(
-                "Equivalent to `get /api/v1/",
+                "Equivalent to `", stringify!($method), " /api/v2/",
                 $url,
-                "`\n# Errors\nIf `access_token` is not set."
+                "`\n# Errors\nIf `access_token` is not set.",
             ),
-            fn $name<'a>(&self, $($param: $typ,)*) -> Result<Page<$ret>> {
-                use serde_urlencoded;
-                use serde::Serialize;
-
-                #[derive(Serialize)]
-                struct Data<'a> {
-                    $(
-                        $(
-                        #[$m]
-                        )*
-                        $param: $typ,
-                    )*
-                    #[serde(skip)]
-                    _marker: ::std::marker::PhantomData<&'a ()>,
-                }
+            fn $name(&self, $param: $typ) -> Result<$ret> {
+                let response = self.send_blocking(
+                        self.client.$method(self.route(concat!("/api/v2/", $url)))
+                            .json(&$body)
+                )?;
 
-                let qs_data = Data {
-                    $(
-                            $param,
-                    )*
-                    _marker: ::std::marker::PhantomData,
-                };
+                let status = response.status();
 
-                let qs = serde_urlencoded::to_string(&qs_data)?;
+                if status.is_client_error() {
+                    return Err(Error::Client(status.into()));
+                } else if status.is_server_error() {
+                    return Err(Error::Server(status.into()));
+                }
 
-                let url = format!(concat!("/api/v1/", $url, "?{}"), &qs);
+                deserialise_blocking(response)
+            }
+        }
 
-                let response = self.send_blocking(
-                        self.client.get(&url)
-                )?;
+        route_v2!{$($rest)*}
+    };
 
-                Page::new(self, response)
+    (($method:ident) $name:ident: $url:expr => $ret:ty, $($rest:tt)*) => {
+        doc_comment::doc_comment! {
+            concat!(
+                "Equivalent to `", stringify!($method), " /api/v2/",
+                $url,
+                "`\n# Errors\nIf `access_token` is not set.",
+            ),
+            fn $name(&self) -> Result<$ret> {
+                self.$method(self.route(concat!("/api/v2/", $url)))
             }
         }
 
-        paged_routes!{$($rest)*}
+        route_v2!{$($rest)*}
     };
 
     () => {}
 }
 
-macro_rules! route_v2 {
-    ((get ($($param:ident: $typ:ty,)*)) $name:ident: $url:expr => $ret:ty, $($rest:tt)*) => {
+macro_rules! route_v2_id {
+
+    (($method:ident json { $param:ident: $typ:ty => $body:expr }) $name:ident: $url:expr => $ret:ty, $($rest:tt)*) => {
         doc_comment::doc_comment! {
             concat!(
-                "Equivalent to `get /api/v2/",
+                "Equivalent to `", stringify!($method), " /api/v2/",
                 $url,
-                "`\n# Errors\nIf `access_token` is not set."
+                "`\n# Errors\nIf `access_token` is not set.",
             ),
-            fn $name<'a>(&self, $($param: $typ,)*) -> Result<$ret> {
-                use serde_urlencoded;
-                use serde::Serialize;
+            fn $name(&self, id: impl AsRef<str>, $param: $typ) -> Result<$ret> {
+                let response = self.send_blocking(
+                        self.client.$method(self.route(&format!(concat!("/api/v2/", $url), crate::routes::encode_segment(id.as_ref())?)))
+                            .json(&$body)
+                )?;
 
-                #[derive(Serialize)]
-                struct Data<'a> {
-                    $(
-                        $param: $typ,
-                    )*
-                    #[serde(skip)]
-                    _marker: ::std::marker::PhantomData<&'a ()>,
-                }
+                let status = response.status();
 
-                let qs_data = Data {
-                    $(
-                            $param,
-                    )*
-                    _marker: ::std::marker::PhantomData,
-                };
+                if status.is_client_error() {
+                    return Err(Error::Client(status.into()));
+                } else if status.is_server_error() {
+                    return Err(Error::Server(status.into()));
+                }
 
-                let qs = serde_urlencoded::to_string(&qs_data)?;
+                deserialise_blocking(response)
+            }
+        }
 
-                let url = format!(concat!("/api/v2/", $url, "?{}"), &qs);
+        route_v2_id!{$($rest)*}
+    };
 
-                Ok(self.get(self.route(&url))?)
+    (($method:ident) $name:ident: $url:expr => $ret:ty, $($rest:tt)*) => {
+        doc_comment::doc_comment! {
+            concat!(
+                "Equivalent to `", stringify!($method), " /api/v2/",
+                $url,
+                "`\n# Errors\nIf `access_token` is not set.",
+            ),
+            fn $name(&self, id: impl AsRef<str>) -> Result<$ret> {
+                self.$method(self.route(&format!(concat!("/api/v2/", $url), crate::routes::encode_segment(id.as_ref())?)))
             }
         }
 
-        route_v2!{$($rest)*}
+        route_v2_id!{$($rest)*}
     };
 
     () => {}
@@ -146,38 +192,56 @@ macro_rules! route_v2 {
 
 macro_rules! route {
 
-    ((get ($($param:ident: $typ:ty,)*)) $name:ident: $url:expr => $ret:ty, $($rest:tt)*) => {
+    (($method:ident json { $param:ident: $typ:ty => $body:expr }) $name:ident: $url:expr => $ret:ty, $($rest:tt)*) => {
         doc_comment::doc_comment! {
             concat!(
-                "Equivalent to `get /api/v1/",
+                "Equivalent to `", stringify!($method), " /api/v1/",
                 $url,
-                "`\n# Errors\nIf `access_token` is not set."
+                "`\n# Errors\nIf `access_token` is not set.",
             ),
-            fn $name<'a>(&self, $($param: $typ,)*) -> Result<$ret> {
-                use serde_urlencoded;
-                use serde::Serialize;
+            fn $name(&self, $param: $typ) -> Result<$ret> {
+                let response = self.send_blocking(
+                        self.client.$method(&self.route(concat!("/api/v1/", $url)))
+                            .json(&$body)
+                )?;
 
-                #[derive(Serialize)]
-                struct Data<'a> {
-                    $(
-                        $param: $typ,
-                    )*
-                    #[serde(skip)]
-                    _marker: ::std::marker::PhantomData<&'a ()>,
+                let status = response.status();
+
+                if status.is_client_error() {
+                    return Err(Error::Client(status.into()));
+                } else if status.is_server_error() {
+                    return Err(Error::Server(status.into()));
                 }
 
-                let qs_data = Data {
-                    $(
-                            $param,
-                    )*
-                    _marker: ::std::marker::PhantomData,
-                };
+                deserialise_blocking(response)
+            }
+        }
 
-                let qs = serde_urlencoded::to_string(&qs_data)?;
+        route!{$($rest)*}
+    };
 
-                let url = format!(concat!("/api/v1/", $url, "?{}"), &qs);
+    (($method:ident multipart { $param:ident: $typ:ty => $body:expr }) $name:ident: $url:expr => $ret:ty, $($rest:tt)*) => {
+        doc_comment::doc_comment! {
+            concat!(
+                "Equivalent to `", stringify!($method), " /api/v1/",
+                $url,
+                "`\n# Errors\nIf `access_token` is not set.",
+            ),
+            fn $name(&self, $param: $typ) -> Result<$ret> {
+                let response = self.send_blocking(
+                        self.client.$method(&self.route(concat!("/api/v1/", $url)))
+                            .multipart($body)
+                )?;
+
+                let status = response.status();
+
+                if status.is_client_error() {
+                    return Err(Error::Client(status.into()));
+                } else if status.is_server_error() {
+                    return Err(Error::Server(status.into()));
+                }
 
-                Ok(self.get(self.route(&url))?)
+                deserialise_blocking(response)
             }
         }
 
@@ -207,9 +271,9 @@ macro_rules! route {
                 let status = response.status().clone();
 
                 if status.is_client_error() {
-                    return Err(Error::Client(status));
+                    return Err(Error::Client(status.into()));
                 } else if status.is_server_error() {
-                    return Err(Error::Server(status));
+                    return Err(Error::Server(status.into()));
                 }
 
                 deserialise_blocking(response)
@@ -236,6 +300,7 @@ macro_rules! route {
                 "#     client_secret: \"htnjdiuae\".into(),\n",
                 "#     redirect: \"https://example.com\".into(),\n",
                 "#     token: \"tsaohueaheis\".into(),\n",
+                "#     ..Default::default()\n",
                 "# };\n",
                 "let client = Mastodon::from(data);\n",
                 "client.", stringify!($name), "();\n",
@@ -256,42 +321,73 @@ macro_rules! route {
 
 macro_rules! route_id {
 
-    ($(($method:ident) $name:ident: $url:expr => $ret:ty,)*) => {
-        $(
-            doc_comment::doc_comment! {
-                concat!(
-                    "Equivalent to `", stringify!($method), " /api/v1/",
-                    $url,
-                    "`\n# Errors\nIf `access_token` is not set.",
-                    "\n",
-                    "```no_run",
-                    "# extern crate elefren;\n",
-                    "# use elefren::prelude::*;\n",
-                    "# fn main() -> Result<(), Box<::std::error::Error>> {\n",
-                    "# let data = Data {\n",
-                    "#     base: \"https://example.com\".into(),\n",
-                    "#     client_id: \"taosuah\".into(),\n",
-                    "#     client_secret: \"htnjdiuae\".into(),\n",
-                    "#     redirect: \"https://example.com\".into(),\n",
-                    "#     token: \"tsaohueaheis\".into(),\n",
-                    "# };\n",
-                    "let client = Mastodon::from(data);\n",
-                    "client.", stringify!($name), "(\"42\");\n",
-                    "#   Ok(())\n",
-                    "# }\n",
-                    "```"
-                ),
-                fn $name(&self, id: &str) -> Result<$ret> {
-                    self.$method(self.route(&format!(concat!("/api/v1/", $url), id)))
+    (($method:ident json { $param:ident: $typ:ty => $body:expr }) $name:ident: $url:expr => $ret:ty, $($rest:tt)*) => {
+        doc_comment::doc_comment! {
+            concat!(
+                "Equivalent to `", stringify!($method), " /api/v1/",
+                $url,
+                "`\n# Errors\nIf `access_token` is not set.",
+            ),
+            fn $name(&self, id: impl AsRef<str>, $param: $typ) -> Result<$ret> {
+                let response = self.send_blocking(
+                        self.client.$method(&self.route(&format!(concat!("/api/v1/", $url), crate::routes::encode_segment(id.as_ref())?)))
+                            .json(&$body)
+                )?;
+
+                let status = response.status();
+
+                if status.is_client_error() {
+                    return Err(Error::Client(status.into()));
+                } else if status.is_server_error() {
+                    return Err(Error::Server(status.into()));
                 }
+
+                deserialise_blocking(response)
+            }
+        }
+
+        route_id!{$($rest)*}
+    };
+
+    (($method:ident) $name:ident: $url:expr => $ret:ty, $($rest:tt)*) => {
+        doc_comment::doc_comment! {
+            concat!(
+                "Equivalent to `", stringify!($method), " /api/v1/",
+                $url,
+                "`\n# Errors\nIf `access_token` is not set.",
+                "\n",
+                "```no_run",
+                "# extern crate elefren;\n",
+                "# use elefren::prelude::*;\n",
+                "# fn main() -> Result<(), Box<::std::error::Error>> {\n",
+                "# let data = Data {\n",
+                "#     base: \"https://example.com\".into(),\n",
+                "#     client_id: \"taosuah\".into(),\n",
+                "#     client_secret: \"htnjdiuae\".into(),\n",
+                "#     redirect: \"https://example.com\".into(),\n",
+                "#     token: \"tsaohueaheis\".into(),\n",
+                "#     ..Default::default()\n",
+                "# };\n",
+                "let client = Mastodon::from(data);\n",
+                "client.", stringify!($name), "(\"42\");\n",
+                "#   Ok(())\n",
+                "# }\n",
+                "```"
+            ),
+            fn $name(&self, id: impl AsRef<str>) -> Result<$ret> {
+                self.$method(self.route(&format!(concat!("/api/v1/", $url), crate::routes::encode_segment(id.as_ref())?)))
             }
-         )*
-    }
+        }
+
+        route_id!{$($rest)*}
+    };
 
+    () => {}
 }
+
 macro_rules! paged_routes_with_id {
 
-    (($method:ident) $name:ident: $url:expr => $ret:ty, $($rest:tt)*) => {
+    (($method:ident) $name:ident $(, $name_with:ident)?: $url:expr => $ret:ty, $($rest:tt)*) => {
         doc_comment::doc_comment! {
             concat!(
                 "Equivalent to `", stringify!($method), " /api/v1/",
@@ -308,6 +404,7 @@ macro_rules! paged_routes_with_id {
                 "#     client_secret: \"htnjdiuae\".into(),\n",
                 "#     redirect: \"https://example.com\".into(),\n",
                 "#     token: \"tsaohueaheis\".into(),\n",
+                "#     ..Default::default()\n",
                 "# };\n",
                 "let client = Mastodon::from(data);\n",
                 "client.", stringify!($name), "(\"some-id\");\n",
@@ -315,8 +412,47 @@ macro_rules! paged_routes_with_id {
                 "# }\n",
                 "```"
             ),
-            fn $name(&self, id: &str) -> Result<Page<$ret>> {
-                let url = self.route(&format!(concat!("/api/v1/", $url), id));
+            fn $name(&self, id: impl AsRef<str>) -> Result<Page<$ret, H>> {
+                let url = self.paged_url(
+                    &self.route(&format!(concat!("/api/v1/", $url), crate::routes::encode_segment(id.as_ref())?)),
+                    &PageRequest::new(),
+                )?;
+                let response = self.send_blocking(
+                        self.client.$method(&url)
+                )?;
+
+                Page::new(self, response)
+            }
+        }
+
+        $(
+        doc_comment::doc_comment! {
+            concat!(
+                "Equivalent to `", stringify!($method), " /api/v1/",
+                $url,
+                "`, bounded by `request`\n# Errors\nIf `access_token` is not set.",
+                "\n",
+                "```no_run",
+                "# extern crate elefren;\n",
+                "# use elefren::prelude::*;\n",
+                "# use elefren::PageRequest;\n",
+                "# fn main() -> Result<(), Box<::std::error::Error>> {\n",
+                "# let data = Data {\n",
+                "#     base: \"https://example.com\".into(),\n",
+                "#     client_id: \"taosuah\".into(),\n",
+                "#     client_secret: \"htnjdiuae\".into(),\n",
+                "#     redirect: \"https://example.com\".into(),\n",
+                "#     token: \"tsaohueaheis\".into(),\n",
+                "#     ..Default::default()\n",
+                "# };\n",
+                "let client = Mastodon::from(data);\n",
+                "client.", stringify!($name_with), "(\"some-id\", &PageRequest::new().limit(40));\n",
+                "#   Ok(())\n",
+                "# }\n",
+                "```"
+            ),
+            fn $name_with(&self, id: impl AsRef<str>, request: &PageRequest) -> Result<Page<$ret, H>> {
+                let url = self.paged_url(&self.route(&format!(concat!("/api/v1/", $url), crate::routes::encode_segment(id.as_ref())?)), request)?;
                 let response = self.send_blocking(
                         self.client.$method(&url)
                 )?;
@@ -324,6 +460,7 @@ macro_rules! paged_routes_with_id {
                 Page::new(self, response)
             }
         }
+        )?
 
         paged_routes_with_id!{$($rest)*}
     };
@@ -1,114 +1,415 @@
-use std::borrow::Cow;
+use chrono::{DateTime, Utc};
 
 use crate::{
-    entities::prelude::*,
+    clock::{Clock, SystemClock},
+    entities::{prelude::*, status::Tag},
     errors::Result,
+    http_send::{HttpSend, HttpSender},
     media_builder::MediaBuilder,
     page::Page,
+    rate_limit::RateLimit,
     requests::{
-        AddFilterRequest, AddPushRequest, StatusesRequest, UpdateCredsRequest, UpdatePushRequest,
+        AddFilterRequest, AddFilterV2Request, AddPushRequest, DirectoryRequest, DomainBlockRequest,
+        FilterKeywordRequest, HashtagRequest, ListRequest, NotificationsRequest, PageRequest,
+        ReportRequest, SearchRequest, StatusesRequest, TimelineRequest, UpdateCredsRequest,
+        UpdatePushRequest,
     },
+    routes,
+    social_graph::SocialGraph,
     status_builder::NewStatus,
 };
 
 /// Represents the set of methods that a Mastodon Client can do, so that
 /// implementations might be swapped out for testing
+///
+/// Methods return a `Page` where the server paginates the results with
+/// `Link` headers, so that the following pages can be fetched, and a `Vec`
+/// where it returns everything at once, e.g. `get_filters`, or only takes a
+/// `limit`, e.g. `trends` and `search_accounts`.
 #[allow(unused)]
 #[async_trait::async_trait]
-pub trait MastodonClient {
+pub trait MastodonClient<H: HttpSend = HttpSender> {
     /// Type that wraps streaming API streams
     type Stream: Iterator<Item = Event>;
 
     /// GET /api/v1/favourites
-    fn favourites(&self) -> Result<Page<Status>> {
+    fn favourites(&self) -> Result<Page<Status, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/favourites, bounded by `request`
+    ///
+    /// The favourites are ordered by when they were favourited, so their
+    /// pages can't be bounded by status ids. Use the bounds of a page, from
+    /// `next_cursor`, to resume fetching them later.
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use std::error::Error;
+    /// # use elefren::prelude::*;
+    /// use elefren::PageRequest;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// let page = client.favourites_with(&PageRequest::new().limit(40))?;
+    /// // e.g. saved to disk as JSON, and read back after a restart
+    /// let checkpoint = serde_json::to_string(&page.next_cursor())?;
+    /// if let Some(cursor) = serde_json::from_str::<Option<PageRequest>>(&checkpoint)? {
+    ///     let rest = client.favourites_with(&cursor)?;
+    /// }
+    /// #   Ok(())
+    /// # }
+    /// ```
+    fn favourites_with(&self, request: &PageRequest) -> Result<Page<Status, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/bookmarks
+    fn bookmarks(&self) -> Result<Page<Status, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/bookmarks, bounded by `request`
+    ///
+    /// Like favourites, bookmarks can only be resumed from the bounds of a
+    /// page, see `favourites_with`.
+    fn bookmarks_with(&self, request: &PageRequest) -> Result<Page<Status, H>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/blocks
-    fn blocks(&self) -> Result<Page<Account>> {
+    fn blocks(&self) -> Result<Page<Account, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/blocks, bounded by `request`
+    fn blocks_with(&self, request: &PageRequest) -> Result<Page<Account, H>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/domain_blocks
-    fn domain_blocks(&self) -> Result<Page<String>> {
+    fn domain_blocks(&self) -> Result<Page<String, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/domain_blocks, bounded by `request`
+    fn domain_blocks_with(&self, request: &PageRequest) -> Result<Page<String, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/admin/domain_blocks
+    ///
+    /// The domains blocked by the instance, which only its moderators can
+    /// see. Unlike `domain_blocks`, these apply to all of its users.
+    fn admin_domain_blocks(&self) -> Result<Page<DomainBlock, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/admin/domain_blocks, bounded by `request`
+    fn admin_domain_blocks_with(&self, request: &PageRequest) -> Result<Page<DomainBlock, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// POST /api/v1/admin/domain_blocks
+    fn admin_block_domain(&self, request: &DomainBlockRequest) -> Result<DomainBlock> {
+        unimplemented!("This method was not implemented");
+    }
+    /// PUT /api/v1/admin/domain_blocks/:id
+    fn admin_update_domain_block(
+        &self,
+        id: impl AsRef<str>,
+        request: &DomainBlockRequest,
+    ) -> Result<DomainBlock> {
+        unimplemented!("This method was not implemented");
+    }
+    /// DELETE /api/v1/admin/domain_blocks/:id
+    fn admin_unblock_domain(&self, id: impl AsRef<str>) -> Result<()> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/follow_requests
-    fn follow_requests(&self) -> Result<Page<Account>> {
+    fn follow_requests(&self) -> Result<Page<Account, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/follow_requests, bounded by `request`
+    fn follow_requests_with(&self, request: &PageRequest) -> Result<Page<Account, H>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/timelines/home
-    fn get_home_timeline(&self) -> Result<Page<Status>> {
+    fn get_home_timeline(&self) -> Result<Page<Status, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/timelines/home, bounded by `request`
+    fn get_home_timeline_with(&self, request: &PageRequest) -> Result<Page<Status, H>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/custom_emojis
-    fn get_emojis(&self) -> Result<Page<Emoji>> {
+    fn get_emojis(&self) -> Result<Vec<Emoji>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/directory
+    fn directory<I: Into<Option<DirectoryRequest>>>(&self, request: I) -> Result<Vec<Account>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/trends, at most `limit` tags if given
+    fn trends<I: Into<Option<usize>>>(&self, limit: I) -> Result<Vec<Tag>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/mutes
-    fn mutes(&self) -> Result<Page<Account>> {
+    fn mutes(&self) -> Result<Page<Account, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/mutes, bounded by `request`
+    fn mutes_with(&self, request: &PageRequest) -> Result<Page<Account, H>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/notifications
-    fn notifications(&self) -> Result<Page<Notification>> {
+    fn notifications(&self) -> Result<Page<Notification, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/notifications, bounded by `request`
+    fn notifications_with(&self, request: &PageRequest) -> Result<Page<Notification, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/notifications, filtered by `options` and bounded by
+    /// `request`
+    fn notifications_with_options(
+        &self,
+        options: &NotificationsRequest,
+        request: &PageRequest,
+    ) -> Result<Page<Notification, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/conversations
+    fn conversations(&self) -> Result<Page<Conversation, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/conversations, bounded by `request`
+    fn conversations_with(&self, request: &PageRequest) -> Result<Page<Conversation, H>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/reports
-    fn reports(&self) -> Result<Page<Report>> {
+    fn reports(&self) -> Result<Page<Report, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/reports, bounded by `request`
+    fn reports_with(&self, request: &PageRequest) -> Result<Page<Report, H>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/accounts/:id/followers
-    fn followers(&self, id: &str) -> Result<Page<Account>> {
+    fn followers(&self, id: impl AsRef<str>) -> Result<Page<Account, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/accounts/:id/followers, bounded by `request`
+    fn followers_with(
+        &self,
+        id: impl AsRef<str>,
+        request: &PageRequest,
+    ) -> Result<Page<Account, H>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/accounts/:id/following
-    fn following(&self, id: &str) -> Result<Page<Account>> {
+    fn following(&self, id: impl AsRef<str>) -> Result<Page<Account, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/accounts/:id/following, bounded by `request`
+    fn following_with(
+        &self,
+        id: impl AsRef<str>,
+        request: &PageRequest,
+    ) -> Result<Page<Account, H>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/statuses/:id/reblogged_by
-    fn reblogged_by(&self, id: &str) -> Result<Page<Account>> {
+    fn reblogged_by(&self, id: impl AsRef<str>) -> Result<Page<Account, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/statuses/:id/reblogged_by, bounded by `request`
+    fn reblogged_by_with(
+        &self,
+        id: impl AsRef<str>,
+        request: &PageRequest,
+    ) -> Result<Page<Account, H>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/statuses/:id/favourited_by
-    fn favourited_by(&self, id: &str) -> Result<Page<Account>> {
+    fn favourited_by(&self, id: impl AsRef<str>) -> Result<Page<Account, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/statuses/:id/favourited_by, bounded by `request`
+    fn favourited_by_with(
+        &self,
+        id: impl AsRef<str>,
+        request: &PageRequest,
+    ) -> Result<Page<Account, H>> {
         unimplemented!("This method was not implemented");
     }
     /// DELETE /api/v1/domain_blocks
-    fn unblock_domain(&self, domain: String) -> Result<Empty> {
+    fn unblock_domain(&self, domain: impl AsRef<str>) -> Result<()> {
+        unimplemented!("This method was not implemented");
+    }
+    /// Whether `domain` is blocked, looking through all pages of
+    /// `domain_blocks`
+    fn is_domain_blocked(&self, domain: impl AsRef<str>) -> Result<bool> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/instance
     fn instance(&self) -> Result<Instance> {
         unimplemented!("This method was not implemented");
     }
+    /// GET /api/v1/instance/peers
+    ///
+    /// Large instances know tens of thousands of peers; consider
+    /// `peers_matching`, or `MastodonBuilder::max_body_size` to bound how
+    /// much is read.
+    fn instance_peers(&self) -> Result<Vec<String>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/instance/peers, only keeping the domains that contain
+    /// `text`, ignoring case
+    ///
+    /// The other domains are dropped as the response is parsed, rather than
+    /// all being collected first. Like any other response, it's bounded by
+    /// `MastodonBuilder::max_body_size`.
+    fn peers_matching(&self, text: impl AsRef<str>) -> Result<Vec<String>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/instance/translation_languages
+    ///
+    /// Useful to know whether a status can be translated before asking for
+    /// a translation.
+    fn instance_translation_languages(&self) -> Result<TranslationLanguages> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/instance/rules
+    ///
+    /// The ids of these rules are the ones a report can be for, see
+    /// `ReportRequest::rules_matching` and `ReportRequest::validate_rules`.
+    fn instance_rules(&self) -> Result<Vec<Rule>> {
+        unimplemented!("This method was not implemented");
+    }
     /// GET /api/v1/accounts/verify_credentials
     fn verify_credentials(&self) -> Result<Account> {
         unimplemented!("This method was not implemented");
     }
+    /// GET /api/v1/apps/verify_credentials
+    ///
+    /// The returned `Application` includes the `vapid_key` needed to
+    /// subscribe to push notifications.
+    fn verify_app_credentials(&self) -> Result<Application> {
+        unimplemented!("This method was not implemented");
+    }
     /// POST /api/v1/reports
+    #[deprecated(since = "0.23.0", note = "use `report_with` and `ReportRequest`")]
     fn report(&self, account_id: &str, status_ids: Vec<&str>, comment: String) -> Result<Report> {
         unimplemented!("This method was not implemented");
     }
+    /// POST /api/v1/reports, with the statuses, comment, category and rules
+    /// of `request`
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use std::error::Error;
+    /// # use elefren::prelude::*;
+    /// use elefren::{entities::report::ReportCategory, requests::ReportRequest};
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// let request = ReportRequest::new("42")
+    ///     .status_id("108882889550545820")
+    ///     .rule_id("2")
+    ///     .comment("slurs in replies");
+    /// let report = client.report_with(&request)?;
+    /// assert_eq!(report.category, Some(ReportCategory::Violation));
+    /// #   Ok(())
+    /// # }
+    /// ```
+    fn report_with(&self, request: &ReportRequest) -> Result<Report> {
+        unimplemented!("This method was not implemented");
+    }
     /// POST /api/v1/domain_blocks
-    fn block_domain(&self, domain: String) -> Result<Empty> {
+    fn block_domain(&self, domain: impl AsRef<str>) -> Result<()> {
+        unimplemented!("This method was not implemented");
+    }
+    /// POST /api/v1/follow_requests/:id/authorize
+    fn authorize_follow_request(&self, id: impl AsRef<str>) -> Result<()> {
+        unimplemented!("This method was not implemented");
+    }
+    /// POST /api/v1/follow_requests/:id/reject
+    fn reject_follow_request(&self, id: impl AsRef<str>) -> Result<()> {
         unimplemented!("This method was not implemented");
     }
-    /// POST /api/v1/accounts/follow_requests/authorize
-    fn authorize_follow_request(&self, id: &str) -> Result<Empty> {
+    /// The rate limit reported by the server in its last response, if any
+    fn rate_limit(&self) -> Option<RateLimit> {
+        None
+    }
+    /// The clock used to wait for the rate limit to reset, and for the time
+    /// of exports
+    fn clock(&self) -> &dyn Clock {
+        &SystemClock
+    }
+    /// The number of pending follow requests
+    fn follow_requests_count(&self) -> Result<usize> {
+        unimplemented!("This method was not implemented");
+    }
+    /// Goes through all pending follow requests, authorizing those for which
+    /// `predicate` returns `true` and rejecting the rest. Returns the number
+    /// of authorized and rejected requests.
+    fn authorize_all_follow_requests<F>(&self, predicate: F) -> Result<(usize, usize)>
+    where
+        F: FnMut(&Account) -> bool,
+    {
         unimplemented!("This method was not implemented");
     }
-    /// POST /api/v1/accounts/follow_requests/reject
-    fn reject_follow_request(&self, id: &str) -> Result<Empty> {
+    /// All accounts following the account with id `account_id` and followed
+    /// by it, for backups or network analysis
+    fn export_social_graph(&self, account_id: impl AsRef<str>) -> Result<SocialGraph> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/search
-    fn search<'a>(&self, q: &'a str, resolve: bool) -> Result<SearchResult> {
+    fn search(&self, q: impl AsRef<str>, resolve: bool) -> Result<SearchResult> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v2/search
-    fn search_v2<'a>(&self, q: &'a str, resolve: bool) -> Result<SearchResultV2> {
+    fn search_v2(&self, q: impl AsRef<str>, resolve: bool) -> Result<SearchResultV2> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v2/search, with the options and offset of `request`
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use std::error::Error;
+    /// # use elefren::prelude::*;
+    /// use elefren::requests::{SearchRequest, SearchType};
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// # let client = Mastodon::from(data);
+    /// let mut request = Some(SearchRequest::new("rust").search_type(SearchType::Hashtags));
+    /// while let Some(current) = request {
+    ///     let result = client.search_v2_with(&current)?;
+    ///     for tag in &result.hashtags {
+    ///         println!("#{}", tag.name);
+    ///     }
+    ///     request = current.next_page(&result);
+    /// }
+    /// #   Ok(())
+    /// # }
+    /// ```
+    fn search_v2_with(&self, request: &SearchRequest) -> Result<SearchResultV2> {
         unimplemented!("This method was not implemented");
     }
     /// POST /api/v1/follows
-    fn follows(&self, uri: Cow<'static, str>) -> Result<Account> {
+    fn follows(&self, uri: impl AsRef<str>) -> Result<Account> {
         unimplemented!("This method was not implemented");
     }
     /// POST /api/v1/media
@@ -116,75 +417,83 @@ pub trait MastodonClient {
         unimplemented!("This method was not implemented");
     }
     /// POST /api/v1/notifications/clear
-    fn clear_notifications(&self) -> Result<Empty> {
+    fn clear_notifications(&self) -> Result<()> {
         unimplemented!("This method was not implemented");
     }
     /// POST /api/v1/notifications/dismiss
-    fn dismiss_notification(&self, id: &str) -> Result<Empty> {
+    fn dismiss_notification(&self, id: impl AsRef<str>) -> Result<()> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/accounts/:id
-    fn get_account(&self, id: &str) -> Result<Account> {
+    fn get_account(&self, id: impl AsRef<str>) -> Result<Account> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/accounts/:id/identity_proofs
+    fn identity_proofs(&self, id: impl AsRef<str>) -> Result<Vec<IdentityProof>> {
         unimplemented!("This method was not implemented");
     }
     /// POST /api/v1/accounts/:id/follow
-    fn follow(&self, id: &str) -> Result<Relationship> {
+    fn follow(&self, id: impl AsRef<str>) -> Result<Relationship> {
         unimplemented!("This method was not implemented");
     }
     /// POST /api/v1/accounts/:id/unfollow
-    fn unfollow(&self, id: &str) -> Result<Relationship> {
+    fn unfollow(&self, id: impl AsRef<str>) -> Result<Relationship> {
         unimplemented!("This method was not implemented");
     }
-    /// GET /api/v1/accounts/:id/block
-    fn block(&self, id: &str) -> Result<Relationship> {
+    /// POST /api/v1/accounts/:id/block
+    fn block(&self, id: impl AsRef<str>) -> Result<Relationship> {
         unimplemented!("This method was not implemented");
     }
-    /// GET /api/v1/accounts/:id/unblock
-    fn unblock(&self, id: &str) -> Result<Relationship> {
+    /// POST /api/v1/accounts/:id/unblock
+    fn unblock(&self, id: impl AsRef<str>) -> Result<Relationship> {
         unimplemented!("This method was not implemented");
     }
-    /// GET /api/v1/accounts/:id/mute
-    fn mute(&self, id: &str) -> Result<Relationship> {
+    /// POST /api/v1/accounts/:id/mute
+    fn mute(&self, id: impl AsRef<str>) -> Result<Relationship> {
         unimplemented!("This method was not implemented");
     }
-    /// GET /api/v1/accounts/:id/unmute
-    fn unmute(&self, id: &str) -> Result<Relationship> {
+    /// POST /api/v1/accounts/:id/unmute
+    fn unmute(&self, id: impl AsRef<str>) -> Result<Relationship> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/notifications/:id
-    fn get_notification(&self, id: &str) -> Result<Notification> {
+    fn get_notification(&self, id: impl AsRef<str>) -> Result<Notification> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/statuses/:id
-    fn get_status(&self, id: &str) -> Result<Status> {
+    fn get_status(&self, id: impl AsRef<str>) -> Result<Status> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/statuses/:id/context
-    fn get_context(&self, id: &str) -> Result<Context> {
+    ///
+    /// Large threads may be cut short by the server, see
+    /// `Context::may_be_truncated`. `entities::context::Thread` assembles the
+    /// result into a tree.
+    fn get_context(&self, id: impl AsRef<str>) -> Result<Context> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/statuses/:id/card
-    fn get_card(&self, id: &str) -> Result<Card> {
+    fn get_card(&self, id: impl AsRef<str>) -> Result<Card> {
         unimplemented!("This method was not implemented");
     }
     /// POST /api/v1/statuses/:id/reblog
-    fn reblog(&self, id: &str) -> Result<Status> {
+    fn reblog(&self, id: impl AsRef<str>) -> Result<Status> {
         unimplemented!("This method was not implemented");
     }
     /// POST /api/v1/statuses/:id/unreblog
-    fn unreblog(&self, id: &str) -> Result<Status> {
+    fn unreblog(&self, id: impl AsRef<str>) -> Result<Status> {
         unimplemented!("This method was not implemented");
     }
     /// POST /api/v1/statuses/:id/favourite
-    fn favourite(&self, id: &str) -> Result<Status> {
+    fn favourite(&self, id: impl AsRef<str>) -> Result<Status> {
         unimplemented!("This method was not implemented");
     }
     /// POST /api/v1/statuses/:id/unfavourite
-    fn unfavourite(&self, id: &str) -> Result<Status> {
+    fn unfavourite(&self, id: impl AsRef<str>) -> Result<Status> {
         unimplemented!("This method was not implemented");
     }
     /// DELETE /api/v1/statuses/:id
-    fn delete_status(&self, id: &str) -> Result<Empty> {
+    fn delete_status(&self, id: impl AsRef<str>) -> Result<()> {
         unimplemented!("This method was not implemented");
     }
     /// PATCH /api/v1/accounts/update_credentials
@@ -195,36 +504,179 @@ pub trait MastodonClient {
     fn new_status(&self, status: NewStatus) -> Result<Status> {
         unimplemented!("This method was not implemented");
     }
+    /// POST /api/v2/media for each of `media`, then POST /api/v1/statuses
+    /// with the uploaded attachments added to `status`
+    ///
+    /// Uploads of larger files may be answered before the server has
+    /// processed them; each attachment is waited for, checking once a second
+    /// for up to a minute, as statuses can't be posted with unprocessed
+    /// media.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// use elefren::MediaBuilder;
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// let status = StatusBuilder::new().status("the view today").build()?;
+    /// let photo = MediaBuilder::new("view.jpg".into())
+    ///     .description("A snowy mountain behind a lake".into());
+    /// client.post_status_with_media(status, vec![photo])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn post_status_with_media(
+        &self,
+        status: NewStatus,
+        media: Vec<MediaBuilder>,
+    ) -> Result<Status> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/media/:id
+    fn get_media(&self, id: impl AsRef<str>) -> Result<Attachment> {
+        unimplemented!("This method was not implemented");
+    }
+    /// POST /api/v1/statuses, for a status built with
+    /// `StatusBuilder::scheduled_at`
+    fn schedule_status(&self, status: NewStatus) -> Result<ScheduledStatus> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/pleroma/accounts/:id/favourites
+    ///
+    /// The statuses an account has favourited, if it made them public. This
+    /// is a Pleroma and Akkoma extension of the API.
+    #[cfg(feature = "pleroma")]
+    fn pleroma_favourites(&self, id: impl AsRef<str>) -> Result<Page<Status, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/pleroma/accounts/:id/favourites, bounded by `request`
+    #[cfg(feature = "pleroma")]
+    fn pleroma_favourites_with(
+        &self,
+        id: impl AsRef<str>,
+        request: &PageRequest,
+    ) -> Result<Page<Status, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/scheduled_statuses
+    fn scheduled_statuses(&self) -> Result<Page<ScheduledStatus, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/scheduled_statuses, bounded by `request`
+    fn scheduled_statuses_with(&self, request: &PageRequest) -> Result<Page<ScheduledStatus, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/scheduled_statuses/:id
+    fn get_scheduled_status(&self, id: impl AsRef<str>) -> Result<ScheduledStatus> {
+        unimplemented!("This method was not implemented");
+    }
+    /// PUT /api/v1/scheduled_statuses/:id
+    fn update_scheduled_status(
+        &self,
+        id: impl AsRef<str>,
+        scheduled_at: DateTime<Utc>,
+    ) -> Result<ScheduledStatus> {
+        unimplemented!("This method was not implemented");
+    }
+    /// DELETE /api/v1/scheduled_statuses/:id
+    fn delete_scheduled_status(&self, id: impl AsRef<str>) -> Result<()> {
+        unimplemented!("This method was not implemented");
+    }
     /// GET /api/v1/timelines/public?local=true
-    fn get_local_timeline(&self) -> Result<Page<Status>> {
+    fn get_local_timeline(&self) -> Result<Page<Status, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/timelines/public?local=true, bounded by `request`
+    fn get_local_timeline_with(&self, request: &PageRequest) -> Result<Page<Status, H>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/timelines/public?local=false
-    fn get_federated_timeline(&self) -> Result<Page<Status>> {
+    fn get_federated_timeline(&self) -> Result<Page<Status, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/timelines/public?local=false, bounded by `request`
+    fn get_federated_timeline_with(&self, request: &PageRequest) -> Result<Page<Status, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/timelines/public, filtered by `options` and bounded by
+    /// `request`
+    fn get_public_timeline_with_options(
+        &self,
+        options: &TimelineRequest,
+        request: &PageRequest,
+    ) -> Result<Page<Status, H>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/timelines/tag/:hashtag
-    fn get_hashtag_timeline(&self, hashtag: &str, local: bool) -> Result<Page<Status>> {
+    fn get_hashtag_timeline(
+        &self,
+        hashtag: impl AsRef<str>,
+        local: bool,
+    ) -> Result<Page<Status, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/timelines/tag/:hashtag, bounded by `request`
+    fn get_hashtag_timeline_with(
+        &self,
+        hashtag: impl AsRef<str>,
+        local: bool,
+        request: &PageRequest,
+    ) -> Result<Page<Status, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/timelines/tag/:hashtag, filtered by `options` and bounded
+    /// by `request`
+    fn get_hashtag_timeline_with_options(
+        &self,
+        hashtag: impl AsRef<str>,
+        options: &HashtagRequest,
+        request: &PageRequest,
+    ) -> Result<Page<Status, H>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/accounts/:id/statuses
-    fn statuses<'a, 'b: 'a, S>(&'b self, id: &'b str, request: S) -> Result<Page<Status>>
+    fn statuses<'a, 'b: 'a, S>(&'b self, id: impl AsRef<str>, request: S) -> Result<Page<Status, H>>
     where
         S: Into<Option<StatusesRequest<'a>>>,
     {
         unimplemented!("This method was not implemented");
     }
+    /// GET /api/v1/accounts/:id/statuses?pinned=1, then
+    /// GET /api/v1/accounts/:id/statuses for the first page of statuses
+    fn profile_view(&self, id: impl AsRef<str>) -> Result<ProfileView> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/accounts/:id/statuses, following the pages until `limit`
+    /// statuses have been fetched
+    fn statuses_all(
+        &self,
+        id: impl AsRef<str>,
+        request: StatusesRequest,
+        limit: Option<usize>,
+    ) -> Result<Vec<Status>> {
+        unimplemented!("This method was not implemented");
+    }
     /// GET /api/v1/accounts/relationships
-    fn relationships(&self, ids: &[&str]) -> Result<Page<Relationship>> {
+    fn relationships(&self, ids: &[impl AsRef<str>]) -> Result<Vec<Relationship>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/accounts/search?q=:query&limit=:limit&following=:following
     fn search_accounts(
         &self,
-        query: &str,
+        query: impl AsRef<str>,
         limit: Option<u64>,
         following: bool,
-    ) -> Result<Page<Account>> {
+    ) -> Result<Vec<Account>> {
         unimplemented!("This method was not implemented");
     }
     /// POST /api/v1/push/subscription
@@ -240,7 +692,7 @@ pub trait MastodonClient {
         unimplemented!("This method was not implemented");
     }
     /// DELETE /api/v1/push/subscription
-    fn delete_push_subscription(&self) -> Result<Empty> {
+    fn delete_push_subscription(&self) -> Result<()> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/filters
@@ -252,35 +704,185 @@ pub trait MastodonClient {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/filters/:id
-    fn get_filter(&self, id: &str) -> Result<Filter> {
+    fn get_filter(&self, id: impl AsRef<str>) -> Result<Filter> {
         unimplemented!("This method was not implemented");
     }
     /// PUT /api/v1/filters/:id
-    fn update_filter(&self, id: &str, request: &mut AddFilterRequest) -> Result<Filter> {
+    fn update_filter(&self, id: impl AsRef<str>, request: &mut AddFilterRequest) -> Result<Filter> {
         unimplemented!("This method was not implemented");
     }
     /// DELETE /api/v1/filters/:id
-    fn delete_filter(&self, id: &str) -> Result<Empty> {
+    fn delete_filter(&self, id: impl AsRef<str>) -> Result<()> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/lists
+    fn get_lists(&self) -> Result<Vec<List>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// POST /api/v1/lists
+    fn create_list(&self, request: &ListRequest) -> Result<List> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/lists/:id
+    fn get_list(&self, id: impl AsRef<str>) -> Result<List> {
+        unimplemented!("This method was not implemented");
+    }
+    /// PUT /api/v1/lists/:id
+    fn update_list(&self, id: impl AsRef<str>, request: &ListRequest) -> Result<List> {
+        unimplemented!("This method was not implemented");
+    }
+    /// DELETE /api/v1/lists/:id
+    fn delete_list(&self, id: impl AsRef<str>) -> Result<()> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/lists/:id/accounts
+    fn list_accounts(&self, id: impl AsRef<str>) -> Result<Page<Account, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/lists/:id/accounts, bounded by `request`
+    fn list_accounts_with(
+        &self,
+        id: impl AsRef<str>,
+        request: &PageRequest,
+    ) -> Result<Page<Account, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// POST /api/v1/lists/:id/accounts
+    ///
+    /// Only accounts the user follows can be added.
+    fn add_list_accounts(
+        &self,
+        id: impl AsRef<str>,
+        account_ids: &[impl AsRef<str>],
+    ) -> Result<()> {
+        unimplemented!("This method was not implemented");
+    }
+    /// DELETE /api/v1/lists/:id/accounts
+    fn remove_list_accounts(
+        &self,
+        id: impl AsRef<str>,
+        account_ids: &[impl AsRef<str>],
+    ) -> Result<()> {
+        unimplemented!("This method was not implemented");
+    }
+    /// Makes the accounts with `account_ids` the members of the list with id
+    /// `id`, adding and removing only the accounts that differ
+    fn reconcile_list(
+        &self,
+        id: impl AsRef<str>,
+        account_ids: &[impl AsRef<str>],
+    ) -> Result<ListReconciliation> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/timelines/list/:id
+    fn get_list_timeline(&self, id: impl AsRef<str>) -> Result<Page<Status, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/timelines/list/:id, bounded by `request`
+    fn get_list_timeline_with(
+        &self,
+        id: impl AsRef<str>,
+        request: &PageRequest,
+    ) -> Result<Page<Status, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v2/filters
+    fn get_filters_v2(&self) -> Result<Vec<FilterV2>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// POST /api/v2/filters
+    fn add_filter_v2(&self, request: &AddFilterV2Request) -> Result<FilterV2> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v2/filters/:id
+    fn get_filter_v2(&self, id: impl AsRef<str>) -> Result<FilterV2> {
+        unimplemented!("This method was not implemented");
+    }
+    /// DELETE /api/v2/filters/:id
+    fn delete_filter_v2(&self, id: impl AsRef<str>) -> Result<()> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v2/filters/:filter_id/keywords
+    fn filter_keywords(&self, filter_id: impl AsRef<str>) -> Result<Vec<FilterKeyword>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// POST /api/v2/filters/:filter_id/keywords
+    fn add_filter_keyword(
+        &self,
+        filter_id: impl AsRef<str>,
+        request: &FilterKeywordRequest,
+    ) -> Result<FilterKeyword> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v2/filters/keywords/:id
+    fn get_filter_keyword(&self, id: impl AsRef<str>) -> Result<FilterKeyword> {
+        unimplemented!("This method was not implemented");
+    }
+    /// PUT /api/v2/filters/keywords/:id
+    fn update_filter_keyword(
+        &self,
+        id: impl AsRef<str>,
+        request: &FilterKeywordRequest,
+    ) -> Result<FilterKeyword> {
+        unimplemented!("This method was not implemented");
+    }
+    /// DELETE /api/v2/filters/keywords/:id
+    fn delete_filter_keyword(&self, id: impl AsRef<str>) -> Result<()> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v2/filters/:filter_id/statuses
+    fn filter_statuses(&self, filter_id: impl AsRef<str>) -> Result<Vec<FilterStatus>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// POST /api/v2/filters/:filter_id/statuses
+    ///
+    /// Makes the filter apply to the status `status_id`, whatever its
+    /// content.
+    fn add_filter_status(
+        &self,
+        filter_id: impl AsRef<str>,
+        status_id: impl AsRef<str>,
+    ) -> Result<FilterStatus> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v2/filters/statuses/:id
+    fn get_filter_status(&self, id: impl AsRef<str>) -> Result<FilterStatus> {
+        unimplemented!("This method was not implemented");
+    }
+    /// DELETE /api/v2/filters/statuses/:id
+    fn delete_filter_status(&self, id: impl AsRef<str>) -> Result<()> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/suggestions
+    #[deprecated(
+        since = "0.23.0",
+        note = "use `get_follow_suggestions_v2`, which says why each account is suggested"
+    )]
     fn get_follow_suggestions(&self) -> Result<Vec<Account>> {
         unimplemented!("This method was not implemented");
     }
+    /// GET /api/v2/suggestions
+    fn get_follow_suggestions_v2(&self) -> Result<Vec<Suggestion>> {
+        unimplemented!("This method was not implemented");
+    }
     /// DELETE /api/v1/suggestions/:account_id
-    fn delete_from_suggestions(&self, id: &str) -> Result<Empty> {
+    fn delete_from_suggestions(&self, id: impl AsRef<str>) -> Result<()> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/endorsements
-    fn get_endorsements(&self) -> Result<Page<Account>> {
+    fn get_endorsements(&self) -> Result<Page<Account, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/endorsements, bounded by `request`
+    fn get_endorsements_with(&self, request: &PageRequest) -> Result<Page<Account, H>> {
         unimplemented!("This method was not implemented");
     }
     /// POST /api/v1/accounts/:id/pin
-    fn endorse_user(&self, id: &str) -> Result<Relationship> {
+    fn endorse_user(&self, id: impl AsRef<str>) -> Result<Relationship> {
         unimplemented!("This method was not implemented");
     }
     /// POST /api/v1/accounts/:id/unpin
-    fn unendorse_user(&self, id: &str) -> Result<Relationship> {
+    fn unendorse_user(&self, id: impl AsRef<str>) -> Result<Relationship> {
         unimplemented!("This method was not implemented");
     }
     /// Shortcut for: `let me = client.verify_credentials(); client.followers()`
@@ -296,12 +898,13 @@ pub trait MastodonClient {
     /// #   client_secret: "".into(),
     /// #   redirect: "".into(),
     /// #   token: "".into(),
+    /// #   ..Default::default()
     /// # };
     /// # let client = Mastodon::from(data);
     /// let follows_me = client.follows_me()?;
     /// #   Ok(())
     /// # }
-    fn follows_me(&self) -> Result<Page<Account>> {
+    fn follows_me(&self) -> Result<Page<Account, H>> {
         unimplemented!("This method was not implemented");
     }
     /// Shortcut for
@@ -318,48 +921,101 @@ pub trait MastodonClient {
     /// #   client_secret: "".into(),
     /// #   redirect: "".into(),
     /// #   token: "".into(),
+    /// #   ..Default::default()
     /// # };
     /// # let client = Mastodon::from(data);
     /// let follows_me = client.followed_by_me()?;
     /// #   Ok(())
     /// # }
-    fn followed_by_me(&self) -> Result<Page<Account>> {
+    fn followed_by_me(&self) -> Result<Page<Account, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// Shortcut for
+    /// `client.statuses(&client.me()?.id, StatusesRequest::new().pinned())`
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use std::error::Error;
+    /// # use elefren::prelude::*;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// # let client = Mastodon::from(data);
+    /// let pinned = client.my_pinned_statuses()?;
+    /// #   Ok(())
+    /// # }
+    fn my_pinned_statuses(&self) -> Result<Page<Status, H>> {
         unimplemented!("This method was not implemented");
     }
 
     /// Returns events that are relevant to the authorized user, i.e. home
     /// timeline and notifications
     fn streaming_user(&self) -> Result<Self::Stream> {
-        unimplemented!("This method was not implemented");
+        self.streaming(StreamKind::User)
+    }
+
+    /// Returns only the notifications of the authorized user, without its
+    /// home timeline, for much less traffic when those are all that's needed
+    fn streaming_user_notifications(&self) -> Result<Self::Stream> {
+        self.streaming(StreamKind::UserNotification)
     }
 
     /// Returns all public statuses
     fn streaming_public(&self) -> Result<Self::Stream> {
-        unimplemented!("This method was not implemented");
+        self.streaming(StreamKind::Public {
+            local: false,
+            media_only: false,
+        })
     }
 
     /// Returns all local statuses
     fn streaming_local(&self) -> Result<Self::Stream> {
-        unimplemented!("This method was not implemented");
+        self.streaming(StreamKind::Public {
+            local: true,
+            media_only: false,
+        })
     }
 
     /// Returns all public statuses for a particular hashtag
-    fn streaming_public_hashtag(&self, hashtag: &str) -> Result<Self::Stream> {
-        unimplemented!("This method was not implemented");
+    fn streaming_public_hashtag(&self, hashtag: impl AsRef<str>) -> Result<Self::Stream> {
+        self.streaming_hashtag(hashtag, &HashtagRequest::new())
     }
 
     /// Returns all local statuses for a particular hashtag
-    fn streaming_local_hashtag(&self, hashtag: &str) -> Result<Self::Stream> {
-        unimplemented!("This method was not implemented");
+    fn streaming_local_hashtag(&self, hashtag: impl AsRef<str>) -> Result<Self::Stream> {
+        self.streaming_hashtag(hashtag, &HashtagRequest::new().local())
+    }
+
+    /// Returns the statuses for a particular hashtag, filtered by `options`
+    fn streaming_hashtag(
+        &self,
+        hashtag: impl AsRef<str>,
+        options: &HashtagRequest,
+    ) -> Result<Self::Stream> {
+        self.streaming(StreamKind::hashtag(hashtag.as_ref(), options))
     }
 
     /// Returns statuses for a list
-    fn streaming_list(&self, list_id: &str) -> Result<Self::Stream> {
-        unimplemented!("This method was not implemented");
+    fn streaming_list(&self, list_id: impl AsRef<str>) -> Result<Self::Stream> {
+        self.streaming(StreamKind::List(list_id.as_ref().to_string()))
     }
 
     /// Returns all direct messages
     fn streaming_direct(&self) -> Result<Self::Stream> {
+        self.streaming(StreamKind::Direct)
+    }
+
+    /// Returns the events of `stream`.
+    ///
+    /// All the `streaming_*` methods are implemented in terms of this one, so
+    /// it is the only one a mock or a wrapper needs to provide.
+    fn streaming(&self, stream: StreamKind) -> Result<Self::Stream> {
         unimplemented!("This method was not implemented");
     }
 }
@@ -367,12 +1023,16 @@ pub trait MastodonClient {
 /// Trait that represents clients that can make unauthenticated calls to a
 /// mastodon instance
 #[allow(unused)]
-pub trait MastodonUnauthenticated {
+pub trait MastodonUnauthenticated<H: HttpSend = HttpSender> {
     /// GET /api/v1/statuses/:id
     fn get_status(&self, id: &str) -> Result<Status> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/statuses/:id/context
+    ///
+    /// Large threads may be cut short by the server, see
+    /// `Context::may_be_truncated`. `entities::context::Thread` assembles the
+    /// result into a tree.
     fn get_context(&self, id: &str) -> Result<Context> {
         unimplemented!("This method was not implemented");
     }
@@ -381,11 +1041,175 @@ pub trait MastodonUnauthenticated {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/statuses/:id/reblogged_by
-    fn reblogged_by(&self, id: &str) -> Result<Page<Account>> {
+    fn reblogged_by(&self, id: &str) -> Result<Page<Account, H>> {
         unimplemented!("This method was not implemented");
     }
     /// GET /api/v1/statuses/:id/favourited_by
-    fn favourited_by(&self, id: &str) -> Result<Page<Account>> {
+    fn favourited_by(&self, id: &str) -> Result<Page<Account, H>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/custom_emojis
+    fn custom_emojis(&self) -> Result<Vec<Emoji>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/instance/rules, e.g. to show them before signing up
+    fn instance_rules(&self) -> Result<Vec<Rule>> {
         unimplemented!("This method was not implemented");
     }
+    /// GET /api/v1/directory
+    fn directory<I: Into<Option<DirectoryRequest>>>(&self, request: I) -> Result<Vec<Account>> {
+        unimplemented!("This method was not implemented");
+    }
+    /// GET /api/v1/trends, at most `limit` tags if given
+    fn trends<I: Into<Option<usize>>>(&self, limit: I) -> Result<Vec<Tag>> {
+        unimplemented!("This method was not implemented");
+    }
+}
+
+/// The streams that can be subscribed to through the streaming API
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::StreamKind;
+///
+/// let stream = StreamKind::Hashtag {
+///     tag: "rust".to_string(),
+///     local: true,
+///     media_only: false,
+/// };
+/// assert_eq!(stream.name(), "hashtag:local");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StreamKind {
+    /// Events relevant to the authorized user: home timeline and
+    /// notifications
+    User,
+    /// Only the notifications of the authorized user
+    UserNotification,
+    /// All public statuses
+    Public {
+        /// Only statuses originating from this instance
+        local: bool,
+        /// Only statuses with media attachments
+        media_only: bool,
+    },
+    /// All public statuses with a particular hashtag
+    Hashtag {
+        /// The hashtag, without the leading `#`
+        tag: String,
+        /// Only statuses originating from this instance
+        local: bool,
+        /// Only statuses with media attachments
+        media_only: bool,
+    },
+    /// Statuses for the list with this id
+    List(String),
+    /// Direct messages
+    Direct,
+}
+
+impl StreamKind {
+    /// The hashtag stream for `tag`, filtered by `options`. The tag is
+    /// normalized the same way as for the hashtag timeline.
+    pub fn hashtag(tag: &str, options: &HashtagRequest) -> StreamKind {
+        StreamKind::Hashtag {
+            tag: routes::tag_name(tag),
+            local: options.local,
+            media_only: options.only_media,
+        }
+    }
+
+    /// The name of the stream, as used by the streaming API
+    pub fn name(&self) -> &'static str {
+        match *self {
+            StreamKind::User => "user",
+            StreamKind::UserNotification => "user:notification",
+            StreamKind::Public {
+                local: false,
+                media_only: false,
+            } => "public",
+            StreamKind::Public {
+                local: true,
+                media_only: false,
+            } => "public:local",
+            StreamKind::Public {
+                local: false,
+                media_only: true,
+            } => "public:media",
+            StreamKind::Public {
+                local: true,
+                media_only: true,
+            } => "public:local:media",
+            StreamKind::Hashtag { local: false, .. } => "hashtag",
+            StreamKind::Hashtag { local: true, .. } => "hashtag:local",
+            StreamKind::List(_) => "list",
+            StreamKind::Direct => "direct",
+        }
+    }
+
+    /// The query parameters selecting this stream
+    #[cfg_attr(not(feature = "websocket"), allow(dead_code))]
+    pub(crate) fn query_pairs(&self) -> Vec<(&'static str, &str)> {
+        let mut pairs = vec![("stream", self.name())];
+        match *self {
+            StreamKind::Hashtag {
+                ref tag,
+                media_only,
+                ..
+            } => {
+                pairs.push(("tag", tag));
+                if media_only {
+                    pairs.push(("only_media", "1"));
+                }
+            },
+            StreamKind::List(ref id) => pairs.push(("list", id)),
+            _ => {},
+        }
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream_kind_query_pairs() {
+        assert_eq!(StreamKind::User.query_pairs(), vec![("stream", "user")]);
+        assert_eq!(
+            StreamKind::UserNotification.query_pairs(),
+            vec![("stream", "user:notification")]
+        );
+        assert_eq!(
+            StreamKind::Public {
+                local: true,
+                media_only: true
+            }
+            .query_pairs(),
+            vec![("stream", "public:local:media")]
+        );
+        assert_eq!(
+            StreamKind::Hashtag {
+                tag: "rust".into(),
+                local: false,
+                media_only: false
+            }
+            .query_pairs(),
+            vec![("stream", "hashtag"), ("tag", "rust")]
+        );
+        assert_eq!(
+            StreamKind::hashtag("#Rust", &HashtagRequest::new().local().only_media()).query_pairs(),
+            vec![
+                ("stream", "hashtag:local"),
+                ("tag", "rust"),
+                ("only_media", "1")
+            ]
+        );
+        assert_eq!(
+            StreamKind::List("42".into()).query_pairs(),
+            vec![("stream", "list"), ("list", "42")]
+        );
+    }
 }
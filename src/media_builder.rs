@@ -10,6 +10,12 @@ pub struct MediaBuilder {
     pub description: Option<Cow<'static, str>>,
     /// The focus point for images.
     pub focus: Option<(f32, f32)>,
+    /// The contents of the attachment, uploaded instead of reading `file`,
+    /// which then only names it.
+    #[serde(skip)]
+    pub data: Option<Vec<u8>>,
+    /// The MIME type of `data`, e.g. `image/png`.
+    pub mime_type: Option<Cow<'static, str>>,
 }
 
 impl MediaBuilder {
@@ -19,6 +25,17 @@ impl MediaBuilder {
             file,
             description: None,
             focus: None,
+            data: None,
+            mime_type: None,
+        }
+    }
+
+    /// Create a new attachment from its contents, e.g. media downloaded from
+    /// somewhere else, and the file name to upload them as.
+    pub fn from_bytes(data: Vec<u8>, file_name: Cow<'static, str>) -> Self {
+        MediaBuilder {
+            data: Some(data),
+            ..MediaBuilder::new(file_name)
         }
     }
 
@@ -33,6 +50,12 @@ impl MediaBuilder {
         self.focus = Some((f1, f2));
         self
     }
+
+    /// Set the MIME type of an attachment created with `from_bytes`.
+    pub fn mime_type(mut self, mime_type: Cow<'static, str>) -> Self {
+        self.mime_type = Some(mime_type);
+        self
+    }
 }
 
 // Convenience helper so that the mastodon.media() method can be called with a
@@ -43,6 +66,8 @@ impl From<String> for MediaBuilder {
             file: file.into(),
             description: None,
             focus: None,
+            data: None,
+            mime_type: None,
         }
     }
 }
@@ -55,6 +80,8 @@ impl From<&'static str> for MediaBuilder {
             file: file.into(),
             description: None,
             focus: None,
+            data: None,
+            mime_type: None,
         }
     }
 }
@@ -67,6 +94,8 @@ impl From<Cow<'static, str>> for MediaBuilder {
             file,
             description: None,
             focus: None,
+            data: None,
+            mime_type: None,
         }
     }
 }
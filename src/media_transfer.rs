@@ -0,0 +1,208 @@
+//! Copying media attachments from one instance to another, e.g. to cross-post
+//! a status or to move it to a new account
+
+use std::borrow::Cow;
+
+use reqwest::{
+    blocking::{Client, Response},
+    header::CONTENT_TYPE,
+};
+
+use crate::{
+    entities::attachment::Attachment,
+    errors::{Error, Result},
+    http_send::HttpSend,
+    mastodon_client::MastodonClient,
+    media_builder::MediaBuilder,
+};
+
+/// Downloads the media of attachments and uploads them to another instance
+///
+/// The media is downloaded without credentials, from the attachment's `url`,
+/// or from its `remote_url` if that fails. Its description and focal point
+/// are carried over.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// # use std::error::Error;
+/// use elefren::media_transfer::MediaTransfer;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let data = Data {
+/// #   base: "".into(),
+/// #   client_id: "".into(),
+/// #   client_secret: "".into(),
+/// #   redirect: "".into(),
+/// #   token: "".into(),
+/// #   ..Default::default()
+/// # };
+/// # let other_data = data.clone();
+/// let source = Mastodon::from(data);
+/// let target = Mastodon::from(other_data);
+/// let status = source.get_status("110")?;
+/// let transfer = MediaTransfer::new();
+/// let mut media_ids = Vec::new();
+/// for attachment in &status.media_attachments {
+///     media_ids.push(transfer.reupload(attachment, &target)?);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MediaTransfer {
+    client: Client,
+}
+
+impl MediaTransfer {
+    /// Download media with a default HTTP client
+    pub fn new() -> MediaTransfer {
+        MediaTransfer::default()
+    }
+
+    /// Download media with `client`, e.g. to set a timeout or a proxy
+    pub fn with_client(client: Client) -> MediaTransfer {
+        MediaTransfer { client }
+    }
+
+    /// Downloads the media of `attachment`, ready to be uploaded with
+    /// `media()`
+    pub fn download(&self, attachment: &Attachment) -> Result<MediaBuilder> {
+        let response = match self.fetch(&attachment.url) {
+            Ok(response) => response,
+            Err(e) => match attachment.remote_url {
+                Some(ref remote_url) => {
+                    log::warn!(
+                        "downloading {} failed, trying {}: {}",
+                        attachment.url,
+                        remote_url,
+                        e
+                    );
+                    self.fetch(remote_url)?
+                },
+                None => return Err(e),
+            },
+        };
+
+        let file_name = file_name(response.url());
+        let mime_type = match response.headers().get(CONTENT_TYPE) {
+            Some(value) => Some(value.to_str()?.to_string()),
+            None => None,
+        };
+        let data = response.bytes()?.to_vec();
+
+        let mut media = MediaBuilder::from_bytes(data, file_name.into());
+        if let Some(mime_type) = mime_type {
+            media = media.mime_type(mime_type.into());
+        }
+        if let Some(ref description) = attachment.description {
+            media = media.description(Cow::Owned(description.clone()));
+        }
+        if let Some(focus) = attachment.meta.as_ref().and_then(|meta| meta.focus) {
+            media = media.focus(focus.x as f32, focus.y as f32);
+        }
+        Ok(media)
+    }
+
+    /// Downloads the media of `attachment` and uploads it through `to`,
+    /// returning the id of the new attachment
+    pub fn reupload<H: HttpSend, C: MastodonClient<H>>(
+        &self,
+        attachment: &Attachment,
+        to: &C,
+    ) -> Result<String> {
+        let media = self.download(attachment)?;
+        Ok(to.media(media)?.id)
+    }
+
+    fn fetch(&self, url: &str) -> Result<Response> {
+        let response = self.client.get(url).send()?;
+        let status = response.status();
+        if status.is_client_error() {
+            return Err(Error::Client(status.into()));
+        } else if status.is_server_error() {
+            return Err(Error::Server(status.into()));
+        }
+        Ok(response)
+    }
+}
+
+// The last segment of the path of `url`, which for media is the file name the
+// instance stored it as
+fn file_name(url: &url::Url) -> String {
+    url.path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("media")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{data::Data, Mastodon};
+    use mockito::Matcher;
+
+    fn attachment(url: &str, remote_url: Option<&str>) -> Attachment {
+        serde_json::from_value(serde_json::json!({
+            "id": "1",
+            "type": "image",
+            "url": url,
+            "remote_url": remote_url,
+            "preview_url": null,
+            "text_url": null,
+            "meta": {"focus": {"x": -0.5, "y": 0.25}},
+            "description": "a cat"
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_reupload() {
+        let missing = mockito::mock("GET", "/media/missing.png")
+            .with_status(404)
+            .create();
+        let original = mockito::mock("GET", "/media/cat.png")
+            .with_header("content-type", "image/png")
+            .with_body("cat pixels")
+            .create();
+        let upload = mockito::mock("POST", "/api/v1/media")
+            .match_header("authorization", "Bearer token")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::Regex(r#"filename="cat.png""#.into()),
+                Matcher::Regex("image/png".into()),
+                Matcher::Regex("cat pixels".into()),
+                Matcher::Regex("a cat".into()),
+                Matcher::Regex("-0.5,0.25".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"id": "2", "type": "image", "url": "https://example.com/2.png", "remote_url": null, "preview_url": null, "text_url": null, "meta": null, "description": "a cat"}"#,
+            )
+            .create();
+
+        let target = Mastodon::from(Data {
+            base: mockito::server_url().into(),
+            token: "token".into(),
+            ..Default::default()
+        });
+        let attachment = attachment(
+            &format!("{}/media/missing.png", mockito::server_url()),
+            Some(&format!("{}/media/cat.png", mockito::server_url())),
+        );
+        let id = MediaTransfer::new().reupload(&attachment, &target).unwrap();
+        assert_eq!(id, "2");
+        missing.assert();
+        original.assert();
+        upload.assert();
+    }
+
+    #[test]
+    fn test_file_name() {
+        let url = url::Url::parse("https://example.com/media/original/cat.png?v=2").unwrap();
+        assert_eq!(file_name(&url), "cat.png");
+        let url = url::Url::parse("https://example.com/").unwrap();
+        assert_eq!(file_name(&url), "media");
+    }
+}
@@ -0,0 +1,208 @@
+//! Deciding whether and how to answer a mention
+//!
+//! Auto-responders ask the same questions about every mention they receive:
+//! whether it's from an account they ignore, whether it's a reply to them,
+//! whether it was sent as a direct message, and what the command is once
+//! the mentions are taken out. [`MentionGuard`](struct.MentionGuard.html)
+//! answers them; it pairs with [`Bot::on_mention`](../bot/struct.Bot.html#method.on_mention)
+//! but works with statuses from anywhere.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # extern crate elefren;
+//! # use elefren::prelude::*;
+//! # use std::error::Error;
+//! use elefren::{bot::Bot, mention_guard::MentionGuard};
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! # let data = Data {
+//! #   base: "".into(),
+//! #   client_id: "".into(),
+//! #   client_secret: "".into(),
+//! #   redirect: "".into(),
+//! #   token: "".into(),
+//! #   ..Default::default()
+//! # };
+//! let client = Mastodon::from(data);
+//! let guard = MentionGuard::load(&client)?;
+//! let mut bot = Bot::new(&client);
+//! bot.on_mention(|status| {
+//!     if guard.is_ignored(status) {
+//!         return;
+//!     }
+//!     match guard.command_text(status).as_str() {
+//!         "ping" => println!("pong for {}", status.account.acct),
+//!         other => println!("unknown command {:?}", other),
+//!     }
+//! });
+//! bot.run()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashSet;
+
+use crate::{entities::status::Status, helpers::html::to_plain_text, status_builder::Visibility};
+#[cfg(feature = "client")]
+use crate::{
+    errors::Result, http_send::HttpSend, mastodon_client::MastodonClient, requests::PageRequest,
+};
+
+/// Answers the questions a bot has about a status mentioning it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MentionGuard {
+    me: String,
+    blocked: HashSet<String>,
+    muted: HashSet<String>,
+}
+
+impl MentionGuard {
+    /// A guard for the account with id `me`, not ignoring anyone yet
+    pub fn new<S: Into<String>>(me: S) -> MentionGuard {
+        MentionGuard {
+            me: me.into(),
+            ..Default::default()
+        }
+    }
+
+    /// A guard for the authenticated account, ignoring the accounts it
+    /// blocks or mutes
+    ///
+    /// This fetches all of them once; use `block` and `mute` to keep the
+    /// guard up to date afterwards.
+    #[cfg(feature = "client")]
+    pub fn load<H: HttpSend, C: MastodonClient<H>>(client: &C) -> Result<MentionGuard> {
+        let mut guard = MentionGuard::new(client.verify_credentials()?.id);
+        let request = PageRequest::new().limit(80);
+        for account in client.blocks_with(&request)?.try_items_iter() {
+            guard.blocked.insert(account?.id);
+        }
+        for account in client.mutes_with(&request)?.try_items_iter() {
+            guard.muted.insert(account?.id);
+        }
+        Ok(guard)
+    }
+
+    /// Treats the account with id `account_id` as blocked
+    pub fn block<S: Into<String>>(&mut self, account_id: S) -> &mut Self {
+        self.blocked.insert(account_id.into());
+        self
+    }
+
+    /// Treats the account with id `account_id` as muted
+    pub fn mute<S: Into<String>>(&mut self, account_id: S) -> &mut Self {
+        self.muted.insert(account_id.into());
+        self
+    }
+
+    /// Whether the status is from a blocked account
+    pub fn is_blocked(&self, status: &Status) -> bool {
+        self.blocked.contains(&status.account.id)
+    }
+
+    /// Whether the status is from a muted account
+    pub fn is_muted(&self, status: &Status) -> bool {
+        self.muted.contains(&status.account.id)
+    }
+
+    /// Whether the status shouldn't be answered: it's from a blocked or
+    /// muted account, or from the bot itself, which would otherwise end up
+    /// talking to itself
+    pub fn is_ignored(&self, status: &Status) -> bool {
+        self.is_blocked(status) || self.is_muted(status) || self.is_from_me(status)
+    }
+
+    /// Whether the bot posted the status
+    pub fn is_from_me(&self, status: &Status) -> bool {
+        status.account.id == self.me
+    }
+
+    /// Whether the status mentions the bot
+    pub fn mentions_me(&self, status: &Status) -> bool {
+        status.mentions.iter().any(|mention| mention.id == self.me)
+    }
+
+    /// Whether the status is a reply to one of the bot's statuses
+    pub fn is_reply_to_me(&self, status: &Status) -> bool {
+        status.in_reply_to_account_id.as_deref() == Some(self.me.as_str())
+    }
+
+    /// Whether the status was sent as a direct message
+    pub fn is_direct(&self, status: &Status) -> bool {
+        status.visibility == Visibility::Direct
+    }
+
+    /// The text of the status without the mentions of accounts, e.g.
+    /// `roll 2d6` for `@dicebot roll 2d6`, with whitespace collapsed
+    pub fn command_text(&self, status: &Status) -> String {
+        let is_mention = |word: &str| {
+            let word = word.trim_end_matches(|c: char| c.is_ascii_punctuation());
+            let name = match word.strip_prefix('@') {
+                Some(name) => name,
+                None => return false,
+            };
+            status.mentions.iter().any(|mention| {
+                name.eq_ignore_ascii_case(&mention.acct)
+                    || name.eq_ignore_ascii_case(&mention.username)
+            })
+        };
+        to_plain_text(&status.content)
+            .split_whitespace()
+            .filter(|word| !is_mention(word))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::status;
+
+    #[test]
+    fn test_command_text() {
+        let mut status = status();
+        let guard = MentionGuard::new("111");
+        assert_eq!(guard.command_text(&status), "hey");
+
+        status.content = "<p><span class=\"h-card\"><a href=\"https://example.com/@bob\" \
+                          class=\"u-url mention\">@<span>bob</span></a></span> roll \
+                          2d6\n for @carol@other.example, not @dave</p>"
+            .to_string();
+        assert_eq!(guard.command_text(&status), "roll 2d6 for not @dave");
+    }
+
+    #[test]
+    fn test_mentions_and_replies() {
+        let mut status = status();
+        let guard = MentionGuard::new("111");
+        assert!(guard.mentions_me(&status));
+        assert!(!guard.is_reply_to_me(&status));
+        assert!(!guard.is_direct(&status));
+
+        status.in_reply_to_account_id = Some("111".to_string());
+        status.visibility = Visibility::Direct;
+        assert!(guard.is_reply_to_me(&status));
+        assert!(guard.is_direct(&status));
+        assert!(!MentionGuard::new("112").is_reply_to_me(&status));
+    }
+
+    #[test]
+    fn test_ignored_accounts() {
+        let status = status();
+        let mut guard = MentionGuard::new("111");
+        assert!(!guard.is_ignored(&status));
+
+        guard.mute(status.account.id.clone());
+        assert!(guard.is_muted(&status));
+        assert!(!guard.is_blocked(&status));
+        assert!(guard.is_ignored(&status));
+
+        let mut guard = MentionGuard::new("111");
+        guard.block(status.account.id.clone());
+        assert!(guard.is_blocked(&status));
+        assert!(guard.is_ignored(&status));
+
+        assert!(MentionGuard::new(status.account.id.clone()).is_ignored(&status));
+    }
+}
@@ -1,17 +1,26 @@
 use super::{deserialise_blocking, Mastodon, Result};
-use crate::entities::itemsiter::ItemsIter;
-use hyper_old_types::header::{parsing, Link, RelationType};
+use crate::{
+    entities::itemsiter::{ItemsIter, PrefetchingItemsIter, RevItemsIter, TryItemsIter},
+    http_send::{HttpSend, HttpSender},
+    link_header,
+    requests::PageRequest,
+};
 use reqwest::blocking::Response;
 use reqwest::header::LINK;
 use serde::Deserialize;
+use std::mem;
 use url::Url;
 
 macro_rules! pages {
-    ($($direction:ident: $fun:ident),*) => {
+    ($($direction:ident: $fun:ident, $cursor:ident, $has:ident),*) => {
 
         $(
             doc_comment::doc_comment!(concat!(
-                    "Method to retrieve the ", stringify!($direction), " page of results"),
+                    "Method to retrieve the ", stringify!($direction), " page of results\n\n",
+                    "Returns `None` once there are no more pages. A page can be empty ",
+                    "and still link to the ", stringify!($direction), " one, e.g. when ",
+                    "the server filters the results after paging them, so an empty ",
+                    "page isn't the end; `", stringify!($has), "` tells."),
             pub fn $fun(&mut self) -> Result<Option<Vec<T>>> {
                 let url = match self.$direction.take() {
                     Some(s) => s,
@@ -19,15 +28,37 @@ macro_rules! pages {
                 };
 
                 let response = self.mastodon.send_blocking(
-                    self.mastodon.client.get(url)
+                    self.mastodon.client.get(url.clone())
                 )?;
 
                 let (prev, next) = get_links(&response)?;
                 self.next = next;
                 self.prev = prev;
+                // a page linking to itself would otherwise be fetched forever
+                if self.$direction.as_ref() == Some(&url) {
+                    self.$direction = None;
+                }
 
                 deserialise_blocking(response)
             });
+
+            doc_comment::doc_comment!(concat!(
+                    "Whether there is a ", stringify!($direction), " page of results, ",
+                    "i.e. whether `", stringify!($fun), "` would fetch one"),
+            pub fn $has(&self) -> bool {
+                self.$direction.is_some()
+            });
+
+            doc_comment::doc_comment!(concat!(
+                    "The bounds of the ", stringify!($direction), " page of results, ",
+                    "to fetch it later with the `_with` version of the method that ",
+                    "returned this page, e.g. after a restart\n\n",
+                    "The ids are taken from the `Link` header as they are; for some ",
+                    "routes, such as favourites and bookmarks, they aren't status ids ",
+                    "and can only be used this way."),
+            pub fn $cursor(&self) -> Option<PageRequest> {
+                self.$direction.as_ref().map(PageRequest::from_url)
+            });
          )*
     }
 }
@@ -35,6 +66,9 @@ macro_rules! pages {
 /// Owned version of the `Page` struct in this module. Allows this to be more
 /// easily stored for later use
 ///
+/// Converting a `Page` is cheap, as the client it holds shares its `Data`
+/// with the one the page was fetched with.
+///
 /// # Example
 ///
 /// ```no_run
@@ -51,6 +85,7 @@ macro_rules! pages {
 /// #   client_secret: "".into(),
 /// #   redirect: "".into(),
 /// #   token: "".into(),
+/// #   ..Default::default()
 /// # };
 /// struct HomeTimeline {
 ///     client: Mastodon,
@@ -66,23 +101,23 @@ macro_rules! pages {
 /// # }
 /// ```
 #[derive(Debug, Clone)]
-pub struct OwnedPage<T: for<'de> Deserialize<'de>> {
-    mastodon: Mastodon,
+pub struct OwnedPage<T: for<'de> Deserialize<'de>, H: HttpSend = HttpSender> {
+    mastodon: Mastodon<H>,
     next: Option<Url>,
     prev: Option<Url>,
     /// Initial set of items
     pub initial_items: Vec<T>,
 }
 
-impl<T: for<'de> Deserialize<'de>> OwnedPage<T> {
+impl<T: for<'de> Deserialize<'de>, H: HttpSend> OwnedPage<T, H> {
     pages! {
-        next: next_page,
-        prev: prev_page
+        next: next_page, next_cursor, has_next,
+        prev: prev_page, prev_cursor, has_prev
     }
 }
 
-impl<'a, T: for<'de> Deserialize<'de>> From<Page<'a, T>> for OwnedPage<T> {
-    fn from(page: Page<'a, T>) -> OwnedPage<T> {
+impl<'a, T: for<'de> Deserialize<'de>, H: HttpSend> From<Page<'a, T, H>> for OwnedPage<T, H> {
+    fn from(page: Page<'a, T, H>) -> OwnedPage<T, H> {
         OwnedPage {
             mastodon: page.mastodon.clone(),
             next: page.next,
@@ -94,21 +129,31 @@ impl<'a, T: for<'de> Deserialize<'de>> From<Page<'a, T>> for OwnedPage<T> {
 
 /// Represents a single page of API results
 #[derive(Debug, Clone)]
-pub struct Page<'a, T: for<'de> Deserialize<'de>> {
-    mastodon: &'a Mastodon,
+pub struct Page<'a, T: for<'de> Deserialize<'de>, H: 'a + HttpSend = HttpSender> {
+    mastodon: &'a Mastodon<H>,
     next: Option<Url>,
     prev: Option<Url>,
     /// Initial set of items
     pub initial_items: Vec<T>,
 }
 
-impl<'a, T: for<'de> Deserialize<'de>> Page<'a, T> {
+impl<'a, T: for<'de> Deserialize<'de>, H: HttpSend> Page<'a, T, H> {
     pages! {
-        next: next_page,
-        prev: prev_page
+        next: next_page, next_cursor, has_next,
+        prev: prev_page, prev_cursor, has_prev
+    }
+
+    // Fetches all remaining pages, returning their items along with the
+    // initial ones
+    pub(crate) fn collect_all(mut self) -> Result<Vec<T>> {
+        let mut items = mem::take(&mut self.initial_items);
+        while let Some(next) = self.next_page()? {
+            items.extend(next);
+        }
+        Ok(items)
     }
 
-    pub(crate) fn new(mastodon: &'a Mastodon, response: Response) -> Result<Self> {
+    pub(crate) fn new(mastodon: &'a Mastodon<H>, response: Response) -> Result<Self> {
         let (prev, next) = get_links(&response)?;
         Ok(Page {
             initial_items: deserialise_blocking(response)?,
@@ -119,7 +164,7 @@ impl<'a, T: for<'de> Deserialize<'de>> Page<'a, T> {
     }
 }
 
-impl<'a, T: Clone + for<'de> Deserialize<'de>> Page<'a, T> {
+impl<'a, T: Clone + for<'de> Deserialize<'de>, H: HttpSend> Page<'a, T, H> {
     /// Returns an owned version of this struct that doesn't borrow the client
     /// that created it
     ///
@@ -139,6 +184,7 @@ impl<'a, T: Clone + for<'de> Deserialize<'de>> Page<'a, T> {
     /// #   client_secret: "".into(),
     /// #   redirect: "".into(),
     /// #   token: "".into(),
+    /// #   ..Default::default()
     /// # };
     /// struct HomeTimeline {
     ///     client: Mastodon,
@@ -153,7 +199,7 @@ impl<'a, T: Clone + for<'de> Deserialize<'de>> Page<'a, T> {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn into_owned(self) -> OwnedPage<T> {
+    pub fn into_owned(self) -> OwnedPage<T, H> {
         OwnedPage::from(self)
     }
 
@@ -180,6 +226,7 @@ impl<'a, T: Clone + for<'de> Deserialize<'de>> Page<'a, T> {
     /// #       client_secret: "".into(),
     /// #       redirect: "".into(),
     /// #       token: "".into(),
+    /// #       ..Default::default()
     /// #   };
     /// let mastodon = Mastodon::from(data);
     /// let req = StatusesRequest::new();
@@ -196,28 +243,127 @@ impl<'a, T: Clone + for<'de> Deserialize<'de>> Page<'a, T> {
     {
         ItemsIter::new(self)
     }
-}
 
-fn get_links(response: &Response) -> Result<(Option<Url>, Option<Url>)> {
-    let mut prev = None;
-    let mut next = None;
-
-    if let Some(link_header) = response.headers().get(LINK) {
-        let link_header = link_header.to_str()?;
-        let link_header = link_header.as_bytes();
-        let link_header: Link = parsing::from_raw_str(&link_header)?;
-        for value in link_header.values() {
-            if let Some(relations) = value.rel() {
-                if relations.contains(&RelationType::Next) {
-                    next = Some(Url::parse(value.link())?);
-                }
+    /// Like `items_iter`, but yields `Result`s, so that an error fetching
+    /// the next page can be told apart from the end of the items
+    ///
+    /// The error is the last item, iteration ends after it. `items_iter`
+    /// ends at the first error instead, which is easy to mistake for having
+    /// seen all items, e.g. during a backfill.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use std::error::Error;
+    /// use elefren::prelude::*;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #   let data = Data {
+    /// #       base: "".into(),
+    /// #       client_id: "".into(),
+    /// #       client_secret: "".into(),
+    /// #       redirect: "".into(),
+    /// #       token: "".into(),
+    /// #       ..Default::default()
+    /// #   };
+    /// let mastodon = Mastodon::from(data);
+    /// for status in mastodon.get_home_timeline()?.try_items_iter() {
+    ///     let status = status?;
+    ///     // do something with status
+    /// }
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn try_items_iter(self) -> impl Iterator<Item = Result<T>> + 'a
+    where
+        T: 'a,
+    {
+        TryItemsIter::new(self)
+    }
 
-                if relations.contains(&RelationType::Prev) {
-                    prev = Some(Url::parse(value.link())?);
-                }
-            }
-        }
+    /// Returns an iterator that walks backwards through the pages, from the
+    /// oldest item to the newest
+    ///
+    /// Items of this page are yielded first, oldest first, then the `prev`
+    /// link is followed to fetch newer pages, until there are no more items.
+    /// To walk a whole timeline from its very beginning, start from a page
+    /// requested with a `min_id`, e.g. `PageRequest::new().min_id("0")`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use std::error::Error;
+    /// use elefren::{prelude::*, PageRequest};
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #   let data = Data {
+    /// #       base: "".into(),
+    /// #       client_id: "".into(),
+    /// #       client_secret: "".into(),
+    /// #       redirect: "".into(),
+    /// #       token: "".into(),
+    /// #       ..Default::default()
+    /// #   };
+    /// let mastodon = Mastodon::from(data);
+    /// let oldest = mastodon.get_home_timeline_with(&PageRequest::new().min_id("0"))?;
+    /// for status in oldest.items_iter_rev() {
+    ///     // statuses arrive in chronological order
+    /// }
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn items_iter_rev(self) -> impl Iterator<Item = T> + 'a
+    where
+        T: 'a,
+    {
+        RevItemsIter::new(self)
+    }
+
+    /// Like `items_iter`, but fetches the next page on a background thread
+    /// while the current one is being iterated over.
+    ///
+    /// This roughly halves the time it takes to walk through many pages when
+    /// handling each item takes a while, at the cost of possibly fetching one
+    /// page that is never used. As with `items_iter`, iteration stops at the
+    /// first error; see `try_items_iter` to see it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use std::error::Error;
+    /// use elefren::prelude::*;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// #   let data = Data {
+    /// #       base: "".into(),
+    /// #       client_id: "".into(),
+    /// #       client_secret: "".into(),
+    /// #       redirect: "".into(),
+    /// #       token: "".into(),
+    /// #       ..Default::default()
+    /// #   };
+    /// let mastodon = Mastodon::from(data);
+    /// for status in mastodon.get_home_timeline()?.prefetching_items_iter() {
+    ///     // do something with status
+    /// }
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn prefetching_items_iter(self) -> impl Iterator<Item = T>
+    where
+        T: Send + 'static,
+        H: Send + 'static,
+    {
+        PrefetchingItemsIter::new(self.into_owned())
     }
+}
 
-    Ok((prev, next))
+fn get_links(response: &Response) -> Result<(Option<Url>, Option<Url>)> {
+    let headers = response
+        .headers()
+        .get_all(LINK)
+        .iter()
+        .map(|value| value.to_str())
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    link_header::from_headers(headers)
 }
@@ -0,0 +1,344 @@
+//! Polling for notifications where the streaming API can't be used, e.g.
+//! behind a proxy that blocks websockets
+
+use std::{cmp::Ordering, collections::VecDeque, fmt, thread, time::Duration};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    bot::compare_ids,
+    entities::{event::Event, notification::Notification},
+    errors::Result,
+    mastodon_client::MastodonClient,
+    requests::PageRequest,
+};
+
+/// Polls the notifications endpoint, producing the same
+/// `Event::Notification` values as the user stream
+///
+/// The interval between polls adapts to activity: it drops back to the
+/// minimum whenever a poll finds something new, and doubles up to the
+/// maximum while nothing happens. It's also stretched so that polling never
+/// takes more than half of the requests the rate limit has left, waiting for
+/// the window to reset once they're used up.
+///
+/// Only notifications newer than the newest one seen are passed on, so none
+/// are produced twice. They're fetched oldest page first, so that none are
+/// skipped when more than a page of them arrived between two polls.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// # use std::error::Error;
+/// use elefren::{entities::event::Event, poller::NotificationPoller};
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let data = Data {
+/// #   base: "".into(),
+/// #   client_id: "".into(),
+/// #   client_secret: "".into(),
+/// #   redirect: "".into(),
+/// #   token: "".into(),
+/// #   ..Default::default()
+/// # };
+/// let client = Mastodon::from(data);
+/// for event in NotificationPoller::new(&client) {
+///     if let Event::Notification(notification) = event {
+///         println!("{:?}", notification.notification_type);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct NotificationPoller<'a, C: MastodonClient> {
+    client: &'a C,
+    newest_id: Option<String>,
+    baseline: bool,
+    min_interval: Duration,
+    max_interval: Duration,
+    interval: Duration,
+    pending: VecDeque<Event>,
+    polled: bool,
+}
+
+impl<'a, C: MastodonClient> fmt::Debug for NotificationPoller<'a, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NotificationPoller")
+            .field("newest_id", &self.newest_id)
+            .field("min_interval", &self.min_interval)
+            .field("max_interval", &self.max_interval)
+            .field("interval", &self.interval)
+            .field("pending", &self.pending.len())
+            .finish()
+    }
+}
+
+impl<'a, C: MastodonClient> NotificationPoller<'a, C> {
+    /// Poll `client` for notifications received from now on
+    ///
+    /// The first poll only finds out what the newest notification is.
+    pub fn new(client: &'a C) -> NotificationPoller<'a, C> {
+        let min_interval = Duration::from_secs(10);
+        NotificationPoller {
+            client,
+            newest_id: None,
+            baseline: true,
+            min_interval,
+            max_interval: Duration::from_secs(300),
+            interval: min_interval,
+            pending: VecDeque::new(),
+            polled: false,
+        }
+    }
+
+    /// Poll `client` for notifications newer than the one with id
+    /// `newest_id`, e.g. the last one seen before a restart
+    pub fn since<S: Into<String>>(client: &'a C, newest_id: S) -> NotificationPoller<'a, C> {
+        NotificationPoller {
+            newest_id: Some(newest_id.into()),
+            baseline: false,
+            ..NotificationPoller::new(client)
+        }
+    }
+
+    /// The shortest time to wait between polls. Defaults to 10 seconds.
+    pub fn min_interval(&mut self, interval: Duration) -> &mut Self {
+        self.min_interval = interval;
+        self.interval = self.interval.max(interval);
+        self
+    }
+
+    /// The longest time to wait between polls while nothing happens,
+    /// unless the rate limit calls for more. Defaults to 5 minutes.
+    pub fn max_interval(&mut self, interval: Duration) -> &mut Self {
+        self.max_interval = interval;
+        self.interval = self.interval.min(interval);
+        self
+    }
+
+    /// The id of the newest notification seen so far
+    pub fn newest_id(&self) -> Option<&str> {
+        self.newest_id.as_deref()
+    }
+
+    /// Fetches the notifications that arrived since the last poll, oldest
+    /// first
+    ///
+    /// A failed poll backs off like one that found nothing.
+    pub fn poll(&mut self) -> Result<Vec<Event>> {
+        self.polled = true;
+        let mut notifications = match self.fetch() {
+            Ok(notifications) => notifications,
+            Err(e) => {
+                self.interval = (self.interval * 2).min(self.max_interval);
+                return Err(e);
+            },
+        };
+
+        notifications.retain(|notification| match self.newest_id {
+            Some(ref newest) => compare_ids(&notification.id, newest) == Ordering::Greater,
+            None => true,
+        });
+        notifications.sort_by(|a, b| compare_ids(&a.id, &b.id));
+        if let Some(newest) = notifications.last() {
+            self.newest_id = Some(newest.id.clone());
+        }
+        if self.baseline {
+            self.baseline = false;
+            notifications.clear();
+        }
+
+        self.interval = if notifications.is_empty() {
+            (self.interval * 2).min(self.max_interval)
+        } else {
+            self.min_interval
+        };
+        Ok(notifications.into_iter().map(Event::Notification).collect())
+    }
+
+    // Fetches the notifications newer than `newest_id` page by page, starting
+    // from the oldest: asking for the ones `since_id` gets the newest page,
+    // skipping those in between when more than a page arrived
+    fn fetch(&self) -> Result<Vec<Notification>> {
+        let mut min_id = match self.newest_id {
+            Some(ref newest_id) => newest_id.clone(),
+            // the baseline only needs the newest notification
+            None => {
+                let page = self.client.notifications_with(&PageRequest::new())?;
+                return Ok(page.initial_items);
+            },
+        };
+        let mut notifications = Vec::new();
+        loop {
+            let request = PageRequest::new().min_id(min_id.as_str());
+            let page = self.client.notifications_with(&request)?.initial_items;
+            let newest = page
+                .iter()
+                .map(|notification| &notification.id)
+                .filter(|id| compare_ids(id, &min_id) == Ordering::Greater)
+                .max_by(|a, b| compare_ids(a, b))
+                .cloned();
+            match newest {
+                Some(newest) => min_id = newest,
+                None => break,
+            }
+            notifications.extend(page);
+        }
+        Ok(notifications)
+    }
+
+    /// How long to wait before the next poll at `now`, given the client's
+    /// rate limit
+    pub fn next_interval(&self, now: DateTime<Utc>) -> Duration {
+        let rate_limit = match self.client.rate_limit() {
+            Some(rate_limit) => rate_limit,
+            None => return self.interval,
+        };
+        let until_reset = (rate_limit.reset - now).to_std().unwrap_or_default();
+        let budget = u32::try_from(rate_limit.remaining / 2).unwrap_or(u32::MAX);
+        if budget == 0 {
+            return self.interval.max(until_reset);
+        }
+        self.interval.max(until_reset / budget)
+    }
+}
+
+/// Polls forever, waiting between polls as `next_interval` says. Failed polls
+/// are logged and retried.
+impl<'a, C: MastodonClient> Iterator for NotificationPoller<'a, C> {
+    type Item = Event;
+
+    fn next(&mut self) -> Option<Event> {
+        while self.pending.is_empty() {
+            if self.polled {
+                thread::sleep(self.next_interval(Utc::now()));
+            }
+            match self.poll() {
+                Ok(events) => self.pending.extend(events),
+                Err(e) => log::warn!("polling notifications failed: {}", e),
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{data::Data, test_fixtures, Mastodon};
+    use chrono::TimeZone;
+    use mockito::Matcher;
+
+    fn client() -> Mastodon {
+        Mastodon::from(Data {
+            base: mockito::server_url().into(),
+            token: "token".into(),
+            ..Default::default()
+        })
+    }
+
+    fn notifications(ids: &[&str]) -> String {
+        let notifications: Vec<String> = ids
+            .iter()
+            .map(|id| {
+                format!(
+                    r#"{{"id": "{}", "type": "follow", "created_at": "2023-01-02T10:00:00Z", "account": {}}}"#,
+                    id,
+                    test_fixtures::ACCOUNT
+                )
+            })
+            .collect();
+        format!("[{}]", notifications.join(","))
+    }
+
+    fn ids(events: Vec<Event>) -> Vec<String> {
+        events
+            .into_iter()
+            .map(|event| match event {
+                Event::Notification(notification) => notification.id,
+                event => panic!("unexpected event {:?}", event),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_poll() {
+        let client = client();
+        let mut poller = NotificationPoller::since(&client, "9100");
+        poller
+            .min_interval(Duration::from_secs(1))
+            .max_interval(Duration::from_secs(4));
+
+        let mock = mockito::mock("GET", "/api/v1/notifications")
+            .match_query(Matcher::UrlEncoded("min_id".into(), "9100".into()))
+            .with_header("content-type", "application/json")
+            .with_body(notifications(&["9102", "9100", "9101"]))
+            .create();
+        let empty = mockito::mock("GET", "/api/v1/notifications")
+            .match_query(Matcher::UrlEncoded("min_id".into(), "9102".into()))
+            .with_header("content-type", "application/json")
+            .with_header("x-ratelimit-limit", "300")
+            .with_header("x-ratelimit-remaining", "20")
+            .with_header("x-ratelimit-reset", "2023-01-02T10:05:00Z")
+            .with_body("[]")
+            .expect(2)
+            .create();
+        assert_eq!(ids(poller.poll().unwrap()), ["9101", "9102"]);
+        mock.assert();
+        assert_eq!(poller.newest_id(), Some("9102"));
+        assert_eq!(poller.next_interval(Utc::now()), Duration::from_secs(1));
+
+        assert!(poller.poll().unwrap().is_empty());
+        empty.assert();
+        let now = Utc.with_ymd_and_hms(2023, 1, 2, 10, 0, 0).unwrap();
+        // 300 seconds left for 10 polls
+        assert_eq!(poller.next_interval(now), Duration::from_secs(30));
+        // without a rate limit to respect, the interval doubles
+        let later = Utc.with_ymd_and_hms(2023, 1, 2, 10, 5, 0).unwrap();
+        assert_eq!(poller.next_interval(later), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_poll_pages() {
+        let client = client();
+        let mut poller = NotificationPoller::since(&client, "9300");
+        let pages = [
+            ("9300", &["9302", "9301"][..]),
+            ("9302", &["9304", "9303"]),
+            ("9304", &[]),
+        ];
+        let mocks: Vec<_> = pages
+            .iter()
+            .map(|(min_id, ids)| {
+                mockito::mock("GET", "/api/v1/notifications")
+                    .match_query(Matcher::UrlEncoded("min_id".into(), min_id.to_string()))
+                    .with_header("content-type", "application/json")
+                    .with_body(notifications(ids))
+                    .create()
+            })
+            .collect();
+        assert_eq!(
+            ids(poller.poll().unwrap()),
+            ["9301", "9302", "9303", "9304"]
+        );
+        for mock in mocks {
+            mock.assert();
+        }
+        assert_eq!(poller.newest_id(), Some("9304"));
+    }
+
+    #[test]
+    fn test_first_poll_is_baseline() {
+        let client = client();
+        let mut poller = NotificationPoller::new(&client);
+        let mock = mockito::mock("GET", "/api/v1/notifications")
+            .match_query(Matcher::Missing)
+            .with_header("content-type", "application/json")
+            .with_body(notifications(&["9201"]))
+            .create();
+        assert!(poller.poll().unwrap().is_empty());
+        mock.assert();
+        assert_eq!(poller.newest_id(), Some("9201"));
+    }
+}
@@ -0,0 +1,163 @@
+//! Checking that an instance is up before doing real work with it
+
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use reqwest::blocking::Client;
+
+use crate::{
+    deserialise_blocking,
+    entities::instance::Instance,
+    errors::{Error, Result},
+    InstanceUrl,
+};
+
+/// How long `probe` waits for each request
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The result of probing an instance with `probe`
+#[derive(Debug)]
+pub struct ProbeReport {
+    /// The instance probed
+    pub base: InstanceUrl,
+    /// The instance's information, from `/api/v1/instance`
+    pub instance: Result<Instance>,
+    /// How long the instance took to answer `/api/v1/instance`, whether it
+    /// succeeded or not
+    pub latency: Duration,
+    /// Whether the streaming API answered its health check
+    pub streaming: Result<()>,
+}
+
+impl ProbeReport {
+    /// Whether both the REST and the streaming API are working
+    pub fn is_healthy(&self) -> bool {
+        self.instance.is_ok() && self.streaming.is_ok()
+    }
+
+    /// The version of the software the instance runs, if it answered
+    pub fn version(&self) -> Option<&str> {
+        self.instance
+            .as_ref()
+            .ok()
+            .map(|instance| instance.version.as_str())
+    }
+}
+
+/// Checks the REST API, the streaming API and the response time of the
+/// instance at `base`, waiting at most 10 seconds for each
+///
+/// The checks run at the same time. Only an invalid `base` is an error; the
+/// outcome of each check is in the report.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// use elefren::probe::probe;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let report = probe("mastodon.social")?;
+/// if report.is_healthy() {
+///     println!(
+///         "{} runs {} and answered in {:?}",
+///         report.base,
+///         report.version().unwrap_or("?"),
+///         report.latency
+///     );
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn probe(base: &str) -> Result<ProbeReport> {
+    let client = Client::builder().timeout(PROBE_TIMEOUT).build()?;
+    probe_with(base, &client)
+}
+
+/// Like `probe`, but sends the requests through `client`, e.g. to set a
+/// different timeout or a proxy
+pub fn probe_with(base: &str, client: &Client) -> Result<ProbeReport> {
+    let base = InstanceUrl::parse(base)?;
+    let instance_url = base.join("/api/v1/instance")?;
+    let health_url = base.join("/api/v1/streaming/health")?;
+
+    let ((instance, latency), streaming) = thread::scope(|scope| {
+        let streaming = scope.spawn(|| check_streaming(client, health_url));
+        let started = Instant::now();
+        let instance = client
+            .get(instance_url)
+            .send()
+            .map_err(Error::from)
+            .and_then(deserialise_blocking::<Instance>);
+        let latency = started.elapsed();
+        let streaming = streaming
+            .join()
+            .unwrap_or_else(|_| Err(Error::Other("streaming health check panicked".into())));
+        ((instance, latency), streaming)
+    });
+
+    Ok(ProbeReport {
+        base,
+        instance,
+        latency,
+        streaming,
+    })
+}
+
+// The streaming server answers its health check with `OK`
+fn check_streaming(client: &Client, url: url::Url) -> Result<()> {
+    let response = client.get(url).send()?;
+    let status = response.status();
+    if status.is_client_error() {
+        return Err(Error::Client(status.into()));
+    } else if status.is_server_error() {
+        return Err(Error::Server(status.into()));
+    }
+    let body = response.text()?;
+    if body.trim() != "OK" {
+        return Err(Error::Other(format!(
+            "unexpected streaming health check response: {}",
+            body
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe() {
+        let instance = mockito::mock("GET", "/api/v1/instance")
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"uri": "example.com", "title": "Example", "description": "", "email": "", "version": "4.1.2"}"#,
+            )
+            .create();
+        let health = mockito::mock("GET", "/api/v1/streaming/health")
+            .with_body("OK")
+            .create();
+        let report = probe(&mockito::server_url()).unwrap();
+        instance.assert();
+        health.assert();
+        assert!(report.is_healthy());
+        assert_eq!(report.version(), Some("4.1.2"));
+        drop(health);
+
+        let health = mockito::mock("GET", "/api/v1/streaming/health")
+            .with_status(502)
+            .create();
+        let report = probe(&mockito::server_url()).unwrap();
+        health.assert();
+        assert!(report.instance.is_ok());
+        assert!(matches!(report.streaming, Err(Error::Server(_))));
+        assert!(!report.is_healthy());
+    }
+
+    #[test]
+    fn test_invalid_base() {
+        assert!(probe("ftp://example.com").is_err());
+    }
+}
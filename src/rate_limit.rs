@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use std::time::Duration;
+
+/// The rate limit state reported by the server in the `X-RateLimit-*`
+/// headers of its last response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// The number of requests allowed in the current window
+    pub limit: u64,
+    /// The number of requests left in the current window
+    pub remaining: u64,
+    /// When the current window ends and `remaining` is reset to `limit`
+    pub reset: DateTime<Utc>,
+}
+
+impl RateLimit {
+    /// Reads the rate limit from a response's headers, if the server sent
+    /// all of them
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Option<RateLimit> {
+        let header = |name: &str| headers.get(name)?.to_str().ok();
+        Some(RateLimit {
+            limit: header("x-ratelimit-limit")?.trim().parse().ok()?,
+            remaining: header("x-ratelimit-remaining")?.trim().parse().ok()?,
+            reset: DateTime::parse_from_rfc3339(header("x-ratelimit-reset")?.trim())
+                .ok()?
+                .with_timezone(&Utc),
+        })
+    }
+
+    /// How long to wait at `now` before more than `reserve` requests are
+    /// left, i.e. zero if they already are and the time until the window
+    /// resets otherwise
+    pub fn wait_time(&self, reserve: u64, now: DateTime<Utc>) -> Duration {
+        if self.remaining > reserve {
+            return Duration::from_secs(0);
+        }
+        (self.reset - now).to_std().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use reqwest::header::HeaderValue;
+
+    fn headers(remaining: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-limit", HeaderValue::from_static("300"));
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static(remaining));
+        headers.insert(
+            "x-ratelimit-reset",
+            HeaderValue::from_static("2023-05-01T12:05:00.000Z"),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_from_headers() {
+        let rate_limit = RateLimit::from_headers(&headers("299")).unwrap();
+        assert_eq!(rate_limit.limit, 300);
+        assert_eq!(rate_limit.remaining, 299);
+        assert_eq!(
+            rate_limit.reset,
+            Utc.with_ymd_and_hms(2023, 5, 1, 12, 5, 0).unwrap()
+        );
+        assert_eq!(RateLimit::from_headers(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_wait_time() {
+        let rate_limit = RateLimit::from_headers(&headers("2")).unwrap();
+        let now = Utc.with_ymd_and_hms(2023, 5, 1, 12, 4, 30).unwrap();
+        assert_eq!(rate_limit.wait_time(1, now), Duration::from_secs(0));
+        assert_eq!(rate_limit.wait_time(2, now), Duration::from_secs(30));
+        let later = Utc.with_ymd_and_hms(2023, 5, 1, 12, 6, 0).unwrap();
+        assert_eq!(rate_limit.wait_time(2, later), Duration::from_secs(0));
+    }
+}
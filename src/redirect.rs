@@ -0,0 +1,106 @@
+use url::Url;
+
+/// Controls which redirects are followed for authenticated requests.
+///
+/// By default, only redirects that stay on the origin (scheme, host and
+/// port) of the original request are followed, so the access token is never
+/// sent to a different server, or over plain HTTP after starting out with
+/// HTTPS. A redirect to a different origin fails the request with
+/// `Error::CrossOriginRedirect`.
+///
+/// Connecting to the streaming API always follows the instance's redirect to
+/// its streaming server, which may live on a different host; the access token
+/// is only added once that redirect has been resolved.
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::redirect::RedirectPolicy;
+/// use url::Url;
+///
+/// # fn main() -> Result<(), url::ParseError> {
+/// let from = Url::parse("https://example.com/api/v1/instance")?;
+/// let to = Url::parse("https://example.com/api/v2/instance")?;
+/// assert!(RedirectPolicy::default().allows(&from, &to));
+///
+/// let to = Url::parse("https://example.net/api/v1/instance")?;
+/// assert!(!RedirectPolicy::default().allows(&from, &to));
+/// assert!(RedirectPolicy::Any.allows(&from, &to));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RedirectPolicy {
+    /// Never follow redirects
+    None,
+    /// Follow redirects on the same origin only
+    #[default]
+    SameOrigin,
+    /// Follow all redirects. The access token is dropped when the host
+    /// changes, like browsers do.
+    Any,
+}
+
+impl RedirectPolicy {
+    /// The maximum number of redirects followed for a single request
+    pub const MAX_REDIRECTS: usize = 10;
+
+    /// Whether a redirect from `from` to `to` is followed
+    pub fn allows(self, from: &Url, to: &Url) -> bool {
+        match self {
+            RedirectPolicy::None => false,
+            RedirectPolicy::SameOrigin => from.origin() == to.origin(),
+            RedirectPolicy::Any => true,
+        }
+    }
+
+    #[cfg(feature = "client")]
+    // The equivalent `reqwest` policy. Redirects this policy doesn't allow
+    // aren't followed, and the redirect response is returned instead.
+    pub(crate) fn to_reqwest(self) -> reqwest::redirect::Policy {
+        match self {
+            RedirectPolicy::None => reqwest::redirect::Policy::none(),
+            RedirectPolicy::Any => reqwest::redirect::Policy::limited(Self::MAX_REDIRECTS),
+            RedirectPolicy::SameOrigin => reqwest::redirect::Policy::custom(|attempt| {
+                // `previous` always holds at least the original URL
+                let original = &attempt.previous()[0];
+                if attempt.previous().len() > Self::MAX_REDIRECTS {
+                    attempt.error("too many redirects")
+                } else if RedirectPolicy::SameOrigin.allows(original, attempt.url()) {
+                    attempt.follow()
+                } else {
+                    attempt.stop()
+                }
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_same_origin() {
+        let policy = RedirectPolicy::SameOrigin;
+        let from = url("https://example.com/api/v1/accounts/1");
+        assert!(policy.allows(&from, &url("https://example.com/api/v1/accounts/2")));
+        assert!(policy.allows(&from, &url("https://example.com:443/")));
+        assert!(!policy.allows(&from, &url("http://example.com/api/v1/accounts/2")));
+        assert!(!policy.allows(&from, &url("https://example.com:8443/")));
+        assert!(!policy.allows(&from, &url("https://evil.example.com/")));
+    }
+
+    #[test]
+    fn test_none_and_any() {
+        let from = url("https://example.com/");
+        let to = url("https://example.com/other");
+        assert!(!RedirectPolicy::None.allows(&from, &to));
+        assert!(RedirectPolicy::Any.allows(&from, &url("http://example.net/")));
+    }
+}
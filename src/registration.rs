@@ -1,13 +1,15 @@
 use std::borrow::Cow;
 
+use chrono::prelude::*;
 use reqwest::blocking::{Client, RequestBuilder, Response};
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
 use std::convert::TryInto;
 
 use crate::{
     apps::{App, AppBuilder},
+    http_send::{HttpSend, HttpSender},
     scopes::Scopes,
-    Data, Error, Mastodon, MastodonBuilder, Result,
+    ApiError, Data, Error, InstanceUrl, Mastodon, MastodonBuilder, Result,
 };
 
 const DEFAULT_REDIRECT_URI: &str = "urn:ietf:wg:oauth:2.0:oob";
@@ -15,9 +17,10 @@ const DEFAULT_REDIRECT_URI: &str = "urn:ietf:wg:oauth:2.0:oob";
 /// Handles registering your mastodon app to your instance. It is recommended
 /// you cache your data struct to avoid registering on every run.
 #[derive(Debug, Clone)]
-pub struct Registration<'a> {
+pub struct Registration<'a, H: HttpSend = HttpSender> {
     base: String,
     client: Client,
+    http_sender: H,
     app_builder: AppBuilder<'a>,
     force_login: bool,
 }
@@ -28,35 +31,89 @@ struct OAuth {
     client_secret: String,
     #[serde(default = "default_redirect_uri")]
     redirect_uri: String,
+    vapid_key: Option<String>,
 }
 
 fn default_redirect_uri() -> String {
     DEFAULT_REDIRECT_URI.to_string()
 }
 
+// Deserializes the response to a registration or token request. OAuth errors
+// are reported in a body with `error` and `error_description` fields, which
+// are turned into `Error::OAuth`.
+pub(crate) fn oauth_response<T: DeserializeOwned>(response: Response) -> Result<T> {
+    let url = response.url().to_string();
+    let status = response.status();
+    let body = response.text()?;
+    match serde_json::from_str(&body) {
+        Ok(t) => Ok(t),
+        Err(e) => match serde_json::from_str::<ApiError>(&body) {
+            Ok(ApiError {
+                error: Some(error),
+                error_description,
+            }) => Err(Error::OAuth {
+                status: status.into(),
+                error,
+                description: error_description,
+            }),
+            _ => Err(Error::deserialize(url, status, &body, e)),
+        },
+    }
+}
+
 #[derive(Deserialize)]
-struct AccessToken {
-    access_token: String,
+pub(crate) struct AccessToken {
+    pub(crate) access_token: String,
+    pub(crate) scope: Option<String>,
+    pub(crate) created_at: Option<i64>,
+    pub(crate) refresh_token: Option<String>,
+    // seconds from `created_at`
+    pub(crate) expires_in: Option<i64>,
+}
+
+impl AccessToken {
+    // when the token was issued, or `now` if the server doesn't say
+    pub(crate) fn created_at(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        self.created_at
+            .and_then(|created_at| Utc.timestamp_opt(created_at, 0).single())
+            .unwrap_or(now)
+    }
+
+    pub(crate) fn expires_at(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.expires_in
+            .map(|expires_in| self.created_at(now) + chrono::Duration::seconds(expires_in))
+    }
 }
 
 impl<'a> Registration<'a> {
     /// Construct a new registration process to the instance of the `base` url.
+    ///
+    /// `base` may be a full URL such as `http://localhost:3000`; without a
+    /// scheme, `https://` is assumed.
+    ///
     /// ```
     /// use elefren::prelude::*;
     ///
     /// let registration = Registration::new("https://mastodon.social");
     /// ```
     pub fn new<I: Into<String>>(base: I) -> Self {
+        Registration::with_sender(base, HttpSender)
+    }
+}
+
+impl<'a, H: HttpSend> Registration<'a, H> {
+    /// Construct a new registration process to the instance of the `base`
+    /// url, sending all requests through `http_sender`
+    pub fn with_sender<I: Into<String>>(base: I, http_sender: H) -> Self {
         Registration {
             base: base.into(),
             client: Client::new(),
+            http_sender,
             app_builder: AppBuilder::new(),
             force_login: false,
         }
     }
-}
 
-impl<'a> Registration<'a> {
     /// Sets the name of this app
     ///
     /// This is required, and if this isn't set then the AppBuilder::build
@@ -86,6 +143,15 @@ impl<'a> Registration<'a> {
         self
     }
 
+    /// Use a preconfigured `reqwest` client, e.g. to set timeouts or a proxy
+    ///
+    /// The client is passed on to the `Mastodon` client created by
+    /// `Registered::complete`, so that they share a connection pool.
+    pub fn client(&mut self, client: Client) -> &mut Self {
+        self.client = client;
+        self
+    }
+
     /// Forces the user to re-login (useful if you need to re-auth as a
     /// different user on the same instance
     pub fn force_login(&mut self, force_login: bool) -> &mut Self {
@@ -95,7 +161,7 @@ impl<'a> Registration<'a> {
 
     fn send(&self, req: RequestBuilder) -> Result<Response> {
         let req = req.build()?;
-        Ok(self.client.execute(req)?)
+        self.http_sender.execute(&self.client, req)
     }
 
     /// Register the given application
@@ -119,21 +185,24 @@ impl<'a> Registration<'a> {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn register<I: TryInto<App>>(&mut self, app: I) -> Result<Registered>
+    pub fn register<I: TryInto<App>>(&mut self, app: I) -> Result<Registered<H>>
     where
         Error: From<<I as TryInto<App>>::Error>,
     {
         let app = app.try_into()?;
-        let oauth = self.send_app(&app)?;
+        let base = InstanceUrl::parse(&self.base)?;
+        let oauth = self.send_app(&base, &app)?;
 
         Ok(Registered {
-            base: self.base.clone(),
+            base: base.to_string(),
             client: self.client.clone(),
+            http_sender: self.http_sender.clone(),
             client_id: oauth.client_id,
             client_secret: oauth.client_secret,
             redirect: oauth.redirect_uri,
             scopes: app.scopes().clone(),
             force_login: self.force_login,
+            vapid_key: oauth.vapid_key,
         })
     }
 
@@ -157,24 +226,27 @@ impl<'a> Registration<'a> {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn build(&mut self) -> Result<Registered> {
+    pub fn build(&mut self) -> Result<Registered<H>> {
         let app: App = self.app_builder.clone().build()?;
-        let oauth = self.send_app(&app)?;
+        let base = InstanceUrl::parse(&self.base)?;
+        let oauth = self.send_app(&base, &app)?;
 
         Ok(Registered {
-            base: self.base.clone(),
+            base: base.to_string(),
             client: self.client.clone(),
+            http_sender: self.http_sender.clone(),
             client_id: oauth.client_id,
             client_secret: oauth.client_secret,
             redirect: oauth.redirect_uri,
             scopes: app.scopes().clone(),
             force_login: self.force_login,
+            vapid_key: oauth.vapid_key,
         })
     }
 
-    fn send_app(&self, app: &App) -> Result<OAuth> {
-        let url = format!("{}/api/v1/apps", self.base);
-        Ok(self.send(self.client.post(&url).json(&app))?.json()?)
+    fn send_app(&self, base: &InstanceUrl, app: &App) -> Result<OAuth> {
+        let url = base.join("/api/v1/apps")?;
+        oauth_response(self.send(self.client.post(url).json(&app))?)
     }
 }
 
@@ -218,19 +290,21 @@ impl Registered {
         Registered {
             base: base.to_string(),
             client: Client::new(),
+            http_sender: HttpSender,
             client_id: client_id.to_string(),
             client_secret: client_secret.to_string(),
             redirect: redirect.to_string(),
             scopes,
             force_login,
+            vapid_key: None,
         }
     }
 }
 
-impl Registered {
+impl<H: HttpSend> Registered<H> {
     fn send(&self, req: RequestBuilder) -> Result<Response> {
         let req = req.build()?;
-        Ok(self.client.execute(req)?)
+        self.http_sender.execute(&self.client, req)
     }
 
     /// Returns the parts of the `Registered` struct that can be used to
@@ -281,10 +355,16 @@ impl Registered {
         )
     }
 
+    /// The key to use when subscribing to push notifications, if the server
+    /// sent one when the app was registered
+    pub fn vapid_key(&self) -> Option<&str> {
+        self.vapid_key.as_deref()
+    }
+
     /// Returns the full url needed for authorisation. This needs to be opened
     /// in a browser.
     pub fn authorize_url(&self) -> Result<String> {
-        let mut url = url::Url::parse(&self.base)?.join("/oauth/authorize")?;
+        let mut url = InstanceUrl::parse(&self.base)?.join("/oauth/authorize")?;
 
         url.query_pairs_mut()
             .append_pair("client_id", &self.client_id)
@@ -298,40 +378,111 @@ impl Registered {
 
     /// Create an access token from the client id, client secret, and code
     /// provided by the authorisation url.
-    pub fn complete(&self, code: &str) -> Result<Mastodon> {
-        let url = format!(
-            "{}/oauth/token?client_id={}&client_secret={}&code={}&grant_type=authorization_code&\
-             redirect_uri={}",
-            self.base, self.client_id, self.client_secret, code, self.redirect
-        );
+    ///
+    /// This doesn't change the `Registered`, so one registration can
+    /// complete the codes of any number of users, also concurrently from
+    /// several threads, e.g. in a web app. The clients share the connection
+    /// pool of the registration.
+    pub fn complete(&self, code: &str) -> Result<Mastodon<H>> {
+        let data = self.exchange_code(code)?;
+        let mut builder = MastodonBuilder::with_sender(self.http_sender.clone());
+        builder.client(self.client.clone()).data(data);
+        Ok(builder.build()?)
+    }
 
-        let token: AccessToken = self.send(self.client.post(&url))?.json()?;
+    /// Like `complete`, but only returns the `Data` of the new access token,
+    /// without creating a client, e.g. for web backends that store it and
+    /// create a client for each request
+    ///
+    /// This is the same as [`exchange_code`](#method.exchange_code).
+    pub fn complete_owned(&self, code: &str) -> Result<Data> {
+        self.exchange_code(code)
+    }
 
-        let data = Data {
+    /// Exchanges the code provided by the authorisation url for an access
+    /// token, returning the `Data` to persist without building a client
+    ///
+    /// A client can be created from the `Data` later, e.g. with a
+    /// `MastodonBuilder` using an HTTP client configured by the application.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// use elefren::registration::Registered;
+    /// use std::thread;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let registered = Registered::from_parts(
+    ///     "https://example.social",
+    ///     "the-client-id",
+    ///     "the-client-secret",
+    ///     "https://app.example/callback",
+    ///     Scopes::read_all(),
+    ///     false,
+    /// );
+    /// let codes = vec!["code-of-alice", "code-of-bob"];
+    /// let accounts = thread::scope(|scope| {
+    ///     let handles: Vec<_> = codes
+    ///         .iter()
+    ///         .map(|code| scope.spawn(|| registered.exchange_code(code)))
+    ///         .collect();
+    ///     handles
+    ///         .into_iter()
+    ///         .map(|handle| handle.join().unwrap())
+    ///         .collect::<Result<Vec<Data>, _>>()
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn exchange_code(&self, code: &str) -> Result<Data> {
+        let mut url = InstanceUrl::parse(&self.base)?.join("/oauth/token")?;
+        url.query_pairs_mut()
+            .append_pair("client_id", &self.client_id)
+            .append_pair("client_secret", &self.client_secret)
+            .append_pair("code", code)
+            .append_pair("grant_type", "authorization_code")
+            .append_pair("redirect_uri", &self.redirect);
+
+        let token: AccessToken = oauth_response(self.send(self.client.post(url))?)?;
+
+        let now = Utc::now();
+        let token_created_at = token.created_at(now);
+        let expires_at = token.expires_at(now);
+        let scopes = token
+            .scope
+            .and_then(|scope| scope.parse().ok())
+            .unwrap_or_else(|| self.scopes.clone());
+
+        Ok(Data {
             base: self.base.clone().into(),
             client_id: self.client_id.clone().into(),
             client_secret: self.client_secret.clone().into(),
             redirect: self.redirect.clone().into(),
             token: token.access_token.into(),
-        };
-
-        let mut builder = MastodonBuilder::new();
-        builder.client(self.client.clone()).data(data);
-        Ok(builder.build()?)
+            token_created_at: Some(token_created_at),
+            scopes: Some(scopes),
+            vapid_key: self.vapid_key.clone(),
+            refresh_token: token.refresh_token,
+            expires_at,
+        })
     }
 }
 
 /// Represents the state of the auth flow when the app has been registered but
 /// the user is not authenticated
 #[derive(Debug, Clone)]
-pub struct Registered {
+pub struct Registered<H: HttpSend = HttpSender> {
     base: String,
     client: Client,
+    http_sender: H,
     client_id: String,
     client_secret: String,
     redirect: String,
     scopes: Scopes,
     force_login: bool,
+    vapid_key: Option<String>,
 }
 
 #[cfg(test)]
@@ -401,4 +552,120 @@ mod tests {
     fn test_default_redirect_uri() {
         assert_eq!(&default_redirect_uri()[..], DEFAULT_REDIRECT_URI);
     }
+
+    #[test]
+    fn test_vapid_key_is_kept() {
+        let apps = mockito::mock("POST", "/api/v1/apps")
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"id":"1","name":"elefren","website":null,
+                    "redirect_uri":"urn:ietf:wg:oauth:2.0:oob",
+                    "client_id":"the-id","client_secret":"the-secret",
+                    "vapid_key":"the-vapid-key"}"#,
+            )
+            .create();
+        let token = mockito::mock("POST", "/oauth/token")
+            .match_query(mockito::Matcher::Any)
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"access_token":"the-token","scope":"read","created_at":0}"#)
+            .create();
+
+        let registered = Registration::new(mockito::server_url())
+            .client_name("elefren")
+            .build()
+            .unwrap();
+        assert_eq!(registered.vapid_key(), Some("the-vapid-key"));
+        let mastodon = registered.complete("code").unwrap();
+        assert_eq!(mastodon.data.vapid_key.as_deref(), Some("the-vapid-key"));
+        apps.assert();
+        token.assert();
+    }
+
+    #[test]
+    fn test_oauth_error() {
+        let token = mockito::mock("POST", "/oauth/token")
+            .match_query(mockito::Matcher::Any)
+            .with_status(400)
+            .with_header("content-type", "application/json")
+            .with_body(
+                r#"{"error":"invalid_grant","error_description":"The provided authorization grant is invalid."}"#,
+            )
+            .create();
+
+        let registered = Registered::from_parts(
+            &mockito::server_url(),
+            "the-id",
+            "the-secret",
+            DEFAULT_REDIRECT_URI,
+            Scopes::read_all(),
+            false,
+        );
+        match registered.complete("wrong") {
+            Err(Error::OAuth {
+                status,
+                error,
+                description,
+            }) => {
+                assert_eq!(status.as_u16(), 400);
+                assert_eq!(error, "invalid_grant");
+                assert_eq!(
+                    description.as_deref(),
+                    Some("The provided authorization grant is invalid.")
+                );
+            },
+            other => panic!("expected Error::OAuth, got {:?}", other.map(|_| ())),
+        }
+        token.assert();
+    }
+
+    #[test]
+    fn test_complete_concurrently() {
+        let codes = ["code&of=alice", "code+of bob", "code-of-carol"];
+        let tokens: Vec<_> = codes
+            .iter()
+            .enumerate()
+            .map(|(n, code)| {
+                mockito::mock("POST", "/oauth/token")
+                    .match_query(mockito::Matcher::AllOf(vec![
+                        mockito::Matcher::UrlEncoded("code".into(), code.to_string()),
+                        mockito::Matcher::UrlEncoded(
+                            "redirect_uri".into(),
+                            "https://app.example/callback?from=elefren".into(),
+                        ),
+                    ]))
+                    .with_header("content-type", "application/json")
+                    .with_body(format!(
+                        r#"{{"access_token":"token-{}","scope":"read","created_at":0}}"#,
+                        n
+                    ))
+                    .create()
+            })
+            .collect();
+
+        let registered = Registered::from_parts(
+            &mockito::server_url(),
+            "the-id",
+            "the-secret",
+            "https://app.example/callback?from=elefren",
+            Scopes::read_all(),
+            false,
+        );
+        let data = std::thread::scope(|scope| {
+            let handles: Vec<_> = codes
+                .iter()
+                .map(|code| scope.spawn(|| registered.exchange_code(code)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap().unwrap())
+                .collect::<Vec<_>>()
+        });
+        for (n, data) in data.iter().enumerate() {
+            assert_eq!(data.token, format!("token-{}", n));
+            assert_eq!(data.client_id, "the-id");
+        }
+        for token in tokens {
+            token.assert();
+        }
+    }
 }
@@ -0,0 +1,226 @@
+//! Answering questions about relationships to other accounts without a
+//! request per account
+
+use std::{
+    collections::HashMap,
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    entities::relationship::Relationship, errors::Result, mastodon_client::MastodonClient,
+};
+
+/// The relationships of the authenticated account to others, fetched in
+/// batches and kept for a while
+///
+/// `warm` fetches the relationships that aren't cached yet, or have expired,
+/// in as few requests as possible. The questions are then answered from the
+/// cache; they return `None` for accounts whose relationship isn't known.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// # use std::error::Error;
+/// use elefren::relationship_cache::RelationshipCache;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let data = Data {
+/// #   base: "".into(),
+/// #   client_id: "".into(),
+/// #   client_secret: "".into(),
+/// #   redirect: "".into(),
+/// #   token: "".into(),
+/// #   ..Default::default()
+/// # };
+/// let client = Mastodon::from(data);
+/// let mut relationships = RelationshipCache::new(&client);
+/// let statuses = client.get_home_timeline()?.initial_items;
+/// relationships.warm(statuses.iter().map(|status| &status.account.id))?;
+/// for status in &statuses {
+///     if relationships.is_following(&status.account.id) == Some(false) {
+///         println!("not following {}", status.account.acct);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct RelationshipCache<'a, C: MastodonClient> {
+    client: &'a C,
+    ttl: Duration,
+    chunk_size: usize,
+    relationships: HashMap<String, (Relationship, Instant)>,
+}
+
+impl<'a, C: MastodonClient> fmt::Debug for RelationshipCache<'a, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RelationshipCache")
+            .field("ttl", &self.ttl)
+            .field("chunk_size", &self.chunk_size)
+            .field("relationships", &self.relationships.len())
+            .finish()
+    }
+}
+
+impl<'a, C: MastodonClient> RelationshipCache<'a, C> {
+    /// An empty cache, fetching relationships through `client`
+    pub fn new(client: &'a C) -> RelationshipCache<'a, C> {
+        RelationshipCache {
+            client,
+            ttl: Duration::from_secs(5 * 60),
+            chunk_size: 40,
+            relationships: HashMap::new(),
+        }
+    }
+
+    /// How long a relationship is kept before it's fetched again. Defaults
+    /// to 5 minutes.
+    pub fn ttl(&mut self, ttl: Duration) -> &mut Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// The most relationships to fetch with one request. Defaults to 40;
+    /// values below 1 are treated as 1.
+    pub fn chunk_size(&mut self, chunk_size: usize) -> &mut Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Fetches the relationships to the accounts with ids `ids` that aren't
+    /// cached, or have expired
+    pub fn warm<S: AsRef<str>, I: IntoIterator<Item = S>>(&mut self, ids: I) -> Result<()> {
+        let mut missing: Vec<String> = Vec::new();
+        for id in ids {
+            let id = id.as_ref();
+            if self.get(id).is_none() && !missing.iter().any(|m| m == id) {
+                missing.push(id.to_string());
+            }
+        }
+        for chunk in missing.chunks(self.chunk_size) {
+            let ids: Vec<&str> = chunk.iter().map(String::as_str).collect();
+            let fetched_at = Instant::now();
+            for relationship in self.client.relationships(&ids)? {
+                self.relationships
+                    .insert(relationship.id.clone(), (relationship, fetched_at));
+            }
+        }
+        Ok(())
+    }
+
+    /// The relationship to the account with id `id`, if it's cached and
+    /// hasn't expired
+    pub fn get(&self, id: &str) -> Option<&Relationship> {
+        match self.relationships.get(id) {
+            Some((relationship, fetched_at)) if fetched_at.elapsed() < self.ttl => {
+                Some(relationship)
+            },
+            _ => None,
+        }
+    }
+
+    /// Whether the authenticated account follows the account with id `id`
+    pub fn is_following(&self, id: &str) -> Option<bool> {
+        self.get(id).map(|relationship| relationship.following)
+    }
+
+    /// Whether the account with id `id` follows the authenticated account
+    pub fn is_followed_by(&self, id: &str) -> Option<bool> {
+        self.get(id).map(|relationship| relationship.followed_by)
+    }
+
+    /// Whether the authenticated account blocks the account with id `id`
+    pub fn is_blocked(&self, id: &str) -> Option<bool> {
+        self.get(id).map(|relationship| relationship.blocking)
+    }
+
+    /// Whether the authenticated account mutes the account with id `id`
+    pub fn is_muted(&self, id: &str) -> Option<bool> {
+        self.get(id).map(|relationship| relationship.muting)
+    }
+
+    /// Caches `relationship`, e.g. the one returned by `follow` or `block`
+    pub fn insert(&mut self, relationship: Relationship) {
+        self.relationships
+            .insert(relationship.id.clone(), (relationship, Instant::now()));
+    }
+
+    /// Forgets the relationship to the account with id `id`, so that it's
+    /// fetched again
+    pub fn invalidate(&mut self, id: &str) {
+        self.relationships.remove(id);
+    }
+
+    /// Forgets all relationships
+    pub fn clear(&mut self) {
+        self.relationships.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{data::Data, Mastodon};
+    use mockito::Matcher;
+
+    fn client() -> Mastodon {
+        Mastodon::from(Data {
+            base: mockito::server_url().into(),
+            token: "token".into(),
+            ..Default::default()
+        })
+    }
+
+    fn relationship(id: &str, following: bool) -> String {
+        format!(
+            r#"{{"id": "{}", "following": {}, "followed_by": false, "blocking": false, "muting": false, "requested": false, "muting_notifications": false, "domain_blocking": false, "showing_reblogs": true, "endorsed": false}}"#,
+            id, following
+        )
+    }
+
+    fn mock_relationships(query: &str, body: String) -> mockito::Mock {
+        mockito::mock("GET", "/api/v1/accounts/relationships")
+            .match_query(Matcher::Exact(query.into()))
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .expect(1)
+            .create()
+    }
+
+    #[test]
+    fn test_warm_in_chunks() {
+        let client = client();
+        let mut cache = RelationshipCache::new(&client);
+        cache.chunk_size(2);
+
+        let first = mock_relationships(
+            "id[]=6101&id[]=6102",
+            format!(
+                "[{}, {}]",
+                relationship("6101", true),
+                relationship("6102", false)
+            ),
+        );
+        let second = mock_relationships("id=6103", format!("[{}]", relationship("6103", true)));
+        cache.warm(vec!["6101", "6102", "6101", "6103"]).unwrap();
+        // all cached, so nothing is fetched
+        cache.warm(vec!["6102", "6103"]).unwrap();
+        first.assert();
+        second.assert();
+
+        assert_eq!(cache.is_following("6101"), Some(true));
+        assert_eq!(cache.is_following("6102"), Some(false));
+        assert_eq!(cache.is_blocked("6103"), Some(false));
+        assert_eq!(cache.is_following("6104"), None);
+    }
+
+    #[test]
+    fn test_expired() {
+        let client = client();
+        let mut cache = RelationshipCache::new(&client);
+        cache.ttl(Duration::from_secs(0));
+        cache.insert(serde_json::from_str(&relationship("6201", true)).unwrap());
+        assert_eq!(cache.is_following("6201"), None);
+    }
+}
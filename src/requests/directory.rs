@@ -1,34 +1,41 @@
+use super::querystring::{bool_qs_serialize, ToQueryString};
 use crate::errors::Error;
-use serde::Serialize;
-use std::borrow::Cow;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
-mod bool_qs_serialize {
-    use serde::Serializer;
-
-    pub fn is_false(b: &bool) -> bool {
-        !*b
-    }
+/// The order of the accounts in the directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DirectoryOrder {
+    /// Recently active accounts first
+    Active,
+    /// Newly created accounts first
+    New,
+}
 
-    pub fn serialize<S: Serializer>(b: &bool, s: S) -> Result<S::Ok, S::Error> {
-        if *b {
-            s.serialize_i64(1)
-        } else {
-            s.serialize_i64(0)
-        }
+impl fmt::Display for DirectoryOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            DirectoryOrder::Active => "active",
+            DirectoryOrder::New => "new",
+        })
     }
 }
 
+// the maximum number of accounts the server returns at once
+const MAX_LIMIT: usize = 80;
+
 /// Represents the options for the directory request
-#[derive(Debug, Clone, Default, PartialEq, Serialize)]
-pub struct DirectoryRequest<'a> {
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct DirectoryRequest {
     offset: Option<usize>,
     limit: Option<usize>,
-    order: Option<Cow<'a, str>>, // TODO enum
+    order: Option<DirectoryOrder>,
     #[serde(skip_serializing_if = "bool_qs_serialize::is_false")]
     #[serde(serialize_with = "bool_qs_serialize::serialize")]
     local: bool,
 }
-impl<'a> DirectoryRequest<'a> {
+impl DirectoryRequest {
     /// make a new DirectoryRequest builder
     pub fn new() -> Self {
         DirectoryRequest::default()
@@ -40,15 +47,16 @@ impl<'a> DirectoryRequest<'a> {
         self
     }
 
-    /// sets the limit
+    /// sets the limit, which the server caps at 80, so larger values are
+    /// lowered to that
     pub fn limit(mut self, limit: usize) -> Self {
-        self.limit = Some(limit);
+        self.limit = Some(limit.min(MAX_LIMIT));
         self
     }
 
     /// sets the order
-    pub fn order<I: Into<Cow<'a, str>>>(mut self, order: I) -> Self {
-        self.order = Some(order.into());
+    pub fn order(mut self, order: DirectoryOrder) -> Self {
+        self.order = Some(order);
         self
     }
 
@@ -58,7 +66,8 @@ impl<'a> DirectoryRequest<'a> {
         self
     }
 
-    /// Turns this builder into a querystring
+    /// Turns this builder into a querystring, without the leading `?` that
+    /// `ToQueryString::to_querystring` includes for every builder
     ///
     /// # Example
     ///
@@ -74,7 +83,48 @@ impl<'a> DirectoryRequest<'a> {
     ///     "limit=10"
     /// );
     /// ```
+    #[deprecated(
+        since = "0.23.0",
+        note = "use `ToQueryString::to_querystring`, which includes the leading `?`"
+    )]
     pub fn to_querystring(&self) -> Result<String, Error> {
         Ok(serde_qs::to_string(&self)?)
     }
 }
+
+impl ToQueryString for DirectoryRequest {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_order() {
+        let request = DirectoryRequest::new().order(DirectoryOrder::New).local();
+        assert_eq!(
+            ToQueryString::to_querystring(&request).unwrap(),
+            "?order=new&local=1"
+        );
+        assert_eq!(DirectoryOrder::Active.to_string(), "active");
+        assert_eq!(
+            serde_json::from_str::<DirectoryOrder>("\"active\"").unwrap(),
+            DirectoryOrder::Active
+        );
+    }
+
+    #[test]
+    fn test_limit_is_capped() {
+        let request = DirectoryRequest::new().limit(200);
+        assert_eq!(
+            ToQueryString::to_querystring(&request).unwrap(),
+            "?limit=80"
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_inherent_to_querystring() {
+        let request = DirectoryRequest::new().limit(10);
+        assert_eq!(request.to_querystring().unwrap(), "limit=10");
+    }
+}
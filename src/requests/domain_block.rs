@@ -0,0 +1,84 @@
+use crate::entities::admin::DomainBlockSeverity;
+use serde::Serialize;
+
+/// Form used by moderators to block a domain, or to change how it's blocked
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::{entities::admin::DomainBlockSeverity, requests::DomainBlockRequest};
+///
+/// let request = DomainBlockRequest::new("spam.example")
+///     .severity(DomainBlockSeverity::Suspend)
+///     .public_comment("spam");
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DomainBlockRequest {
+    domain: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    severity: Option<DomainBlockSeverity>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reject_media: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reject_reports: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    private_comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    public_comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    obfuscate: Option<bool>,
+}
+
+impl DomainBlockRequest {
+    /// Create a new DomainBlockRequest for `domain`. The server ignores the
+    /// domain when updating a block.
+    pub fn new(domain: &str) -> DomainBlockRequest {
+        DomainBlockRequest {
+            domain: domain.to_string(),
+            severity: None,
+            reject_media: None,
+            reject_reports: None,
+            private_comment: None,
+            public_comment: None,
+            obfuscate: None,
+        }
+    }
+
+    /// Set what happens to the domain's accounts and statuses. The server
+    /// defaults to `Silence`.
+    pub fn severity(mut self, severity: DomainBlockSeverity) -> Self {
+        self.severity = Some(severity);
+        self
+    }
+
+    /// Set whether media from the domain is rejected
+    pub fn reject_media(mut self, reject: bool) -> Self {
+        self.reject_media = Some(reject);
+        self
+    }
+
+    /// Set whether reports from the domain are rejected
+    pub fn reject_reports(mut self, reject: bool) -> Self {
+        self.reject_reports = Some(reject);
+        self
+    }
+
+    /// Set a comment only shown to the moderators
+    pub fn private_comment(mut self, comment: &str) -> Self {
+        self.private_comment = Some(comment.to_string());
+        self
+    }
+
+    /// Set a comment shown on the instance's about page
+    pub fn public_comment(mut self, comment: &str) -> Self {
+        self.public_comment = Some(comment.to_string());
+        self
+    }
+
+    /// Set whether the domain is partly hidden on the about page
+    pub fn obfuscate(mut self, obfuscate: bool) -> Self {
+        self.obfuscate = Some(obfuscate);
+        self
+    }
+}
@@ -1,9 +1,12 @@
-use crate::entities::filter::FilterContext;
+use crate::entities::filter::{FilterAction, FilterContext};
 use serde::Serialize;
 use std::time::Duration;
 
 /// Form used to create a filter
 ///
+/// A filter applies to one or more contexts; the first is given to `new`,
+/// more can be added with `context`.
+///
 /// # Example
 ///
 /// ```
@@ -11,14 +14,15 @@ use std::time::Duration;
 /// # use std::error::Error;
 /// use elefren::{entities::filter::FilterContext, requests::AddFilterRequest};
 /// # fn main() -> Result<(), Box<dyn Error>> {
-/// let request = AddFilterRequest::new("foo", FilterContext::Home);
+/// let request = AddFilterRequest::new("foo", FilterContext::Home).context(FilterContext::Public);
 /// #   Ok(())
 /// # }
 /// ```
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct AddFilterRequest {
     phrase: String,
-    context: FilterContext,
+    #[serde(serialize_with = "serialize_context::ser")]
+    context: Vec<FilterContext>,
     irreversible: Option<bool>,
     whole_word: Option<bool>,
     #[serde(serialize_with = "serialize_duration::ser")]
@@ -30,13 +34,21 @@ impl AddFilterRequest {
     pub fn new(phrase: &str, context: FilterContext) -> AddFilterRequest {
         AddFilterRequest {
             phrase: phrase.to_string(),
-            context,
+            context: vec![context],
             irreversible: None,
             whole_word: None,
             expires_in: None,
         }
     }
 
+    /// Also apply the filter in `context`
+    pub fn context(mut self, context: FilterContext) -> Self {
+        if !self.context.contains(&context) {
+            self.context.push(context);
+        }
+        self
+    }
+
     /// Set `irreversible` to `true`
     pub fn irreversible(mut self) -> Self {
         self.irreversible = Some(true);
@@ -56,6 +68,132 @@ impl AddFilterRequest {
     }
 }
 
+/// Form used to create a filter with the v2 filters API
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::{
+///     entities::filter::{FilterAction, FilterContext},
+///     requests::{AddFilterV2Request, FilterKeywordRequest},
+/// };
+///
+/// let request = AddFilterV2Request::new("spoilers", FilterContext::Home)
+///     .context(FilterContext::Public)
+///     .filter_action(FilterAction::Hide)
+///     .keyword(FilterKeywordRequest::new("finale"))
+///     .keyword(FilterKeywordRequest::new("ending").whole_word());
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AddFilterV2Request {
+    title: String,
+    #[serde(serialize_with = "serialize_context::ser")]
+    context: Vec<FilterContext>,
+    #[serde(skip_serializing_if = "is_none_or_unknown")]
+    filter_action: Option<FilterAction>,
+    #[serde(serialize_with = "serialize_duration::ser")]
+    expires_in: Option<Duration>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    keywords_attributes: Vec<FilterKeywordRequest>,
+}
+
+impl AddFilterV2Request {
+    /// Create a new AddFilterV2Request, applying in `context`
+    pub fn new(title: &str, context: FilterContext) -> AddFilterV2Request {
+        AddFilterV2Request {
+            title: title.to_string(),
+            context: vec![context],
+            filter_action: None,
+            expires_in: None,
+            keywords_attributes: Vec::new(),
+        }
+    }
+
+    /// Also apply the filter in `context`
+    pub fn context(mut self, context: FilterContext) -> Self {
+        if !self.context.contains(&context) {
+            self.context.push(context);
+        }
+        self
+    }
+
+    /// Set what to do with matching statuses. The server defaults to `Warn`.
+    pub fn filter_action(mut self, action: FilterAction) -> Self {
+        self.filter_action = Some(action);
+        self
+    }
+
+    /// Set `expires_in` to a duration
+    pub fn expires_in(mut self, d: Duration) -> Self {
+        self.expires_in = Some(d);
+        self
+    }
+
+    /// Create the filter with `keyword`
+    pub fn keyword(mut self, keyword: FilterKeywordRequest) -> Self {
+        self.keywords_attributes.push(keyword);
+        self
+    }
+}
+
+/// Form used to add a keyword to a v2 filter, or to change one
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::requests::FilterKeywordRequest;
+///
+/// let request = FilterKeywordRequest::new("rust").whole_word();
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FilterKeywordRequest {
+    keyword: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    whole_word: Option<bool>,
+}
+
+impl FilterKeywordRequest {
+    /// Create a new FilterKeywordRequest
+    pub fn new(keyword: &str) -> FilterKeywordRequest {
+        FilterKeywordRequest {
+            keyword: keyword.to_string(),
+            whole_word: None,
+        }
+    }
+
+    /// Set `whole_word` to `true`
+    pub fn whole_word(mut self) -> Self {
+        self.whole_word = Some(true);
+        self
+    }
+}
+
+// `FilterAction::Unknown` has no name the server knows, so the server's
+// default is used instead
+fn is_none_or_unknown(action: &Option<FilterAction>) -> bool {
+    matches!(action, None | Some(FilterAction::Unknown))
+}
+
+// Leaves out `FilterContext::Unknown`, which has no name the server knows,
+// e.g. when updating a filter with the contexts it was fetched with
+mod serialize_context {
+    use crate::entities::filter::FilterContext;
+    use serde::ser::Serializer;
+
+    pub(crate) fn ser<S>(context: &[FilterContext], s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        s.collect_seq(
+            context
+                .iter()
+                .filter(|&&context| context != FilterContext::Unknown),
+        )
+    }
+}
+
 mod serialize_duration {
     use serde::ser::Serializer;
     use std::time::Duration;
@@ -79,6 +217,21 @@ mod tests {
     use serde_json;
     use std::time::Duration;
 
+    #[test]
+    fn test_unknown_values_are_not_sent() {
+        let request =
+            AddFilterRequest::new("foo", FilterContext::Home).context(FilterContext::Unknown);
+        let ser = serde_json::to_value(&request).expect("Couldn't serialize");
+        assert_eq!(ser["context"], serde_json::json!(["home"]));
+
+        let request = AddFilterV2Request::new("foo", FilterContext::Unknown)
+            .context(FilterContext::Thread)
+            .filter_action(FilterAction::Unknown);
+        let ser = serde_json::to_value(&request).expect("Couldn't serialize");
+        assert_eq!(ser["context"], serde_json::json!(["thread"]));
+        assert!(ser.get("filter_action").is_none());
+    }
+
     #[test]
     fn test_new() {
         let request = AddFilterRequest::new("foo", FilterContext::Home);
@@ -86,7 +239,7 @@ mod tests {
             request,
             AddFilterRequest {
                 phrase: "foo".to_string(),
-                context: FilterContext::Home,
+                context: vec![FilterContext::Home],
                 irreversible: None,
                 whole_word: None,
                 expires_in: None,
@@ -101,7 +254,7 @@ mod tests {
             request,
             AddFilterRequest {
                 phrase: "foo".to_string(),
-                context: FilterContext::Home,
+                context: vec![FilterContext::Home],
                 irreversible: Some(true),
                 whole_word: None,
                 expires_in: None,
@@ -116,7 +269,7 @@ mod tests {
             request,
             AddFilterRequest {
                 phrase: "foo".to_string(),
-                context: FilterContext::Home,
+                context: vec![FilterContext::Home],
                 irreversible: None,
                 whole_word: Some(true),
                 expires_in: None,
@@ -132,7 +285,7 @@ mod tests {
             request,
             AddFilterRequest {
                 phrase: "foo".to_string(),
-                context: FilterContext::Home,
+                context: vec![FilterContext::Home],
                 irreversible: None,
                 whole_word: None,
                 expires_in: Some(Duration::from_secs(300)),
@@ -140,6 +293,19 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_context() {
+        let request = AddFilterRequest::new("foo", FilterContext::Home)
+            .context(FilterContext::Thread)
+            .context(FilterContext::Home);
+        assert_eq!(
+            request.context,
+            vec![FilterContext::Home, FilterContext::Thread]
+        );
+        let ser = serde_json::to_string(&request).expect("Couldn't serialize");
+        assert!(ser.contains(r#""context":["home","thread"]"#));
+    }
+
     #[test]
     fn test_serialize_request() {
         let request =
@@ -147,7 +313,20 @@ mod tests {
         let ser = serde_json::to_string(&request).expect("Couldn't serialize");
         assert_eq!(
             ser,
-            r#"{"phrase":"foo","context":"home","irreversible":null,"whole_word":null,"expires_in":300}"#
+            r#"{"phrase":"foo","context":["home"],"irreversible":null,"whole_word":null,"expires_in":300}"#
+        )
+    }
+
+    #[test]
+    fn test_serialize_v2_request() {
+        let request = AddFilterV2Request::new("foo", FilterContext::Home)
+            .filter_action(FilterAction::Hide)
+            .keyword(FilterKeywordRequest::new("bar").whole_word())
+            .keyword(FilterKeywordRequest::new("baz"));
+        let ser = serde_json::to_string(&request).expect("Couldn't serialize");
+        assert_eq!(
+            ser,
+            r#"{"title":"foo","context":["home"],"filter_action":"hide","expires_in":null,"keywords_attributes":[{"keyword":"bar","whole_word":true},{"keyword":"baz"}]}"#
         )
     }
 }
@@ -0,0 +1,62 @@
+use super::querystring::{bool_qs_serialize, ToQueryString};
+use serde::Serialize;
+
+/// Represents the options for the hashtag timeline and the hashtag stream
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::requests::{HashtagRequest, ToQueryString};
+///
+/// let request = HashtagRequest::new().local().only_media();
+/// assert_eq!(
+///     &request.to_querystring().expect("Couldn't serialize qs"),
+///     "?local=1&only_media=1"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+pub struct HashtagRequest {
+    #[serde(skip_serializing_if = "bool_qs_serialize::is_false")]
+    #[serde(serialize_with = "bool_qs_serialize::serialize")]
+    pub(crate) local: bool,
+    #[serde(skip_serializing_if = "bool_qs_serialize::is_false")]
+    #[serde(serialize_with = "bool_qs_serialize::serialize")]
+    pub(crate) only_media: bool,
+}
+
+impl HashtagRequest {
+    /// make a new HashtagRequest builder
+    pub fn new() -> Self {
+        HashtagRequest::default()
+    }
+
+    /// Only the statuses originating from this instance
+    pub fn local(mut self) -> Self {
+        self.local = true;
+        self
+    }
+
+    /// Only the statuses with media attachments
+    pub fn only_media(mut self) -> Self {
+        self.only_media = true;
+        self
+    }
+}
+
+impl ToQueryString for HashtagRequest {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let request = HashtagRequest::new();
+        assert_eq!(request.to_querystring().unwrap(), "?");
+        assert_eq!(
+            request.only_media().to_querystring().unwrap(),
+            "?only_media=1"
+        );
+    }
+}
@@ -0,0 +1,66 @@
+use crate::entities::list::RepliesPolicy;
+use serde::Serialize;
+
+/// Form used to create a list, or to update one
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::{entities::list::RepliesPolicy, requests::ListRequest};
+///
+/// let request = ListRequest::new("friends")
+///     .replies_policy(RepliesPolicy::Followed)
+///     .exclusive(true);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ListRequest {
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    replies_policy: Option<RepliesPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exclusive: Option<bool>,
+}
+
+impl ListRequest {
+    /// Create a new ListRequest
+    pub fn new(title: &str) -> ListRequest {
+        ListRequest {
+            title: title.to_string(),
+            replies_policy: None,
+            exclusive: None,
+        }
+    }
+
+    /// Set which replies are shown in the list
+    pub fn replies_policy(mut self, policy: RepliesPolicy) -> Self {
+        self.replies_policy = Some(policy);
+        self
+    }
+
+    /// Set whether the list's members are removed from the home timeline
+    pub fn exclusive(mut self, exclusive: bool) -> Self {
+        self.exclusive = Some(exclusive);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn test_serialize_request() {
+        let request = ListRequest::new("foo");
+        let ser = serde_json::to_string(&request).expect("Couldn't serialize");
+        assert_eq!(ser, r#"{"title":"foo"}"#);
+
+        let request = request.replies_policy(RepliesPolicy::None).exclusive(true);
+        let ser = serde_json::to_string(&request).expect("Couldn't serialize");
+        assert_eq!(
+            ser,
+            r#"{"title":"foo","replies_policy":"none","exclusive":true}"#
+        );
+    }
+}
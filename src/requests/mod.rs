@@ -1,16 +1,53 @@
-/// Data structure for the MastodonClient::directory method
-pub use self::directory::DirectoryRequest;
+//! The options of requests, shared by the blocking and the async client
+//!
+//! The builders here don't depend on either client or their HTTP stack: they
+//! turn into a query string with `ToQueryString`, or into a JSON or form
+//! body, and document what they send. Both clients build their requests from
+//! the same builders, so an option behaves the same with either of them.
+
+/// Data structures for the MastodonClient::directory method
+pub use self::directory::{DirectoryOrder, DirectoryRequest};
+/// Data structure for the MastodonClient admin domain block methods
+pub use self::domain_block::DomainBlockRequest;
 /// Data structure for the MastodonClient::add_filter method
 pub use self::filter::AddFilterRequest;
+/// Data structures for the MastodonClient v2 filter methods
+pub use self::filter::{AddFilterV2Request, FilterKeywordRequest};
+/// Data structure for the MastodonClient hashtag timeline and stream methods
+pub use self::hashtag::HashtagRequest;
+/// Data structure for the MastodonClient::create_list and update_list methods
+pub use self::list::ListRequest;
+/// Data structure for the MastodonClient::notifications_with_options method
+pub use self::notifications::NotificationsRequest;
+/// Data structure for the paged MastodonClient::*_with methods
+pub use self::page::PageRequest;
 /// Data structure for the MastodonClient::add_push_subscription method
 pub use self::push::{AddPushRequest, Keys, UpdatePushRequest};
+/// Trait shared by the request builders sent as query strings
+pub use self::querystring::ToQueryString;
+/// Data structure for the MastodonClient::report_with method
+pub use self::report::ReportRequest;
+/// Data structures for the MastodonClient::search_v2_with method
+pub use self::search::{SearchRequest, SearchType};
 /// Data structure for the MastodonClient::statuses method
 pub use self::statuses::StatusesRequest;
+/// Data structure for the MastodonClient::get_public_timeline_with_options
+/// method
+pub use self::timeline::TimelineRequest;
 /// Data structure for the MastodonClient::update_credentials method
 pub use self::update_credentials::UpdateCredsRequest;
 
 mod directory;
+mod domain_block;
 mod filter;
+mod hashtag;
+mod list;
+mod notifications;
+mod page;
 mod push;
+mod querystring;
+mod report;
+mod search;
 mod statuses;
+mod timeline;
 mod update_credentials;
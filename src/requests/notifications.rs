@@ -0,0 +1,120 @@
+use super::querystring::ToQueryString;
+use crate::{entities::notification::NotificationType, errors::Error};
+use serde::Serialize;
+use std::borrow::Cow;
+use url::form_urlencoded;
+
+/// Represents the options for the notifications request
+///
+/// The types are sent as `types[]` and `exclude_types[]`, which is how the
+/// server expects lists in the query string.
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::{
+///     entities::notification::NotificationType,
+///     requests::{NotificationsRequest, ToQueryString},
+/// };
+///
+/// let request = NotificationsRequest::new()
+///     .types(&[NotificationType::Mention, NotificationType::Status])
+///     .account_id("42");
+/// assert_eq!(
+///     &request.to_querystring().expect("Couldn't serialize qs"),
+///     "?types[]=mention&types[]=status&account_id=42"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct NotificationsRequest<'a> {
+    types: Vec<NotificationType>,
+    exclude_types: Vec<NotificationType>,
+    account_id: Option<Cow<'a, str>>,
+}
+
+impl<'a> NotificationsRequest<'a> {
+    /// make a new NotificationsRequest builder, for all notifications
+    pub fn new() -> Self {
+        NotificationsRequest::default()
+    }
+
+    /// Only the notifications of these types
+    pub fn types(mut self, types: &[NotificationType]) -> Self {
+        self.types.extend_from_slice(types);
+        self
+    }
+
+    /// None of the notifications of these types
+    pub fn exclude_types(mut self, types: &[NotificationType]) -> Self {
+        self.exclude_types.extend_from_slice(types);
+        self
+    }
+
+    /// Only the notifications caused by the account with id `account_id`
+    pub fn account_id<S: Into<Cow<'a, str>>>(mut self, account_id: S) -> Self {
+        self.account_id = Some(account_id.into());
+        self
+    }
+}
+
+impl<'a> ToQueryString for NotificationsRequest<'a> {
+    /// `NotificationType::Unknown` can't be sent, as there is no name for it
+    fn validate(&self) -> Result<(), Error> {
+        if self
+            .types
+            .iter()
+            .chain(&self.exclude_types)
+            .any(|kind| kind.name().is_none())
+        {
+            return Err(Error::Other(
+                "unknown notification types can't be filtered by".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn to_querystring(&self) -> Result<String, Error> {
+        self.validate()?;
+        let mut pairs = Vec::new();
+        let lists = [
+            ("types[]", &self.types),
+            ("exclude_types[]", &self.exclude_types),
+        ];
+        for (key, kinds) in &lists {
+            for name in kinds.iter().filter_map(NotificationType::name) {
+                pairs.push(format!("{}={}", key, name));
+            }
+        }
+        if let Some(ref account_id) = self.account_id {
+            let account_id: String =
+                form_urlencoded::byte_serialize(account_id.as_bytes()).collect();
+            pairs.push(format!("account_id={}", account_id));
+        }
+        Ok(format!("?{}", pairs.join("&")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_querystring() {
+        assert_eq!(NotificationsRequest::new().to_querystring().unwrap(), "?");
+        assert_eq!(
+            NotificationsRequest::new()
+                .exclude_types(&[NotificationType::Favourite, NotificationType::AdminSignUp])
+                .types(&[NotificationType::FollowRequest])
+                .account_id("a&b")
+                .to_querystring()
+                .unwrap(),
+            "?types[]=follow_request&exclude_types[]=favourite&exclude_types[]=admin.sign_up&\
+             account_id=a%26b"
+        );
+        assert!(NotificationsRequest::new()
+            .types(&[NotificationType::Unknown])
+            .to_querystring()
+            .is_err());
+    }
+}
@@ -0,0 +1,154 @@
+use super::querystring::ToQueryString;
+use crate::errors::Error;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Bounds for a request that returns a page of results, such as
+/// `client.favourites_with()` or `client.followers_with()`
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// # use elefren::{requests::ToQueryString, PageRequest};
+/// let request = PageRequest::new().limit(40).max_id("1234");
+/// # assert_eq!(&request.to_querystring().expect("Couldn't serialize qs")[..], "?max_id=1234&limit=40");
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PageRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<usize>,
+}
+
+impl PageRequest {
+    /// Construct a new, unbounded `PageRequest`
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only return results older than `max_id`
+    pub fn max_id<S: Into<String>>(mut self, max_id: S) -> Self {
+        self.max_id = Some(max_id.into());
+        self
+    }
+
+    /// Only return results newer than `since_id`
+    pub fn since_id<S: Into<String>>(mut self, since_id: S) -> Self {
+        self.since_id = Some(since_id.into());
+        self
+    }
+
+    /// Return the results immediately newer than `min_id`
+    pub fn min_id<S: Into<String>>(mut self, min_id: S) -> Self {
+        self.min_id = Some(min_id.into());
+        self
+    }
+
+    /// Return at most `limit` results per page
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    // The bounds in the query string of `url`, e.g. a `Link` header's next
+    // page. The ids are kept as they are: for favourites and bookmarks
+    // they're internal keys rather than status ids.
+    pub(crate) fn from_url(url: &Url) -> PageRequest {
+        let mut request = PageRequest::new();
+        for (key, value) in url.query_pairs() {
+            match &*key {
+                "max_id" => request.max_id = Some(value.into_owned()),
+                "since_id" => request.since_id = Some(value.into_owned()),
+                "min_id" => request.min_id = Some(value.into_owned()),
+                "limit" => request.limit = value.parse().ok(),
+                _ => (),
+            }
+        }
+        request
+    }
+
+    // The same bounds, with `limit` filled in if none was set
+    pub(crate) fn or_limit(&self, limit: Option<usize>) -> PageRequest {
+        PageRequest {
+            limit: self.limit.or(limit),
+            ..self.clone()
+        }
+    }
+
+    // Appends the query string to `url`, which may already have one
+    pub(crate) fn apply_to(&self, url: &str) -> Result<String, Error> {
+        let qs = serde_qs::to_string(&self)?;
+        Ok(match (qs.is_empty(), url.contains('?')) {
+            (true, _) => url.to_string(),
+            (false, true) => format!("{}&{}", url, qs),
+            (false, false) => format!("{}?{}", url, qs),
+        })
+    }
+}
+
+impl ToQueryString for PageRequest {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let request = PageRequest::new();
+        assert_eq!(
+            request.to_querystring().expect("Couldn't serialize qs"),
+            "?"
+        );
+    }
+
+    #[test]
+    fn test_all_bounds() {
+        let request = PageRequest::new()
+            .limit(10)
+            .min_id("1")
+            .since_id("2")
+            .max_id("3");
+        assert_eq!(
+            request.to_querystring().expect("Couldn't serialize qs"),
+            "?max_id=3&since_id=2&min_id=1&limit=10"
+        );
+    }
+
+    #[test]
+    fn test_from_url() {
+        let url =
+            Url::parse("https://example.com/api/v1/favourites?limit=20&max_id=AbC%2B1&local=true")
+                .unwrap();
+        let request = PageRequest::from_url(&url);
+        assert_eq!(request, PageRequest::new().max_id("AbC+1").limit(20));
+    }
+
+    #[test]
+    fn test_apply_to() {
+        let request = PageRequest::new().limit(5);
+        assert_eq!(
+            request
+                .apply_to("https://example.com/api/v1/favourites")
+                .unwrap(),
+            "https://example.com/api/v1/favourites?limit=5"
+        );
+        assert_eq!(
+            request
+                .apply_to("https://example.com/api/v1/timelines/public?local=true")
+                .unwrap(),
+            "https://example.com/api/v1/timelines/public?local=true&limit=5"
+        );
+        assert_eq!(
+            PageRequest::new()
+                .apply_to("https://example.com/api/v1/blocks")
+                .unwrap(),
+            "https://example.com/api/v1/blocks"
+        );
+    }
+}
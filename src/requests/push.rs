@@ -1,5 +1,5 @@
 use crate::{
-    entities::push::{add_subscription, update_data},
+    entities::push::{add_subscription, update_data, Policy, Subscription},
     errors::Result,
 };
 use serde::Serialize;
@@ -53,6 +53,7 @@ impl Keys {
 /// #   client_secret: "".into(),
 /// #   redirect: "".into(),
 /// #   token: "".into(),
+/// #   ..Default::default()
 /// # };
 /// use elefren::requests::{AddPushRequest, Keys};
 ///
@@ -77,6 +78,7 @@ pub struct AddPushRequest {
     favourite: Option<bool>,
     reblog: Option<bool>,
     mention: Option<bool>,
+    policy: Option<Policy>,
 }
 
 impl AddPushRequest {
@@ -164,6 +166,73 @@ impl AddPushRequest {
         self
     }
 
+    /// Only push notifications from the accounts described by `policy`
+    ///
+    /// # Example
+    /// ```
+    /// # extern crate elefren;
+    /// use elefren::{
+    ///     entities::push::Policy,
+    ///     requests::{AddPushRequest, Keys},
+    /// };
+    /// let keys = Keys::new("abcdef===", "foobar==");
+    /// let push_endpoint = "https://example.com/push/endpoint";
+    /// let request = AddPushRequest::new(push_endpoint, &keys).policy(Policy::Followed);
+    /// ```
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Whether `subscription`, as currently stored on the server, already
+    /// matches this request, so that it doesn't need to be sent again
+    ///
+    /// The endpoint, the alerts and the policy are compared. The keys can't
+    /// be, as the server never returns them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::{MastodonClient, Mastodon, Data};
+    /// # fn main() -> Result<(), elefren::Error> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// use elefren::requests::{AddPushRequest, Keys};
+    ///
+    /// let client = Mastodon::from(data);
+    /// let keys = Keys::new("stahesuahoei293ise===", "tasecoa,nmeozka==");
+    /// let request = AddPushRequest::new("http://example.com/push/endpoint", &keys).mention();
+    ///
+    /// let up_to_date = match client.get_push_subscription() {
+    ///     Ok(current) => request.subscription_matches(&current),
+    ///     Err(_) => false,
+    /// };
+    /// if !up_to_date {
+    ///     client.add_push_subscription(&request)?;
+    /// }
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn subscription_matches(&self, subscription: &Subscription) -> bool {
+        let alerts = &subscription.alerts;
+        let same = |wanted: Option<bool>, current: Option<bool>| {
+            wanted.unwrap_or(false) == current.unwrap_or(false)
+        };
+        subscription.endpoint == self.endpoint
+            && same(self.follow, alerts.follow)
+            && same(self.favourite, alerts.favourite)
+            && same(self.reblog, alerts.reblog)
+            && same(self.mention, alerts.mention)
+            && self.policy.unwrap_or_default() == subscription.policy
+    }
+
     fn flags_present(&self) -> bool {
         self.follow.is_some()
             || self.favourite.is_some()
@@ -207,6 +276,12 @@ impl AddPushRequest {
 
             form.data = Some(Data {
                 alerts: Some(alerts),
+                policy: self.policy,
+            });
+        } else if self.policy.is_some() {
+            form.data = Some(Data {
+                alerts: None,
+                policy: self.policy,
             });
         }
         Ok(form)
@@ -227,6 +302,7 @@ impl AddPushRequest {
 /// #   client_secret: "".into(),
 /// #   redirect: "".into(),
 /// #   token: "".into(),
+/// #   ..Default::default()
 /// # };
 /// use elefren::requests::UpdatePushRequest;
 ///
@@ -246,6 +322,7 @@ pub struct UpdatePushRequest {
     favourite: Option<bool>,
     reblog: Option<bool>,
     mention: Option<bool>,
+    policy: Option<Policy>,
 }
 
 impl UpdatePushRequest {
@@ -321,6 +398,19 @@ impl UpdatePushRequest {
         self
     }
 
+    /// Only push notifications from the accounts described by `policy`
+    ///
+    /// # Example
+    /// ```
+    /// # extern crate elefren;
+    /// use elefren::{entities::push::Policy, requests::UpdatePushRequest};
+    /// let request = UpdatePushRequest::new("foobar").policy(Policy::Follower);
+    /// ```
+    pub fn policy(mut self, policy: Policy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
     fn flags_present(&self) -> bool {
         self.follow.is_some()
             || self.favourite.is_some()
@@ -329,10 +419,7 @@ impl UpdatePushRequest {
     }
 
     pub(crate) fn build(&self) -> update_data::Form {
-        use crate::entities::push::{
-            update_data::{Data, Form},
-            Alerts,
-        };
+        use crate::entities::push::{update_data::Form, Alerts};
 
         let mut form = Form {
             id: self.id.clone(),
@@ -353,10 +440,9 @@ impl UpdatePushRequest {
             if let Some(mention) = self.mention {
                 alerts.mention = Some(mention);
             }
-            form.data = Data {
-                alerts: Some(alerts),
-            };
+            form.data.alerts = Some(alerts);
         }
+        form.data.policy = self.policy;
         form
     }
 }
@@ -393,6 +479,7 @@ mod tests {
                 favourite: None,
                 reblog: None,
                 mention: None,
+                policy: None,
             }
         );
     }
@@ -411,6 +498,7 @@ mod tests {
                 favourite: None,
                 reblog: None,
                 mention: None,
+                policy: None,
             }
         );
     }
@@ -430,6 +518,7 @@ mod tests {
                 favourite: Some(true),
                 reblog: None,
                 mention: None,
+                policy: None,
             }
         );
     }
@@ -448,6 +537,7 @@ mod tests {
                 favourite: None,
                 reblog: Some(true),
                 mention: None,
+                policy: None,
             }
         );
     }
@@ -466,6 +556,7 @@ mod tests {
                 favourite: None,
                 reblog: None,
                 mention: Some(true),
+                policy: None,
             }
         );
     }
@@ -513,6 +604,7 @@ mod tests {
                         reblog: Some(true),
                         mention: None,
                     }),
+                    policy: None,
                 }),
             }
         );
@@ -529,6 +621,7 @@ mod tests {
                 favourite: None,
                 reblog: None,
                 mention: None,
+                policy: None,
             }
         );
     }
@@ -544,6 +637,7 @@ mod tests {
                 favourite: None,
                 reblog: None,
                 mention: None,
+                policy: None,
             }
         );
     }
@@ -558,6 +652,7 @@ mod tests {
                 favourite: Some(true),
                 reblog: None,
                 mention: None,
+                policy: None,
             }
         );
     }
@@ -572,6 +667,7 @@ mod tests {
                 favourite: None,
                 reblog: Some(true),
                 mention: None,
+                policy: None,
             }
         );
     }
@@ -586,6 +682,7 @@ mod tests {
                 favourite: None,
                 reblog: None,
                 mention: Some(true),
+                policy: None,
             }
         );
     }
@@ -597,7 +694,10 @@ mod tests {
             form,
             update_data::Form {
                 id: "some-id".to_string(),
-                data: update_data::Data { alerts: None },
+                data: update_data::Data {
+                    alerts: None,
+                    policy: None,
+                },
             }
         );
     }
@@ -617,8 +717,57 @@ mod tests {
                         reblog: None,
                         mention: None,
                     }),
+                    policy: None,
                 },
             }
         );
     }
+
+    #[test]
+    fn test_add_push_request_build_policy() {
+        let keys = Keys::new("anetohias===", "oeatssah=");
+        let req = AddPushRequest::new("https://example.com/push/endpoint", &keys)
+            .policy(Policy::Followed);
+        let form = req.build().expect("Couldn't build form");
+        assert_eq!(
+            form.data,
+            Some(add_subscription::Data {
+                alerts: None,
+                policy: Some(Policy::Followed),
+            })
+        );
+    }
+
+    #[test]
+    fn test_subscription_matches() {
+        let subscription: Subscription = serde_json::from_str(
+            r#"{"id":"1","endpoint":"https://example.com/push/endpoint","server_key":"key",
+                "alerts":{"follow":true,"favourite":false,"reblog":false,"mention":true},
+                "policy":"followed"}"#,
+        )
+        .unwrap();
+        let keys = Keys::new("anetohias===", "oeatssah=");
+        let req = AddPushRequest::new("https://example.com/push/endpoint", &keys)
+            .follow()
+            .mention();
+        assert!(!req.subscription_matches(&subscription));
+        let req = req.policy(Policy::Followed);
+        assert!(req.subscription_matches(&subscription));
+        assert!(!req.clone().reblog().subscription_matches(&subscription));
+        let elsewhere = AddPushRequest {
+            endpoint: "https://example.com/other".to_string(),
+            ..req
+        };
+        assert!(!elsewhere.subscription_matches(&subscription));
+    }
+
+    #[test]
+    fn test_subscription_defaults() {
+        let subscription: Subscription = serde_json::from_str(
+            r#"{"id":"1","endpoint":"https://example.com/push/endpoint","server_key":"key"}"#,
+        )
+        .unwrap();
+        assert_eq!(subscription.alerts, Alerts::default());
+        assert_eq!(subscription.policy, Policy::All);
+    }
 }
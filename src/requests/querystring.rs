@@ -0,0 +1,47 @@
+use crate::errors::Error;
+use serde::Serialize;
+
+/// Request builders that are sent as the query string of a `GET` request
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::requests::{PageRequest, ToQueryString};
+///
+/// let request = PageRequest::new().limit(10);
+/// assert_eq!(
+///     &request.to_querystring().expect("Couldn't serialize qs"),
+///     "?limit=10"
+/// );
+/// ```
+pub trait ToQueryString: Serialize + Sized {
+    /// Checks the options for combinations the server can't handle. Called by
+    /// `to_querystring`; by default, every combination is fine.
+    fn validate(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Turns this builder into a query string, including the leading `?`
+    fn to_querystring(&self) -> Result<String, Error> {
+        self.validate()?;
+        Ok(format!("?{}", serde_qs::to_string(self)?))
+    }
+}
+
+// Flags are sent as `1`, and left out when they aren't set
+pub(crate) mod bool_qs_serialize {
+    use serde::Serializer;
+
+    pub fn is_false(b: &bool) -> bool {
+        !*b
+    }
+
+    pub fn serialize<S: Serializer>(b: &bool, s: S) -> Result<S::Ok, S::Error> {
+        if *b {
+            s.serialize_i64(1)
+        } else {
+            s.serialize_i64(0)
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use crate::{
+    entities::{instance::Rule, report::ReportCategory},
+    errors::{Error, Result},
+};
+use serde::Serialize;
+
+/// Form used to report an account, and optionally some of its statuses
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::{entities::report::ReportCategory, requests::ReportRequest};
+///
+/// let request = ReportRequest::new("42")
+///     .status_id("108882889550545820")
+///     .comment("spamming replies")
+///     .category(ReportCategory::Spam)
+///     .forward(true);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReportRequest {
+    account_id: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    status_ids: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    forward: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    category: Option<ReportCategory>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    rule_ids: Vec<String>,
+}
+
+impl ReportRequest {
+    /// Create a new ReportRequest, reporting the account with id `account_id`
+    pub fn new(account_id: &str) -> ReportRequest {
+        ReportRequest {
+            account_id: account_id.to_string(),
+            status_ids: Vec::new(),
+            comment: None,
+            forward: None,
+            category: None,
+            rule_ids: Vec::new(),
+        }
+    }
+
+    /// Attach a status of the reported account to the report
+    pub fn status_id<S: Into<String>>(mut self, id: S) -> Self {
+        self.status_ids.push(id.into());
+        self
+    }
+
+    /// Attach several statuses of the reported account to the report
+    pub fn status_ids<I, S>(mut self, ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.status_ids.extend(ids.into_iter().map(Into::into));
+        self
+    }
+
+    /// Set the reason for the report, shown to the moderators
+    pub fn comment(mut self, comment: &str) -> Self {
+        self.comment = Some(comment.to_string());
+        self
+    }
+
+    /// Set whether the report is also forwarded to the moderators of the
+    /// reported account's instance, if it is a remote one
+    pub fn forward(mut self, forward: bool) -> Self {
+        self.forward = Some(forward);
+        self
+    }
+
+    /// Set why the account is reported
+    pub fn category(mut self, category: ReportCategory) -> Self {
+        self.category = Some(category);
+        self
+    }
+
+    /// Add an instance rule the account violated. Sets the category to
+    /// `Violation`, which is the only one rules can be given for.
+    pub fn rule_id<S: Into<String>>(mut self, id: S) -> Self {
+        self.rule_ids.push(id.into());
+        self.category = Some(ReportCategory::Violation);
+        self
+    }
+
+    /// Add the rules among `rules` for which `predicate` returns `true`, as
+    /// with `rule_id`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate elefren;
+    /// # use elefren::prelude::*;
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # let data = Data {
+    /// #   base: "".into(),
+    /// #   client_id: "".into(),
+    /// #   client_secret: "".into(),
+    /// #   redirect: "".into(),
+    /// #   token: "".into(),
+    /// #   ..Default::default()
+    /// # };
+    /// let client = Mastodon::from(data);
+    /// let rules = client.instance_rules()?;
+    /// let request = ReportRequest::new("42")
+    ///     .status_id("108882889550545820")
+    ///     .rules_matching(&rules, |rule| rule.text.to_lowercase().contains("spam"));
+    /// client.report_with(&request)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn rules_matching<F>(mut self, rules: &[Rule], mut predicate: F) -> Self
+    where
+        F: FnMut(&Rule) -> bool,
+    {
+        for rule in rules {
+            if predicate(rule) {
+                self = self.rule_id(rule.id.clone());
+            }
+        }
+        self
+    }
+
+    /// Checks that the rules added to the report are among `rules`, as
+    /// returned by `instance_rules`
+    ///
+    /// Instances can edit their rules at any time, and reporting a rule that
+    /// no longer exists fails with a 422 error.
+    pub fn validate_rules(&self, rules: &[Rule]) -> Result<()> {
+        match self
+            .rule_ids
+            .iter()
+            .find(|id| !rules.iter().any(|rule| &rule.id == *id))
+        {
+            Some(id) => Err(Error::Other(format!("the instance has no rule '{}'", id))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn test_serialize_request() {
+        let request = ReportRequest::new("42");
+        let ser = serde_json::to_string(&request).expect("Couldn't serialize");
+        assert_eq!(ser, r#"{"account_id":"42"}"#);
+
+        let id = String::from("2");
+        let request = request
+            .status_ids(vec!["1", &id])
+            .comment("rude")
+            .forward(false)
+            .rule_id(id);
+        let ser = serde_json::to_string(&request).expect("Couldn't serialize");
+        assert_eq!(
+            ser,
+            r#"{"account_id":"42","status_ids":["1","2"],"comment":"rude","forward":false,"category":"violation","rule_ids":["2"]}"#
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        let rules: Vec<Rule> = serde_json::from_str(
+            r#"[{"id": "1", "text": "No spam"}, {"id": "2", "text": "Be nice", "hint": "Really"}]"#,
+        )
+        .expect("Couldn't deserialize");
+        let request = ReportRequest::new("42").rules_matching(&rules, |rule| rule.id == "3");
+        assert_eq!(request, ReportRequest::new("42"));
+
+        let request =
+            ReportRequest::new("42").rules_matching(&rules, |rule| rule.text.contains("spam"));
+        assert_eq!(request.rule_ids, vec!["1"]);
+        assert_eq!(request.category, Some(ReportCategory::Violation));
+        assert!(request.validate_rules(&rules).is_ok());
+
+        let request = request.rule_id("3");
+        match request.validate_rules(&rules) {
+            Err(Error::Other(message)) => assert!(message.contains("'3'")),
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use super::querystring::ToQueryString;
+use crate::entities::search_result::SearchResultV2;
+use serde::Serialize;
+use std::borrow::Cow;
+
+/// The kind of results a search is restricted to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchType {
+    /// Only search for accounts
+    Accounts,
+    /// Only search for hashtags
+    Hashtags,
+    /// Only search for statuses
+    Statuses,
+}
+
+/// Builder for making a client.search_v2_with() call
+///
+/// Search results are paged by offset; `next_page` builds the request for
+/// the results following a page.
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::requests::{SearchRequest, SearchType};
+/// # use elefren::requests::ToQueryString;
+///
+/// let request = SearchRequest::new("rust")
+///     .search_type(SearchType::Statuses)
+///     .limit(10);
+/// # assert_eq!(&request.to_querystring().expect("Couldn't serialize qs")[..], "?q=rust&type=statuses&limit=10");
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct SearchRequest<'a> {
+    q: Cow<'a, str>,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    search_type: Option<SearchType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resolve: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    following: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    account_id: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset: Option<usize>,
+}
+
+// the number of results per page when no limit is given
+const DEFAULT_LIMIT: usize = 20;
+
+impl<'a> SearchRequest<'a> {
+    /// Search for `q`
+    pub fn new<Q: Into<Cow<'a, str>>>(q: Q) -> Self {
+        SearchRequest {
+            q: q.into(),
+            search_type: None,
+            resolve: None,
+            following: None,
+            account_id: None,
+            limit: None,
+            offset: None,
+        }
+    }
+
+    /// Only return results of the given type
+    pub fn search_type(mut self, search_type: SearchType) -> Self {
+        self.search_type = Some(search_type);
+        self
+    }
+
+    /// Look up remote accounts and statuses through WebFinger
+    pub fn resolve(mut self) -> Self {
+        self.resolve = Some(true);
+        self
+    }
+
+    /// Only return accounts the user is following
+    pub fn following(mut self) -> Self {
+        self.following = Some(true);
+        self
+    }
+
+    /// Only return statuses posted by the account with the id `account_id`
+    pub fn account_id<I: Into<Cow<'a, str>>>(mut self, account_id: I) -> Self {
+        self.account_id = Some(account_id.into());
+        self
+    }
+
+    /// Return at most `limit` results of each type
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Skip the first `offset` results of each type
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// The request for the results following `result`, which was returned
+    /// for this request, or `None` if `result` was the last page
+    ///
+    /// Without a `search_type`, the offset applies to each type of result,
+    /// so paging continues as long as any type filled a whole page.
+    pub fn next_page(&self, result: &SearchResultV2) -> Option<SearchRequest<'a>> {
+        let count = match self.search_type {
+            Some(SearchType::Accounts) => result.accounts.len(),
+            Some(SearchType::Hashtags) => result.hashtags.len(),
+            Some(SearchType::Statuses) => result.statuses.len(),
+            None => result
+                .accounts
+                .len()
+                .max(result.hashtags.len())
+                .max(result.statuses.len()),
+        };
+        if count == 0 || count < self.limit.unwrap_or(DEFAULT_LIMIT) {
+            return None;
+        }
+        let offset = self.offset.unwrap_or(0) + count;
+        Some(self.clone().offset(offset))
+    }
+}
+
+impl<'a> ToQueryString for SearchRequest<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    fn result(hashtags: usize, statuses: usize) -> SearchResultV2 {
+        let status = test_fixtures::status();
+        let tag = serde_json::from_str(
+            r#"{"name": "rust", "url": "https://example.com/tags/rust", "history": []}"#,
+        )
+        .unwrap();
+        SearchResultV2 {
+            accounts: vec![],
+            statuses: vec![status; statuses],
+            hashtags: vec![tag; hashtags],
+        }
+    }
+
+    #[test]
+    fn test_querystring() {
+        let request = SearchRequest::new("foo bar")
+            .resolve()
+            .following()
+            .account_id("42")
+            .offset(20);
+        assert_eq!(
+            request.to_querystring().expect("Couldn't serialize qs"),
+            "?q=foo+bar&resolve=true&following=true&account_id=42&offset=20"
+        );
+    }
+
+    #[test]
+    fn test_next_page() {
+        let request = SearchRequest::new("rust")
+            .search_type(SearchType::Statuses)
+            .limit(2);
+        let next = request.next_page(&result(0, 2)).unwrap();
+        assert_eq!(next, request.clone().offset(2));
+        assert_eq!(next.next_page(&result(0, 2)).unwrap().offset, Some(4));
+        assert_eq!(next.next_page(&result(5, 1)), None);
+
+        let request = SearchRequest::new("rust").limit(2);
+        assert_eq!(request.next_page(&result(2, 1)).unwrap().offset, Some(2));
+        assert_eq!(request.next_page(&result(0, 0)), None);
+    }
+}
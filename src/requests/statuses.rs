@@ -1,35 +1,20 @@
+use super::querystring::{bool_qs_serialize, ToQueryString};
 use crate::errors::Error;
 use serde::Serialize;
 use std::{borrow::Cow, convert::Into};
 
-mod bool_qs_serialize {
-    use serde::Serializer;
-
-    pub fn is_false(b: &bool) -> bool {
-        !*b
-    }
-
-    pub fn serialize<S: Serializer>(b: &bool, s: S) -> Result<S::Ok, S::Error> {
-        if *b {
-            s.serialize_i64(1)
-        } else {
-            s.serialize_i64(0)
-        }
-    }
-}
-
 /// Builder for making a client.statuses() call
 ///
 /// # Example
 ///
 /// ```
 /// # extern crate elefren;
-/// # use elefren::StatusesRequest;
+/// # use elefren::{requests::ToQueryString, StatusesRequest};
 /// let request = StatusesRequest::new()
 ///     .only_media()
 ///     .pinned()
 ///     .since_id("foo");
-/// # assert_eq!(&request.to_querystring().expect("Couldn't serialize qs")[..], "?only_media=1&pinned=1&since_id=foo");
+/// # assert_eq!(&ToQueryString::to_querystring(&request).expect("Couldn't serialize qs")[..], "?only_media=1&pinned=1&since_id=foo");
 /// ```
 #[derive(Clone, Debug, Default, PartialEq, Serialize)]
 pub struct StatusesRequest<'a> {
@@ -53,6 +38,8 @@ pub struct StatusesRequest<'a> {
     #[serde(skip_serializing_if = "bool_qs_serialize::is_false")]
     #[serde(serialize_with = "bool_qs_serialize::serialize")]
     exclude_reblogs: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tagged: Option<Cow<'a, str>>,
 }
 
 impl<'a> Into<Option<StatusesRequest<'a>>> for &'a mut StatusesRequest<'a> {
@@ -66,6 +53,7 @@ impl<'a> Into<Option<StatusesRequest<'a>>> for &'a mut StatusesRequest<'a> {
             limit: self.limit,
             min_id: self.min_id.clone(),
             exclude_reblogs: self.exclude_reblogs,
+            tagged: self.tagged.clone(),
         })
     }
 }
@@ -77,7 +65,7 @@ impl<'a> StatusesRequest<'a> {
     ///
     /// ```
     /// # extern crate elefren;
-    /// # use elefren::StatusesRequest;
+    /// # use elefren::{requests::ToQueryString, StatusesRequest};
     /// let request = StatusesRequest::new();
     /// ```
     pub fn new() -> Self {
@@ -90,9 +78,9 @@ impl<'a> StatusesRequest<'a> {
     ///
     /// ```
     /// # extern crate elefren;
-    /// # use elefren::StatusesRequest;
+    /// # use elefren::{requests::ToQueryString, StatusesRequest};
     /// let mut request = StatusesRequest::new();
-    /// assert_eq!(&request.only_media().to_querystring().expect("Couldn't serialize qs"), "?only_media=1");
+    /// assert_eq!(&ToQueryString::to_querystring(&request.only_media()).expect("Couldn't serialize qs"), "?only_media=1");
     pub fn only_media(mut self) -> Self {
         self.only_media = true;
         self
@@ -104,12 +92,10 @@ impl<'a> StatusesRequest<'a> {
     ///
     /// ```
     /// # extern crate elefren;
-    /// # use elefren::StatusesRequest;
+    /// # use elefren::{requests::ToQueryString, StatusesRequest};
     /// let mut request = StatusesRequest::new();
     /// assert_eq!(
-    ///     &request
-    ///         .exclude_reblogs()
-    ///         .to_querystring()
+    ///     &ToQueryString::to_querystring(&request.exclude_reblogs())
     ///         .expect("Couldn't serialize qs"),
     ///     "?exclude_reblogs=1"
     /// );
@@ -125,12 +111,10 @@ impl<'a> StatusesRequest<'a> {
     ///
     /// ```
     /// # extern crate elefren;
-    /// # use elefren::StatusesRequest;
+    /// # use elefren::{requests::ToQueryString, StatusesRequest};
     /// let mut request = StatusesRequest::new();
     /// assert_eq!(
-    ///     &request
-    ///         .exclude_replies()
-    ///         .to_querystring()
+    ///     &ToQueryString::to_querystring(&request.exclude_replies())
     ///         .expect("Couldn't serialize qs"),
     ///     "?exclude_replies=1"
     /// );
@@ -146,12 +130,10 @@ impl<'a> StatusesRequest<'a> {
     ///
     /// ```
     /// # extern crate elefren;
-    /// # use elefren::StatusesRequest;
+    /// # use elefren::{requests::ToQueryString, StatusesRequest};
     /// let mut request = StatusesRequest::new();
     /// assert_eq!(
-    ///     &request
-    ///         .pinned()
-    ///         .to_querystring()
+    ///     &ToQueryString::to_querystring(&request.pinned())
     ///         .expect("Couldn't serialize qs"),
     ///     "?pinned=1"
     /// );
@@ -167,12 +149,10 @@ impl<'a> StatusesRequest<'a> {
     ///
     /// ```
     /// # extern crate elefren;
-    /// # use elefren::StatusesRequest;
+    /// # use elefren::{requests::ToQueryString, StatusesRequest};
     /// let mut request = StatusesRequest::new();
     /// assert_eq!(
-    ///     &request
-    ///         .max_id("foo")
-    ///         .to_querystring()
+    ///     &ToQueryString::to_querystring(&request.max_id("foo"))
     ///         .expect("Couldn't serialize qs"),
     ///     "?max_id=foo"
     /// );
@@ -188,12 +168,10 @@ impl<'a> StatusesRequest<'a> {
     ///
     /// ```
     /// # extern crate elefren;
-    /// # use elefren::StatusesRequest;
+    /// # use elefren::{requests::ToQueryString, StatusesRequest};
     /// let mut request = StatusesRequest::new();
     /// assert_eq!(
-    ///     &request
-    ///         .since_id("foo")
-    ///         .to_querystring()
+    ///     &ToQueryString::to_querystring(&request.since_id("foo"))
     ///         .expect("Couldn't serialize qs"),
     ///     "?since_id=foo"
     /// );
@@ -209,12 +187,10 @@ impl<'a> StatusesRequest<'a> {
     ///
     /// ```
     /// # extern crate elefren;
-    /// # use elefren::StatusesRequest;
+    /// # use elefren::{requests::ToQueryString, StatusesRequest};
     /// let mut request = StatusesRequest::new();
     /// assert_eq!(
-    ///     &request
-    ///         .limit(10)
-    ///         .to_querystring()
+    ///     &ToQueryString::to_querystring(&request.limit(10))
     ///         .expect("Couldn't serialize qs"),
     ///     "?limit=10"
     /// );
@@ -224,18 +200,24 @@ impl<'a> StatusesRequest<'a> {
         self
     }
 
+    // The same request, with `limit` filled in if none was set
+    pub(crate) fn or_limit(self, limit: Option<usize>) -> Self {
+        StatusesRequest {
+            limit: self.limit.or(limit),
+            ..self
+        }
+    }
+
     /// Set the `?min_id=:min_id` flag for the .statuses() request
     ///
     /// # Example
     ///
     /// ```
     /// # extern crate elefren;
-    /// # use elefren::StatusesRequest;
+    /// # use elefren::{requests::ToQueryString, StatusesRequest};
     /// let mut request = StatusesRequest::new();
     /// assert_eq!(
-    ///     &request
-    ///         .min_id("foobar")
-    ///         .to_querystring()
+    ///     &ToQueryString::to_querystring(&request.min_id("foobar"))
     ///         .expect("Couldn't serialize qs"),
     ///     "?min_id=foobar"
     /// );
@@ -245,25 +227,77 @@ impl<'a> StatusesRequest<'a> {
         self
     }
 
-    /// Turns this builder into a querystring
+    /// Set the `?tagged=:hashtag` flag for the .statuses() request, to only
+    /// get statuses with the hashtag. A leading `#` is left out.
     ///
     /// # Example
     ///
     /// ```
     /// # extern crate elefren;
-    /// # use elefren::StatusesRequest;
+    /// # use elefren::{requests::ToQueryString, StatusesRequest};
     /// let mut request = StatusesRequest::new();
     /// assert_eq!(
-    ///     &request
-    ///         .limit(10)
-    ///         .pinned()
-    ///         .to_querystring()
+    ///     &ToQueryString::to_querystring(&request.tagged("#caturday"))
     ///         .expect("Couldn't serialize qs"),
-    ///     "?pinned=1&limit=10"
+    ///     "?tagged=caturday"
     /// );
     /// ```
+    pub fn tagged<S: Into<Cow<'a, str>>>(mut self, hashtag: S) -> Self {
+        self.tagged = Some(match hashtag.into() {
+            Cow::Borrowed(hashtag) => Cow::Borrowed(hashtag.trim_start_matches('#')),
+            Cow::Owned(hashtag) => Cow::Owned(hashtag.trim_start_matches('#').to_string()),
+        });
+        self
+    }
+
+    /// Turns this builder into a querystring
+    #[deprecated(since = "0.23.0", note = "use `ToQueryString::to_querystring`")]
     pub fn to_querystring(&self) -> Result<String, Error> {
-        Ok(format!("?{}", serde_qs::to_string(&self)?))
+        ToQueryString::to_querystring(self)
+    }
+}
+
+impl<'a> ToQueryString for StatusesRequest<'a> {
+    /// Checks for combinations of options servers don't handle well
+    ///
+    /// Pinned statuses aren't paged, and some servers reject `pinned` together
+    /// with `max_id`, so that is an error. `only_media` together with
+    /// `exclude_replies` is only logged as a warning: some servers ignore
+    /// `exclude_replies` then, and return media replies as well.
+    ///
+    /// `tagged` needs a hashtag. It narrows down any of the other options,
+    /// e.g. `pinned` and `tagged` together are the pinned statuses with the
+    /// hashtag. `exclude_reblogs` has no effect on pinned statuses, which
+    /// are never reblogs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate elefren;
+    /// # use elefren::{requests::ToQueryString, StatusesRequest};
+    /// assert!(StatusesRequest::new().pinned().max_id("foo").validate().is_err());
+    /// assert!(StatusesRequest::new().pinned().limit(5).validate().is_ok());
+    /// assert!(StatusesRequest::new().tagged("#").validate().is_err());
+    /// ```
+    fn validate(&self) -> Result<(), Error> {
+        if self.pinned && self.max_id.is_some() {
+            return Err(Error::Other(
+                "pinned statuses aren't paged, `pinned` can't be combined with `max_id`"
+                    .to_string(),
+            ));
+        }
+        if self.tagged.as_deref() == Some("") {
+            return Err(Error::Other(
+                "`tagged` needs a hashtag, not an empty string".to_string(),
+            ));
+        }
+        if self.only_media && self.exclude_replies {
+            log::warn!(
+                "some servers ignore `exclude_replies` together with `only_media`, replies \
+                 with media may be returned"
+            );
+        }
+        Ok(())
     }
 }
 
@@ -285,6 +319,7 @@ mod tests {
                 limit: None,
                 min_id: None,
                 exclude_reblogs: false,
+                tagged: None,
             }
         );
     }
@@ -303,6 +338,7 @@ mod tests {
                 limit: None,
                 min_id: None,
                 exclude_reblogs: false,
+                tagged: None,
             }
         );
     }
@@ -321,6 +357,7 @@ mod tests {
                 limit: None,
                 min_id: None,
                 exclude_reblogs: false,
+                tagged: None,
             }
         );
     }
@@ -338,6 +375,7 @@ mod tests {
                 limit: None,
                 min_id: None,
                 exclude_reblogs: false,
+                tagged: None,
             }
         );
     }
@@ -355,6 +393,7 @@ mod tests {
                 limit: None,
                 min_id: None,
                 exclude_reblogs: false,
+                tagged: None,
             }
         );
     }
@@ -372,6 +411,7 @@ mod tests {
                 limit: None,
                 min_id: None,
                 exclude_reblogs: false,
+                tagged: None,
             }
         );
     }
@@ -389,6 +429,7 @@ mod tests {
                 limit: Some(42),
                 min_id: None,
                 exclude_reblogs: false,
+                tagged: None,
             }
         );
     }
@@ -406,6 +447,25 @@ mod tests {
                 limit: None,
                 min_id: Some("foo".into()),
                 exclude_reblogs: false,
+                tagged: None,
+            }
+        );
+    }
+    #[test]
+    fn test_tagged() {
+        let request = StatusesRequest::new().tagged("#foo");
+        assert_eq!(
+            request,
+            StatusesRequest {
+                only_media: false,
+                exclude_replies: false,
+                pinned: false,
+                max_id: None,
+                since_id: None,
+                limit: None,
+                min_id: None,
+                exclude_reblogs: false,
+                tagged: Some("foo".into()),
             }
         );
     }
@@ -415,9 +475,8 @@ mod tests {
             (| $r:ident | $b:block, $expected:expr) => {{
                 let $r = StatusesRequest::new();
                 let $r = $b;
-                let qs = $r
-                    .to_querystring()
-                    .expect("Failed to serialize querystring");
+                let qs =
+                    ToQueryString::to_querystring(&$r).expect("Failed to serialize querystring");
                 assert_eq!(&qs, $expected);
             }};
         }
@@ -479,10 +538,6 @@ mod tests {
             |request| { request.pinned().exclude_replies() },
             "?exclude_replies=1&pinned=1"
         );
-        qs_test!(
-            |request| { request.pinned().max_id("foo") },
-            "?pinned=1&max_id=foo"
-        );
         qs_test!(
             |request| { request.pinned().since_id("foo") },
             "?pinned=1&since_id=foo"
@@ -499,10 +554,6 @@ mod tests {
             |request| { request.max_id("foo").exclude_replies() },
             "?exclude_replies=1&max_id=foo"
         );
-        qs_test!(
-            |request| { request.max_id("foo").pinned() },
-            "?pinned=1&max_id=foo"
-        );
         qs_test!(
             |request| { request.max_id("foo").since_id("foo") },
             "?max_id=foo&since_id=foo"
@@ -551,5 +602,47 @@ mod tests {
             |request| { request.limit(42).since_id("foo") },
             "?since_id=foo&limit=42"
         );
+        qs_test!(|request| { request.tagged("foo") }, "?tagged=foo");
+        qs_test!(
+            |request| { request.tagged("#foo").pinned() },
+            "?pinned=1&tagged=foo"
+        );
+        qs_test!(
+            |request| { request.pinned().exclude_reblogs().tagged("foo") },
+            "?pinned=1&exclude_reblogs=1&tagged=foo"
+        );
+        qs_test!(
+            |request| { request.exclude_reblogs().tagged("foo").max_id("bar") },
+            "?max_id=bar&exclude_reblogs=1&tagged=foo"
+        );
+        qs_test!(
+            |request| { request.tagged("café au lait".to_string()) },
+            "?tagged=caf%C3%A9+au+lait"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(StatusesRequest::new().validate().is_ok());
+        let request = StatusesRequest::new().pinned().max_id("foo");
+        assert!(matches!(request.validate(), Err(Error::Other(_))));
+        assert!(ToQueryString::to_querystring(&request).is_err());
+        assert!(
+            ToQueryString::to_querystring(&StatusesRequest::new().max_id("foo").pinned()).is_err()
+        );
+        assert!(StatusesRequest::new().tagged("").validate().is_err());
+        assert!(StatusesRequest::new().tagged("##").validate().is_err());
+        assert!(StatusesRequest::new()
+            .pinned()
+            .tagged("foo")
+            .exclude_reblogs()
+            .validate()
+            .is_ok());
+        // only warned about
+        assert!(StatusesRequest::new()
+            .only_media()
+            .exclude_replies()
+            .validate()
+            .is_ok());
     }
 }
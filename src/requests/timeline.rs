@@ -0,0 +1,92 @@
+use super::querystring::{bool_qs_serialize, ToQueryString};
+use crate::errors::Error;
+use serde::Serialize;
+
+/// Represents the options for the public timeline
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::requests::{TimelineRequest, ToQueryString};
+///
+/// let request = TimelineRequest::new().local().only_media();
+/// assert_eq!(
+///     &request.to_querystring().expect("Couldn't serialize qs"),
+///     "?local=1&only_media=1"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize)]
+pub struct TimelineRequest {
+    #[serde(skip_serializing_if = "bool_qs_serialize::is_false")]
+    #[serde(serialize_with = "bool_qs_serialize::serialize")]
+    local: bool,
+    #[serde(skip_serializing_if = "bool_qs_serialize::is_false")]
+    #[serde(serialize_with = "bool_qs_serialize::serialize")]
+    remote: bool,
+    #[serde(skip_serializing_if = "bool_qs_serialize::is_false")]
+    #[serde(serialize_with = "bool_qs_serialize::serialize")]
+    only_media: bool,
+}
+
+impl TimelineRequest {
+    /// make a new TimelineRequest builder, for all public statuses the
+    /// instance knows about
+    pub fn new() -> Self {
+        TimelineRequest::default()
+    }
+
+    /// Only the statuses originating from this instance
+    pub fn local(mut self) -> Self {
+        self.local = true;
+        self
+    }
+
+    /// Only the statuses originating from other instances
+    pub fn remote(mut self) -> Self {
+        self.remote = true;
+        self
+    }
+
+    /// Only the statuses with media attachments
+    pub fn only_media(mut self) -> Self {
+        self.only_media = true;
+        self
+    }
+}
+
+impl ToQueryString for TimelineRequest {
+    /// `local` and `remote` exclude each other, so together they are an
+    /// error
+    fn validate(&self) -> Result<(), Error> {
+        if self.local && self.remote {
+            return Err(Error::Other(
+                "`local` and `remote` can't be combined, no status is both".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_querystring() {
+        assert_eq!(TimelineRequest::new().to_querystring().unwrap(), "?");
+        assert_eq!(
+            TimelineRequest::new()
+                .remote()
+                .only_media()
+                .to_querystring()
+                .unwrap(),
+            "?remote=1&only_media=1"
+        );
+        assert!(TimelineRequest::new()
+            .local()
+            .remote()
+            .to_querystring()
+            .is_err());
+    }
+}
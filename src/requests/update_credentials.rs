@@ -23,6 +23,7 @@ use crate::{
 /// #   client_secret: "".into(),
 /// #   redirect: "".into(),
 /// #   token: "".into(),
+/// #   ..Default::default()
 /// # };
 /// use elefren::{prelude::*, status_builder::Visibility, UpdateCredsRequest};
 ///
@@ -190,7 +191,7 @@ impl UpdateCredsRequest {
             display_name: self.display_name.clone(),
             note: self.note.clone(),
             avatar: self.avatar.clone(),
-            header: self.avatar.clone(),
+            header: self.header.clone(),
             source: Some(UpdateSource {
                 privacy: self.privacy,
                 sensitive: self.sensitive,
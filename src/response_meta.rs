@@ -0,0 +1,109 @@
+use reqwest::{blocking::Response, header::HeaderMap, StatusCode};
+
+use crate::rate_limit::RateLimit;
+
+/// Metadata about the response to a request, as returned by
+/// `Mastodon::get_with_meta` and `Mastodon::post_with_meta`, and kept for the
+/// last response by `Mastodon::last_response`
+///
+/// This is mostly useful for debugging, e.g. to inspect caching headers, or
+/// to tell an instance admin the `X-Request-Id` of a failing request.
+#[derive(Debug, Clone)]
+pub struct ResponseMeta {
+    /// The HTTP status of the response
+    pub status: StatusCode,
+    /// All headers of the response
+    pub headers: HeaderMap,
+    /// The rate limit reported in the response's `X-RateLimit-*` headers
+    pub rate_limit: Option<RateLimit>,
+    /// The id the server assigned to the request, from the `X-Request-Id`
+    /// header
+    pub request_id: Option<String>,
+    /// The `Deprecation` header, sent when the endpoint is deprecated. This
+    /// is either `true` or the date it was deprecated on.
+    pub deprecation: Option<String>,
+    /// The `Sunset` header, the date a deprecated endpoint will be removed
+    pub sunset: Option<String>,
+}
+
+impl ResponseMeta {
+    pub(crate) fn from_response(response: &Response) -> ResponseMeta {
+        ResponseMeta::from_parts(response.status(), response.headers().clone())
+    }
+
+    fn from_parts(status: StatusCode, headers: HeaderMap) -> ResponseMeta {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_string())
+        };
+        ResponseMeta {
+            status,
+            rate_limit: RateLimit::from_headers(&headers),
+            request_id: header("x-request-id"),
+            deprecation: header("deprecation"),
+            sunset: header("sunset"),
+            headers,
+        }
+    }
+
+    /// Whether the server flagged the endpoint as deprecated
+    pub fn is_deprecated(&self) -> bool {
+        self.deprecation.is_some()
+    }
+
+    // Logs the request id, and warns about deprecated endpoints
+    pub(crate) fn log(&self, url: &str) {
+        if let Some(ref request_id) = self.request_id {
+            log::debug!("{} {} (request id {})", self.status, url, request_id);
+        }
+        if self.is_deprecated() {
+            match self.sunset {
+                Some(ref sunset) => log::warn!("{} is deprecated, sunset on {}", url, sunset),
+                None => log::warn!("{} is deprecated", url),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_from_parts() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", HeaderValue::from_static("8a3b2c1d"));
+        headers.insert("cache-control", HeaderValue::from_static("no-store"));
+        let meta = ResponseMeta::from_parts(StatusCode::OK, headers);
+        assert_eq!(meta.status, StatusCode::OK);
+        assert_eq!(meta.request_id.as_deref(), Some("8a3b2c1d"));
+        assert_eq!(meta.headers["cache-control"], "no-store");
+        assert!(meta.rate_limit.is_none());
+        assert!(!meta.is_deprecated());
+    }
+
+    #[test]
+    fn test_deprecation() {
+        let mut headers = HeaderMap::new();
+        headers.insert("deprecation", HeaderValue::from_static("true"));
+        headers.insert(
+            "sunset",
+            HeaderValue::from_static("Wed, 01 Jan 2025 00:00:00 GMT"),
+        );
+        let meta = ResponseMeta::from_parts(StatusCode::OK, headers);
+        assert!(meta.is_deprecated());
+        assert_eq!(
+            meta.sunset.as_deref(),
+            Some("Wed, 01 Jan 2025 00:00:00 GMT")
+        );
+    }
+
+    #[test]
+    fn test_no_request_id() {
+        let meta = ResponseMeta::from_parts(StatusCode::NOT_FOUND, HeaderMap::new());
+        assert!(meta.request_id.is_none());
+    }
+}
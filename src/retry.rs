@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use reqwest::{header::HeaderMap, Method, StatusCode};
+
+/// Controls whether and how often a failed request is retried.
+///
+/// Requests are retried when the server responds with `429 Too Many
+/// Requests`, `502 Bad Gateway`, `503 Service Unavailable` or `504 Gateway
+/// Timeout`, or when connecting to the server fails or times out.
+///
+/// Requests that aren't idempotent, e.g. posting a status or following an
+/// account, are only retried when the server can't have acted on them: when
+/// connecting fails or the server answers `429`. A `502`, `504` or timeout
+/// may come after the server already did, and retrying would do it twice.
+///
+/// The delay
+/// between attempts doubles every time, starting at `initial_backoff` and
+/// capped at `max_backoff`; a `Retry-After` header sent by the server takes
+/// precedence.
+///
+/// Requests with a streaming body (e.g. media uploads) are never retried.
+///
+/// # Example
+///
+/// ```
+/// # extern crate elefren;
+/// use elefren::retry::RetryPolicy;
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy::new(3)
+///     .initial_backoff(Duration::from_millis(500))
+///     .max_backoff(Duration::from_secs(10));
+/// assert_eq!(policy.max_retries(), 3);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy::none()
+    }
+}
+
+impl RetryPolicy {
+    /// Never retry. This is the default.
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::new(0)
+    }
+
+    /// Retry failed requests up to `max_retries` times, starting with a 1
+    /// second delay
+    pub fn new(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+
+    /// Set the delay before the first retry
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Set the maximum delay between two attempts
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// The maximum number of retries
+    pub fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    // Whether sending the request twice has the same effect as sending it once
+    pub(crate) fn is_idempotent(method: &Method) -> bool {
+        matches!(
+            *method,
+            Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+        )
+    }
+
+    pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+        matches!(
+            status,
+            StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        )
+    }
+
+    /// How long to wait before retrying after the `attempt`th failure
+    /// (starting at 0)
+    pub(crate) fn delay(&self, attempt: u32, headers: Option<&HeaderMap>) -> Duration {
+        let retry_after = headers
+            .and_then(|headers| headers.get(reqwest::header::RETRY_AFTER))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let backoff = retry_after.unwrap_or_else(|| {
+            self.initial_backoff
+                .checked_mul(2u32.saturating_pow(attempt))
+                .unwrap_or(self.max_backoff)
+        });
+        backoff.min(self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderValue, RETRY_AFTER};
+
+    #[test]
+    fn test_default_is_none() {
+        assert_eq!(RetryPolicy::default().max_retries(), 0);
+    }
+
+    #[test]
+    fn test_exponential_backoff() {
+        let policy = RetryPolicy::new(5)
+            .initial_backoff(Duration::from_secs(1))
+            .max_backoff(Duration::from_secs(5));
+        assert_eq!(policy.delay(0, None), Duration::from_secs(1));
+        assert_eq!(policy.delay(1, None), Duration::from_secs(2));
+        assert_eq!(policy.delay(2, None), Duration::from_secs(4));
+        assert_eq!(policy.delay(3, None), Duration::from_secs(5));
+        assert_eq!(policy.delay(40, None), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let policy = RetryPolicy::new(1).max_backoff(Duration::from_secs(60));
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("7"));
+        assert_eq!(policy.delay(0, Some(&headers)), Duration::from_secs(7));
+    }
+
+    #[test]
+    fn test_retryable_statuses() {
+        assert!(RetryPolicy::is_retryable_status(
+            StatusCode::TOO_MANY_REQUESTS
+        ));
+        assert!(RetryPolicy::is_retryable_status(
+            StatusCode::SERVICE_UNAVAILABLE
+        ));
+        assert!(!RetryPolicy::is_retryable_status(StatusCode::NOT_FOUND));
+        assert!(!RetryPolicy::is_retryable_status(
+            StatusCode::INTERNAL_SERVER_ERROR
+        ));
+    }
+}
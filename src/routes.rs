@@ -0,0 +1,188 @@
+// Endpoints provided by both the blocking and the async client. Each table
+// hands its entries to the macro it is given, so that both clients are
+// generated from the same declarations and can't drift apart.
+
+use crate::{
+    errors::{Error, Result},
+    requests::{
+        DirectoryRequest, HashtagRequest, NotificationsRequest, TimelineRequest, ToQueryString,
+    },
+};
+
+// Routes without parameters
+macro_rules! shared_routes {
+    ($mac:ident) => {
+        $mac! {
+            (get) instance: "instance" => Instance,
+            (get) instance_translation_languages: "instance/translation_languages" => TranslationLanguages,
+            (get) instance_rules: "instance/rules" => Vec<Rule>,
+        }
+    };
+}
+
+// Routes taking the id of the resource
+macro_rules! shared_routes_id {
+    ($mac:ident) => {
+        $mac! {
+            (get) get_account: "accounts/{}" => Account,
+            (get) get_status: "statuses/{}" => Status,
+            (get) get_context: "statuses/{}/context" => Context,
+        }
+    };
+}
+
+// Paged routes taking the id of the resource
+macro_rules! shared_paged_routes_with_id {
+    ($mac:ident) => {
+        $mac! {
+            (get) followers, followers_with: "accounts/{}/followers" => Account,
+            (get) following, following_with: "accounts/{}/following" => Account,
+            (get) reblogged_by, reblogged_by_with: "statuses/{}/reblogged_by" => Account,
+            (get) favourited_by, favourited_by_with: "statuses/{}/favourited_by" => Account,
+            (get) get_list_timeline, get_list_timeline_with: "timelines/list/{}" => Status,
+            (get) list_accounts, list_accounts_with: "lists/{}/accounts" => Account,
+        }
+    };
+}
+
+// The path and query of the directory, shared so that both clients accept
+// the same `DirectoryRequest`
+pub(crate) fn directory(request: Option<DirectoryRequest>) -> Result<String> {
+    Ok(match request {
+        Some(request) => with_query("api/v1/directory", &request)?,
+        None => "api/v1/directory".to_string(),
+    })
+}
+
+// The path and query of the trending tags, at most `limit` of them
+pub(crate) fn trends(limit: Option<usize>) -> String {
+    match limit {
+        Some(limit) => format!("api/v1/trends?limit={}", limit),
+        None => "api/v1/trends".to_string(),
+    }
+}
+
+// The path and query of the public timeline
+pub(crate) fn public_timeline(request: &TimelineRequest) -> Result<String> {
+    with_query("api/v1/timelines/public", request)
+}
+
+// The path and query of the notifications
+pub(crate) fn notifications(request: &NotificationsRequest) -> Result<String> {
+    with_query("api/v1/notifications", request)
+}
+
+// `path` followed by the query string of `request`, if it has one
+fn with_query<R: ToQueryString>(path: &str, request: &R) -> Result<String> {
+    Ok(match request.to_querystring()?.as_str() {
+        "?" => path.to_string(),
+        qs => format!("{}{}", path, qs),
+    })
+}
+
+// Strips the leading `#` that users often include when naming a hashtag, and
+// lowercases it, as the server matches hashtags case-insensitively anyway
+pub(crate) fn tag_name(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+// The path and query of the timeline of `tag`
+pub(crate) fn hashtag_timeline(tag: &str, request: &HashtagRequest) -> Result<String> {
+    with_query(
+        &format!("api/v1/timelines/tag/{}", encode_tag(tag)),
+        request,
+    )
+}
+
+// Turns a hashtag into a path segment, so that e.g. `#café` or `c++`
+// produce a valid URL
+pub(crate) fn encode_tag(tag: &str) -> String {
+    percent_encode(&tag_name(tag))
+}
+
+// Turns an id or other parameter interpolated into a path into a single path
+// segment. Mastodon's ids are numeric, but other backends use ids that may
+// contain e.g. `/`, `?` or `#`, which would otherwise change the route that
+// is requested. `.` and `..` can't be encoded, as URLs treat `%2E` like a
+// dot, so they are refused.
+pub(crate) fn encode_segment(segment: &str) -> Result<String> {
+    if segment.is_empty() || segment == "." || segment == ".." {
+        return Err(Error::Other(format!(
+            "{:?} can't be used as an id in a path",
+            segment
+        )));
+    }
+    Ok(percent_encode(segment))
+}
+
+// Percent-encodes everything but the unreserved characters
+pub(crate) fn percent_encode(value: &str) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            },
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_name() {
+        assert_eq!(tag_name("#rust"), "rust");
+        assert_eq!(tag_name("rust"), "rust");
+        assert_eq!(tag_name(" #Rust"), "rust");
+    }
+
+    #[test]
+    fn test_hashtag_timeline() {
+        assert_eq!(
+            hashtag_timeline("#Café", &HashtagRequest::new()).unwrap(),
+            "api/v1/timelines/tag/caf%C3%A9"
+        );
+        assert_eq!(
+            hashtag_timeline("rust", &HashtagRequest::new().local().only_media()).unwrap(),
+            "api/v1/timelines/tag/rust?local=1&only_media=1"
+        );
+    }
+
+    #[test]
+    fn test_directory_and_trends() {
+        assert_eq!(directory(None).unwrap(), "api/v1/directory");
+        assert_eq!(
+            directory(Some(DirectoryRequest::new().local())).unwrap(),
+            "api/v1/directory?local=1"
+        );
+        assert_eq!(trends(None), "api/v1/trends");
+        assert_eq!(trends(Some(5)), "api/v1/trends?limit=5");
+    }
+
+    #[test]
+    fn test_encode_segment() {
+        assert_eq!(encode_segment("109384").unwrap(), "109384");
+        assert_eq!(encode_segment("9mX-1_a.b~").unwrap(), "9mX-1_a.b~");
+        assert_eq!(encode_segment("../../admin").unwrap(), "..%2F..%2Fadmin");
+        assert_eq!(
+            encode_segment("1?limit=80#x").unwrap(),
+            "1%3Flimit%3D80%23x"
+        );
+        assert_eq!(encode_segment("a b%").unwrap(), "a%20b%25");
+        assert!(encode_segment("").is_err());
+        assert!(encode_segment(".").is_err());
+        assert!(encode_segment("..").is_err());
+    }
+
+    #[test]
+    fn test_encode_tag() {
+        assert_eq!(encode_tag("rust"), "rust");
+        assert_eq!(encode_tag("#café"), "caf%C3%A9");
+        assert_eq!(encode_tag("c++ / c#"), "c%2B%2B%20%2F%20c%23");
+        assert_eq!(encode_tag("日本"), "%E6%97%A5%E6%9C%AC");
+    }
+}
@@ -2,7 +2,7 @@ use std::{
     cmp::{Ordering, PartialEq, PartialOrd},
     collections::HashSet,
     fmt,
-    ops::BitOr,
+    ops::{BitOr, Sub},
     str::FromStr,
 };
 
@@ -214,6 +214,78 @@ impl Scopes {
         Scopes { scopes: newset }
     }
 
+    /// Takes the scopes of `other` out of these
+    ///
+    /// Removing a granular scope from a full one leaves the other granular
+    /// scopes, e.g. `read` without `read:notifications` is every other
+    /// `read:___` scope.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use elefren::{prelude::*, scopes::Read};
+    ///
+    /// let scopes = Scopes::all().remove(Scopes::push() | Scopes::write_all());
+    /// assert_eq!(scopes, Scopes::read_all() | Scopes::follow());
+    ///
+    /// let scopes = Scopes::read_all().remove(Scopes::read(Read::Notifications));
+    /// assert!(!Scopes::read(Read::Notifications).is_subset_of(&scopes));
+    /// assert!(Scopes::read(Read::Statuses).is_subset_of(&scopes));
+    /// ```
+    pub fn remove(self, other: Scopes) -> Scopes {
+        let removed = |scope: &Scope| other.scopes.iter().any(|other| other.covers(scope));
+        let mut scopes = HashSet::new();
+        for scope in self.scopes {
+            if removed(&scope) {
+                continue;
+            }
+            if other.scopes.iter().any(|other| scope.covers(other)) {
+                scopes.extend(scope.subscopes().filter(|scope| !removed(scope)));
+            } else {
+                scopes.insert(scope);
+            }
+        }
+        Scopes { scopes }
+    }
+
+    /// Whether these scopes grant nothing more than `other` does, i.e.
+    /// whether an app with the scopes of `other` can do everything these
+    /// allow. A full scope like `read` includes the granular `read:___`
+    /// ones.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use elefren::{prelude::*, scopes::Read};
+    ///
+    /// let granted = Scopes::read_all() | Scopes::follow();
+    /// assert!(Scopes::read(Read::Accounts).is_subset_of(&granted));
+    /// assert!(!Scopes::write_all().is_subset_of(&granted));
+    /// ```
+    pub fn is_subset_of(&self, other: &Scopes) -> bool {
+        self.scopes
+            .iter()
+            .all(|scope| other.scopes.iter().any(|other| other.covers(scope)))
+    }
+
+    /// The individual scopes, in the order they are displayed in
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use elefren::{prelude::*, scopes::Scope};
+    ///
+    /// let scopes = Scopes::push() | Scopes::read_all();
+    /// let names: Vec<String> = scopes.iter().map(|scope| scope.to_string()).collect();
+    /// assert_eq!(names, ["read", "push"]);
+    /// assert_eq!(scopes.iter().next(), Some(Scope::Read(None)));
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = Scope> {
+        let mut scopes = self.scopes.iter().copied().collect::<Vec<_>>();
+        scopes.sort();
+        scopes.into_iter()
+    }
+
     fn _write(subscope: Option<Write>) -> Scopes {
         Scopes::new(Scope::Write(subscope))
     }
@@ -237,6 +309,14 @@ impl BitOr for Scopes {
     }
 }
 
+impl Sub for Scopes {
+    type Output = Scopes;
+
+    fn sub(self, other: Scopes) -> Self::Output {
+        self.remove(other)
+    }
+}
+
 impl PartialEq for Scopes {
     fn eq(&self, other: &Scopes) -> bool {
         self.scopes
@@ -286,7 +366,7 @@ impl fmt::Display for Scopes {
 /// [Details on what each permission provides][1]
 /// [1]: https://github.com/tootsuite/documentation/blob/master/Using-the-API/OAuth-details.md)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
-enum Scope {
+pub enum Scope {
     /// Read only permissions.
     #[serde(rename = "read")]
     Read(Option<Read>),
@@ -376,6 +456,28 @@ impl fmt::Display for Scope {
     }
 }
 
+impl Scope {
+    // Whether `self` grants everything `other` does
+    fn covers(&self, other: &Scope) -> bool {
+        match (*self, *other) {
+            (Scope::Read(None), Scope::Read(_)) | (Scope::Write(None), Scope::Write(_)) => true,
+            (scope, other) => scope == other,
+        }
+    }
+
+    // The granular scopes that together make up a full one
+    fn subscopes(&self) -> impl Iterator<Item = Scope> {
+        let (read, write): (&[Read], &[Write]) = match *self {
+            Scope::Read(None) => (&Read::ALL, &[]),
+            Scope::Write(None) => (&[], &Write::ALL),
+            _ => (&[], &[]),
+        };
+        read.iter()
+            .map(|read| Scope::Read(Some(*read)))
+            .chain(write.iter().map(|write| Scope::Write(Some(*write))))
+    }
+}
+
 impl Default for Scope {
     fn default() -> Self {
         Scope::Read(None)
@@ -420,6 +522,22 @@ pub enum Read {
     Statuses,
 }
 
+impl Read {
+    const ALL: [Read; 11] = [
+        Read::Accounts,
+        Read::Blocks,
+        Read::Favourites,
+        Read::Filters,
+        Read::Follows,
+        Read::Lists,
+        Read::Mutes,
+        Read::Notifications,
+        Read::Reports,
+        Read::Search,
+        Read::Statuses,
+    ];
+}
+
 impl FromStr for Read {
     type Err = Error;
 
@@ -515,6 +633,22 @@ pub enum Write {
     Statuses,
 }
 
+impl Write {
+    const ALL: [Write; 11] = [
+        Write::Accounts,
+        Write::Blocks,
+        Write::Favourites,
+        Write::Filters,
+        Write::Follows,
+        Write::Lists,
+        Write::Media,
+        Write::Mutes,
+        Write::Notifications,
+        Write::Reports,
+        Write::Statuses,
+    ];
+}
+
 impl FromStr for Write {
     type Err = Error;
 
@@ -723,6 +857,53 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_scopes_remove() {
+        assert_eq!(
+            Scopes::all() - Scopes::all(),
+            Scopes {
+                scopes: HashSet::new()
+            }
+        );
+        assert_eq!(
+            (Scopes::read(Read::Accounts) | Scopes::follow()) - Scopes::read_all(),
+            Scopes::follow()
+        );
+        let scopes = Scopes::read_all() | Scopes::push();
+        let scopes = scopes - (Scopes::read(Read::Search) | Scopes::read(Read::Filters));
+        assert_eq!(scopes.iter().count(), 10);
+        assert!(!Scopes::read(Read::Search).is_subset_of(&scopes));
+        assert!(!Scopes::read_all().is_subset_of(&scopes));
+        assert!(Scopes::read(Read::Statuses).is_subset_of(&scopes));
+        assert!(Scopes::push().is_subset_of(&scopes));
+    }
+
+    #[test]
+    fn test_scopes_is_subset_of() {
+        let granted = Scopes::read_all() | Scopes::write(Write::Statuses);
+        assert!(Scopes::read(Read::Lists).is_subset_of(&granted));
+        assert!(granted.is_subset_of(&granted));
+        assert!(granted.is_subset_of(&Scopes::all()));
+        assert!(!Scopes::all().is_subset_of(&granted));
+        assert!(!Scopes::write_all().is_subset_of(&granted));
+        assert!(!Scopes::write(Write::Media).is_subset_of(&granted));
+        assert!(!granted.is_subset_of(&Scopes::read(Read::Lists)));
+    }
+
+    #[test]
+    fn test_scopes_iter() {
+        let scopes: Scopes = "push read:lists write read".parse().unwrap();
+        assert_eq!(
+            scopes.iter().collect::<Vec<_>>(),
+            [
+                Scope::Read(None),
+                Scope::Read(Some(Read::Lists)),
+                Scope::Write(None),
+                Scope::Push
+            ]
+        );
+    }
+
     #[test]
     fn test_scopes_default() {
         let default: Scope = Default::default();
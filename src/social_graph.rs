@@ -0,0 +1,194 @@
+//! Exporting who an account follows and who follows it, e.g. for backups or
+//! to analyse the network around it
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::account::Account, errors::Result, http_send::HttpSend,
+    mastodon_client::MastodonClient, page::Page,
+};
+
+/// How an account in a `SocialGraph` is connected to the exported account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FollowDirection {
+    /// The account follows the exported account
+    Follower,
+    /// The exported account follows the account
+    Following,
+    /// Both follow each other
+    Mutual,
+}
+
+impl FollowDirection {
+    /// Whether the account follows the exported account
+    pub fn is_follower(self) -> bool {
+        self != FollowDirection::Following
+    }
+
+    /// Whether the exported account follows the account
+    pub fn is_following(self) -> bool {
+        self != FollowDirection::Follower
+    }
+}
+
+/// An account connected to the exported account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Connection {
+    /// The id of the account, on the instance the graph was exported from
+    pub id: String,
+    /// The account's `username@domain`, or `username` if it's on the
+    /// instance the graph was exported from
+    pub acct: String,
+    /// The URL of the account's profile
+    pub url: String,
+    /// How the account is connected to the exported account
+    pub direction: FollowDirection,
+}
+
+/// The accounts following an account and followed by it, as returned by
+/// `export_social_graph`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SocialGraph {
+    /// The id of the exported account
+    pub account_id: String,
+    /// When the graph was exported
+    pub exported_at: DateTime<Utc>,
+    /// The connected accounts, each appearing once
+    pub connections: Vec<Connection>,
+}
+
+impl SocialGraph {
+    /// The graph of the account with id `account_id`, given the accounts
+    /// following it and those it follows
+    ///
+    /// Accounts in both lists appear once, as `Mutual`. The accounts it
+    /// follows come first, in the order given, followed by the rest of its
+    /// followers.
+    pub fn new(
+        account_id: &str,
+        followers: Vec<Account>,
+        following: Vec<Account>,
+        exported_at: DateTime<Utc>,
+    ) -> SocialGraph {
+        let mut connections: Vec<Connection> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let lists = [
+            (following, FollowDirection::Following),
+            (followers, FollowDirection::Follower),
+        ];
+        for (accounts, direction) in lists {
+            for account in accounts {
+                match index.get(&account.id) {
+                    Some(&i) => {
+                        if connections[i].direction != direction {
+                            connections[i].direction = FollowDirection::Mutual;
+                        }
+                    },
+                    None => {
+                        index.insert(account.id.clone(), connections.len());
+                        connections.push(Connection {
+                            id: account.id,
+                            acct: account.acct,
+                            url: account.url,
+                            direction,
+                        });
+                    },
+                }
+            }
+        }
+        SocialGraph {
+            account_id: account_id.to_string(),
+            exported_at,
+            connections,
+        }
+    }
+
+    /// Iterator over the accounts following the exported account
+    pub fn followers(&self) -> impl Iterator<Item = &Connection> {
+        self.connections
+            .iter()
+            .filter(|connection| connection.direction.is_follower())
+    }
+
+    /// Iterator over the accounts the exported account follows
+    pub fn following(&self) -> impl Iterator<Item = &Connection> {
+        self.connections
+            .iter()
+            .filter(|connection| connection.direction.is_following())
+    }
+
+    /// Iterator over the accounts following the exported account that it
+    /// follows back
+    pub fn mutuals(&self) -> impl Iterator<Item = &Connection> {
+        self.connections
+            .iter()
+            .filter(|connection| connection.direction == FollowDirection::Mutual)
+    }
+}
+
+// Collects every page starting at `page`, waiting before each request for
+// the next one if the rate limit is almost used up
+pub(crate) fn collect_paced<H: HttpSend, C: MastodonClient<H>>(
+    client: &C,
+    mut page: Page<Account, H>,
+) -> Result<Vec<Account>> {
+    let mut accounts = std::mem::take(&mut page.initial_items);
+    loop {
+        if let Some(rate_limit) = client.rate_limit() {
+            let wait = rate_limit.wait_time(1, client.clock().now());
+            if wait > std::time::Duration::from_secs(0) {
+                log::debug!("rate limit almost exhausted, waiting {:?}", wait);
+                client.clock().sleep(wait);
+            }
+        }
+        match page.next_page()? {
+            Some(next) => accounts.extend(next),
+            None => break,
+        }
+    }
+    Ok(accounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures;
+
+    fn account(id: &str, acct: &str) -> Account {
+        Account {
+            acct: acct.to_string(),
+            ..test_fixtures::account_with_id(id)
+        }
+    }
+
+    #[test]
+    fn test_new() {
+        let followers = vec![account("1", "alice"), account("2", "bob@example.com")];
+        let following = vec![account("3", "carol"), account("1", "alice")];
+        let graph = SocialGraph::new("100", followers, following, Utc::now());
+
+        let directions: Vec<(&str, FollowDirection)> = graph
+            .connections
+            .iter()
+            .map(|connection| (connection.id.as_str(), connection.direction))
+            .collect();
+        assert_eq!(
+            directions,
+            vec![
+                ("3", FollowDirection::Following),
+                ("1", FollowDirection::Mutual),
+                ("2", FollowDirection::Follower),
+            ]
+        );
+        assert_eq!(graph.followers().count(), 2);
+        assert_eq!(graph.following().count(), 2);
+        assert_eq!(graph.mutuals().next().unwrap().acct, "alice");
+
+        let json = serde_json::to_value(&graph).unwrap();
+        assert_eq!(json["connections"][1]["direction"], "mutual");
+    }
+}
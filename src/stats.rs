@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// What a client and its clones have sent and received so far, as returned
+/// by `Mastodon::stats`, e.g. to publish as metrics
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let data = Data {
+/// #   base: "".into(),
+/// #   client_id: "".into(),
+/// #   client_secret: "".into(),
+/// #   redirect: "".into(),
+/// #   token: "".into(),
+/// #   ..Default::default()
+/// # };
+/// let client = Mastodon::from(data);
+/// client.verify_credentials()?;
+/// let stats = client.stats();
+/// println!("elefren_requests_total {}", stats.requests);
+/// println!("elefren_retries_total {}", stats.retries);
+/// println!("elefren_received_bytes_total {}", stats.bytes_received);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ClientStats {
+    /// The number of requests sent, including retries
+    pub requests: u64,
+    /// The number of requests that were retries of a failed one
+    pub retries: u64,
+    /// The number of bytes of the response bodies the client deserialized
+    /// or downloaded; streamed ones, from `get_stream`, aren't counted
+    pub bytes_received: u64,
+    /// The number of requests left in the current rate limit window, as of
+    /// the last response reporting it
+    pub rate_limit_remaining: Option<u64>,
+}
+
+// The counters behind `ClientStats`, shared between a client's clones.
+// They're also attached to responses, to count the bytes read from them.
+#[derive(Debug, Default)]
+pub(crate) struct StatsCounters {
+    requests: AtomicU64,
+    retries: AtomicU64,
+    bytes_received: AtomicU64,
+}
+
+impl StatsCounters {
+    pub(crate) fn count_request(&self, retry: bool) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if retry {
+            self.retries.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn count_bytes(&self, bytes: u64) {
+        self.bytes_received.fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self, rate_limit_remaining: Option<u64>) -> ClientStats {
+        ClientStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+            rate_limit_remaining,
+        }
+    }
+}
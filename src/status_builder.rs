@@ -1,4 +1,13 @@
-use isolang::Language;
+use std::{fmt, str::FromStr};
+
+use crate::{
+    clock::{Clock, SystemClock},
+    entities::{account::Account, status::Status},
+    errors::Error,
+    helpers::text::{spoiler_length, status_length},
+    language::Language,
+};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 
 /// A builder pattern struct for constructing a status.
@@ -29,6 +38,14 @@ pub struct StatusBuilder {
     content_type: Option<String>,
     visibility: Option<Visibility>,
     language: Option<Language>,
+    scheduled_at: Option<DateTime<Utc>>,
+    expires_in: Option<u64>,
+    local_only: Option<bool>,
+    poll: Option<NewPoll>,
+    max_characters: Option<usize>,
+    // set by `reply_to`, applied in `build`
+    reply_mentions: Vec<String>,
+    reply_visibility: Option<Visibility>,
 }
 
 impl StatusBuilder {
@@ -46,6 +63,7 @@ impl StatusBuilder {
     /// #     client_secret: "".into(),
     /// #     redirect: "".into(),
     /// #     token: "".into(),
+    /// #     ..Default::default()
     /// # };
     /// # let client = Mastodon::from(data);
     /// let status = StatusBuilder::new()
@@ -60,6 +78,52 @@ impl StatusBuilder {
         StatusBuilder::default()
     }
 
+    /// Create a StatusBuilder for a post in the same style as `status`, e.g.
+    /// to continue it in a new post that isn't a reply
+    ///
+    /// Taken over from `status` are:
+    ///
+    /// * its spoiler text, if it has one
+    /// * its visibility
+    /// * its language
+    /// * whether it is marked as sensitive
+    ///
+    /// Its content, media, poll and reply target are not; a reblog is used as
+    /// a template by taking the reblogged status.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use elefren::prelude::*;
+    /// # fn main() -> Result<(), elefren::Error> {
+    /// # let data = Data {
+    /// #     base: "".into(),
+    /// #     client_id: "".into(),
+    /// #     client_secret: "".into(),
+    /// #     redirect: "".into(),
+    /// #     token: "".into(),
+    /// #     ..Default::default()
+    /// # };
+    /// # let client = Mastodon::from(data);
+    /// let template = client.get_status("12345")?;
+    /// let status = StatusBuilder::from_status_template(&template)
+    ///     .status("more on that")
+    ///     .build()?;
+    /// client.new_status(status)?;
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn from_status_template(status: &Status) -> StatusBuilder {
+        let status = status.reblog.as_deref().unwrap_or(status);
+        StatusBuilder {
+            sensitive: Some(status.sensitive),
+            spoiler_text: Some(status.spoiler_text.clone()).filter(|text| !text.is_empty()),
+            visibility: Some(status.visibility),
+            language: status.language,
+            ..StatusBuilder::default()
+        }
+    }
+
     /// Set the text for the post
     ///
     /// # Example
@@ -95,6 +159,99 @@ impl StatusBuilder {
         self
     }
 
+    /// Make the post a reply to `status`
+    ///
+    /// Besides setting `in_reply_to_id`, this mentions the author of `status`
+    /// and everyone it mentions, copies its spoiler text, and makes sure the
+    /// reply is not more visible than `status` itself. A reblog is replied to
+    /// by replying to the reblogged status.
+    ///
+    /// To avoid mentioning yourself, use
+    /// [`reply_to_as`](#method.reply_to_as) instead.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use elefren::prelude::*;
+    /// # fn main() -> Result<(), elefren::Error> {
+    /// # let data = Data {
+    /// #     base: "".into(),
+    /// #     client_id: "".into(),
+    /// #     client_secret: "".into(),
+    /// #     redirect: "".into(),
+    /// #     token: "".into(),
+    /// #     ..Default::default()
+    /// # };
+    /// # let client = Mastodon::from(data);
+    /// let status = client.get_status("12345")?;
+    /// let reply = StatusBuilder::new()
+    ///     .reply_to(&status)
+    ///     .status("awoo back")
+    ///     .build()?;
+    /// client.new_status(reply)?;
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn reply_to(&mut self, status: &Status) -> &mut Self {
+        let status = status.reblog.as_deref().unwrap_or(status);
+        self.in_reply_to_id = Some(status.id.clone());
+        self.reply_mentions = Vec::new();
+        let accts = Some(&status.account.acct)
+            .into_iter()
+            .chain(status.mentions.iter().map(|mention| &mention.acct));
+        for acct in accts {
+            if !self.reply_mentions.contains(acct) {
+                self.reply_mentions.push(acct.clone());
+            }
+        }
+        if !status.spoiler_text.is_empty() {
+            self.spoiler_text = Some(status.spoiler_text.clone());
+        }
+        self.reply_visibility = Some(status.visibility.to_send());
+        self
+    }
+
+    /// Like [`reply_to`](#method.reply_to), but doesn't mention `me`, the
+    /// account that is replying
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use elefren::prelude::*;
+    /// # fn main() -> Result<(), elefren::Error> {
+    /// # let data = Data {
+    /// #     base: "".into(),
+    /// #     client_id: "".into(),
+    /// #     client_secret: "".into(),
+    /// #     redirect: "".into(),
+    /// #     token: "".into(),
+    /// #     ..Default::default()
+    /// # };
+    /// # let client = Mastodon::from(data);
+    /// let status = client.get_status("12345")?;
+    /// let reply = StatusBuilder::new()
+    ///     .reply_to_as(&status, &client.me()?)
+    ///     .status("awoo back")
+    ///     .build()?;
+    /// client.new_status(reply)?;
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn reply_to_as(&mut self, status: &Status, me: &Account) -> &mut Self {
+        self.reply_to(status);
+        let status = status.reblog.as_deref().unwrap_or(status);
+        let own = |acct: &String| {
+            acct == &me.acct
+                || (acct == &status.account.acct && status.account.id == me.id)
+                || status
+                    .mentions
+                    .iter()
+                    .any(|mention| &mention.acct == acct && mention.id == me.id)
+        };
+        self.reply_mentions.retain(|acct| !own(acct));
+        self
+    }
+
     /// Set the media_ids for the post
     ///
     /// # Example
@@ -106,14 +263,31 @@ impl StatusBuilder {
     /// #   Ok(())
     /// # }
     /// ```
-    pub fn media_ids<S: std::fmt::Display, I: IntoIterator<Item = S>>(
-        &mut self,
-        ids: I,
-    ) -> &mut Self {
+    pub fn media_ids<S: fmt::Display, I: IntoIterator<Item = S>>(&mut self, ids: I) -> &mut Self {
         self.media_ids = Some(ids.into_iter().map(|s| s.to_string()).collect::<Vec<_>>());
         self
     }
 
+    /// Attach a poll to the post. A post can't have both a poll and media.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use elefren::prelude::*;
+    /// use elefren::status_builder::NewPoll;
+    /// # fn main() -> Result<(), elefren::Error> {
+    /// let status = StatusBuilder::new()
+    ///     .status("tabs or spaces?")
+    ///     .poll(NewPoll::new(vec!["tabs", "spaces"], 24 * 60 * 60))
+    ///     .build()?;
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn poll(&mut self, poll: NewPoll) -> &mut Self {
+        self.poll = Some(poll);
+        self
+    }
+
     /// Set the sensitive attribute for the post
     ///
     /// # Example
@@ -203,6 +377,9 @@ impl StatusBuilder {
 
     /// Set the language for the post
     ///
+    /// With the `detect-language` feature, the language is guessed from the
+    /// status text when it isn't set explicitly.
+    ///
     /// # Example
     ///
     /// ```rust
@@ -221,6 +398,118 @@ impl StatusBuilder {
         self
     }
 
+    /// Schedule the post to be published at `scheduled_at`, which has to be
+    /// at least 5 minutes in the future. Post it with
+    /// `client.schedule_status()`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate chrono;
+    /// # use elefren::prelude::*;
+    /// # fn main() -> Result<(), elefren::Error> {
+    /// use chrono::{Duration, Utc};
+    ///
+    /// let status = StatusBuilder::new()
+    ///     .status("awoo tomorrow")
+    ///     .scheduled_at(Utc::now() + Duration::days(1))
+    ///     .build()?;
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn scheduled_at(&mut self, scheduled_at: DateTime<Utc>) -> &mut Self {
+        self.scheduled_at = Some(scheduled_at);
+        self
+    }
+
+    /// Delete the post `expires_in` seconds after it was published
+    ///
+    /// This is a Pleroma and Akkoma extension of the API, available with the
+    /// `pleroma` feature.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use elefren::prelude::*;
+    /// # fn main() -> Result<(), elefren::Error> {
+    /// let status = StatusBuilder::new()
+    ///     .status("this status will self-destruct in one hour")
+    ///     .expires_in(60 * 60)
+    ///     .build()?;
+    /// #   Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "pleroma")]
+    pub fn expires_in(&mut self, expires_in: u64) -> &mut Self {
+        self.expires_in = Some(expires_in);
+        self
+    }
+
+    /// Set whether the post is only visible on the user's own instance, and
+    /// not federated
+    ///
+    /// This is a glitch-soc and Hometown extension of the API. Other servers
+    /// ignore it and federate the post, so check
+    /// `Instance::supports_local_only` first.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use elefren::prelude::*;
+    /// # fn main() -> Result<(), elefren::Error> {
+    /// # let data = Data {
+    /// #     base: "".into(),
+    /// #     client_id: "".into(),
+    /// #     client_secret: "".into(),
+    /// #     redirect: "".into(),
+    /// #     token: "".into(),
+    /// #     ..Default::default()
+    /// # };
+    /// # let client = Mastodon::from(data);
+    /// if client.instance()?.supports_local_only() {
+    ///     let status = StatusBuilder::new()
+    ///         .status("just between us")
+    ///         .local_only(true)
+    ///         .build()?;
+    ///     client.new_status(status)?;
+    /// }
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn local_only(&mut self, local_only: bool) -> &mut Self {
+        self.local_only = Some(local_only);
+        self
+    }
+
+    /// Check in `build` that the status fits into the instance's character
+    /// limit, counted like the server does, see
+    /// [`helpers::text::status_length`](../helpers/text/fn.status_length.html)
+    ///
+    /// Together with `status_length`, this lets clients show a character
+    /// counter that agrees with the server. Mastodon's default limit is 500.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use elefren::prelude::*;
+    /// # fn main() -> Result<(), elefren::Error> {
+    /// let status = StatusBuilder::new()
+    ///     .status("read https://example.org/a/rather/long/link/to/an/article")
+    ///     .max_characters(30)
+    ///     .build()?;
+    /// assert!(StatusBuilder::new()
+    ///     .status("awoo".repeat(10))
+    ///     .max_characters(30)
+    ///     .build()
+    ///     .is_err());
+    /// #   Ok(())
+    /// # }
+    /// ```
+    pub fn max_characters(&mut self, max_characters: usize) -> &mut Self {
+        self.max_characters = Some(max_characters);
+        self
+    }
+
     /// Constructs a NewStatus
     ///
     /// # Example
@@ -232,29 +521,129 @@ impl StatusBuilder {
     /// #   Ok(())
     /// # }
     /// ```
-    pub fn build(&self) -> Result<NewStatus, crate::Error> {
+    pub fn build(&self) -> Result<NewStatus, Error> {
+        self.build_with_clock(&SystemClock)
+    }
+
+    /// Like `build`, but checks `scheduled_at` against the time of `clock`
+    pub fn build_with_clock(&self, clock: &dyn Clock) -> Result<NewStatus, Error> {
         if self.status.is_none() && self.media_ids.is_none() {
-            return Err(crate::Error::Other(
+            return Err(Error::Other(
                 "status text or media ids are required in order to post a status".to_string(),
             ));
         }
+        if self.poll.is_some() && self.media_ids.is_some() {
+            return Err(Error::Other(
+                "a status can't have both a poll and media".to_string(),
+            ));
+        }
+        if let Some(scheduled_at) = self.scheduled_at {
+            if scheduled_at < clock.now() + Duration::minutes(MIN_SCHEDULE_MINUTES) {
+                return Err(Error::Other(format!(
+                    "statuses have to be scheduled at least {} minutes in the future",
+                    MIN_SCHEDULE_MINUTES
+                )));
+            }
+        }
+        let status = self.status_with_mentions();
+        if let Some(max_characters) = self.max_characters {
+            let length = status.as_deref().map_or(0, status_length)
+                + self.spoiler_text.as_deref().map_or(0, spoiler_length);
+            if length > max_characters {
+                return Err(Error::Other(format!(
+                    "the status is {} characters long, but at most {} are allowed",
+                    length, max_characters
+                )));
+            }
+        }
         Ok(NewStatus {
-            status: self.status.clone(),
+            status,
             in_reply_to_id: self.in_reply_to_id.clone(),
             media_ids: self.media_ids.clone(),
             sensitive: self.sensitive,
             spoiler_text: self.spoiler_text.clone(),
-            visibility: self.visibility,
-            language: self.language,
+            visibility: match (self.visibility, self.reply_visibility) {
+                (Some(visibility), Some(limit)) => Some(visibility.at_most(limit)),
+                // otherwise the server would use the account's default
+                (None, Some(limit)) if limit < Visibility::Public => Some(limit),
+                (visibility, _) => visibility,
+            },
+            language: self.language.or_else(|| self.detect_language()),
             content_type: self.content_type.clone(),
+            scheduled_at: self.scheduled_at,
+            expires_in: self.expires_in,
+            local_only: self.local_only,
+            poll: self.poll.clone(),
         })
     }
+
+    // Guesses the language of the status text, ignoring mentions, hashtags and
+    // links. Only reliable guesses are used.
+    #[cfg(feature = "detect-language")]
+    fn detect_language(&self) -> Option<Language> {
+        let text = self
+            .status
+            .as_deref()?
+            .split_whitespace()
+            .filter(|word| !word.starts_with(&['@', '#'][..]) && !word.contains("://"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let info = whatlang::detect(&text)?;
+        if !info.is_reliable() {
+            return None;
+        }
+        Language::from_639_3(info.lang().code())
+    }
+
+    #[cfg(not(feature = "detect-language"))]
+    fn detect_language(&self) -> Option<Language> {
+        None
+    }
+
+    // Prefixes the status text with the mentions added by `reply_to`, unless
+    // the text already mentions them
+    fn status_with_mentions(&self) -> Option<String> {
+        let text = self.status.as_deref().unwrap_or("");
+        let mentions = self
+            .reply_mentions
+            .iter()
+            .map(|acct| format!("@{}", acct))
+            .filter(|mention| {
+                !text.split_whitespace().any(|word| {
+                    word.trim_end_matches(|c: char| c.is_ascii_punctuation()) == mention
+                })
+            })
+            .collect::<Vec<_>>();
+        match (mentions.is_empty(), self.status.as_ref()) {
+            (true, status) => status.cloned(),
+            (false, _) => Some(
+                format!("{} {}", mentions.join(" "), text)
+                    .trim_end()
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+// how far in the future a status has to be scheduled, at least
+const MIN_SCHEDULE_MINUTES: i64 = 5;
+
+// Sends `Visibility::Unknown`, e.g. copied from a status, as `Direct`
+fn serialize_visibility<S: serde::Serializer>(
+    visibility: &Option<Visibility>,
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    visibility.map(Visibility::to_send).serialize(s)
 }
 
 /// Represents a post that can be sent to the POST /api/v1/status endpoint
-#[derive(Debug, Default, Clone, Serialize, PartialEq)]
+///
+/// The `params` of a `ScheduledStatus` are deserialized into a `NewStatus`,
+/// so that they can be posted again.
+#[derive(Debug, Default, Clone, Deserialize, Serialize, PartialEq)]
 pub struct NewStatus {
-    #[serde(skip_serializing_if = "Option::is_none")]
+    // scheduled statuses call the text `text`
+    #[serde(default, alias = "text", skip_serializing_if = "Option::is_none")]
     status: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     in_reply_to_id: Option<String>,
@@ -264,18 +653,101 @@ pub struct NewStatus {
     sensitive: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     spoiler_text: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_visibility"
+    )]
     visibility: Option<Visibility>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(
+        default,
+        deserialize_with = "crate::entities::status::language",
+        skip_serializing_if = "Option::is_none"
+    )]
     language: Option<Language>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scheduled_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_in: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    local_only: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    poll: Option<NewPoll>,
+}
+
+impl NewStatus {
+    /// When the status is scheduled to be published, if it is
+    pub fn scheduled_at(&self) -> Option<DateTime<Utc>> {
+        self.scheduled_at
+    }
+
+    // Adds the attachments with the ids `media_ids` after those already set
+    pub(crate) fn with_media_ids(mut self, media_ids: Vec<String>) -> NewStatus {
+        if !media_ids.is_empty() {
+            self.media_ids
+                .get_or_insert_with(Vec::new)
+                .extend(media_ids);
+        }
+        self
+    }
+
+    // Sets the language to `language` if it isn't set yet
+    pub(crate) fn or_language(mut self, language: Option<Language>) -> NewStatus {
+        self.language = self.language.or(language);
+        self
+    }
+}
+
+/// A poll to attach to a new status
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct NewPoll {
+    options: Vec<String>,
+    expires_in: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    multiple: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hide_totals: Option<bool>,
+}
+
+impl NewPoll {
+    /// A poll between `options` that ends `expires_in` seconds after it was
+    /// published
+    pub fn new<S: Into<String>, I: IntoIterator<Item = S>>(options: I, expires_in: u64) -> NewPoll {
+        NewPoll {
+            options: options.into_iter().map(Into::into).collect(),
+            expires_in,
+            multiple: None,
+            hide_totals: None,
+        }
+    }
+
+    /// Allow choosing more than one option
+    pub fn multiple(mut self, multiple: bool) -> Self {
+        self.multiple = Some(multiple);
+        self
+    }
+
+    /// Hide the number of votes until the poll ends
+    pub fn hide_totals(mut self, hide_totals: bool) -> Self {
+        self.hide_totals = Some(hide_totals);
+        self
+    }
 }
 
 /// The visibility of a status.
-#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq)]
+///
+/// Visibilities are ordered from the most restricted to the most public,
+/// i.e. `Unknown < Direct < Private < Unlisted < Public`. A visibility this
+/// crate doesn't know is taken to be the most restricted, so that limiting
+/// to it with `at_most` never widens it.
+#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum Visibility {
+    /// A visibility this crate doesn't know, e.g. Pleroma's `local`. It's
+    /// sent as `Direct`, as there is no name for it; so are replies to
+    /// statuses with it.
+    Unknown,
     /// A Direct message to a user
     Direct,
     /// Only available to followers
@@ -286,16 +758,79 @@ pub enum Visibility {
     Public,
 }
 
+deserialize_or_unknown!(Visibility {
+    "direct" => Direct,
+    "private" => Private,
+    "unlisted" => Unlisted,
+    "public" => Public,
+});
+
 impl Default for Visibility {
     fn default() -> Self {
         Visibility::Public
     }
 }
 
+impl Visibility {
+    /// Restricts this visibility to at most `limit`, e.g. to reply with no
+    /// more than the visibility of the status being replied to
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # extern crate elefren;
+    /// use elefren::status_builder::Visibility;
+    ///
+    /// assert_eq!(Visibility::Public.at_most(Visibility::Unlisted), Visibility::Unlisted);
+    /// assert_eq!(Visibility::Direct.at_most(Visibility::Public), Visibility::Direct);
+    /// ```
+    pub fn at_most(self, limit: Visibility) -> Visibility {
+        self.min(limit)
+    }
+
+    // The visibility to send for this one: `Unknown` has no name the server
+    // knows, and the most restricted one is the safe guess
+    pub(crate) fn to_send(self) -> Visibility {
+        match self {
+            Visibility::Unknown => Visibility::Direct,
+            visibility => visibility,
+        }
+    }
+}
+
+impl FromStr for Visibility {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Visibility, Self::Err> {
+        Ok(match s {
+            "direct" => Visibility::Direct,
+            "private" => Visibility::Private,
+            "unlisted" => Visibility::Unlisted,
+            "public" => Visibility::Public,
+            _ => return Err(Error::Other(format!("Unknown visibility '{}'", s))),
+        })
+    }
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match *self {
+            Visibility::Direct => "direct",
+            Visibility::Private => "private",
+            Visibility::Unlisted => "unlisted",
+            Visibility::Public => "public",
+            Visibility::Unknown => "unknown",
+        };
+        write!(f, "{}", s)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use isolang::Language;
+    use crate::test_fixtures::status;
+    #[cfg(feature = "language")]
+    use crate::Language;
     use serde_json;
 
     #[test]
@@ -313,10 +848,95 @@ mod tests {
             visibility: None,
             language: None,
             content_type: None,
+            scheduled_at: None,
+            expires_in: None,
+            local_only: None,
+            poll: None,
         };
         assert_eq!(s, expected);
     }
 
+    #[test]
+    #[cfg(feature = "language")]
+    fn test_from_status_template() {
+        let template = status();
+        let status = StatusBuilder::from_status_template(&template)
+            .status("more")
+            .build()
+            .unwrap();
+        assert_eq!(
+            status,
+            NewStatus {
+                status: Some("more".to_string()),
+                sensitive: Some(template.sensitive),
+                spoiler_text: Some("food".to_string()),
+                visibility: Some(Visibility::Unlisted),
+                language: Some(Language::Eng),
+                ..NewStatus::default()
+            }
+        );
+        assert!(StatusBuilder::from_status_template(&template)
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_scheduled_at() {
+        let soon = StatusBuilder::new()
+            .status("soon")
+            .scheduled_at(Utc::now() + Duration::minutes(1))
+            .build();
+        assert!(soon.is_err());
+
+        let later = Utc::now() + Duration::hours(1);
+        let status = StatusBuilder::new()
+            .status("later")
+            .scheduled_at(later)
+            .build()
+            .unwrap();
+        assert_eq!(status.scheduled_at(), Some(later));
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["scheduled_at"], serde_json::to_value(later).unwrap());
+    }
+
+    #[cfg(feature = "pleroma")]
+    #[test]
+    fn test_expires_in() {
+        let status = StatusBuilder::new()
+            .status("bye")
+            .expires_in(3600)
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["expires_in"], 3600);
+    }
+
+    #[test]
+    fn test_local_only() {
+        let status = StatusBuilder::new()
+            .status("local")
+            .local_only(true)
+            .build()
+            .unwrap();
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["local_only"], true);
+    }
+
+    #[test]
+    fn test_poll() {
+        let mut builder = StatusBuilder::new();
+        builder
+            .status("tabs or spaces?")
+            .poll(NewPoll::new(vec!["tabs", "spaces"], 3600).multiple(true));
+        let json = serde_json::to_value(builder.build().unwrap()).unwrap();
+        assert_eq!(
+            json["poll"],
+            serde_json::json!({"options": ["tabs", "spaces"], "expires_in": 3600, "multiple": true})
+        );
+        builder.media_ids(["1"]);
+        assert!(builder.build().is_err());
+    }
+
     #[test]
     fn test_default_visibility() {
         let v: Visibility = Default::default();
@@ -344,6 +964,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "language")]
     fn test_serialize_status() {
         let status = StatusBuilder::new()
             .status("a status")
@@ -364,4 +985,159 @@ mod tests {
             "{\"status\":\"a status\",\"language\":\"eng\"}"
         );
     }
+
+    #[test]
+    fn test_visibility_from_str_and_display() {
+        for visibility in &[
+            Visibility::Direct,
+            Visibility::Private,
+            Visibility::Unlisted,
+            Visibility::Public,
+        ] {
+            let s = visibility.to_string();
+            assert_eq!(
+                s.parse::<Visibility>().expect("couldn't parse visibility"),
+                *visibility
+            );
+        }
+        assert!("friends".parse::<Visibility>().is_err());
+    }
+
+    #[test]
+    fn test_visibility_ordering() {
+        assert!(Visibility::Unknown < Visibility::Direct);
+        assert!(Visibility::Direct < Visibility::Private);
+        assert!(Visibility::Private < Visibility::Unlisted);
+        assert!(Visibility::Unlisted < Visibility::Public);
+        assert_eq!(
+            Visibility::Unlisted.at_most(Visibility::Private),
+            Visibility::Private
+        );
+        assert_eq!(
+            Visibility::Private.at_most(Visibility::Public),
+            Visibility::Private
+        );
+        assert_eq!(
+            Visibility::Unknown.at_most(Visibility::Public),
+            Visibility::Unknown
+        );
+    }
+
+    #[test]
+    fn test_unknown_visibility_is_sent_as_direct() {
+        let mut original = status();
+        original.visibility = Visibility::Unknown;
+        let status = StatusBuilder::from_status_template(&original)
+            .status("hi")
+            .build()
+            .expect("Couldn't build status");
+        let ser = serde_json::to_value(&status).expect("Couldn't serialize status");
+        assert_eq!(ser["visibility"], "direct");
+    }
+
+    #[test]
+    fn test_reply_to() {
+        let status = StatusBuilder::new()
+            .reply_to(&status())
+            .status("hi")
+            .build()
+            .expect("Couldn't build status");
+        let expected = NewStatus {
+            status: Some("@alice@example.org @bob @carol@other.example hi".to_string()),
+            in_reply_to_id: Some("110".to_string()),
+            spoiler_text: Some("food".to_string()),
+            visibility: Some(Visibility::Unlisted),
+            ..Default::default()
+        };
+        assert_eq!(status, expected);
+    }
+
+    #[test]
+    fn test_reply_to_clamps_visibility() {
+        let mut builder = StatusBuilder::new();
+        builder.reply_to(&status()).status("hi");
+        builder.visibility(Visibility::Public);
+        assert_eq!(
+            builder.build().unwrap().visibility,
+            Some(Visibility::Unlisted)
+        );
+        builder.visibility(Visibility::Direct);
+        assert_eq!(
+            builder.build().unwrap().visibility,
+            Some(Visibility::Direct)
+        );
+    }
+
+    #[test]
+    fn test_reply_to_unknown_visibility() {
+        let mut original = status();
+        original.visibility = serde_json::from_str(r#""local""#).unwrap();
+        assert_eq!(original.visibility, Visibility::Unknown);
+        let mut builder = StatusBuilder::new();
+        builder.reply_to(&original).status("hi");
+        assert_eq!(
+            builder.build().unwrap().visibility,
+            Some(Visibility::Direct)
+        );
+    }
+
+    #[test]
+    fn test_reply_to_as_skips_own_mention() {
+        let original = status();
+        let mut me = original.account.clone();
+        me.id = "111".to_string();
+        me.acct = "bob".to_string();
+        let status = StatusBuilder::new()
+            .reply_to_as(&original, &me)
+            .status("thanks @carol@other.example!")
+            .build()
+            .expect("Couldn't build status");
+        assert_eq!(
+            status.status,
+            Some("@alice@example.org thanks @carol@other.example!".to_string())
+        );
+    }
+
+    #[test]
+    fn test_max_characters() {
+        let mut builder = StatusBuilder::new();
+        builder
+            .status("hi @carol@other.example, see https://example.org/some/long/path")
+            .spoiler_text("cw")
+            .max_characters(2 + 3 + 6 + 6 + 23);
+        assert!(builder.build().is_ok());
+        builder.max_characters(39);
+        assert!(builder.build().is_err());
+
+        let mut builder = StatusBuilder::new();
+        builder.reply_to(&status()).status("x".repeat(5));
+        builder.max_characters(10);
+        assert!(builder.build().is_err());
+    }
+
+    #[cfg(feature = "detect-language")]
+    #[test]
+    fn test_detect_language() {
+        let status = StatusBuilder::new()
+            .status(
+                "@bob@example.com Der schnelle braune Fuchs springt über den faulen Hund, \
+                 und dann läuft er weiter in den Wald. #fuchs https://example.com/fuchs",
+            )
+            .build()
+            .expect("Couldn't build status");
+        assert_eq!(status.language, Some(Language::Deu));
+
+        let status = StatusBuilder::new()
+            .status("Der schnelle braune Fuchs springt über den faulen Hund.")
+            .language(Language::Eng)
+            .build()
+            .expect("Couldn't build status");
+        assert_eq!(status.language, Some(Language::Eng));
+
+        let status = StatusBuilder::new()
+            .status("ok")
+            .build()
+            .expect("Couldn't build status");
+        assert_eq!(status.language, None);
+    }
 }
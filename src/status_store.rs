@@ -0,0 +1,293 @@
+//! An in-memory cache of statuses that stays consistent with the server
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::entities::{event::Event, status::Status};
+
+#[derive(Debug, Clone)]
+struct Entry {
+    status: Status,
+    used: u64,
+}
+
+/// Statuses keyed by id, holding at most a fixed number of them and dropping
+/// the least recently used ones first
+///
+/// Statuses fetched through the REST API go in with `insert` or `extend`,
+/// and events received from a stream with `apply`, which also removes
+/// deleted statuses and replaces edited ones, including where they appear as
+/// the reblogged status of another. Everything showing statuses from the
+/// store then shows them as they are now.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// # use std::error::Error;
+/// use elefren::status_store::StatusStore;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let data = Data {
+/// #   base: "".into(),
+/// #   client_id: "".into(),
+/// #   client_secret: "".into(),
+/// #   redirect: "".into(),
+/// #   token: "".into(),
+/// #   ..Default::default()
+/// # };
+/// let client = Mastodon::from(data);
+/// let mut store = StatusStore::new(500);
+/// store.extend(client.get_home_timeline()?.initial_items);
+/// for event in client.streaming_user()? {
+///     store.apply(&event);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct StatusStore {
+    capacity: usize,
+    statuses: HashMap<String, Entry>,
+    // ids by when they were last used, oldest first
+    recency: BTreeMap<u64, String>,
+    clock: u64,
+}
+
+impl Default for StatusStore {
+    fn default() -> Self {
+        StatusStore::new(1000)
+    }
+}
+
+impl StatusStore {
+    /// A store holding at most `capacity` statuses. Values below 1 are
+    /// treated as 1.
+    pub fn new(capacity: usize) -> StatusStore {
+        StatusStore {
+            capacity: capacity.max(1),
+            statuses: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+        }
+    }
+
+    /// The most statuses the store holds
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of statuses in the store
+    pub fn len(&self) -> usize {
+        self.statuses.len()
+    }
+
+    /// Whether the store is empty
+    pub fn is_empty(&self) -> bool {
+        self.statuses.is_empty()
+    }
+
+    /// Whether the status with id `id` is in the store
+    pub fn contains(&self, id: &str) -> bool {
+        self.statuses.contains_key(id)
+    }
+
+    /// The status with id `id`, marking it as recently used
+    pub fn get(&mut self, id: &str) -> Option<&Status> {
+        let used = self.tick();
+        let entry = self.statuses.get_mut(id)?;
+        self.recency.remove(&entry.used);
+        self.recency.insert(used, id.to_string());
+        entry.used = used;
+        Some(&entry.status)
+    }
+
+    /// The status with id `id`, without marking it as recently used
+    pub fn peek(&self, id: &str) -> Option<&Status> {
+        self.statuses.get(id).map(|entry| &entry.status)
+    }
+
+    /// Iterator over the statuses in the store, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = &Status> {
+        self.statuses.values().map(|entry| &entry.status)
+    }
+
+    /// Adds `status` to the store, returning the one it replaces
+    ///
+    /// If the store is full, the least recently used status is dropped.
+    pub fn insert(&mut self, status: Status) -> Option<Status> {
+        let used = self.tick();
+        self.recency.insert(used, status.id.clone());
+        let previous = self
+            .statuses
+            .insert(status.id.clone(), Entry { status, used });
+        if let Some(ref previous) = previous {
+            self.recency.remove(&previous.used);
+        }
+        while self.statuses.len() > self.capacity {
+            let (_, id) = self
+                .recency
+                .pop_first()
+                .expect("every status has a recency entry");
+            self.statuses.remove(&id);
+        }
+        previous.map(|entry| entry.status)
+    }
+
+    /// Removes the status with id `id` from the store
+    pub fn remove(&mut self, id: &str) -> Option<Status> {
+        let entry = self.statuses.remove(id)?;
+        self.recency.remove(&entry.used);
+        Some(entry.status)
+    }
+
+    /// Removes every status from the store
+    pub fn clear(&mut self) {
+        self.statuses.clear();
+        self.recency.clear();
+    }
+
+    /// Updates the store with a streaming event, returning whether it
+    /// changed
+    ///
+    /// New statuses, and those of notifications, are added. Deleted statuses
+    /// are removed, along with the reblogs of them. Edited statuses are
+    /// replaced, here and inside reblogs, if the store holds them; edits to
+    /// other statuses are ignored.
+    pub fn apply(&mut self, event: &Event) -> bool {
+        match *event {
+            Event::Update(ref status) => {
+                self.insert(status.clone());
+                true
+            },
+            Event::Notification(ref notification) => match notification.status {
+                Some(ref status) => {
+                    self.insert(status.clone());
+                    true
+                },
+                None => false,
+            },
+            Event::StatusUpdate(ref status) => {
+                let mut changed = false;
+                for entry in self.statuses.values_mut() {
+                    if entry.status.id == status.id {
+                        entry.status = status.clone();
+                        changed = true;
+                    } else if let Some(ref mut reblog) = entry.status.reblog {
+                        if reblog.id == status.id {
+                            **reblog = status.clone();
+                            changed = true;
+                        }
+                    }
+                }
+                changed
+            },
+            Event::Delete(ref id) => {
+                let deleted: Vec<String> = self
+                    .iter()
+                    .filter(|status| {
+                        status.id == *id
+                            || status
+                                .reblog
+                                .as_ref()
+                                .is_some_and(|reblog| reblog.id == *id)
+                    })
+                    .map(|status| status.id.clone())
+                    .collect();
+                for id in &deleted {
+                    self.remove(id);
+                }
+                !deleted.is_empty()
+            },
+            Event::FiltersChanged | Event::Conversation(_) => false,
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+impl Extend<Status> for StatusStore {
+    fn extend<I: IntoIterator<Item = Status>>(&mut self, statuses: I) {
+        for status in statuses {
+            self.insert(status);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{entities::status::StatusId, test_fixtures};
+
+    fn status(id: &str, content: &str) -> Status {
+        Status {
+            content: content.to_string(),
+            ..test_fixtures::status_with_id(id)
+        }
+    }
+
+    fn reblog(id: &str, of: Status) -> Status {
+        let mut status = status(id, "");
+        status.reblog = Some(Box::new(of));
+        status
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut store = StatusStore::new(2);
+        store.insert(status("1", ""));
+        store.insert(status("2", ""));
+        assert!(store.get("1").is_some());
+        store.insert(status("3", ""));
+        assert!(store.contains("1"));
+        assert!(!store.contains("2"));
+        assert!(store.contains("3"));
+
+        // peeking doesn't count as a use
+        assert!(store.peek("1").is_some());
+        store.insert(status("4", ""));
+        assert!(!store.contains("1"));
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn test_replace_keeps_one_entry() {
+        let mut store = StatusStore::new(2);
+        store.insert(status("1", "old"));
+        let previous = store.insert(status("1", "new"));
+        assert_eq!(previous.unwrap().content, "old");
+        store.insert(status("2", ""));
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.peek("1").unwrap().content, "new");
+    }
+
+    #[test]
+    fn test_apply_edit() {
+        let mut store = StatusStore::default();
+        store.extend(vec![status("1", "old"), reblog("2", status("1", "old"))]);
+
+        assert!(store.apply(&Event::StatusUpdate(status("1", "new"))));
+        assert_eq!(store.peek("1").unwrap().content, "new");
+        let reblogged = store.peek("2").unwrap().reblog.as_ref().unwrap();
+        assert_eq!(reblogged.content, "new");
+
+        assert!(!store.apply(&Event::StatusUpdate(status("3", "new"))));
+        assert!(!store.contains("3"));
+    }
+
+    #[test]
+    fn test_apply_delete() {
+        let mut store = StatusStore::default();
+        store.apply(&Event::Update(status("1", "")));
+        store.apply(&Event::Update(reblog("2", status("1", ""))));
+        store.apply(&Event::Update(status("3", "")));
+
+        assert!(store.apply(&Event::Delete(StatusId::new("1").unwrap())));
+        assert!(!store.contains("1"));
+        assert!(!store.contains("2"));
+        assert!(store.contains("3"));
+        assert!(!store.apply(&Event::Delete(StatusId::new("1").unwrap())));
+    }
+}
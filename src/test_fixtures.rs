@@ -0,0 +1,34 @@
+// The fixtures in `tests/fixtures` that the unit tests build their
+// entities from, so that they don't each parse their own copy
+
+use crate::entities::{account::Account, status::Status};
+
+// A status with id `110` by alice@example.org, mentioning bob and
+// carol@other.example
+pub(crate) const STATUS: &str = include_str!("../tests/fixtures/status.json");
+// alice@example.org, with id `109`
+pub(crate) const ACCOUNT: &str = include_str!("../tests/fixtures/account.json");
+
+pub(crate) fn status() -> Status {
+    serde_json::from_str(STATUS).expect("Couldn't deserialize status")
+}
+
+// The fixture status, with its id set to `id`
+pub(crate) fn status_with_id(id: &str) -> Status {
+    Status {
+        id: id.to_string(),
+        ..status()
+    }
+}
+
+pub(crate) fn account() -> Account {
+    serde_json::from_str(ACCOUNT).expect("Couldn't deserialize account")
+}
+
+// The fixture account, with its id set to `id`
+pub(crate) fn account_with_id(id: &str) -> Account {
+    Account {
+        id: id.to_string(),
+        ..account()
+    }
+}
@@ -0,0 +1,447 @@
+//! A timeline kept up to date through the REST and streaming APIs
+
+use std::{cmp::Ordering, fmt};
+
+use crate::{
+    bot::compare_ids,
+    entities::{event::Event, status::Status},
+    errors::Result,
+    mastodon_client::{MastodonClient, StreamKind},
+    requests::PageRequest,
+    routes,
+};
+
+/// The timelines a `Timeline` can follow
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimelineSource {
+    /// The home timeline
+    Home,
+    /// The statuses of this instance
+    Local,
+    /// The statuses of every instance this one knows about
+    Federated,
+    /// The statuses with a hashtag
+    Hashtag {
+        /// The hashtag, with or without the leading `#`
+        tag: String,
+        /// Only the statuses of this instance
+        local: bool,
+    },
+    /// The statuses of the list with this id
+    List(String),
+}
+
+impl TimelineSource {
+    /// The stream that carries this timeline's updates
+    pub fn stream_kind(&self) -> StreamKind {
+        match *self {
+            TimelineSource::Home => StreamKind::User,
+            TimelineSource::Local => StreamKind::Public {
+                local: true,
+                media_only: false,
+            },
+            TimelineSource::Federated => StreamKind::Public {
+                local: false,
+                media_only: false,
+            },
+            TimelineSource::Hashtag { ref tag, local } => StreamKind::Hashtag {
+                tag: routes::tag_name(tag),
+                local,
+                media_only: false,
+            },
+            TimelineSource::List(ref id) => StreamKind::List(id.clone()),
+        }
+    }
+
+    fn fetch<C: MastodonClient>(&self, client: &C, request: &PageRequest) -> Result<Vec<Status>> {
+        let page = match *self {
+            TimelineSource::Home => client.get_home_timeline_with(request)?,
+            TimelineSource::Local => client.get_local_timeline_with(request)?,
+            TimelineSource::Federated => client.get_federated_timeline_with(request)?,
+            TimelineSource::Hashtag { ref tag, local } => {
+                client.get_hashtag_timeline_with(tag, local, request)?
+            },
+            TimelineSource::List(ref id) => client.get_list_timeline_with(id, request)?,
+        };
+        Ok(page.initial_items)
+    }
+}
+
+/// How a `Timeline` changed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimelineChange {
+    /// The status with this id was added
+    Added(String),
+    /// The status with this id was deleted
+    Removed(String),
+    /// The status with this id was edited
+    Replaced(String),
+}
+
+/// The statuses of a timeline, newest first, kept up to date
+///
+/// Statuses are fetched through the REST API, newer ones with
+/// `poll_updates` and older ones with `load_older`, and received live from
+/// the timeline's stream through `events`. Deleted statuses are removed and
+/// edited ones replaced, whichever way they're found out about.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// # use std::error::Error;
+/// use elefren::timeline::{Timeline, TimelineChange, TimelineSource};
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let data = Data {
+/// #   base: "".into(),
+/// #   client_id: "".into(),
+/// #   client_secret: "".into(),
+/// #   redirect: "".into(),
+/// #   token: "".into(),
+/// #   ..Default::default()
+/// # };
+/// let client = Mastodon::from(data);
+/// let mut timeline = Timeline::new(&client, TimelineSource::Home);
+/// timeline.poll_updates()?;
+/// for change in timeline.events()? {
+///     if let TimelineChange::Added(id) = change {
+///         println!("new status {}", id);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct Timeline<'a, C: MastodonClient> {
+    client: &'a C,
+    source: TimelineSource,
+    page_size: usize,
+    statuses: Vec<Status>,
+}
+
+impl<'a, C: MastodonClient> fmt::Debug for Timeline<'a, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Timeline")
+            .field("source", &self.source)
+            .field("page_size", &self.page_size)
+            .field("statuses", &self.statuses.len())
+            .finish()
+    }
+}
+
+impl<'a, C: MastodonClient> Timeline<'a, C> {
+    /// An empty timeline of `source`, fetched through `client`
+    pub fn new(client: &'a C, source: TimelineSource) -> Timeline<'a, C> {
+        Timeline {
+            client,
+            source,
+            page_size: 40,
+            statuses: Vec::new(),
+        }
+    }
+
+    /// Fetch up to `page_size` statuses per request. Defaults to 40; values
+    /// below 1 are treated as 1.
+    pub fn page_size(&mut self, page_size: usize) -> &mut Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// The timeline being followed
+    pub fn source(&self) -> &TimelineSource {
+        &self.source
+    }
+
+    /// The statuses, newest first
+    pub fn statuses(&self) -> &[Status] {
+        &self.statuses
+    }
+
+    /// The status with id `id`, if it's in the timeline
+    pub fn get(&self, id: &str) -> Option<&Status> {
+        self.position(id).ok().map(|index| &self.statuses[index])
+    }
+
+    /// Fetches the statuses newer than the newest one in the timeline, or
+    /// the latest page if it's empty
+    ///
+    /// The newer statuses are fetched page by page, starting from the
+    /// oldest, so that none are skipped when more than a page of them was
+    /// posted since the last poll. Nothing is added unless every page could
+    /// be fetched.
+    pub fn poll_updates(&mut self) -> Result<Vec<TimelineChange>> {
+        let mut min_id = match self.statuses.first() {
+            Some(newest) => newest.id.clone(),
+            None => {
+                let request = PageRequest::new().limit(self.page_size);
+                let statuses = self.source.fetch(self.client, &request)?;
+                return Ok(self.insert_all(statuses));
+            },
+        };
+        let mut statuses = Vec::new();
+        loop {
+            let request = PageRequest::new()
+                .min_id(min_id.as_str())
+                .limit(self.page_size);
+            let page = self.source.fetch(self.client, &request)?;
+            let full = page.len() >= self.page_size;
+            let newest = page
+                .iter()
+                .map(|status| &status.id)
+                .max_by(|a, b| compare_ids(a, b))
+                .cloned();
+            statuses.extend(page);
+            match newest {
+                Some(newest) if full && compare_ids(&newest, &min_id) == Ordering::Greater => {
+                    min_id = newest
+                },
+                _ => break,
+            }
+        }
+        Ok(self.insert_all(statuses))
+    }
+
+    /// Fetches a page of the statuses older than the oldest one in the
+    /// timeline
+    pub fn load_older(&mut self) -> Result<Vec<TimelineChange>> {
+        let mut request = PageRequest::new().limit(self.page_size);
+        if let Some(oldest) = self.statuses.last() {
+            request = request.max_id(oldest.id.as_str());
+        }
+        let statuses = self.source.fetch(self.client, &request)?;
+        Ok(self.insert_all(statuses))
+    }
+
+    /// Connects to the timeline's stream, returning an iterator that applies
+    /// the events it receives and yields the resulting changes
+    pub fn events(&mut self) -> Result<Events<'_, 'a, C>> {
+        let stream = self.client.streaming(self.source.stream_kind())?;
+        Ok(Events {
+            timeline: self,
+            stream,
+        })
+    }
+
+    /// Applies a streaming event to the timeline
+    ///
+    /// Returns `None` if the timeline didn't change, e.g. because the status
+    /// is already there or because it's a notification.
+    pub fn apply(&mut self, event: Event) -> Option<TimelineChange> {
+        match event {
+            Event::Update(status) => self.insert(status),
+            Event::StatusUpdate(status) => {
+                let index = self.position(&status.id).ok()?;
+                let change = TimelineChange::Replaced(status.id.clone());
+                self.statuses[index] = status;
+                Some(change)
+            },
+            Event::Delete(id) => {
+                let index = self.position(&id).ok()?;
+                self.statuses.remove(index);
+                Some(TimelineChange::Removed(id.into()))
+            },
+            Event::Notification(_) | Event::FiltersChanged | Event::Conversation(_) => None,
+        }
+    }
+
+    // the statuses are sorted by descending id
+    fn position(&self, id: &str) -> std::result::Result<usize, usize> {
+        self.statuses
+            .binary_search_by(|status| compare_ids(id, &status.id))
+    }
+
+    fn insert(&mut self, status: Status) -> Option<TimelineChange> {
+        let index = self.position(&status.id).err()?;
+        let change = TimelineChange::Added(status.id.clone());
+        self.statuses.insert(index, status);
+        Some(change)
+    }
+
+    // Inserts statuses fetched through the REST API, returning the changes
+    // oldest first
+    fn insert_all(&mut self, mut statuses: Vec<Status>) -> Vec<TimelineChange> {
+        statuses.sort_by(|a, b| compare_ids(&a.id, &b.id));
+        statuses
+            .into_iter()
+            .filter_map(|status| self.insert(status))
+            .collect()
+    }
+}
+
+/// Iterator over the changes a `Timeline`'s stream makes to it
+///
+/// Events that don't change the timeline are skipped. Iteration ends when
+/// the stream does.
+pub struct Events<'t, 'a, C: MastodonClient> {
+    timeline: &'t mut Timeline<'a, C>,
+    stream: C::Stream,
+}
+
+impl<'t, 'a, C: MastodonClient> fmt::Debug for Events<'t, 'a, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Events")
+            .field("timeline", &self.timeline)
+            .finish()
+    }
+}
+
+impl<'t, 'a, C: MastodonClient> Iterator for Events<'t, 'a, C> {
+    type Item = TimelineChange;
+
+    fn next(&mut self) -> Option<TimelineChange> {
+        for event in &mut self.stream {
+            if let Some(change) = self.timeline.apply(event) {
+                return Some(change);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::status::StatusId;
+    use crate::{data::Data, test_fixtures, Mastodon};
+    use mockito::Matcher;
+    use std::cell::RefCell;
+
+    struct MockClient {
+        events: RefCell<Option<Vec<Event>>>,
+    }
+
+    impl MastodonClient for MockClient {
+        type Stream = std::vec::IntoIter<Event>;
+
+        fn streaming(&self, stream: StreamKind) -> Result<Self::Stream> {
+            assert_eq!(stream, StreamKind::List("42".to_string()));
+            Ok(self
+                .events
+                .borrow_mut()
+                .take()
+                .unwrap_or_default()
+                .into_iter())
+        }
+    }
+
+    fn status(id: &str, content: &str) -> Status {
+        Status {
+            content: content.to_string(),
+            ..test_fixtures::status_with_id(id)
+        }
+    }
+
+    fn ids<'t, C: MastodonClient>(timeline: &'t Timeline<C>) -> Vec<&'t str> {
+        timeline
+            .statuses()
+            .iter()
+            .map(|status| &status.id[..])
+            .collect()
+    }
+
+    #[test]
+    fn test_events() {
+        let client = MockClient {
+            events: RefCell::new(Some(vec![
+                Event::Update(status("9", "nine")),
+                Event::Update(status("10", "ten")),
+                Event::Update(status("8", "eight")),
+                // already there
+                Event::Update(status("9", "nine")),
+                Event::StatusUpdate(status("9", "edited")),
+                Event::Delete(StatusId::new("8").unwrap()),
+                // not in the timeline
+                Event::Delete(StatusId::new("7").unwrap()),
+                Event::FiltersChanged,
+            ])),
+        };
+        let mut timeline = Timeline::new(&client, TimelineSource::List("42".to_string()));
+        let changes: Vec<TimelineChange> = timeline.events().unwrap().collect();
+
+        assert_eq!(
+            changes,
+            vec![
+                TimelineChange::Added("9".to_string()),
+                TimelineChange::Added("10".to_string()),
+                TimelineChange::Added("8".to_string()),
+                TimelineChange::Replaced("9".to_string()),
+                TimelineChange::Removed("8".to_string()),
+            ]
+        );
+        assert_eq!(ids(&timeline), ["10", "9"]);
+        assert_eq!(timeline.get("9").unwrap().content, "edited");
+    }
+
+    #[test]
+    fn test_stream_kind() {
+        let source = TimelineSource::Hashtag {
+            tag: "#rust".to_string(),
+            local: true,
+        };
+        assert_eq!(
+            source.stream_kind(),
+            StreamKind::Hashtag {
+                tag: "rust".to_string(),
+                local: true,
+                media_only: false,
+            }
+        );
+        assert_eq!(TimelineSource::Home.stream_kind(), StreamKind::User);
+    }
+
+    #[test]
+    fn test_poll_updates() {
+        let client = Mastodon::from(Data {
+            base: mockito::server_url().into(),
+            token: "token".into(),
+            ..Default::default()
+        });
+        let source = TimelineSource::Hashtag {
+            tag: "#elefren".to_string(),
+            local: true,
+        };
+        let mut timeline = Timeline::new(&client, source);
+        timeline.page_size(2);
+        timeline.apply(Event::Update(status("5100", "")));
+
+        let body = test_fixtures::STATUS;
+        let body = format!(
+            "[{}, {}]",
+            body.replacen("\"id\": \"110\"", "\"id\": \"5102\"", 1),
+            body.replacen("\"id\": \"110\"", "\"id\": \"5101\"", 1)
+        );
+        let mock = mockito::mock("GET", "/api/v1/timelines/tag/elefren")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("local".into(), "1".into()),
+                Matcher::UrlEncoded("min_id".into(), "5100".into()),
+                Matcher::UrlEncoded("limit".into(), "2".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(body)
+            .create();
+        // the first page was full, so there may be more
+        let next = mockito::mock("GET", "/api/v1/timelines/tag/elefren")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("min_id".into(), "5102".into()),
+                Matcher::UrlEncoded("limit".into(), "2".into()),
+            ]))
+            .with_header("content-type", "application/json")
+            .with_body(format!(
+                "[{}]",
+                test_fixtures::STATUS.replacen("\"id\": \"110\"", "\"id\": \"5103\"", 1)
+            ))
+            .create();
+        let changes = timeline.poll_updates().unwrap();
+        mock.assert();
+        next.assert();
+        assert_eq!(
+            changes,
+            vec![
+                TimelineChange::Added("5101".to_string()),
+                TimelineChange::Added("5102".to_string()),
+                TimelineChange::Added("5103".to_string()),
+            ]
+        );
+        assert_eq!(ids(&timeline), ["5103", "5102", "5101", "5100"]);
+    }
+}
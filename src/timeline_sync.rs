@@ -0,0 +1,264 @@
+//! Keeping up with the home timeline without missing statuses
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::status::Status, errors::Result, http_send::HttpSend, mastodon_client::MastodonClient,
+    requests::PageRequest,
+};
+
+/// A stretch of the timeline that hasn't been fetched, between two statuses
+/// that have
+///
+/// This is what the official apps show as "load missing posts".
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Gap {
+    /// The newest status that was fetched before the gap
+    pub since_id: String,
+    /// The oldest status that was fetched after the gap
+    pub max_id: String,
+}
+
+/// Fetches new statuses of the home timeline on demand, remembering the
+/// newest one seen and any gaps left behind
+///
+/// Every sync fetches a single page of statuses newer than the last sync.
+/// When that page is full, there may be more statuses between the two than
+/// fit in it, and the remainder is recorded as a [`Gap`](struct.Gap.html)
+/// that can be filled later, or never.
+///
+/// The state can be serialized, e.g. to resume where the app left off.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate elefren;
+/// # use elefren::prelude::*;
+/// # use std::error::Error;
+/// use elefren::timeline_sync::TimelineSync;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let data = Data {
+/// #   base: "".into(),
+/// #   client_id: "".into(),
+/// #   client_secret: "".into(),
+/// #   redirect: "".into(),
+/// #   token: "".into(),
+/// #   ..Default::default()
+/// # };
+/// let client = Mastodon::from(data);
+/// let mut sync = TimelineSync::new();
+/// for status in sync.sync(&client)? {
+///     println!("{}", status.content);
+/// }
+/// // later, when the user asks for the missing posts
+/// if let Some(gap) = sync.gaps().first().cloned() {
+///     let missing = sync.fill_gap(&client, &gap)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimelineSync {
+    newest_id: Option<String>,
+    page_size: usize,
+    gaps: Vec<Gap>,
+}
+
+impl Default for TimelineSync {
+    fn default() -> Self {
+        TimelineSync::new()
+    }
+}
+
+impl TimelineSync {
+    /// Start from the current end of the timeline, fetching 40 statuses at a
+    /// time
+    pub fn new() -> TimelineSync {
+        TimelineSync {
+            newest_id: None,
+            page_size: 40,
+            gaps: Vec::new(),
+        }
+    }
+
+    /// Resume after the status with id `newest_id`
+    pub fn since<S: Into<String>>(newest_id: S) -> TimelineSync {
+        TimelineSync {
+            newest_id: Some(newest_id.into()),
+            ..TimelineSync::new()
+        }
+    }
+
+    /// Fetch up to `page_size` statuses at a time. Values below 1 are
+    /// treated as 1.
+    pub fn page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// The id of the newest status fetched so far
+    pub fn newest_id(&self) -> Option<&str> {
+        self.newest_id.as_deref()
+    }
+
+    /// The gaps that haven't been filled yet, newest first
+    pub fn gaps(&self) -> &[Gap] {
+        &self.gaps
+    }
+
+    /// Fetches the statuses that are newer than the last sync, newest first
+    ///
+    /// The first sync only fetches the latest page, without recording a gap.
+    pub fn sync<H: HttpSend, C: MastodonClient<H>>(&mut self, client: &C) -> Result<Vec<Status>> {
+        let mut request = PageRequest::new().limit(self.page_size);
+        if let Some(ref since_id) = self.newest_id {
+            request = request.since_id(since_id.as_str());
+        }
+        let statuses = client.get_home_timeline_with(&request)?.initial_items;
+
+        if let (Some(newest), Some(oldest)) = (statuses.first(), statuses.last()) {
+            if let Some(since_id) = self.newest_id.take() {
+                if statuses.len() >= self.page_size {
+                    self.gaps.insert(
+                        0,
+                        Gap {
+                            since_id,
+                            max_id: oldest.id.clone(),
+                        },
+                    );
+                }
+            }
+            self.newest_id = Some(newest.id.clone());
+        }
+        Ok(statuses)
+    }
+
+    /// Fetches a page of the statuses missing in `gap`, newest first
+    ///
+    /// If the page is full, the gap shrinks to what's still missing below it;
+    /// otherwise it's closed. Gaps that aren't in `gaps()` are fetched all
+    /// the same but not tracked.
+    pub fn fill_gap<H: HttpSend, C: MastodonClient<H>>(
+        &mut self,
+        client: &C,
+        gap: &Gap,
+    ) -> Result<Vec<Status>> {
+        let request = PageRequest::new()
+            .max_id(gap.max_id.as_str())
+            .since_id(gap.since_id.as_str())
+            .limit(self.page_size);
+        let statuses = client.get_home_timeline_with(&request)?.initial_items;
+
+        if let Some(index) = self.gaps.iter().position(|g| g == gap) {
+            match statuses.last() {
+                Some(oldest) if statuses.len() >= self.page_size => {
+                    self.gaps[index].max_id = oldest.id.clone();
+                },
+                _ => {
+                    self.gaps.remove(index);
+                },
+            }
+        }
+        Ok(statuses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{data::Data, test_fixtures, Mastodon};
+    use mockito::Matcher;
+
+    fn client() -> Mastodon {
+        Mastodon::from(Data {
+            base: mockito::server_url().into(),
+            token: "token".into(),
+            ..Default::default()
+        })
+    }
+
+    fn statuses(ids: &[&str]) -> String {
+        let status = test_fixtures::STATUS;
+        let statuses: Vec<String> = ids
+            .iter()
+            .map(|id| status.replacen("\"id\": \"110\"", &format!("\"id\": \"{}\"", id), 1))
+            .collect();
+        format!("[{}]", statuses.join(","))
+    }
+
+    fn mock_home(query: Vec<(&str, &str)>, ids: &[&str]) -> mockito::Mock {
+        let query = query
+            .into_iter()
+            .map(|(k, v)| Matcher::UrlEncoded(k.into(), v.into()))
+            .collect();
+        mockito::mock("GET", "/api/v1/timelines/home")
+            .match_query(Matcher::AllOf(query))
+            .with_header("content-type", "application/json")
+            .with_body(statuses(ids))
+            .create()
+    }
+
+    #[test]
+    fn test_sync_records_gap() {
+        let client = client();
+        let mut sync = TimelineSync::since("7100").page_size(2);
+
+        let full = mock_home(
+            vec![("since_id", "7100"), ("limit", "2")],
+            &["7200", "7190"],
+        );
+        let ids: Vec<String> = sync
+            .sync(&client)
+            .unwrap()
+            .into_iter()
+            .map(|s| s.id)
+            .collect();
+        full.assert();
+        drop(full);
+        assert_eq!(ids, ["7200", "7190"]);
+        assert_eq!(sync.newest_id(), Some("7200"));
+        let gap = Gap {
+            since_id: "7100".to_string(),
+            max_id: "7190".to_string(),
+        };
+        assert_eq!(sync.gaps(), std::slice::from_ref(&gap));
+
+        let partial = mock_home(vec![("since_id", "7200"), ("limit", "2")], &["7201"]);
+        sync.sync(&client).unwrap();
+        partial.assert();
+        drop(partial);
+        assert_eq!(sync.gaps().len(), 1);
+
+        let first = mock_home(
+            vec![("max_id", "7190"), ("since_id", "7100"), ("limit", "2")],
+            &["7180", "7170"],
+        );
+        sync.fill_gap(&client, &gap).unwrap();
+        first.assert();
+        drop(first);
+        let gap = Gap {
+            since_id: "7100".to_string(),
+            max_id: "7170".to_string(),
+        };
+        assert_eq!(sync.gaps(), std::slice::from_ref(&gap));
+
+        let last = mock_home(
+            vec![("max_id", "7170"), ("since_id", "7100"), ("limit", "2")],
+            &["7160"],
+        );
+        sync.fill_gap(&client, &gap).unwrap();
+        last.assert();
+        assert!(sync.gaps().is_empty());
+    }
+
+    #[test]
+    fn test_first_sync_has_no_gap() {
+        let client = client();
+        let mut sync = TimelineSync::new().page_size(1);
+        let mock = mock_home(vec![("limit", "1")], &["8100"]);
+        sync.sync(&client).unwrap();
+        mock.assert();
+        assert_eq!(sync.newest_id(), Some("8100"));
+        assert!(sync.gaps().is_empty());
+    }
+}
@@ -0,0 +1,288 @@
+//! Recording the responses of an instance and replaying them, so that code
+//! using `Mastodon` can be tested without a server or mocks
+//!
+//! A [`Vcr`](struct.Vcr.html) is an [`HttpSend`](../http_send/trait.HttpSend.html)
+//! implementation: a client built with it either sends its requests to the
+//! instance and records the responses in a cassette, a JSON file, or answers
+//! them from a cassette recorded before. Requests are matched by method, URL
+//! and body, and each recorded response is used once, in the order recorded.
+//!
+//! Request headers aren't recorded, so cassettes don't contain access tokens.
+//! Streaming connections don't go through the `HttpSend` implementation and
+//! can't be recorded.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # extern crate elefren;
+//! use elefren::{prelude::*, vcr::Vcr, MastodonBuilder};
+//! # fn main() -> elefren::Result<()> {
+//! # let data = Data {
+//! #   base: "".into(),
+//! #   client_id: "".into(),
+//! #   client_secret: "".into(),
+//! #   redirect: "".into(),
+//! #   token: "".into(),
+//! #   ..Default::default()
+//! # };
+//! // records the first time it runs, and replays from then on
+//! let vcr = Vcr::auto("tests/cassettes/home_timeline.json")?;
+//! let client = MastodonBuilder::with_sender(vcr.clone())
+//!     .data(data)
+//!     .build()?;
+//! let statuses = client.get_home_timeline()?.initial_items;
+//! vcr.save()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use reqwest::blocking::{Client, Request, Response};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::{Error, Result},
+    http_send::HttpSend,
+};
+
+/// A request recorded in a cassette
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    /// The HTTP method, e.g. `GET`
+    pub method: String,
+    /// The full URL, including the query string
+    pub url: String,
+    /// The body, if there was one that could be recorded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+}
+
+/// A response recorded in a cassette
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    /// The HTTP status code
+    pub status: u16,
+    /// The headers, in the order received
+    pub headers: Vec<(String, String)>,
+    /// The body
+    pub body: String,
+}
+
+/// A request and the response the instance gave to it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    /// The request
+    pub request: RecordedRequest,
+    /// The response
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Record,
+    Replay,
+}
+
+#[derive(Debug)]
+struct State {
+    mode: Mode,
+    path: PathBuf,
+    interactions: Vec<Interaction>,
+    // whether each interaction has been replayed already
+    used: Vec<bool>,
+}
+
+/// An `HttpSend` implementation recording responses to a cassette, or
+/// replaying them from it
+///
+/// Clones share the same cassette, so one can be passed to
+/// `MastodonBuilder::with_sender` and the other kept to call `save`.
+#[derive(Clone)]
+pub struct Vcr(Arc<Mutex<State>>);
+
+impl fmt::Debug for Vcr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let state = self.0.lock().unwrap();
+        f.debug_struct("Vcr")
+            .field("mode", &state.mode)
+            .field("path", &state.path)
+            .field("interactions", &state.interactions.len())
+            .finish()
+    }
+}
+
+impl Vcr {
+    /// Sends requests to the instance, recording them to be saved to `path`
+    /// with `save`
+    pub fn record<P: AsRef<Path>>(path: P) -> Vcr {
+        Vcr::new(Mode::Record, path.as_ref(), Vec::new())
+    }
+
+    /// Answers requests from the cassette at `path`
+    pub fn replay<P: AsRef<Path>>(path: P) -> Result<Vcr> {
+        let path = path.as_ref();
+        let interactions = serde_json::from_slice(&fs::read(path)?)?;
+        Ok(Vcr::new(Mode::Replay, path, interactions))
+    }
+
+    /// Replays the cassette at `path` if it exists, and records it otherwise
+    pub fn auto<P: AsRef<Path>>(path: P) -> Result<Vcr> {
+        if path.as_ref().exists() {
+            Vcr::replay(path)
+        } else {
+            Ok(Vcr::record(path))
+        }
+    }
+
+    /// Whether requests are sent to the instance and recorded
+    pub fn is_recording(&self) -> bool {
+        self.0.lock().unwrap().mode == Mode::Record
+    }
+
+    /// The interactions recorded so far, or loaded from the cassette
+    pub fn interactions(&self) -> Vec<Interaction> {
+        self.0.lock().unwrap().interactions.clone()
+    }
+
+    /// Writes the recorded interactions to the cassette, creating its
+    /// directory if needed. Does nothing when replaying.
+    pub fn save(&self) -> Result<()> {
+        let state = self.0.lock().unwrap();
+        if state.mode == Mode::Replay {
+            return Ok(());
+        }
+        if let Some(dir) = state.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&state.path, serde_json::to_vec_pretty(&state.interactions)?)?;
+        Ok(())
+    }
+
+    fn new(mode: Mode, path: &Path, interactions: Vec<Interaction>) -> Vcr {
+        let used = vec![false; interactions.len()];
+        Vcr(Arc::new(Mutex::new(State {
+            mode,
+            path: path.to_path_buf(),
+            interactions,
+            used,
+        })))
+    }
+}
+
+impl HttpSend for Vcr {
+    fn execute(&self, client: &Client, request: Request) -> Result<Response> {
+        let recorded = RecordedRequest {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            body: request
+                .body()
+                .and_then(|body| body.as_bytes())
+                .map(|body| String::from_utf8_lossy(body).into_owned()),
+        };
+
+        let mut state = self.0.lock().unwrap();
+        if state.mode == Mode::Replay {
+            let State {
+                ref interactions,
+                ref mut used,
+                ..
+            } = *state;
+            let found = interactions
+                .iter()
+                .zip(used.iter_mut())
+                .find(|(interaction, used)| !**used && interaction.request == recorded);
+            return match found {
+                Some((interaction, used)) => {
+                    *used = true;
+                    to_response(&interaction.response)
+                },
+                None => Err(Error::Other(format!(
+                    "no recorded response left for {} {}",
+                    recorded.method, recorded.url
+                ))),
+            };
+        }
+        // other requests wait until this one has been recorded, which keeps
+        // the cassette in order
+        let response = client.execute(request)?;
+        let status = response.status().as_u16();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        let body = response.text()?;
+        let interaction = Interaction {
+            request: recorded,
+            response: RecordedResponse {
+                status,
+                headers,
+                body,
+            },
+        };
+        let response = to_response(&interaction.response);
+        state.interactions.push(interaction);
+        state.used.push(true);
+        response
+    }
+}
+
+fn to_response(recorded: &RecordedResponse) -> Result<Response> {
+    let mut builder = http::Response::builder().status(recorded.status);
+    for (name, value) in &recorded.headers {
+        builder = builder.header(name.as_str(), value.as_str());
+    }
+    let response = builder
+        .body(recorded.body.clone().into_bytes())
+        .map_err(|e| Error::Other(format!("invalid recorded response: {}", e)))?;
+    Ok(Response::from(response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_fixtures, Data, MastodonBuilder, MastodonClient};
+
+    fn client(vcr: &Vcr) -> crate::Mastodon<Vcr> {
+        let data = Data {
+            base: mockito::server_url().into(),
+            token: "token".into(),
+            ..Default::default()
+        };
+        MastodonBuilder::with_sender(vcr.clone())
+            .data(data)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cassettes/status.json");
+
+        let mock = mockito::mock("GET", "/api/v1/statuses/110")
+            .with_header("content-type", "application/json")
+            .with_body(test_fixtures::STATUS)
+            .create();
+        let vcr = Vcr::auto(&path).unwrap();
+        assert!(vcr.is_recording());
+        let recorded = client(&vcr).get_status("110").unwrap();
+        vcr.save().unwrap();
+        mock.assert();
+        drop(mock);
+
+        let vcr = Vcr::auto(&path).unwrap();
+        assert!(!vcr.is_recording());
+        assert_eq!(vcr.interactions().len(), 1);
+        let client = client(&vcr);
+        let replayed = client.get_status("110").unwrap();
+        assert_eq!(replayed, recorded);
+        // each response is replayed once
+        assert!(client.get_status("110").is_err());
+    }
+}